@@ -0,0 +1,46 @@
+/** One frame's telemetry: the `SeiMetadata` fields (enums as raw values) and where the frame sits in the clip. */
+export interface Frame {
+  version: number
+  gearState: number
+  /** A `BigInt`, since sequence numbers can exceed 2^53. */
+  frameSeqNo: bigint
+  vehicleSpeedMps: number
+  acceleratorPedalPosition: number
+  steeringWheelAngle: number
+  blinkerOnLeft: boolean
+  blinkerOnRight: boolean
+  brakeApplied: boolean
+  autopilotState: number
+  latitudeDeg: number
+  longitudeDeg: number
+  headingDeg: number
+  linearAccelerationMps2X: number
+  linearAccelerationMps2Y: number
+  linearAccelerationMps2Z: number
+  /** 0-based sample index, in decode order. */
+  sampleIndex: number
+  /** 0-based position in display order. */
+  presentationIndex: number
+  /** Display time from the start of the clip, in seconds; absent if the track has no timing. */
+  presentationTime?: number
+}
+
+/** An open clip, read one frame at a time. */
+export class SeiExtractor implements AsyncIterable<Frame> {
+  /** Open an MP4 clip and read its index. */
+  constructor(path: string)
+  /** The next frame's telemetry, in decode order; `null` at the end of the clip. */
+  next(): Frame | null
+  /** Like `next`, reading and decoding on the libuv thread pool. */
+  nextAsync(): Promise<Frame | null>
+  /** Number of video samples in the clip, for progress bars. */
+  get totalSamples(): number
+  [Symbol.asyncIterator](): AsyncIterator<Frame>
+}
+
+/** Every frame's telemetry in a clip, in decode order. */
+export function extractTelemetry(path: string): Frame[]
+/** Like `extractTelemetry`, reading the clip on the libuv thread pool. */
+export function extractTelemetryAsync(path: string): Promise<Frame[]>
+/** Async iterable over the frames of the clip at `path`. */
+export function frames(path: string): SeiExtractor
@@ -0,0 +1,18 @@
+'use strict'
+
+const { SeiExtractor, extractTelemetry, extractTelemetryAsync } = require('./tesla-sei.node')
+
+// `for await (const frame of extractor)`: each frame is read on the libuv thread pool.
+SeiExtractor.prototype[Symbol.asyncIterator] = async function* () {
+  let frame
+  while ((frame = await this.nextAsync()) !== null) {
+    yield frame
+  }
+}
+
+// Async iterable over the frames of the clip at `path`.
+function frames(path) {
+  return new SeiExtractor(path)
+}
+
+module.exports = { SeiExtractor, extractTelemetry, extractTelemetryAsync, frames }
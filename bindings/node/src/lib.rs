@@ -0,0 +1,172 @@
+//! Node.js addon over the `tesla-sei` extractor, built with napi-rs.
+//!
+//! `index.js` loads the addon and adds async iteration on top.
+
+use std::fs::File;
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::{AsyncTask, BigInt};
+use napi::{Env, Task};
+use napi_derive::napi;
+use tesla_sei::{SeiEvent, SeiMetadataFlat};
+
+/// One frame's telemetry: the `SeiMetadata` fields (enums as raw values) and where the frame
+/// sits in the clip.
+#[napi(object)]
+pub struct Frame {
+    pub version: u32,
+    pub gear_state: i32,
+    /// A `BigInt`, since sequence numbers can exceed 2^53.
+    pub frame_seq_no: BigInt,
+    pub vehicle_speed_mps: f64,
+    pub accelerator_pedal_position: f64,
+    pub steering_wheel_angle: f64,
+    pub blinker_on_left: bool,
+    pub blinker_on_right: bool,
+    pub brake_applied: bool,
+    pub autopilot_state: i32,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    pub heading_deg: f64,
+    pub linear_acceleration_mps2_x: f64,
+    pub linear_acceleration_mps2_y: f64,
+    pub linear_acceleration_mps2_z: f64,
+    /// 0-based sample index, in decode order.
+    pub sample_index: f64,
+    /// 0-based position in display order.
+    pub presentation_index: f64,
+    /// Display time from the start of the clip, in seconds; absent if the track has no timing.
+    pub presentation_time: Option<f64>,
+}
+
+impl From<SeiEvent> for Frame {
+    fn from(event: SeiEvent) -> Self {
+        let m = SeiMetadataFlat::from(&event.metadata);
+        Frame {
+            version: m.version,
+            gear_state: m.gear_state,
+            frame_seq_no: m.frame_seq_no.into(),
+            vehicle_speed_mps: f64::from(m.vehicle_speed_mps),
+            accelerator_pedal_position: f64::from(m.accelerator_pedal_position),
+            steering_wheel_angle: f64::from(m.steering_wheel_angle),
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: m.autopilot_state,
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+            sample_index: event.sample_index as f64,
+            presentation_index: event.presentation_index as f64,
+            presentation_time: event.presentation_time.map(|t| t.as_secs_f64()),
+        }
+    }
+}
+
+fn js_error(e: tesla_sei::Error) -> napi::Error {
+    napi::Error::from_reason(e.to_string())
+}
+
+type Inner = Arc<Mutex<tesla_sei::SeiExtractor<File>>>;
+
+fn next_frame(inner: &Inner) -> napi::Result<Option<Frame>> {
+    let mut extractor = inner
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(extractor.next_event().map_err(js_error)?.map(Frame::from))
+}
+
+/// An open clip, read one frame at a time.
+#[napi]
+pub struct SeiExtractor {
+    inner: Inner,
+}
+
+#[napi]
+impl SeiExtractor {
+    /// Open an MP4 clip and read its index.
+    #[napi(constructor)]
+    pub fn new(path: String) -> napi::Result<Self> {
+        let extractor = tesla_sei::extractor_from_path(&path).map_err(js_error)?;
+        Ok(SeiExtractor {
+            inner: Arc::new(Mutex::new(extractor)),
+        })
+    }
+
+    /// The next frame's telemetry, in decode order; `null` at the end of the clip.
+    #[napi]
+    pub fn next(&self) -> napi::Result<Option<Frame>> {
+        next_frame(&self.inner)
+    }
+
+    /// Like `next`, reading and decoding on the libuv thread pool.
+    #[napi(ts_return_type = "Promise<Frame | null>")]
+    pub fn next_async(&self) -> AsyncTask<NextFrame> {
+        AsyncTask::new(NextFrame {
+            inner: self.inner.clone(),
+        })
+    }
+
+    /// Number of video samples in the clip, for progress bars.
+    #[napi(getter)]
+    pub fn total_samples(&self) -> f64 {
+        let extractor = self.inner.lock().unwrap_or_else(|p| p.into_inner());
+        extractor.total_samples() as f64
+    }
+}
+
+pub struct NextFrame {
+    inner: Inner,
+}
+
+impl Task for NextFrame {
+    type Output = Option<Frame>;
+    type JsValue = Option<Frame>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        next_frame(&self.inner)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+fn read_all(path: &str) -> napi::Result<Vec<Frame>> {
+    let extractor = tesla_sei::extractor_from_path(path).map_err(js_error)?;
+    extractor
+        .map(|event| event.map(Frame::from).map_err(js_error))
+        .collect()
+}
+
+/// Every frame's telemetry in a clip, in decode order.
+#[napi]
+pub fn extract_telemetry(path: String) -> napi::Result<Vec<Frame>> {
+    read_all(&path)
+}
+
+/// Like `extractTelemetry`, reading the clip on the libuv thread pool.
+#[napi(ts_return_type = "Promise<Frame[]>")]
+pub fn extract_telemetry_async(path: String) -> AsyncTask<ExtractTelemetry> {
+    AsyncTask::new(ExtractTelemetry { path })
+}
+
+pub struct ExtractTelemetry {
+    path: String,
+}
+
+impl Task for ExtractTelemetry {
+    type Output = Vec<Frame>;
+    type JsValue = Vec<Frame>;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        read_all(&self.path)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output)
+    }
+}
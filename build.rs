@@ -1,6 +1,52 @@
 fn main() {
-    prost_build::Config::new()
+    let out_dir = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    let mut config = prost_build::Config::new();
+    config
         .default_package_filename("dashcam")
+        // Embedded by the `reflect` feature as the bundled runtime schema.
+        .file_descriptor_set_path(out_dir.join("dashcam.bin"));
+    if std::env::var_os("CARGO_FEATURE_SERDE").is_some() {
+        // Messages and enums alike; enum fields are stored as `i32`, so they (de)serialize as
+        // their raw values.
+        config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+        // Missing fields take their proto3 defaults, as in the wire format.
+        config.message_attribute(".", "#[serde(default)]");
+    }
+    config
         .compile_protos(&["proto/dashcam.proto"], &["proto"])
         .expect("prost-build failed");
+    write_known_field_numbers(&out_dir);
+
+    // The `grpc` service, streaming the `SeiMetadata` generated above.
+    #[cfg(feature = "grpc")]
+    tonic_prost_build::configure()
+        .extern_path(".SeiMetadata", "crate::pb::SeiMetadata")
+        .compile_protos(&["proto/extractor.proto"], &["proto"])
+        .expect("tonic-prost-build failed");
+}
+
+// `SeiMetadata`'s field numbers as a slice expression, so telling unknown fields apart follows
+// the schema.
+fn write_known_field_numbers(out_dir: &std::path::Path) {
+    use prost::Message;
+
+    let bytes = std::fs::read(out_dir.join("dashcam.bin")).expect("reading dashcam.bin");
+    let set =
+        prost_types::FileDescriptorSet::decode(bytes.as_slice()).expect("decoding dashcam.bin");
+    let message = set
+        .file
+        .iter()
+        .flat_map(|file| &file.message_type)
+        .find(|message| message.name() == "SeiMetadata")
+        .expect("SeiMetadata in dashcam.proto");
+    let numbers: Vec<String> = message
+        .field
+        .iter()
+        .map(|field| field.number().to_string())
+        .collect();
+    std::fs::write(
+        out_dir.join("sei_metadata_fields.rs"),
+        format!("&[{}]", numbers.join(", ")),
+    )
+    .expect("writing sei_metadata_fields.rs");
 }
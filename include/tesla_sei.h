@@ -0,0 +1,117 @@
+#ifndef TESLA_SEI_H
+#define TESLA_SEI_H
+
+/* Generated by cbindgen from src/ffi.rs and src/flat.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a `tesla_sei_*` call. Negative values are errors; [`tesla_sei_last_error`]
+// describes the most recent one.
+typedef enum TeslaSeiStatus {
+  TESLA_SEI_STATUS_OK = 0,
+  // `tesla_sei_next` found no more telemetry.
+  TESLA_SEI_STATUS_END = 1,
+  // A required pointer was null, or the path isn't valid for this platform.
+  TESLA_SEI_STATUS_INVALID_ARGUMENT = -1,
+  // The file couldn't be opened or read.
+  TESLA_SEI_STATUS_IO = -2,
+  // The file isn't a usable MP4: malformed boxes, no video track, broken sample tables.
+  TESLA_SEI_STATUS_INVALID_MP4 = -3,
+  // The video track's codec carries no SEI this library can read.
+  TESLA_SEI_STATUS_UNSUPPORTED_CODEC = -4,
+  // The library panicked; the extractor should be closed.
+  TESLA_SEI_STATUS_PANIC = -5,
+} TeslaSeiStatus;
+
+// An open clip. Opaque to C.
+typedef struct TeslaSeiExtractor TeslaSeiExtractor;
+
+// The `SeiMetadata` fields in proto order, with enums as their raw values.
+//
+// The layout is fixed (`repr(C)`), so it can be handed to C as is or copied between processes
+// built from the same release.
+typedef struct TeslaSeiMetadata {
+  uint32_t version;
+  // `Gear`: 0 park, 1 drive, 2 reverse, 3 neutral.
+  int32_t gear_state;
+  uint64_t frame_seq_no;
+  float vehicle_speed_mps;
+  float accelerator_pedal_position;
+  float steering_wheel_angle;
+  bool blinker_on_left;
+  bool blinker_on_right;
+  bool brake_applied;
+  // `AutopilotState`: 0 none, 1 self driving, 2 autosteer, 3 TACC.
+  int32_t autopilot_state;
+  // 0.0, 0.0 without a GPS fix.
+  double latitude_deg;
+  double longitude_deg;
+  double heading_deg;
+  double linear_acceleration_mps2_x;
+  double linear_acceleration_mps2_y;
+  double linear_acceleration_mps2_z;
+} TeslaSeiMetadata;
+
+// One frame's telemetry plus where the frame sits in the clip.
+typedef struct TeslaSeiTelemetry {
+  // The `SeiMetadata` fields, with enums as their raw values.
+  struct TeslaSeiMetadata metadata;
+  // 0-based sample index, in decode order.
+  uint64_t sample_index;
+  // 0-based position in display order.
+  uint64_t presentation_index;
+  // Display time from the start of the clip, in microseconds; -1 if the track has no timing.
+  int64_t presentation_time_us;
+} TeslaSeiTelemetry;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Open an MP4 clip. On success `*out` holds an extractor to pass to [`tesla_sei_next`] and
+// finally [`tesla_sei_close`]; on failure it is set to null.
+//
+// # Safety
+//
+// `path` must be a NUL-terminated string and `out` must point to writable memory for a
+// pointer.
+enum TeslaSeiStatus tesla_sei_open(const char *path, struct TeslaSeiExtractor **out);
+
+// Decode the next frame's telemetry into `*out`, in decode order. Returns
+// [`TeslaSeiStatus::Ok`] with `*out` filled in, [`TeslaSeiStatus::End`] once the clip is
+// exhausted, or an error.
+//
+// # Safety
+//
+// `extractor` must come from [`tesla_sei_open`] and not yet be closed; `out` must point to
+// writable memory for a `TeslaSeiTelemetry`.
+enum TeslaSeiStatus tesla_sei_next(struct TeslaSeiExtractor *extractor,
+                                   struct TeslaSeiTelemetry *out);
+
+// Number of video samples in the clip, for progress reporting; 0 if `extractor` is null.
+//
+// # Safety
+//
+// `extractor` must come from [`tesla_sei_open`] and not yet be closed, or be null.
+uint64_t tesla_sei_total_samples(const struct TeslaSeiExtractor *extractor);
+
+// Close an extractor and free it. Null is ignored.
+//
+// # Safety
+//
+// `extractor` must come from [`tesla_sei_open`] and not be used again afterwards, or be null.
+void tesla_sei_close(struct TeslaSeiExtractor *extractor);
+
+// Description of the most recent error on this thread, or null if there was none. The string
+// stays valid until the next failing call on the same thread.
+const char *tesla_sei_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TESLA_SEI_H */
@@ -0,0 +1,239 @@
+//! Raw H.264/H.265 elementary streams (Annex B byte streams, e.g. `.h264`/`.h265` captures).
+//!
+//! MP4 samples carry length-prefixed NAL units; elementary streams instead separate them with
+//! start codes (`00 00 01` / `00 00 00 01`). Streams have no container, so events carry a NAL
+//! index and byte offset rather than sample indices and presentation times.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::info::VideoCodec;
+use crate::mp4::CodecConfig;
+use crate::pb;
+use crate::sei::{
+    decode_sei_from_nal, find_start_code, DecodeStats, UnknownField, DEFAULT_SEI_PAYLOAD_TYPES,
+};
+use crate::Error;
+
+/// One decoded SEI payload from an elementary stream.
+#[derive(Debug, Clone)]
+pub struct AnnexBEvent {
+    /// The 0-based index of the NAL unit in the stream.
+    pub nal_index: usize,
+    /// Absolute file offset of the NAL unit header (just past its start code).
+    pub file_offset: u64,
+    /// The decoded protobuf message.
+    pub metadata: pb::SeiMetadata,
+    /// UUID of the `user_data_unregistered` SEI payload, see [`crate::SeiEvent::uuid`].
+    pub uuid: Option<[u8; 16]>,
+    /// Fields newer than the schema, see [`crate::SeiEvent::unknown_fields`].
+    pub unknown_fields: Vec<UnknownField>,
+    /// The protobuf bytes `metadata` was decoded from, see [`crate::SeiEvent::raw_metadata`].
+    #[cfg(feature = "reflect")]
+    pub raw_metadata: Vec<u8>,
+}
+
+/// Guess the codec of an elementary stream from its file extension.
+///
+/// Recognises `.h264`/`.264`/`.avc` and `.h265`/`.265`/`.hevc` (case-insensitive).
+pub fn codec_from_extension(path: impl AsRef<Path>) -> Option<VideoCodec> {
+    let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "h264" | "264" | "avc" => Some(VideoCodec::Avc),
+        "h265" | "265" | "hevc" => Some(VideoCodec::Hevc),
+        _ => None,
+    }
+}
+
+/// Open an elementary stream file for extraction.
+pub fn annexb_extractor_from_path(
+    path: impl AsRef<Path>,
+    codec: VideoCodec,
+) -> Result<AnnexBExtractor<BufReader<File>>, Error> {
+    annexb_extractor_from_reader(BufReader::new(File::open(path)?), codec)
+}
+
+/// Create an extractor from any reader producing an Annex B byte stream.
+///
+/// Unlike the MP4 extractor this only needs `Read`, so pipes and sockets work too.
+pub fn annexb_extractor_from_reader<R: Read>(
+    reader: R,
+    codec: VideoCodec,
+) -> Result<AnnexBExtractor<R>, Error> {
+    let codec = match codec {
+        VideoCodec::Avc => CodecConfig::Avc { nal_len_size: 4 },
+        VideoCodec::Hevc => CodecConfig::Hevc { nal_len_size: 4 },
+        // AV1 streams aren't NAL-based.
+        VideoCodec::Av1 | VideoCodec::Unknown => {
+            return Err(Error::UnsupportedCodec {
+                codec: codec.to_string(),
+            });
+        }
+    };
+
+    Ok(AnnexBExtractor {
+        nals: NalReader::new(reader),
+        codec,
+        pending: VecDeque::new(),
+        decode_stats: DecodeStats::default(),
+    })
+}
+
+/// Streaming extractor over an Annex B elementary stream.
+///
+/// Implements `Iterator<Item = Result<AnnexBEvent, Error>>`; NAL units are split on start codes
+/// as the input is read, so memory use is bounded by the largest NAL unit.
+pub struct AnnexBExtractor<R: Read> {
+    nals: NalReader<R>,
+    codec: CodecConfig,
+    pending: VecDeque<AnnexBEvent>,
+    decode_stats: DecodeStats,
+}
+
+impl<R: Read> AnnexBExtractor<R> {
+    /// Which decode heuristics fired so far; `samples` counts NAL units.
+    pub fn decode_stats(&self) -> &DecodeStats {
+        &self.decode_stats
+    }
+
+    /// Pull the next decoded event, or `Ok(None)` at end of stream.
+    pub fn next_event(&mut self) -> Result<Option<AnnexBEvent>, Error> {
+        let mut decoded = Vec::new();
+        while self.pending.is_empty() {
+            let Some((nal_index, file_offset, nal)) = self.nals.next_nal()? else {
+                break;
+            };
+            self.decode_stats.samples += 1;
+            decode_sei_from_nal(
+                &self.codec,
+                nal,
+                Some(DEFAULT_SEI_PAYLOAD_TYPES),
+                &mut self.decode_stats,
+                &mut decoded,
+            );
+            self.pending.extend(decoded.drain(..).map(|d| AnnexBEvent {
+                nal_index,
+                file_offset,
+                metadata: d.metadata,
+                uuid: d.uuid,
+                unknown_fields: d.unknown_fields,
+                #[cfg(feature = "reflect")]
+                raw_metadata: d.raw_metadata,
+            }));
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+impl<R: Read> Iterator for AnnexBExtractor<R> {
+    type Item = Result<AnnexBEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+const READ_CHUNK: usize = 64 * 1024;
+
+// Splits a byte stream into NAL units on start codes.
+struct NalReader<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    // File offset of `buf[0]`.
+    buf_offset: u64,
+    // Where the next start code search resumes, so long NALs aren't rescanned on every read.
+    scan_from: usize,
+    // Length of the NAL returned by the previous call, dropped at the start of the next one.
+    returned: usize,
+    eof: bool,
+    nal_index: usize,
+}
+
+impl<R: Read> NalReader<R> {
+    fn new(reader: R) -> Self {
+        NalReader {
+            reader,
+            buf: Vec::new(),
+            buf_offset: 0,
+            scan_from: 0,
+            returned: 0,
+            eof: false,
+            nal_index: 0,
+        }
+    }
+
+    // Returns `(nal_index, file_offset, nal)`; the NAL excludes its start code and trailing zeros.
+    fn next_nal(&mut self) -> io::Result<Option<(usize, u64, &[u8])>> {
+        self.consume(self.returned);
+        self.returned = 0;
+
+        // Skip to the next start code; anything before the first one isn't a NAL unit.
+        let start = loop {
+            if let Some(i) = find_start_code(&self.buf, 0) {
+                break i + 3;
+            }
+            let keep = self.buf.len().min(2);
+            self.consume(self.buf.len() - keep);
+            if !self.fill()? {
+                return Ok(None);
+            }
+        };
+        self.consume(start);
+        self.scan_from = 0;
+
+        let end = loop {
+            if let Some(i) = find_start_code(&self.buf, self.scan_from) {
+                break i;
+            }
+            self.scan_from = self.buf.len().saturating_sub(2);
+            if !self.fill()? {
+                break self.buf.len();
+            }
+        };
+
+        let mut nal_end = end;
+        while nal_end > 0 && self.buf[nal_end - 1] == 0 {
+            nal_end -= 1;
+        }
+
+        let index = self.nal_index;
+        self.nal_index += 1;
+        let offset = self.buf_offset;
+
+        // The following start code (if any) stays in the buffer for the next call.
+        self.returned = end;
+        Ok(Some((index, offset, &self.buf[..nal_end])))
+    }
+
+    // Drop `n` bytes from the front of the buffer.
+    fn consume(&mut self, n: usize) {
+        self.buf.drain(..n);
+        self.buf_offset += n as u64;
+    }
+
+    // Append the next chunk; `false` at end of input.
+    fn fill(&mut self) -> io::Result<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+        let len = self.buf.len();
+        self.buf.resize(len + READ_CHUNK, 0);
+        let n = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    return Err(e);
+                }
+            }
+        };
+        self.buf.truncate(len + n);
+        if n == 0 {
+            self.eof = true;
+        }
+        Ok(n > 0)
+    }
+}
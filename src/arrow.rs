@@ -0,0 +1,183 @@
+//! Telemetry as Apache Arrow record batches (`arrow` feature).
+//!
+//! [`events_to_record_batch`] turns decoded events into one [`RecordBatch`] with a typed column
+//! per field, laid out as [`SEI_SCHEMA`] describes, ready to hand to DataFusion, Polars or
+//! pyarrow without another copy. [`metadata_to_record_batch`] does the same for bare decoded
+//! messages.
+
+use std::borrow::Borrow;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use arrow_array::builder::{BooleanBuilder, Float32Builder, Float64Builder, Int32Builder};
+use arrow_array::builder::{UInt32Builder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use crate::extract::SeiEvent;
+use crate::pb;
+
+/// Schema of the batches [`events_to_record_batch`] builds: where each frame sits in the clip,
+/// then the `SeiMetadata` fields in proto order, enums as their raw `i32` values.
+///
+/// `presentation_time_s` is null when the track has no usable timing; no other column has
+/// nulls.
+pub static SEI_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    use DataType::*;
+    let required = |name, data_type| Field::new(name, data_type, false);
+    Arc::new(Schema::new(vec![
+        required("sample_index", UInt64),
+        required("presentation_index", UInt64),
+        Field::new("presentation_time_s", Float64, true),
+        required("version", UInt32),
+        required("gear_state", Int32),
+        required("frame_seq_no", UInt64),
+        required("vehicle_speed_mps", Float32),
+        required("accelerator_pedal_position", Float32),
+        required("steering_wheel_angle", Float32),
+        required("blinker_on_left", Boolean),
+        required("blinker_on_right", Boolean),
+        required("brake_applied", Boolean),
+        required("autopilot_state", Int32),
+        required("latitude_deg", Float64),
+        required("longitude_deg", Float64),
+        required("heading_deg", Float64),
+        required("linear_acceleration_mps2_x", Float64),
+        required("linear_acceleration_mps2_y", Float64),
+        required("linear_acceleration_mps2_z", Float64),
+    ]))
+});
+
+/// Collect events into a single [`RecordBatch`] with the [`SEI_SCHEMA`] layout, one row per
+/// event in iteration order.
+///
+/// Accepts owned events or references, e.g. `extract_all(path)?` or `events.iter()`.
+pub fn events_to_record_batch<I>(events: I) -> Result<RecordBatch, ArrowError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<SeiEvent>,
+{
+    let events = events.into_iter();
+    let rows = events.size_hint().0;
+    let mut sample_index = UInt64Builder::with_capacity(rows);
+    let mut presentation_index = UInt64Builder::with_capacity(rows);
+    let mut metadata = MetadataColumns::with_capacity(rows);
+    for event in events {
+        let event = event.borrow();
+        sample_index.append_value(event.sample_index as u64);
+        presentation_index.append_value(event.presentation_index as u64);
+        metadata.append(event.presentation_time, &event.metadata);
+    }
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(sample_index.finish()),
+        Arc::new(presentation_index.finish()),
+    ];
+    columns.extend(metadata.finish());
+    RecordBatch::try_new(SEI_SCHEMA.clone(), columns)
+}
+
+/// Collect decoded messages and their presentation times into a [`RecordBatch`] laid out like
+/// [`SEI_SCHEMA`] without the `sample_index` and `presentation_index` columns, for records that
+/// no longer know where they sat in a clip (raw streams, merged or thinned exports).
+pub fn metadata_to_record_batch<I, M>(records: I) -> Result<RecordBatch, ArrowError>
+where
+    I: IntoIterator<Item = (Option<Duration>, M)>,
+    M: Borrow<pb::SeiMetadata>,
+{
+    let records = records.into_iter();
+    let mut metadata = MetadataColumns::with_capacity(records.size_hint().0);
+    for (presentation_time, m) in records {
+        metadata.append(presentation_time, m.borrow());
+    }
+    let schema = SEI_SCHEMA.project(&(2..SEI_SCHEMA.fields().len()).collect::<Vec<_>>())?;
+    RecordBatch::try_new(Arc::new(schema), metadata.finish())
+}
+
+// Builders for `presentation_time_s` and the `SeiMetadata` columns, in `SEI_SCHEMA` order.
+struct MetadataColumns {
+    presentation_time: Float64Builder,
+    version: UInt32Builder,
+    gear_state: Int32Builder,
+    frame_seq_no: UInt64Builder,
+    speed: Float32Builder,
+    accelerator: Float32Builder,
+    steering: Float32Builder,
+    blinker_left: BooleanBuilder,
+    blinker_right: BooleanBuilder,
+    brake: BooleanBuilder,
+    autopilot: Int32Builder,
+    latitude: Float64Builder,
+    longitude: Float64Builder,
+    heading: Float64Builder,
+    accel_x: Float64Builder,
+    accel_y: Float64Builder,
+    accel_z: Float64Builder,
+}
+
+impl MetadataColumns {
+    fn with_capacity(rows: usize) -> Self {
+        Self {
+            presentation_time: Float64Builder::with_capacity(rows),
+            version: UInt32Builder::with_capacity(rows),
+            gear_state: Int32Builder::with_capacity(rows),
+            frame_seq_no: UInt64Builder::with_capacity(rows),
+            speed: Float32Builder::with_capacity(rows),
+            accelerator: Float32Builder::with_capacity(rows),
+            steering: Float32Builder::with_capacity(rows),
+            blinker_left: BooleanBuilder::with_capacity(rows),
+            blinker_right: BooleanBuilder::with_capacity(rows),
+            brake: BooleanBuilder::with_capacity(rows),
+            autopilot: Int32Builder::with_capacity(rows),
+            latitude: Float64Builder::with_capacity(rows),
+            longitude: Float64Builder::with_capacity(rows),
+            heading: Float64Builder::with_capacity(rows),
+            accel_x: Float64Builder::with_capacity(rows),
+            accel_y: Float64Builder::with_capacity(rows),
+            accel_z: Float64Builder::with_capacity(rows),
+        }
+    }
+
+    fn append(&mut self, presentation_time: Option<Duration>, m: &pb::SeiMetadata) {
+        self.presentation_time
+            .append_option(presentation_time.map(|t| t.as_secs_f64()));
+        self.version.append_value(m.version);
+        self.gear_state.append_value(m.gear_state);
+        self.frame_seq_no.append_value(m.frame_seq_no);
+        self.speed.append_value(m.vehicle_speed_mps);
+        self.accelerator.append_value(m.accelerator_pedal_position);
+        self.steering.append_value(m.steering_wheel_angle);
+        self.blinker_left.append_value(m.blinker_on_left);
+        self.blinker_right.append_value(m.blinker_on_right);
+        self.brake.append_value(m.brake_applied);
+        self.autopilot.append_value(m.autopilot_state);
+        self.latitude.append_value(m.latitude_deg);
+        self.longitude.append_value(m.longitude_deg);
+        self.heading.append_value(m.heading_deg);
+        self.accel_x.append_value(m.linear_acceleration_mps2_x);
+        self.accel_y.append_value(m.linear_acceleration_mps2_y);
+        self.accel_z.append_value(m.linear_acceleration_mps2_z);
+    }
+
+    fn finish(mut self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(self.presentation_time.finish()),
+            Arc::new(self.version.finish()),
+            Arc::new(self.gear_state.finish()),
+            Arc::new(self.frame_seq_no.finish()),
+            Arc::new(self.speed.finish()),
+            Arc::new(self.accelerator.finish()),
+            Arc::new(self.steering.finish()),
+            Arc::new(self.blinker_left.finish()),
+            Arc::new(self.blinker_right.finish()),
+            Arc::new(self.brake.finish()),
+            Arc::new(self.autopilot.finish()),
+            Arc::new(self.latitude.finish()),
+            Arc::new(self.longitude.finish()),
+            Arc::new(self.heading.finish()),
+            Arc::new(self.accel_x.finish()),
+            Arc::new(self.accel_y.finish()),
+            Arc::new(self.accel_z.finish()),
+        ]
+    }
+}
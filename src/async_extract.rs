@@ -1,12 +1,28 @@
 #![cfg(feature = "async")]
 
-use std::io::{Read, Seek};
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use tokio::sync::mpsc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use tokio::sync::{mpsc, watch, Notify, Semaphore};
+use tokio::task::JoinHandle;
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
 
-use crate::extract::{extractor_from_path, extractor_from_reader, SeiEvent};
+use crate::clip::clip_camera;
+use crate::extract::{
+    extractor_from_path, extractor_from_reader, extractor_from_reader_with_options, ExtractOptions,
+    SeiEvent, SeiExtractor,
+};
+use crate::info::{Resolution, TrackInfo};
+use crate::sei::DecodeStats;
 use crate::Error;
 
 /// Create a Tokio `Stream` of per-sample/per-frame SEI events from an MP4 file on disk.
@@ -108,3 +124,951 @@ where
 
     ReceiverStream::new(rx)
 }
+
+/// How a stream started with [`stream_from_path_with_handle`] or
+/// [`stream_from_reader_with_handle`] ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionOutcome {
+    /// Every sample was read.
+    Completed,
+    /// Stopped by [`ExtractionHandle::abort`] or because the stream was dropped.
+    Cancelled,
+    /// Stopped at an error, which was also sent on the stream; this is its message.
+    Failed(String),
+}
+
+/// Controls the blocking task behind a stream from [`stream_from_path_with_handle`] or
+/// [`stream_from_reader_with_handle`]. Dropping the handle leaves the task running.
+pub struct ExtractionHandle {
+    cancel: Arc<Cancel>,
+    task: JoinHandle<ExtractionOutcome>,
+}
+
+struct Cancel {
+    requested: AtomicBool,
+    notify: Notify,
+}
+
+impl ExtractionHandle {
+    /// Stop extraction. The task notices before reading the next sample, or right away if it is
+    /// waiting for room in the channel, and no further events are sent.
+    pub fn abort(&self) {
+        self.cancel.requested.store(true, Ordering::SeqCst);
+        self.cancel.notify.notify_waiters();
+    }
+
+    /// Whether the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the task to finish and report how it ended.
+    pub async fn outcome(self) -> ExtractionOutcome {
+        match self.task.await {
+            Ok(outcome) => outcome,
+            // The task panicked.
+            Err(e) => ExtractionOutcome::Failed(e.to_string()),
+        }
+    }
+}
+
+/// Like [`stream_from_path_from_sample`], plus a handle to abort extraction and learn whether
+/// it completed. Unlike the plain stream, extraction stops at the first error.
+pub fn stream_from_path_with_handle(
+    path: impl Into<PathBuf>,
+    start_sample: usize,
+    buffer: usize,
+) -> (ReceiverStream<Result<SeiEvent, Error>>, ExtractionHandle) {
+    let path = path.into();
+    spawn_with_handle(move || extractor_from_path(path), start_sample, buffer)
+}
+
+/// Like [`stream_from_reader_from_sample`], plus a handle to abort extraction and learn whether
+/// it completed. Unlike the plain stream, extraction stops at the first error.
+pub fn stream_from_reader_with_handle<R>(
+    reader: R,
+    start_sample: usize,
+    buffer: usize,
+) -> (ReceiverStream<Result<SeiEvent, Error>>, ExtractionHandle)
+where
+    R: Read + Seek + Send + 'static,
+{
+    spawn_with_handle(move || extractor_from_reader(reader), start_sample, buffer)
+}
+
+fn spawn_with_handle<R, F>(
+    open: F,
+    start_sample: usize,
+    buffer: usize,
+) -> (ReceiverStream<Result<SeiEvent, Error>>, ExtractionHandle)
+where
+    R: Read + Seek,
+    F: FnOnce() -> Result<SeiExtractor<R>, Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    let cancel = Arc::new(Cancel {
+        requested: AtomicBool::new(false),
+        notify: Notify::new(),
+    });
+    let worker_cancel = Arc::clone(&cancel);
+    let runtime = tokio::runtime::Handle::current();
+
+    let task = tokio::task::spawn_blocking(move || {
+        let cancel = worker_cancel;
+        // Send one item, giving up if extraction is aborted while the channel is full.
+        let send = |item: Result<SeiEvent, Error>| -> bool {
+            runtime.block_on(async {
+                let mut notified = pin!(cancel.notify.notified());
+                notified.as_mut().enable();
+                if cancel.requested.load(Ordering::SeqCst) {
+                    return false;
+                }
+                let mut sent = pin!(tx.send(item));
+                poll_fn(|cx| {
+                    if let Poll::Ready(result) = sent.as_mut().poll(cx) {
+                        return Poll::Ready(result.is_ok());
+                    }
+                    notified.as_mut().poll(cx).map(|()| false)
+                })
+                .await
+            })
+        };
+        let fail = |err: Error| {
+            let message = err.to_string();
+            if send(Err(err)) {
+                ExtractionOutcome::Failed(message)
+            } else {
+                ExtractionOutcome::Cancelled
+            }
+        };
+
+        let mut extractor = match open().and_then(|mut e| e.seek_sample(start_sample).map(|()| e)) {
+            Ok(e) => e,
+            Err(err) => return fail(err),
+        };
+        loop {
+            if cancel.requested.load(Ordering::SeqCst) {
+                return ExtractionOutcome::Cancelled;
+            }
+            match extractor.next() {
+                None => return ExtractionOutcome::Completed,
+                Some(Ok(event)) => {
+                    if !send(Ok(event)) {
+                        return ExtractionOutcome::Cancelled;
+                    }
+                }
+                Some(Err(err)) => return fail(err),
+            }
+        }
+    });
+
+    (ReceiverStream::new(rx), ExtractionHandle { cancel, task })
+}
+
+/// How far a stream from [`stream_from_path_with_progress`] has read, sent on a
+/// `watch` channel after each sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Samples read so far, counting any skipped by `start_sample`.
+    pub samples_processed: usize,
+    /// Samples in the selected track; 0 until the file has been opened.
+    pub total_samples: usize,
+}
+
+impl Progress {
+    /// `samples_processed / total_samples` in `0.0..=1.0`; 0 before the file is opened.
+    pub fn fraction(&self) -> f64 {
+        if self.total_samples == 0 {
+            return 0.0;
+        }
+        self.samples_processed as f64 / self.total_samples as f64
+    }
+}
+
+/// Like [`stream_from_path_from_sample`], plus a `watch` channel reporting samples processed
+/// out of the total, e.g. for a progress bar on long clips.
+///
+/// The progress value updates after every sample, including those without telemetry; the
+/// receiver's `changed()` returns an error once extraction has ended. Unlike the plain stream,
+/// extraction stops at the first error.
+pub fn stream_from_path_with_progress(
+    path: impl Into<PathBuf>,
+    start_sample: usize,
+    buffer: usize,
+) -> (
+    ReceiverStream<Result<SeiEvent, Error>>,
+    watch::Receiver<Progress>,
+) {
+    let path = path.into();
+    spawn_with_progress(move || extractor_from_path(path), start_sample, buffer)
+}
+
+/// Like [`stream_from_reader_from_sample`], plus a `watch` channel reporting progress; see
+/// [`stream_from_path_with_progress`].
+pub fn stream_from_reader_with_progress<R>(
+    reader: R,
+    start_sample: usize,
+    buffer: usize,
+) -> (
+    ReceiverStream<Result<SeiEvent, Error>>,
+    watch::Receiver<Progress>,
+)
+where
+    R: Read + Seek + Send + 'static,
+{
+    spawn_with_progress(move || extractor_from_reader(reader), start_sample, buffer)
+}
+
+fn spawn_with_progress<R, F>(
+    open: F,
+    start_sample: usize,
+    buffer: usize,
+) -> (
+    ReceiverStream<Result<SeiEvent, Error>>,
+    watch::Receiver<Progress>,
+)
+where
+    R: Read + Seek,
+    F: FnOnce() -> Result<SeiExtractor<R>, Error> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+    let (progress_tx, progress_rx) = watch::channel(Progress::default());
+
+    tokio::task::spawn_blocking(move || {
+        let mut extractor = match open().and_then(|mut e| e.seek_sample(start_sample).map(|()| e)) {
+            Ok(e) => e,
+            Err(err) => {
+                let _ = tx.blocking_send(Err(err));
+                return;
+            }
+        };
+        let total_samples = extractor.total_samples();
+        progress_tx.send_replace(Progress {
+            samples_processed: start_sample,
+            total_samples,
+        });
+
+        for sample_index in start_sample..total_samples {
+            match extractor.read_sample_events(sample_index) {
+                Ok(events) => {
+                    for event in events {
+                        if tx.blocking_send(Ok(event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => {
+                    let _ = tx.blocking_send(Err(err));
+                    return;
+                }
+            }
+            progress_tx.send_replace(Progress {
+                samples_processed: sample_index + 1,
+                total_samples,
+            });
+        }
+    });
+
+    (ReceiverStream::new(rx), progress_rx)
+}
+
+/// Options for [`stream_from_path_metered`] and [`stream_from_reader_metered`].
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    /// First sample to extract.
+    pub start_sample: usize,
+    /// Events buffered between the worker and the consumer before the worker waits.
+    pub buffer: usize,
+    /// Upper bound for adaptive buffering. Above `buffer`, the buffer doubles (up to this) each
+    /// time the worker finds it full, absorbing consumers that stall now and then. Defaults to
+    /// `buffer`, i.e. a fixed buffer.
+    pub max_buffer: usize,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            start_sample: 0,
+            buffer: 64,
+            max_buffer: 64,
+        }
+    }
+}
+
+/// Backpressure counters of a [`MeteredStream`] at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BackpressureStats {
+    /// Items sent by the worker and not yet taken by the consumer.
+    pub queued: usize,
+    /// Current buffer size; grows up to [`StreamOptions::max_buffer`].
+    pub capacity: usize,
+    /// Items sent so far.
+    pub sent: u64,
+    /// Sends that found the buffer full and had to wait for the consumer.
+    pub blocked_sends: u64,
+    /// Total time the worker spent waiting for room.
+    pub blocked_time: Duration,
+    /// Items the worker produced but couldn't deliver because the stream was dropped.
+    pub dropped_sends: u64,
+}
+
+#[derive(Default)]
+struct Backpressure {
+    queued: AtomicUsize,
+    capacity: AtomicUsize,
+    sent: AtomicU64,
+    blocked_sends: AtomicU64,
+    blocked_nanos: AtomicU64,
+    dropped_sends: AtomicU64,
+}
+
+/// Live backpressure counters of a [`MeteredStream`]; cheap to clone and usable after the
+/// stream is gone, e.g. from a metrics exporter.
+#[derive(Clone)]
+pub struct StreamMetrics(Arc<Backpressure>);
+
+impl StreamMetrics {
+    /// Current values of the counters.
+    pub fn snapshot(&self) -> BackpressureStats {
+        let m = &self.0;
+        BackpressureStats {
+            queued: m.queued.load(Ordering::Relaxed),
+            capacity: m.capacity.load(Ordering::Relaxed),
+            sent: m.sent.load(Ordering::Relaxed),
+            blocked_sends: m.blocked_sends.load(Ordering::Relaxed),
+            blocked_time: Duration::from_nanos(m.blocked_nanos.load(Ordering::Relaxed)),
+            dropped_sends: m.dropped_sends.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A `Stream` of events from a blocking worker, with backpressure metrics; created by
+/// [`stream_from_path_metered`] or [`stream_from_reader_metered`].
+pub struct MeteredStream {
+    rx: mpsc::UnboundedReceiver<Result<SeiEvent, Error>>,
+    // One permit per free buffer slot.
+    slots: Arc<Semaphore>,
+    metrics: StreamMetrics,
+}
+
+impl MeteredStream {
+    /// The stream's backpressure counters.
+    pub fn metrics(&self) -> StreamMetrics {
+        self.metrics.clone()
+    }
+}
+
+impl Stream for MeteredStream {
+    type Item = Result<SeiEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = self.rx.poll_recv(cx);
+        if let Poll::Ready(Some(_)) = item {
+            self.metrics.0.queued.fetch_sub(1, Ordering::Relaxed);
+            self.slots.add_permits(1);
+        }
+        item
+    }
+}
+
+impl Drop for MeteredStream {
+    fn drop(&mut self) {
+        // Wake a worker waiting for room so it notices the stream is gone.
+        self.slots.close();
+    }
+}
+
+/// Like [`stream_from_path_from_sample`], but with backpressure metrics (so services can spot
+/// slow consumers) and optionally adaptive buffering; see [`StreamOptions`].
+pub fn stream_from_path_metered(path: impl Into<PathBuf>, opts: &StreamOptions) -> MeteredStream {
+    let path = path.into();
+    spawn_metered(move || extractor_from_path(path), opts)
+}
+
+/// Like [`stream_from_reader_from_sample`], with the metrics and buffering of
+/// [`stream_from_path_metered`].
+pub fn stream_from_reader_metered<R>(reader: R, opts: &StreamOptions) -> MeteredStream
+where
+    R: Read + Seek + Send + 'static,
+{
+    spawn_metered(move || extractor_from_reader(reader), opts)
+}
+
+fn spawn_metered<R, F>(open: F, opts: &StreamOptions) -> MeteredStream
+where
+    R: Read + Seek,
+    F: FnOnce() -> Result<SeiExtractor<R>, Error> + Send + 'static,
+{
+    let buffer = opts.buffer.max(1);
+    let max_buffer = opts.max_buffer.max(buffer);
+    let start_sample = opts.start_sample;
+    let (tx, rx) = mpsc::unbounded_channel();
+    let slots = Arc::new(Semaphore::new(buffer));
+    let metrics = StreamMetrics(Arc::new(Backpressure::default()));
+    metrics.0.capacity.store(buffer, Ordering::Relaxed);
+
+    let worker_slots = Arc::clone(&slots);
+    let m = Arc::clone(&metrics.0);
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        // Wait for a free slot, growing the buffer first if allowed; false once the stream is
+        // dropped.
+        let reserve = || -> bool {
+            if let Ok(permit) = worker_slots.try_acquire() {
+                permit.forget();
+                return true;
+            }
+            let capacity = m.capacity.load(Ordering::Relaxed);
+            if capacity < max_buffer {
+                let grow = capacity.min(max_buffer - capacity);
+                m.capacity.store(capacity + grow, Ordering::Relaxed);
+                worker_slots.add_permits(grow);
+                // The new room means this send doesn't wait.
+                if let Ok(permit) = worker_slots.try_acquire() {
+                    permit.forget();
+                    return true;
+                }
+            }
+            m.blocked_sends.fetch_add(1, Ordering::Relaxed);
+            let waited = Instant::now();
+            let acquired = runtime.block_on(worker_slots.acquire());
+            m.blocked_nanos
+                .fetch_add(waited.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            match acquired {
+                Ok(permit) => {
+                    permit.forget();
+                    true
+                }
+                Err(_) => false,
+            }
+        };
+        let send = |item: Result<SeiEvent, Error>| -> bool {
+            if !reserve() {
+                m.dropped_sends.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            m.queued.fetch_add(1, Ordering::Relaxed);
+            if tx.send(item).is_err() {
+                m.queued.fetch_sub(1, Ordering::Relaxed);
+                m.dropped_sends.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+            m.sent.fetch_add(1, Ordering::Relaxed);
+            true
+        };
+
+        let mut extractor = match open().and_then(|mut e| e.seek_sample(start_sample).map(|()| e)) {
+            Ok(e) => e,
+            Err(err) => {
+                send(Err(err));
+                return;
+            }
+        };
+        for item in &mut extractor {
+            if !send(item) {
+                break;
+            }
+        }
+    });
+
+    MeteredStream { rx, slots, metrics }
+}
+
+/// The events of one fixed-length slice of the clip, from [`SeiStreamExt::time_windows`].
+#[derive(Debug, Clone)]
+pub struct TimeWindow {
+    /// Start of the window, a multiple of the window length since the start of the clip.
+    pub start: Duration,
+    /// Length of the window.
+    pub duration: Duration,
+    /// Lowest `vehicle_speed_mps` in the window.
+    pub min_speed_mps: f32,
+    /// Highest `vehicle_speed_mps` in the window.
+    pub max_speed_mps: f32,
+    /// Mean `vehicle_speed_mps` over the window's events.
+    pub mean_speed_mps: f32,
+    /// The window's events, in stream order; never empty.
+    pub events: Vec<SeiEvent>,
+}
+
+impl TimeWindow {
+    fn new(start: Duration, duration: Duration, event: SeiEvent) -> Self {
+        let speed = event.metadata.vehicle_speed_mps;
+        TimeWindow {
+            start,
+            duration,
+            min_speed_mps: speed,
+            max_speed_mps: speed,
+            mean_speed_mps: speed,
+            events: vec![event],
+        }
+    }
+
+    fn push(&mut self, event: SeiEvent) {
+        let speed = event.metadata.vehicle_speed_mps;
+        self.min_speed_mps = self.min_speed_mps.min(speed);
+        self.max_speed_mps = self.max_speed_mps.max(speed);
+        let n = self.events.len() as f64;
+        self.mean_speed_mps = ((self.mean_speed_mps as f64 * n + speed as f64) / (n + 1.0)) as f32;
+        self.events.push(event);
+    }
+}
+
+/// Combinators for streams of [`SeiEvent`]s, such as the ones returned by [`stream_from_path`]
+/// and [`AsyncSeiExtractor::into_stream`].
+pub trait SeiStreamExt: Stream<Item = Result<SeiEvent, Error>> + Unpin + Sized {
+    /// Group events into consecutive `window`-long slices of presentation time (e.g. one-second
+    /// buckets for a dashboard), each with speed statistics.
+    ///
+    /// A window is yielded once an event past its end arrives, or when the stream ends; windows
+    /// without events are skipped. Events that arrive late (B-frame reordering) or have no
+    /// presentation time join the current window. Errors are passed through as they come.
+    ///
+    /// # Panics
+    ///
+    /// If `window` is zero.
+    fn time_windows(self, window: Duration) -> TimeWindows<Self> {
+        assert!(!window.is_zero(), "time window must be non-zero");
+        TimeWindows {
+            stream: Some(self),
+            window,
+            current: None,
+        }
+    }
+}
+
+impl<S: Stream<Item = Result<SeiEvent, Error>> + Unpin> SeiStreamExt for S {}
+
+/// Stream of [`TimeWindow`]s, created by [`SeiStreamExt::time_windows`].
+pub struct TimeWindows<S> {
+    // `None` once the inner stream has ended.
+    stream: Option<S>,
+    window: Duration,
+    // The window being filled.
+    current: Option<TimeWindow>,
+}
+
+impl<S: Stream<Item = Result<SeiEvent, Error>> + Unpin> Stream for TimeWindows<S> {
+    type Item = Result<TimeWindow, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            let Some(stream) = this.stream.as_mut() else {
+                return Poll::Ready(this.current.take().map(Ok));
+            };
+            let event = match Pin::new(stream).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => {
+                    this.stream = None;
+                    continue;
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(Some(Ok(event))) => event,
+            };
+
+            let window = this.window.as_nanos();
+            let start = event
+                .presentation_time
+                .map(|t| Duration::from_nanos((t.as_nanos() - t.as_nanos() % window) as u64));
+            match (&mut this.current, start) {
+                (Some(w), start) if start.is_none_or(|s| s <= w.start) => w.push(event),
+                (slot, start) => {
+                    let next = TimeWindow::new(start.unwrap_or_default(), this.window, event);
+                    if let Some(done) = slot.replace(next) {
+                        return Poll::Ready(Some(Ok(done)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One event of a [`merge_cameras`] stream, tagged with the camera it came from.
+#[derive(Debug, Clone)]
+pub struct CameraEvent {
+    /// Index of the camera's file in the list passed to [`merge_cameras`].
+    pub camera: usize,
+    /// Camera name from the TeslaCam file name (`front`, `back`, `left_repeater`, ...), or the
+    /// whole file stem for other names.
+    pub camera_name: Arc<str>,
+    pub event: SeiEvent,
+}
+
+/// Extract several camera files of the same recording concurrently and merge their events into
+/// one stream ordered by `frame_seq_no`.
+///
+/// Each file gets its own extractor on a blocking thread, as with [`stream_from_path`], and
+/// `buffer` is each one's channel capacity. The merge waits until every camera still running
+/// has an event queued, then yields the lowest frame sequence number (ties in file order), so
+/// the output stays ordered even when one camera's file is slower to read.
+///
+/// An error ends that camera's events: it is yielded as an `Err` item and the other cameras
+/// carry on.
+pub fn merge_cameras(
+    paths: impl IntoIterator<Item = impl Into<PathBuf>>,
+    buffer: usize,
+) -> MergedCameraStream {
+    let cameras = paths
+        .into_iter()
+        .map(|path| {
+            let path = path.into();
+            MergeInput {
+                name: camera_name(&path).into(),
+                stream: Some(stream_from_path(path, buffer)),
+                head: None,
+            }
+        })
+        .collect();
+    MergedCameraStream { cameras }
+}
+
+/// Stream of [`CameraEvent`]s, created by [`merge_cameras`].
+pub struct MergedCameraStream {
+    cameras: Vec<MergeInput>,
+}
+
+struct MergeInput {
+    name: Arc<str>,
+    // `None` once the camera's events have ended.
+    stream: Option<ReceiverStream<Result<SeiEvent, Error>>>,
+    // The camera's next event, waiting for the others to catch up.
+    head: Option<SeiEvent>,
+}
+
+impl Stream for MergedCameraStream {
+    type Item = Result<CameraEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut pending = false;
+        for input in &mut self.cameras {
+            let Some(stream) = input.stream.as_mut().filter(|_| input.head.is_none()) else {
+                continue;
+            };
+            match Pin::new(stream).poll_next(cx) {
+                Poll::Pending => pending = true,
+                Poll::Ready(None) => input.stream = None,
+                Poll::Ready(Some(Err(e))) => {
+                    input.stream = None;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(Some(Ok(event))) => input.head = Some(event),
+            }
+        }
+        if pending {
+            return Poll::Pending;
+        }
+
+        let next = self
+            .cameras
+            .iter()
+            .enumerate()
+            .filter_map(|(i, input)| Some((input.head.as_ref()?.metadata.frame_seq_no, i)))
+            .min();
+        let Some((_, camera)) = next else {
+            return Poll::Ready(None);
+        };
+        let input = &mut self.cameras[camera];
+        Poll::Ready(Some(Ok(CameraEvent {
+            camera,
+            camera_name: input.name.clone(),
+            event: input.head.take().expect("head checked above"),
+        })))
+    }
+}
+
+// `front` from `2024-01-01_12-00-00-front.mp4`; the whole stem for other names.
+fn camera_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match clip_camera(&stem) {
+        Some(camera) => camera.to_string(),
+        None => stem.into_owned(),
+    }
+}
+
+/// Extractor over a Tokio `AsyncRead + AsyncSeek` source, such as `tokio::fs::File` or a ranged
+/// network reader.
+///
+/// Unlike the `stream_from_*` functions it runs entirely on the calling task, with no blocking
+/// thread. Opening reads every top-level box except `mdat` (the MP4 index); after that each
+/// sample is read on demand, so memory use doesn't grow with the clip.
+pub struct AsyncSeiExtractor<R> {
+    reader: R,
+    // The sync extractor over an in-memory image holding the index and the current sample.
+    inner: SeiExtractor<MemoryImage>,
+    next_sample_index: usize,
+    pending: VecDeque<SeiEvent>,
+}
+
+/// Open an MP4 file on disk with `tokio::fs`.
+pub async fn async_extractor_from_path(
+    path: impl AsRef<Path>,
+) -> Result<AsyncSeiExtractor<tokio::fs::File>, Error> {
+    let file = tokio::fs::File::open(path).await?;
+    async_extractor_from_reader(file).await
+}
+
+/// Create an async extractor from any `AsyncRead + AsyncSeek` source.
+pub async fn async_extractor_from_reader<R: AsyncRead + AsyncSeek + Unpin>(
+    reader: R,
+) -> Result<AsyncSeiExtractor<R>, Error> {
+    async_extractor_from_reader_with_options(reader, &ExtractOptions::default()).await
+}
+
+/// Create an async extractor with non-default options.
+pub async fn async_extractor_from_reader_with_options<R: AsyncRead + AsyncSeek + Unpin>(
+    mut reader: R,
+    opts: &ExtractOptions,
+) -> Result<AsyncSeiExtractor<R>, Error> {
+    let image = MemoryImage::load_index(&mut reader).await?;
+    Ok(AsyncSeiExtractor {
+        reader,
+        inner: extractor_from_reader_with_options(image, opts)?,
+        next_sample_index: 0,
+        pending: VecDeque::new(),
+    })
+}
+
+/// Decode every event of the MP4 at `path` in one await, e.g. for a web handler that needs the
+/// whole clip's telemetry before responding. The async counterpart of
+/// [`extract_all`](crate::extract::extract_all); runs on the calling task.
+pub async fn extract_all_async(path: impl AsRef<Path>) -> Result<Vec<SeiEvent>, Error> {
+    let mut extractor = async_extractor_from_path(path).await?;
+    let mut events = Vec::new();
+    while let Some(event) = extractor.next_event().await? {
+        events.push(event);
+    }
+    Ok(events)
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncSeiExtractor<R> {
+    /// Total number of MP4 samples in the selected track.
+    pub fn total_samples(&self) -> usize {
+        self.inner.total_samples()
+    }
+
+    /// Movie duration from `mvhd`; see [`SeiExtractor::duration`].
+    pub fn duration(&self) -> Option<Duration> {
+        self.inner.duration()
+    }
+
+    /// Codec, profile and frame size of the selected track; see [`SeiExtractor::track_info`].
+    pub fn track_info(&self) -> TrackInfo {
+        self.inner.track_info()
+    }
+
+    /// Frame size of the selected track.
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.inner.resolution()
+    }
+
+    /// Presentation time of `sample_index`; see [`SeiExtractor::presentation_time`].
+    pub fn presentation_time(&self, sample_index: usize) -> Option<Duration> {
+        self.inner.presentation_time(sample_index)
+    }
+
+    /// Which decode heuristics fired so far; see [`SeiExtractor::decode_stats`].
+    pub fn decode_stats(&self) -> &DecodeStats {
+        self.inner.decode_stats()
+    }
+
+    /// The next decoded event, or `None` once every sample has been read.
+    pub async fn next_event(&mut self) -> Result<Option<SeiEvent>, Error> {
+        while self.pending.is_empty() && self.next_sample_index < self.total_samples() {
+            let events = self.read_sample_events(self.next_sample_index).await?;
+            self.pending = events.into();
+            self.next_sample_index += 1;
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    /// Position the extractor so the next events come from `sample_index`; see
+    /// [`SeiExtractor::seek_sample`].
+    pub fn seek_sample(&mut self, sample_index: usize) -> Result<(), Error> {
+        if sample_index > self.total_samples() {
+            return Err(Error::SampleIndexOutOfRange {
+                sample_index,
+                total_samples: self.total_samples(),
+            });
+        }
+        self.next_sample_index = sample_index;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Decode the events of an arbitrary sample without moving the cursor; see
+    /// [`SeiExtractor::read_sample_events`].
+    pub async fn read_sample_events(
+        &mut self,
+        sample_index: usize,
+    ) -> Result<Vec<SeiEvent>, Error> {
+        let Some((offset, size)) = self.inner.sample_location(sample_index) else {
+            return Err(Error::SampleIndexOutOfRange {
+                sample_index,
+                total_samples: self.total_samples(),
+            });
+        };
+        let mut data = vec![0u8; size as usize];
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        self.reader.read_exact(&mut data).await?;
+        self.inner.reader_mut().sample = Some((offset, data));
+        self.inner.read_sample_events(sample_index)
+    }
+
+    /// Turn the extractor into a `Stream` of events.
+    pub fn into_stream(self) -> AsyncSeiStream<R>
+    where
+        R: Send + 'static,
+    {
+        AsyncSeiStream {
+            state: StreamState::Idle(Box::new(self)),
+        }
+    }
+}
+
+type NextEvent<R> =
+    Pin<Box<dyn Future<Output = (AsyncSeiExtractor<R>, Option<Result<SeiEvent, Error>>)> + Send>>;
+
+/// `Stream` of events from an [`AsyncSeiExtractor`], created by
+/// [`AsyncSeiExtractor::into_stream`]. Ends after the first error.
+pub struct AsyncSeiStream<R> {
+    state: StreamState<R>,
+}
+
+enum StreamState<R> {
+    Idle(Box<AsyncSeiExtractor<R>>),
+    Reading(NextEvent<R>),
+    Done,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin + Send + 'static> Stream for AsyncSeiStream<R> {
+    type Item = Result<SeiEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match std::mem::replace(&mut self.state, StreamState::Done) {
+                StreamState::Idle(mut extractor) => {
+                    self.state = StreamState::Reading(Box::pin(async move {
+                        let item = extractor.next_event().await.transpose();
+                        (*extractor, item)
+                    }));
+                }
+                StreamState::Reading(mut next) => match next.as_mut().poll(cx) {
+                    Poll::Pending => {
+                        self.state = StreamState::Reading(next);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready((extractor, item)) => {
+                        if matches!(item, Some(Ok(_))) {
+                            self.state = StreamState::Idle(Box::new(extractor));
+                        }
+                        return Poll::Ready(item);
+                    }
+                },
+                StreamState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+// Largest `moov` loaded into a `MemoryImage`. Even an hour of 60 fps video indexes in a few MiB.
+const MAX_INDEX_BOX_LEN: u64 = 64 << 20;
+
+// A sparse, in-memory copy of an MP4: `ftyp` and `moov`, the headers of the other top-level
+// boxes, and the sample being decoded. Reading anything else fails.
+struct MemoryImage {
+    len: u64,
+    pos: u64,
+    boxes: Vec<(u64, Vec<u8>)>,
+    sample: Option<(u64, Vec<u8>)>,
+}
+
+impl MemoryImage {
+    async fn load_index<R: AsyncRead + AsyncSeek + Unpin>(reader: &mut R) -> io::Result<Self> {
+        let len = reader.seek(SeekFrom::End(0)).await?;
+        let mut boxes = Vec::new();
+        let mut pos = 0u64;
+        while pos + 8 <= len {
+            let mut header = vec![0u8; (len - pos).min(16) as usize];
+            reader.seek(SeekFrom::Start(pos)).await?;
+            reader.read_exact(&mut header).await?;
+            let size32 = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+            let (size, header_len) = match size32 {
+                0 => (len - pos, 8),
+                1 if header.len() == 16 => {
+                    (u64::from_be_bytes(header[8..16].try_into().unwrap()), 16)
+                }
+                _ => (size32, 8),
+            };
+            // Malformed: keep the header so the parser reports it, and stop.
+            if size < header_len {
+                boxes.push((pos, header));
+                break;
+            }
+            let end = pos.saturating_add(size).min(len);
+            if !matches!(&header[4..8], b"ftyp" | b"moov") {
+                header.truncate(header_len as usize);
+            } else if end - pos > MAX_INDEX_BOX_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{} box of {} bytes exceeds the {MAX_INDEX_BOX_LEN}-byte limit",
+                        String::from_utf8_lossy(&header[4..8]),
+                        end - pos
+                    ),
+                ));
+            } else {
+                let read = header.len().min((end - pos) as usize);
+                header.resize((end - pos) as usize, 0);
+                reader.read_exact(&mut header[read..]).await?;
+            }
+            boxes.push((pos, header));
+            pos = end;
+        }
+        Ok(MemoryImage {
+            len,
+            pos: 0,
+            boxes,
+            sample: None,
+        })
+    }
+}
+
+impl Read for MemoryImage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        let pos = self.pos;
+        let range = self
+            .sample
+            .iter()
+            .chain(&self.boxes)
+            .find(|(start, data)| pos >= *start && pos < start + data.len() as u64);
+        let Some((start, data)) = range else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("byte {pos} was not loaded"),
+            ));
+        };
+        let available = &data[(pos - start) as usize..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for MemoryImage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = new.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of file")
+        })?;
+        Ok(self.pos)
+    }
+}
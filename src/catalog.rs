@@ -0,0 +1,251 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use crate::clip::{parse_clip_name, ClipName};
+use crate::extract::extractor_from_path;
+use crate::geo::{has_gps_fix, haversine_m};
+use crate::Error;
+
+/// A collection of TeslaCam clips discovered on disk.
+///
+/// A catalog is typically built from a `TeslaCam` folder (or any of `RecentClips`,
+/// `SavedClips`, `SentryClips`) and is the starting point for cross-clip queries.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    clips: Vec<CatalogClip>,
+}
+
+/// Which TeslaCam folder a clip was recorded into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipSource {
+    /// Rolling dashcam buffer (`RecentClips`).
+    Recent,
+    /// Manually saved event (`SavedClips/<event>/`).
+    Saved,
+    /// Sentry Mode event (`SentryClips/<event>/`).
+    Sentry,
+    /// Anything outside the standard TeslaCam layout.
+    Other,
+}
+
+/// A single clip in a [`Catalog`].
+#[derive(Debug, Clone)]
+pub struct CatalogClip {
+    pub path: PathBuf,
+    /// Parsed TeslaCam filename, if the file follows the TeslaCam naming scheme.
+    pub name: Option<ClipName>,
+}
+
+impl CatalogClip {
+    /// Local start time from the filename, if known.
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        self.name.as_ref().map(|n| n.start)
+    }
+
+    /// Camera suffix from the filename, if known.
+    pub fn camera(&self) -> Option<&str> {
+        self.name.as_ref().and_then(|n| n.camera.as_deref())
+    }
+
+    /// TeslaCam folder the clip lives in, judged by its nearest recognized ancestor directory.
+    pub fn source(&self) -> ClipSource {
+        for dir in self.path.ancestors().skip(1) {
+            match dir.file_name().and_then(|n| n.to_str()) {
+                Some("RecentClips") => return ClipSource::Recent,
+                Some("SavedClips") => return ClipSource::Saved,
+                Some("SentryClips") => return ClipSource::Sentry,
+                _ => {}
+            }
+        }
+        ClipSource::Other
+    }
+
+    /// The event folder (`SavedClips/<event>` or `SentryClips/<event>`) containing this clip.
+    pub fn event_dir(&self) -> Option<&Path> {
+        match self.source() {
+            ClipSource::Saved | ClipSource::Sentry => self.path.parent(),
+            ClipSource::Recent | ClipSource::Other => None,
+        }
+    }
+}
+
+// Largest gap between the end of one clip and the start of the next for a pass to continue
+// across them. TeslaCam file names have one-second resolution, and the last frame starts a
+// frame before the clip ends.
+const CLIP_GAP: chrono::Duration = chrono::Duration::seconds(2);
+
+/// One pass through a queried area, reported at the point of closest approach.
+#[derive(Debug, Clone)]
+pub struct Pass {
+    pub path: PathBuf,
+    /// Local start time of the clip containing the pass, if known.
+    pub clip_start: Option<NaiveDateTime>,
+    /// Offset of the closest approach from the start of the clip, if the clip has timing info.
+    pub clip_offset: Option<Duration>,
+    /// Sample index of the closest approach.
+    pub sample_index: usize,
+    pub frame_seq_no: u64,
+    /// Distance from the query point at closest approach, in meters.
+    pub distance_m: f64,
+    pub speed_mps: f32,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+}
+
+impl Pass {
+    /// Local wall-clock time of the closest approach, if the clip start is known.
+    pub fn time(&self) -> Option<NaiveDateTime> {
+        let start = self.clip_start?;
+        Some(start + self.clip_offset.unwrap_or_default())
+    }
+}
+
+impl Catalog {
+    /// Recursively scan `root` for `.mp4` files.
+    ///
+    /// `root` may also be a single file. Clips are ordered by filename timestamp, then path.
+    pub fn scan(root: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut paths = Vec::new();
+        collect_mp4s(root.as_ref(), &mut paths)?;
+        Ok(Self::from_paths(paths))
+    }
+
+    /// Build a catalog from an explicit list of clip paths.
+    pub fn from_paths(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut clips: Vec<CatalogClip> = paths
+            .into_iter()
+            .map(|path| CatalogClip {
+                name: parse_clip_name(&path),
+                path,
+            })
+            .collect();
+        clips.sort_by(|a, b| a.start().cmp(&b.start()).then_with(|| a.path.cmp(&b.path)));
+        Catalog { clips }
+    }
+
+    /// All clips, in catalog order.
+    pub fn clips(&self) -> &[CatalogClip] {
+        &self.clips
+    }
+
+    /// One clip per recorded minute, for queries that only need vehicle telemetry.
+    ///
+    /// Every camera of a recording carries the same vehicle telemetry, so decoding all of them
+    /// would report each moment several times. The same minute can also appear in more than one
+    /// folder (`SavedClips` events are copies of `RecentClips`). The `front` camera is preferred
+    /// when present. Clips without a TeslaCam filename are always included.
+    pub fn telemetry_clips(&self) -> Vec<&CatalogClip> {
+        let mut out: Vec<&CatalogClip> = Vec::new();
+        for clip in &self.clips {
+            let Some(start) = clip.start() else {
+                out.push(clip);
+                continue;
+            };
+
+            // Same-minute clips are adjacent because the catalog is sorted by start time.
+            match out.last_mut() {
+                Some(prev) if prev.start() == Some(start) => {
+                    if clip.camera() == Some("front") {
+                        *prev = clip;
+                    }
+                }
+                _ => out.push(clip),
+            }
+        }
+        out
+    }
+
+    /// Find every pass within `radius_m` meters of a coordinate, across all clips.
+    ///
+    /// A pass is a run of consecutive events inside the radius; it is reported once, at the
+    /// event closest to the query point. A pass still inside the radius at the end of a clip
+    /// carries on into the next clip when that one starts where it left off, so driving
+    /// through the area across a clip boundary is one pass. Clips that fail to parse are
+    /// skipped; IO errors are returned.
+    pub fn passes_near(
+        &self,
+        latitude_deg: f64,
+        longitude_deg: f64,
+        radius_m: f64,
+    ) -> Result<Vec<Pass>, Error> {
+        let mut passes = Vec::new();
+        let mut current: Option<Pass> = None;
+        // Local end time of the previous clip, if it was read and its timing is known.
+        let mut prev_end: Option<NaiveDateTime> = None;
+
+        for clip in self.telemetry_clips() {
+            let adjacent = prev_end
+                .zip(clip.start())
+                .is_some_and(|(end, start)| start <= end + CLIP_GAP);
+            if !adjacent {
+                passes.extend(current.take());
+            }
+            prev_end = None;
+
+            let mut extractor = match extractor_from_path(&clip.path) {
+                Ok(e) => e,
+                Err(Error::Io(e)) => return Err(Error::Io(e)),
+                Err(_) => continue,
+            };
+
+            let mut last_offset: Option<Duration> = None;
+            while let Some(event) = extractor.next_event()? {
+                last_offset = last_offset.max(event.presentation_time);
+                let m = &event.metadata;
+                let distance_m = has_gps_fix(m).then(|| {
+                    haversine_m(latitude_deg, longitude_deg, m.latitude_deg, m.longitude_deg)
+                });
+
+                // Events without a fix don't end a pass; the car may just have lost GPS.
+                match distance_m {
+                    Some(d) if d > radius_m => passes.extend(current.take()),
+                    Some(d) if current.as_ref().is_none_or(|p| d < p.distance_m) => {
+                        current = Some(Pass {
+                            path: clip.path.clone(),
+                            clip_start: clip.start(),
+                            clip_offset: event.presentation_time,
+                            sample_index: event.sample_index,
+                            frame_seq_no: m.frame_seq_no,
+                            distance_m: d,
+                            speed_mps: m.vehicle_speed_mps,
+                            latitude_deg: m.latitude_deg,
+                            longitude_deg: m.longitude_deg,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            prev_end = clip
+                .start()
+                .zip(last_offset)
+                .and_then(|(start, offset)| Some(start + chrono::Duration::from_std(offset).ok()?));
+        }
+        passes.extend(current);
+
+        Ok(passes)
+    }
+}
+
+fn collect_mp4s(path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_file() {
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_mp4s(&path, out)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
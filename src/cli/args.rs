@@ -0,0 +1,1044 @@
+//! Command-line arguments, their value types and parsers.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+
+use tesla_sei::extract::SampleTableRepair;
+use tesla_sei::geo::GeoPoint;
+use tesla_sei::info::VideoCodec;
+use tesla_sei::report::GroupBy;
+use tesla_sei::{pb, CompareOptions, DrivingEventOptions, GeoBounds};
+
+// Telemetry columns, in CSV column order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub(crate) enum SeiField {
+    Version,
+    GearState,
+    FrameSeqNo,
+    VehicleSpeedMps,
+    AcceleratorPedalPosition,
+    SteeringWheelAngle,
+    BlinkerOnLeft,
+    BlinkerOnRight,
+    BrakeApplied,
+    AutopilotState,
+    LatitudeDeg,
+    LongitudeDeg,
+    HeadingDeg,
+    LinearAccelerationMps2X,
+    LinearAccelerationMps2Y,
+    LinearAccelerationMps2Z,
+}
+
+impl SeiField {
+    // Column name; the speed column is renamed after `--units`.
+    pub(crate) fn column(self, speed_unit: SpeedUnit, style: ColumnStyle) -> String {
+        if self == SeiField::VehicleSpeedMps {
+            return style.apply(&format!("vehicle_speed_{}", speed_unit.suffix()));
+        }
+        let value = self.to_possible_value().expect("no field is skipped");
+        style.apply(value.get_name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ColumnStyle {
+    /// snake_case, as in dashcam.proto (vehicle_speed_mps)
+    Snake,
+    /// camelCase (vehicleSpeedMps)
+    Camel,
+    /// The names Tesla's JavaScript viewer decodes to (vehicleSpeedMps); needs --units mps
+    Tesla,
+}
+
+impl ColumnStyle {
+    // Spell a snake_case column name in this style. protobuf.js camel-cases the proto field
+    // names the same way, so the Tesla style only differs in insisting on the recorded units.
+    pub(crate) fn apply(self, snake: &str) -> String {
+        match self {
+            ColumnStyle::Snake => snake.to_string(),
+            ColumnStyle::Camel | ColumnStyle::Tesla => {
+                let mut words = snake.split('_');
+                let mut name = words.next().unwrap_or_default().to_string();
+                for word in words {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        name.extend(first.to_uppercase());
+                        name.push_str(chars.as_str());
+                    }
+                }
+                name
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SpeedUnit {
+    /// Meters per second, as recorded
+    Mps,
+    /// Kilometers per hour
+    Kmh,
+    /// Miles per hour
+    Mph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Compression {
+    /// Write the output as is, whatever its file name
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub(crate) fn suffix(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+}
+
+impl SpeedUnit {
+    pub(crate) fn suffix(self) -> &'static str {
+        match self {
+            SpeedUnit::Mps => "mps",
+            SpeedUnit::Kmh => "kmh",
+            SpeedUnit::Mph => "mph",
+        }
+    }
+
+    // As shown to people, e.g. on the overlay HUD.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            SpeedUnit::Mps => "m/s",
+            SpeedUnit::Kmh => "km/h",
+            SpeedUnit::Mph => "mph",
+        }
+    }
+
+    pub(crate) fn convert(self, mps: f32) -> f32 {
+        match self {
+            SpeedUnit::Mps => mps,
+            SpeedUnit::Kmh => (f64::from(mps) * 3.6) as f32,
+            SpeedUnit::Mph => (f64::from(mps) / 0.44704) as f32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum AngleUnit {
+    /// Degrees, as recorded
+    Deg,
+    /// Radians
+    Rad,
+}
+
+// The `--fields` selection; all fields when none were named.
+pub(crate) fn selected_fields(fields: &[SeiField]) -> &[SeiField] {
+    if fields.is_empty() {
+        SeiField::value_variants()
+    } else {
+        fields
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    Json,
+    Csv,
+}
+
+// Output formats of the default extract command: the shared ones plus track formats for
+// mapping tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ExtractFormat {
+    Json,
+    Csv,
+    /// One JSON object per line, written as events are decoded (JSON Lines)
+    Ndjson,
+    /// Apache Parquet with typed columns, for pandas/DuckDB
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// SQLite database with a telemetry table (and a clips table for batches); needs --output
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    /// InfluxDB line protocol, one point per frame, for Telegraf/InfluxDB
+    Influx,
+    /// Length-delimited protobuf: each `SeiMetadata` message prefixed with its varint length,
+    /// for consumers generated from the same dashcam.proto
+    Pb,
+    /// GPX 1.1 track with per-point time, speed and heading, for mapping tools
+    Gpx,
+    /// KML drive path with placemarks for notable events, for Google Earth
+    Kml,
+    /// The KML output zipped as `doc.kml`
+    Kmz,
+    /// GeoJSON FeatureCollection: one LineString per clip, with per-point properties
+    Geojson,
+    /// A single HTML page with a Leaflet map of the drive, colored by speed, and a time scrubber
+    HtmlMap,
+    /// SubRip subtitles timed to the video, for overlays in players like VLC (one clip only)
+    Srt,
+}
+
+// CSV layout options, shared by every command with CSV output.
+#[derive(Args, Debug)]
+#[command(next_help_heading = "CSV output")]
+pub(crate) struct CsvDialect {
+    /// Field delimiter (a single ASCII character, or `tab`)
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = parse_delimiter)]
+    pub(crate) delimiter: u8,
+
+    /// Tab-separated output (same as `--delimiter tab`)
+    #[arg(long, conflicts_with = "delimiter", action = clap::ArgAction::SetTrue)]
+    pub(crate) tsv: bool,
+
+    /// Quote every field, not just those containing the delimiter, quotes or line breaks
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) quote_all: bool,
+}
+
+// One end of an `--start`/`--end` range.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RangeBound {
+    Index(usize),
+    Time(std::time::Duration),
+}
+
+// A bare integer is a frame index; `[[HH:]MM:]SS[.fff]` or `SECONDS s` is a time into the clip.
+fn parse_range_bound(s: &str) -> Result<RangeBound, String> {
+    if let Ok(index) = s.parse() {
+        return Ok(RangeBound::Index(index));
+    }
+    let seconds = match s.strip_suffix('s') {
+        Some(seconds) => seconds.parse::<f64>().ok(),
+        None => {
+            let parts: Vec<&str> = s.split(':').collect();
+            let (seconds, whole) = parts.split_last().expect("split yields a part");
+            let whole = whole
+                .iter()
+                .map(|p| p.parse::<u64>().ok())
+                .collect::<Option<Vec<_>>>();
+            match (whole, seconds.parse::<f64>()) {
+                (Some(whole), Ok(seconds)) if (1..=2).contains(&whole.len()) => {
+                    Some(whole.iter().fold(0.0, |acc, &n| (acc + n as f64) * 60.0) + seconds)
+                }
+                _ => None,
+            }
+        }
+    };
+    seconds
+        .and_then(|s| std::time::Duration::try_from_secs_f64(s).ok())
+        .map(RangeBound::Time)
+        .ok_or_else(|| {
+            "expected a frame index, HH:MM:SS[.fff] or a number of seconds like 20s".into()
+        })
+}
+
+fn parse_hz(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(hz) if hz > 0.0 && hz.is_finite() => Ok(hz),
+        _ => Err("expected a positive number of frames per second".into()),
+    }
+}
+
+fn parse_bbox(s: &str) -> Result<GeoBounds, String> {
+    let degrees: Vec<f64> = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let [lat1, lon1, lat2, lon2] = degrees[..] else {
+        return Err("expected four numbers: LAT1,LON1,LAT2,LON2".into());
+    };
+    if ![lat1, lat2].iter().all(|lat| (-90.0..=90.0).contains(lat))
+        || ![lon1, lon2]
+            .iter()
+            .all(|lon| (-180.0..=180.0).contains(lon))
+    {
+        return Err("latitudes must be within ±90° and longitudes within ±180°".into());
+    }
+    let corner = |latitude_deg, longitude_deg| GeoPoint {
+        latitude_deg,
+        longitude_deg,
+    };
+    Ok(GeoBounds::from_corners(
+        corner(lat1, lon1),
+        corner(lat2, lon2),
+    ))
+}
+
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ => match s.as_bytes() {
+            [b] if b.is_ascii() => Ok(*b),
+            _ => Err("expected a single ASCII character or `tab`".to_string()),
+        },
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "tesla-sei", version)]
+#[command(about = "Extract Tesla dashcam SEI metadata", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    #[command(flatten)]
+    pub(crate) extract: Option<ExtractArgs>,
+
+    /// Print diagnostics on stderr: -v for progress notes, -vv to debug MP4 parsing, -vvv for
+    /// every box and sample
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub(crate) verbose: u8,
+
+    /// Print only errors, not warnings
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub(crate) quiet: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Extract telemetry from clips (what a bare `tesla-sei INPUT...` does)
+    #[command(visible_alias = "export")]
+    Extract(ExtractArgs),
+    /// Describe clips without extracting them: codec, resolution, duration, telemetry presence
+    Info(InfoArgs),
+    /// List notable moments in clips: hard braking, rapid acceleration, autopilot engaging and
+    /// disengaging, blinkers, gear changes
+    Events(EventsArgs),
+    /// Find every pass through an area across a folder of clips
+    Near(NearArgs),
+    /// Group trips across a folder of clips by route, with per-route statistics
+    Routes(RoutesArgs),
+    /// Aggregate distance, drive time, and event counts per day or week, or summarize each clip
+    Stats(StatsArgs),
+    /// Upgrade a JSON export written by an older release to the current schema version
+    Migrate(MigrateArgs),
+    /// Report, per clip, which SEI decode heuristics fired and how often
+    Corpus(CorpusArgs),
+    /// Print the MP4 box tree (offsets, sizes, nesting) for debugging malformed files
+    Boxes(BoxesArgs),
+    /// Dump the raw payload bytes of every SEI message, without decoding them
+    RawSei(RawSeiArgs),
+    /// Hex-dump each sample's SEI messages with their UUID and the offsets the telemetry
+    /// decoder tries, for payloads that stopped decoding
+    DebugSei(DebugSeiArgs),
+    /// Write a copy of a clip with the GPS position in its telemetry zeroed or jittered, or with
+    /// all SEI removed, so it can be shared
+    #[command(visible_alias = "anonymize")]
+    Redact(RedactArgs),
+    /// Write a copy of a clip with its SEI (or just the telemetry) removed
+    Strip(StripArgs),
+    /// Render a speed/gear/autopilot HUD as an .ass subtitle file, or burn it into a copy of
+    /// the video with ffmpeg
+    Overlay(OverlayArgs),
+    /// Cross-check the camera files of each recorded minute (shared frames, GPS agreement,
+    /// missing telemetry); exits with status 1 if they disagree
+    Compare(CompareArgs),
+    /// Print a shell completion script
+    #[command(hide = true)]
+    Completions(CompletionsArgs),
+    /// Check a clip's health: box structure, sample tables, SEI syntax, telemetry presence and
+    /// decode rate, frame sequence continuity; exits with status 1 on any problem
+    Validate(ValidateArgs),
+    /// Serve telemetry extraction over gRPC (`proto/extractor.proto`), for clips in a folder on
+    /// this machine or uploaded by the client
+    #[cfg(feature = "grpc")]
+    ServeGrpc(ServeGrpcArgs),
+    /// Serve telemetry as NDJSON over HTTP: `POST /extract` takes a multipart clip upload,
+    /// `GET /clips/{id}/telemetry` reads a clip from a folder on this machine
+    #[cfg(feature = "rest")]
+    Serve(ServeArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum StreamCodec {
+    H264,
+    H265,
+}
+
+impl From<StreamCodec> for VideoCodec {
+    fn from(c: StreamCodec) -> Self {
+        match c {
+            StreamCodec::H264 => VideoCodec::Avc,
+            StreamCodec::H265 => VideoCodec::Hevc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum RepairMode {
+    /// Drop the samples the chunk tables don't account for
+    Truncate,
+    /// Re-derive each chunk's sample count from chunk offsets and sample sizes
+    InferChunks,
+}
+
+impl From<RepairMode> for SampleTableRepair {
+    fn from(m: RepairMode) -> Self {
+        match m {
+            RepairMode::Truncate => SampleTableRepair::Truncate,
+            RepairMode::InferChunks => SampleTableRepair::InferChunks,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ExtractArgs {
+    /// Input MP4 files or raw .h264/.h265 elementary streams. Directories are searched
+    /// recursively for .mp4 clips (e.g. a whole TeslaCam folder) and patterns such as
+    /// 'TeslaCam/SentryClips/**/*-front.mp4' are expanded. Given a directory, a pattern or
+    /// several inputs, every record gets a `source_file` column and unreadable clips are skipped
+    /// with a warning (exit status 1). `-` reads a clip from stdin
+    #[arg(value_name = "INPUT", required = true)]
+    pub(crate) inputs: Vec<PathBuf>,
+
+    /// Treat the input as a raw Annex B elementary stream of this codec
+    /// (detected automatically for .h264/.264/.h265/.265/.hevc files)
+    #[arg(long, value_enum, value_name = "CODEC")]
+    pub(crate) annexb: Option<StreamCodec>,
+
+    /// If the MP4 has no usable index (e.g. `moov` lost to a power cut), scan `mdat` for SEI
+    /// instead of failing
+    #[arg(long, conflicts_with = "annexb", action = clap::ArgAction::SetTrue)]
+    pub(crate) recover: bool,
+
+    /// Repair MP4 sample tables that disagree with each other instead of failing
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with = "annexb")]
+    pub(crate) repair: Option<RepairMode>,
+
+    /// Keep re-scanning a clip that is still being written and output new frames as they land,
+    /// like `tail -f`; stops once the clip is finalized, and fails if it stops growing for 10 s
+    /// without being finalized. Takes one MP4 and a streaming format (csv, ndjson, influx, pb or
+    /// --mqtt)
+    #[arg(long, conflicts_with_all = ["annexb", "recover"], action = clap::ArgAction::SetTrue)]
+    pub(crate) follow: bool,
+
+    /// First record to export: a frame index counted from 0 (e.g. 600) or a time into the clip
+    /// (e.g. 00:00:20, 1:05.5 or 20s). An MP4 starts reading at a frame index; raw streams
+    /// (--annexb, .h264, .h265) and --recover have no index to jump with, so everything before
+    /// the start is still parsed
+    #[arg(long, value_name = "FRAME|TIME", value_parser = parse_range_bound)]
+    pub(crate) start: Option<RangeBound>,
+
+    /// Last record to export (inclusive), given like --start
+    #[arg(long, value_name = "FRAME|TIME", value_parser = parse_range_bound)]
+    pub(crate) end: Option<RangeBound>,
+
+    /// Keep only every Nth frame, counted from a frame index --start. MP4 samples in between
+    /// are never read
+    #[arg(long, value_name = "N", conflicts_with = "hz")]
+    pub(crate) every: Option<std::num::NonZeroUsize>,
+
+    /// Keep about this many frames per second, from the clip's frame rate (MP4 input only)
+    #[arg(long, value_name = "RATE", value_parser = parse_hz)]
+    pub(crate) hz: Option<f64>,
+
+    /// Drop frames whose telemetry (apart from frame_seq_no) repeats the previous frame's, e.g.
+    /// the long unchanged stretches of a parked Sentry clip
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) dedupe: bool,
+
+    /// Drop frames slower than this, in the --units unit (m/s by default)
+    #[arg(long, value_name = "SPEED")]
+    pub(crate) min_speed: Option<f32>,
+
+    /// Drop frames where the car is standing still (speed 0), e.g. most of a Sentry clip
+    #[arg(long, conflicts_with = "min_speed", action = clap::ArgAction::SetTrue)]
+    pub(crate) moving_only: bool,
+
+    /// Keep only frames whose GPS fix lies in this box, given by two opposite corners in degrees
+    #[arg(long, value_name = "LAT1,LON1,LAT2,LON2", value_parser = parse_bbox, allow_hyphen_values = true)]
+    pub(crate) bbox: Option<GeoBounds>,
+
+    /// Drop the frames inside --bbox instead, e.g. to leave home out; frames without a fix stay
+    #[arg(long, requires = "bbox", action = clap::ArgAction::SetTrue)]
+    pub(crate) exclude: bool,
+
+    /// Read this many clips of a batch at once, on worker threads. Output keeps the usual order,
+    /// one clip at a time
+    #[arg(short = 'j', long, value_name = "N", default_value = "1")]
+    pub(crate) jobs: std::num::NonZeroUsize,
+
+    /// Fail at the first clip with no telemetry or that can't be read, with a distinct exit
+    /// status: 2 no SEI found, 3 corrupt or unsupported MP4, 4 I/O error
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) strict: bool,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Write each input to its own file, named by this template, instead of one merged output
+    /// (e.g. 'out/{date}/{stem}.{ext}'). Placeholders: {stem}, {ext}, {format}, {camera},
+    /// {date}, {time}; missing directories are created
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_output_template, conflicts_with = "output")]
+    pub(crate) output_template: Option<String>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = ExtractFormat::Json, conflicts_with_all = ["csv", "json"])]
+    pub(crate) format: ExtractFormat,
+
+    /// Alias for `--format csv`
+    #[arg(long, conflicts_with_all = ["json", "format"], action = clap::ArgAction::SetTrue)]
+    pub(crate) csv: bool,
+
+    /// Alias for `--format json`
+    #[arg(long, conflicts_with_all = ["csv", "format"], action = clap::ArgAction::SetTrue)]
+    pub(crate) json: bool,
+
+    /// Print protobuf enums as their string names (e.g. GEAR_DRIVE) instead of numeric values
+    #[arg(short = 'e', long = "enum", action = clap::ArgAction::SetTrue)]
+    pub(crate) enum_strings: bool,
+
+    /// Add a leading ISO 8601 `timestamp` column: the clip's start time (MP4 creation time in
+    /// UTC, else the TeslaCam file name's local time) plus each frame's offset. Empty where
+    /// either is unknown
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) timestamp: bool,
+
+    /// Write absolute times (--timestamp, GPX, KML and GeoJSON) in this zone, with its UTC
+    /// offset: an IANA name (e.g. America/Los_Angeles), `local` or `utc`. TeslaCam file-name
+    /// times are taken to be local time in this zone
+    #[arg(long, value_name = "ZONE", value_parser = parse_timezone)]
+    pub(crate) timezone: Option<TimeZoneArg>,
+
+    /// Telemetry fields to output, comma-separated and in that order (CSV, JSON and NDJSON
+    /// output; a batch keeps its leading source_file)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELD,...")]
+    pub(crate) fields: Vec<SeiField>,
+
+    /// Speed unit for CSV, JSON and NDJSON output, where the column is renamed to match (e.g.
+    /// vehicle_speed_mph), and for SRT subtitles [default: mps; SRT shows km/h and mph]
+    #[arg(long, value_enum, value_name = "UNIT")]
+    pub(crate) units: Option<SpeedUnit>,
+
+    /// Unit of steering_wheel_angle in CSV, JSON and NDJSON output
+    #[arg(long, value_enum, value_name = "UNIT", default_value_t = AngleUnit::Deg)]
+    pub(crate) steering_units: AngleUnit,
+
+    /// How CSV, JSON and NDJSON field names are spelled
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = ColumnStyle::Snake)]
+    pub(crate) column_style: ColumnStyle,
+
+    /// Publish each event as JSON to an MQTT broker (mqtt://[user:password@]host[:port])
+    /// instead of writing output
+    #[cfg(feature = "mqtt")]
+    #[arg(long, value_name = "URL", conflicts_with_all = ["output", "output_template", "format", "csv", "json", "compress"])]
+    pub(crate) mqtt: Option<String>,
+
+    /// Topic for --mqtt
+    #[cfg(feature = "mqtt")]
+    #[arg(
+        long,
+        value_name = "TOPIC",
+        default_value = "tesla-sei/telemetry",
+        requires = "mqtt"
+    )]
+    pub(crate) mqtt_topic: String,
+
+    /// With --mqtt, publish at the clip's own frame rate instead of as fast as possible
+    #[cfg(feature = "mqtt")]
+    #[arg(long, requires = "mqtt", action = clap::ArgAction::SetTrue)]
+    pub(crate) realtime: bool,
+
+    /// Compress the output. By default files ending in .gz or .zst are compressed to match, and
+    /// --output-template's {ext} gets the suffix when this is given
+    #[arg(long, value_enum, value_name = "CODEC")]
+    pub(crate) compress: Option<Compression>,
+
+    /// Write JSON and GeoJSON on as few lines as possible, and JSON as it is decoded instead of
+    /// all at once. The default when writing to files
+    #[arg(long, conflicts_with = "pretty", action = clap::ArgAction::SetTrue)]
+    pub(crate) compact: bool,
+
+    /// Indent JSON and GeoJSON output. The default when writing to stdout
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) pretty: bool,
+
+    // The `CsvDialect` options, spelled out: clap can't tell whether an optional flattened
+    // struct (`Cli::extract`) was given when it flattens another one.
+    /// Field delimiter (a single ASCII character, or `tab`)
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = parse_delimiter, help_heading = "CSV output")]
+    pub(crate) delimiter: u8,
+
+    /// Tab-separated output (same as `--delimiter tab`)
+    #[arg(long, conflicts_with = "delimiter", action = clap::ArgAction::SetTrue, help_heading = "CSV output")]
+    pub(crate) tsv: bool,
+
+    /// Quote every field, not just those containing the delimiter, quotes or line breaks
+    #[arg(long, action = clap::ArgAction::SetTrue, help_heading = "CSV output")]
+    pub(crate) quote_all: bool,
+}
+
+impl ExtractArgs {
+    pub(crate) fn speed_unit(&self) -> SpeedUnit {
+        self.units.unwrap_or(SpeedUnit::Mps)
+    }
+
+    // Apply `--units` and `--steering-units` to a decoded message.
+    pub(crate) fn convert_units(&self, mut msg: pb::SeiMetadata) -> pb::SeiMetadata {
+        msg.vehicle_speed_mps = self.speed_unit().convert(msg.vehicle_speed_mps);
+        if self.steering_units == AngleUnit::Rad {
+            msg.steering_wheel_angle = msg.steering_wheel_angle.to_radians();
+        }
+        msg
+    }
+
+    // `--compress`, else whatever the output file's extension asks for.
+    pub(crate) fn compression(&self, output: &Option<PathBuf>) -> Compression {
+        if let Some(compression) = self.compress {
+            return compression;
+        }
+        let extension = output
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(|e| e.to_str());
+        match extension {
+            Some(e) if e.eq_ignore_ascii_case("gz") => Compression::Gzip,
+            Some(e) if e.eq_ignore_ascii_case("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    pub(crate) fn csv_dialect(&self) -> CsvDialect {
+        CsvDialect {
+            delimiter: self.delimiter,
+            tsv: self.tsv,
+            quote_all: self.quote_all,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct NearArgs {
+    /// TeslaCam folder (or a single clip) to search
+    #[arg(value_name = "PATH")]
+    pub(crate) path: PathBuf,
+
+    /// Latitude of the point of interest, in degrees
+    #[arg(long, allow_hyphen_values = true)]
+    pub(crate) lat: f64,
+
+    /// Longitude of the point of interest, in degrees
+    #[arg(long, allow_hyphen_values = true)]
+    pub(crate) lon: f64,
+
+    /// Search radius in meters
+    #[arg(long, default_value_t = 50.0)]
+    pub(crate) radius: f64,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Csv)]
+    pub(crate) format: OutputFormat,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RoutesArgs {
+    /// TeslaCam folder to analyze
+    #[arg(value_name = "PATH")]
+    pub(crate) path: PathBuf,
+
+    /// Grid cell size used to match start/end points and paths, in meters
+    #[arg(long, default_value_t = 500.0)]
+    pub(crate) cell_size: f64,
+
+    /// Largest gap between clips of the same trip, in seconds
+    #[arg(long, default_value_t = 120)]
+    pub(crate) max_gap: u64,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Csv)]
+    pub(crate) format: OutputFormat,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct MigrateArgs {
+    /// JSON export to upgrade
+    #[arg(value_name = "INPUT.json")]
+    pub(crate) input: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct BoxesArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RawSeiArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Json)]
+    pub(crate) format: OutputFormat,
+
+    /// Only dump messages of this SEI payload type (repeatable)
+    #[arg(long = "payload-type", value_name = "TYPE")]
+    pub(crate) payload_types: Vec<u32>,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CompletionsArgs {
+    /// Shell to complete for
+    #[arg(value_enum)]
+    pub(crate) shell: clap_complete::Shell,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct DebugSeiArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Only dump this sample, counted from 0 (repeatable)
+    #[arg(long = "sample", value_name = "N")]
+    pub(crate) samples: Vec<usize>,
+
+    /// Only dump messages of this SEI payload type (repeatable)
+    #[arg(long = "payload-type", value_name = "TYPE")]
+    pub(crate) payload_types: Vec<u32>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RedactArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output MP4 file
+    #[arg(short = 'o', long = "output", value_name = "OUTPUT.mp4")]
+    pub(crate) output: PathBuf,
+
+    /// Shift the whole route by a random offset of up to this many meters instead of zeroing
+    /// positions
+    #[arg(long, value_name = "METERS")]
+    pub(crate) jitter: Option<f64>,
+
+    /// Heading noise, in degrees either way, when jittering
+    #[arg(long, value_name = "DEG", default_value_t = 10.0, requires = "jitter")]
+    pub(crate) heading_jitter: f64,
+
+    /// Seed for the jitter, for reproducible output
+    #[arg(long, requires = "jitter")]
+    pub(crate) seed: Option<u64>,
+
+    /// Remove every SEI message, telemetry and all, instead of editing the GPS fields (what
+    /// `strip` does)
+    #[arg(long, conflicts_with = "jitter", action = clap::ArgAction::SetTrue)]
+    pub(crate) strip_all_sei: bool,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct StripArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output MP4 file
+    #[arg(short = 'o', long = "output", value_name = "OUTPUT.mp4")]
+    pub(crate) output: PathBuf,
+
+    /// Remove only Tesla's telemetry messages and keep other SEI
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    pub(crate) telemetry_only: bool,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct OverlayArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Where to write the .ass file (use '-' for stdout). With --burn it defaults to the video
+    /// path with an .ass extension
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Also run ffmpeg to write a copy of the clip with the HUD burned in
+    #[arg(long, value_name = "VIDEO")]
+    pub(crate) burn: Option<PathBuf>,
+
+    /// The ffmpeg executable for --burn
+    #[arg(long, value_name = "PATH", default_value = "ffmpeg", requires = "burn")]
+    pub(crate) ffmpeg: PathBuf,
+
+    /// Speed unit shown on the HUD
+    #[arg(long, value_enum, value_name = "UNIT", default_value_t = SpeedUnit::Kmh)]
+    pub(crate) units: SpeedUnit,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ValidateArgs {
+    /// Input MP4 file
+    #[arg(value_name = "INPUT.mp4")]
+    pub(crate) input: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Report format
+    #[arg(long = "format", value_enum, default_value_t = ReportFormat::Text)]
+    pub(crate) format: ReportFormat,
+}
+
+#[cfg(feature = "grpc")]
+#[derive(Args, Debug)]
+pub(crate) struct ServeGrpcArgs {
+    /// Address to listen on
+    #[arg(
+        long = "listen",
+        value_name = "ADDR",
+        default_value = "127.0.0.1:50051"
+    )]
+    pub(crate) listen: std::net::SocketAddr,
+
+    /// Folder that ExtractPath requests may read clips from; without it clients can only upload
+    #[arg(long = "root", value_name = "DIR")]
+    pub(crate) root: Option<PathBuf>,
+
+    /// Largest clip ExtractUpload accepts, in MiB
+    #[arg(long = "max-upload-mb", value_name = "MIB", default_value_t = 256)]
+    pub(crate) max_upload_mb: usize,
+}
+
+#[cfg(feature = "rest")]
+#[derive(Args, Debug)]
+pub(crate) struct ServeArgs {
+    /// Address to listen on
+    #[arg(long = "listen", value_name = "ADDR", default_value = "127.0.0.1:8080")]
+    pub(crate) listen: std::net::SocketAddr,
+
+    /// Folder of clips served under /clips, searched recursively; without it only /extract is
+    /// available
+    #[arg(long = "root", value_name = "DIR")]
+    pub(crate) root: Option<PathBuf>,
+
+    /// Largest upload /extract accepts, in MiB
+    #[arg(long = "max-upload-mb", value_name = "MIB", default_value_t = 256)]
+    pub(crate) max_upload_mb: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ReportFormat {
+    /// One line per problem, then a summary
+    Text,
+    /// A single JSON object, for scripts and CI
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CompareArgs {
+    /// Camera files of one event, an event folder, or quoted patterns; files are grouped by the
+    /// minute in their TeslaCam name
+    #[arg(value_name = "INPUT", required = true)]
+    pub(crate) inputs: Vec<PathBuf>,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Largest distance, in meters, between two cameras' GPS fixes for the same frame
+    #[arg(long, value_name = "METERS", default_value_t = CompareOptions::default().gps_tolerance_m)]
+    pub(crate) gps_tolerance: f64,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct InfoArgs {
+    /// MP4 files, folders (searched recursively) or quoted patterns
+    #[arg(value_name = "INPUT", required = true)]
+    pub(crate) inputs: Vec<PathBuf>,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Json)]
+    pub(crate) format: OutputFormat,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct EventsArgs {
+    /// MP4 files, folders (searched recursively) or quoted patterns
+    #[arg(value_name = "INPUT", required = true)]
+    pub(crate) inputs: Vec<PathBuf>,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Json)]
+    pub(crate) format: OutputFormat,
+
+    /// Deceleration, in g, that counts as hard braking
+    #[arg(long, value_name = "G", default_value_t = DrivingEventOptions::default().hard_braking_g)]
+    pub(crate) braking_g: f64,
+
+    /// Acceleration, in g, that counts as rapid acceleration
+    #[arg(long, value_name = "G", default_value_t = DrivingEventOptions::default().rapid_acceleration_g)]
+    pub(crate) acceleration_g: f64,
+
+    /// Zone for the timestamp column, as for extract --timezone
+    #[arg(long, value_name = "ZONE", value_parser = parse_timezone)]
+    pub(crate) timezone: Option<TimeZoneArg>,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct CorpusArgs {
+    /// Folder of clips (searched recursively) or a single clip
+    #[arg(value_name = "PATH")]
+    pub(crate) path: PathBuf,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Json)]
+    pub(crate) format: OutputFormat,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum StatsGroupBy {
+    Day,
+    Week,
+    /// One summary row per clip instead of per period
+    Clip,
+}
+
+impl StatsGroupBy {
+    // The catalog reporting period; `None` for per-clip summaries.
+    pub(crate) fn period(self) -> Option<GroupBy> {
+        match self {
+            StatsGroupBy::Day => Some(GroupBy::Day),
+            StatsGroupBy::Week => Some(GroupBy::Week),
+            StatsGroupBy::Clip => None,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct StatsArgs {
+    /// TeslaCam folder (or a single clip) to summarize
+    #[arg(value_name = "PATH")]
+    pub(crate) path: PathBuf,
+
+    /// Reporting period
+    #[arg(long, value_enum, default_value_t = StatsGroupBy::Day)]
+    pub(crate) group_by: StatsGroupBy,
+
+    /// Largest gap between clips of the same trip, in seconds
+    #[arg(long, default_value_t = 120)]
+    pub(crate) max_gap: u64,
+
+    /// Output file path (use '-' for stdout)
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Csv)]
+    pub(crate) format: OutputFormat,
+
+    #[command(flatten)]
+    pub(crate) csv_dialect: CsvDialect,
+}
+
+// `--timezone`: the zone absolute times are written in.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TimeZoneArg {
+    Utc,
+    Local,
+    Named(chrono_tz::Tz),
+}
+
+fn parse_timezone(s: &str) -> Result<TimeZoneArg, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "utc" => Ok(TimeZoneArg::Utc),
+        "local" => Ok(TimeZoneArg::Local),
+        _ => s.parse().map(TimeZoneArg::Named).map_err(|_| {
+            format!(
+                "unknown time zone '{s}' (expected an IANA name like Europe/Berlin, local or utc)"
+            )
+        }),
+    }
+}
+
+const OUTPUT_TEMPLATE_PLACEHOLDERS: &[&str] = &["stem", "ext", "format", "camera", "date", "time"];
+
+// Checks that every `{...}` in an --output-template is a known placeholder.
+fn parse_output_template(s: &str) -> Result<String, String> {
+    let mut rest = s;
+    while let Some(open) = rest.find('{') {
+        let Some(len) = rest[open..].find('}') else {
+            return Err(format!("unclosed '{{' in {s:?}"));
+        };
+        let name = &rest[open + 1..open + len];
+        if !OUTPUT_TEMPLATE_PLACEHOLDERS.contains(&name) {
+            return Err(format!(
+                "unknown placeholder {{{name}}}; expected one of {}",
+                OUTPUT_TEMPLATE_PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{p}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        rest = &rest[open + len + 1..];
+    }
+    Ok(s.to_string())
+}
@@ -0,0 +1,425 @@
+//! Commands over a whole TeslaCam folder: near, routes, stats and corpus.
+
+use serde::Serialize;
+use std::io::Write;
+
+use tesla_sei::extract;
+use tesla_sei::route::{cluster_routes, RouteOptions};
+use tesla_sei::schema::write_json_export;
+use tesla_sei::{clip_summary, Catalog, ClipSummary, DecodeStats, TripOptions};
+
+use crate::cli::args::{CorpusArgs, NearArgs, OutputFormat, RoutesArgs, StatsArgs};
+use crate::cli::error::Error;
+use crate::cli::records::{fmt_f32, fmt_f64};
+
+#[derive(Debug, Serialize)]
+struct CorpusRow {
+    file: String,
+    samples: usize,
+    messages: usize,
+    decoded: usize,
+    undecoded: usize,
+    by_magic_prefix: usize,
+    by_uuid_skip: usize,
+    by_raw_payload: usize,
+    by_tag_scan: usize,
+    trailing_byte_stripped: usize,
+    rejected_empty: usize,
+    /// Why the clip couldn't be scanned, if it couldn't.
+    error: Option<String>,
+}
+
+impl CorpusRow {
+    fn new(file: String, stats: &DecodeStats, error: Option<String>) -> Self {
+        CorpusRow {
+            file,
+            samples: stats.samples,
+            messages: stats.messages,
+            decoded: stats.decoded,
+            undecoded: stats.undecoded,
+            by_magic_prefix: stats.by_magic_prefix,
+            by_uuid_skip: stats.by_uuid_skip,
+            by_raw_payload: stats.by_raw_payload,
+            by_tag_scan: stats.by_tag_scan,
+            trailing_byte_stripped: stats.trailing_byte_stripped,
+            rejected_empty: stats.rejected_empty,
+            error,
+        }
+    }
+}
+
+fn corpus_csv_header() -> &'static str {
+    "file,samples,messages,decoded,undecoded,by_magic_prefix,by_uuid_skip,by_raw_payload,by_tag_scan,trailing_byte_stripped,rejected_empty,error"
+}
+
+#[derive(Debug, Serialize)]
+struct PeriodRow {
+    period: String,
+    start: String,
+    trips: usize,
+    distance_m: f64,
+    drive_time_s: f64,
+    clips: usize,
+    saved_events: usize,
+    sentry_events: usize,
+}
+
+fn period_csv_header() -> &'static str {
+    "period,start,trips,distance_m,drive_time_s,clips,saved_events,sentry_events"
+}
+
+#[derive(Debug, Serialize)]
+struct ClipStatsRow {
+    file: String,
+    frames: usize,
+    duration_s: Option<f64>,
+    distance_m: f64,
+    min_speed_mps: Option<f64>,
+    max_speed_mps: Option<f64>,
+    mean_speed_mps: Option<f64>,
+    park_s: f64,
+    drive_s: f64,
+    reverse_s: f64,
+    neutral_s: f64,
+    /// Percentage of the clip with autopilot in any mode.
+    autopilot_pct: Option<f64>,
+    max_horizontal_g: Option<f64>,
+    min_latitude_deg: Option<f64>,
+    min_longitude_deg: Option<f64>,
+    max_latitude_deg: Option<f64>,
+    max_longitude_deg: Option<f64>,
+    /// Why the clip couldn't be summarized, if it couldn't.
+    error: Option<String>,
+}
+
+impl ClipStatsRow {
+    fn new(file: String, summary: &ClipSummary, error: Option<String>) -> Self {
+        let gear = &summary.gear_time;
+        let bounds = summary.bounds;
+        ClipStatsRow {
+            file,
+            frames: summary.frames,
+            duration_s: summary.duration.map(|d| d.as_secs_f64()),
+            distance_m: summary.distance_m,
+            min_speed_mps: summary.min_speed_mps,
+            max_speed_mps: summary.max_speed_mps,
+            mean_speed_mps: summary.mean_speed_mps,
+            park_s: gear.park.as_secs_f64(),
+            drive_s: gear.drive.as_secs_f64(),
+            reverse_s: gear.reverse.as_secs_f64(),
+            neutral_s: gear.neutral.as_secs_f64(),
+            autopilot_pct: summary.autopilot_fraction.map(|f| f * 100.0),
+            max_horizontal_g: summary.max_horizontal_g,
+            min_latitude_deg: bounds.map(|b| b.min_latitude_deg),
+            min_longitude_deg: bounds.map(|b| b.min_longitude_deg),
+            max_latitude_deg: bounds.map(|b| b.max_latitude_deg),
+            max_longitude_deg: bounds.map(|b| b.max_longitude_deg),
+            error,
+        }
+    }
+}
+
+fn clip_stats_csv_header() -> &'static str {
+    "file,frames,duration_s,distance_m,min_speed_mps,max_speed_mps,mean_speed_mps,park_s,drive_s,reverse_s,neutral_s,autopilot_pct,max_horizontal_g,min_latitude_deg,min_longitude_deg,max_latitude_deg,max_longitude_deg,error"
+}
+
+#[derive(Debug, Serialize)]
+struct RouteRow {
+    route: usize,
+    trips: usize,
+    start_latitude_deg: f64,
+    start_longitude_deg: f64,
+    end_latitude_deg: f64,
+    end_longitude_deg: f64,
+    mean_distance_m: f64,
+    mean_duration_s: f64,
+    min_duration_s: f64,
+    max_duration_s: f64,
+    first_start: Option<String>,
+    last_start: Option<String>,
+}
+
+fn route_csv_header() -> &'static str {
+    "route,trips,start_latitude_deg,start_longitude_deg,end_latitude_deg,end_longitude_deg,mean_distance_m,mean_duration_s,min_duration_s,max_duration_s,first_start,last_start"
+}
+
+#[derive(Debug, Serialize)]
+struct NearRow {
+    date: Option<String>,
+    time: Option<String>,
+    speed_mps: f32,
+    distance_m: f64,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    frame_seq_no: u64,
+    sample_index: usize,
+    file: String,
+}
+
+fn near_csv_header() -> &'static str {
+    "date,time,speed_mps,distance_m,latitude_deg,longitude_deg,frame_seq_no,sample_index,file"
+}
+
+pub(crate) fn run_near(args: &NearArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let catalog = Catalog::scan(&args.path)?;
+    let passes = catalog.passes_near(args.lat, args.lon, args.radius)?;
+
+    let rows = passes.into_iter().map(|p| NearRow {
+        date: p.time().map(|t| t.format("%Y-%m-%d").to_string()),
+        time: p.time().map(|t| t.format("%H:%M:%S%.3f").to_string()),
+        speed_mps: p.speed_mps,
+        distance_m: p.distance_m,
+        latitude_deg: p.latitude_deg,
+        longitude_deg: p.longitude_deg,
+        frame_seq_no: p.frame_seq_no,
+        sample_index: p.sample_index,
+        file: p.path.display().to_string(),
+    });
+
+    match args.format {
+        OutputFormat::Json => {
+            let rows: Vec<NearRow> = rows.collect();
+            write_json_export(out, &rows)?;
+        }
+        OutputFormat::Csv => {
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(near_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.date.unwrap_or_default(),
+                    r.time.unwrap_or_default(),
+                    fmt_f32(r.speed_mps),
+                    fmt_f64(r.distance_m),
+                    fmt_f64(r.latitude_deg),
+                    fmt_f64(r.longitude_deg),
+                    r.frame_seq_no.to_string(),
+                    r.sample_index.to_string(),
+                    r.file,
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run_routes(args: &RoutesArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let catalog = Catalog::scan(&args.path)?;
+    let trip_opts = TripOptions {
+        max_gap: std::time::Duration::from_secs(args.max_gap),
+        ..TripOptions::default()
+    };
+    let trips = catalog.trips(&trip_opts)?;
+    let route_opts = RouteOptions {
+        cell_size_m: args.cell_size,
+        ..RouteOptions::default()
+    };
+
+    let fmt_time =
+        |t: Option<chrono::NaiveDateTime>| t.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+    let rows = cluster_routes(&trips, &route_opts)
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| RouteRow {
+            route: i + 1,
+            trips: c.trip_count(),
+            start_latitude_deg: c.start.latitude_deg,
+            start_longitude_deg: c.start.longitude_deg,
+            end_latitude_deg: c.end.latitude_deg,
+            end_longitude_deg: c.end.longitude_deg,
+            mean_distance_m: c.mean_distance_m(),
+            mean_duration_s: c.mean_duration().as_secs_f64(),
+            min_duration_s: c.min_duration.as_secs_f64(),
+            max_duration_s: c.max_duration.as_secs_f64(),
+            first_start: fmt_time(c.first_start),
+            last_start: fmt_time(c.last_start),
+        });
+
+    match args.format {
+        OutputFormat::Json => {
+            let rows: Vec<RouteRow> = rows.collect();
+            write_json_export(out, &rows)?;
+        }
+        OutputFormat::Csv => {
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(route_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.route.to_string(),
+                    r.trips.to_string(),
+                    fmt_f64(r.start_latitude_deg),
+                    fmt_f64(r.start_longitude_deg),
+                    fmt_f64(r.end_latitude_deg),
+                    fmt_f64(r.end_longitude_deg),
+                    fmt_f64(r.mean_distance_m),
+                    fmt_f64(r.mean_duration_s),
+                    fmt_f64(r.min_duration_s),
+                    fmt_f64(r.max_duration_s),
+                    r.first_start.unwrap_or_default(),
+                    r.last_start.unwrap_or_default(),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run_stats(args: &StatsArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let catalog = Catalog::scan(&args.path)?;
+    let Some(group_by) = args.group_by.period() else {
+        return write_clip_stats(args, &catalog, out);
+    };
+    let trip_opts = TripOptions {
+        max_gap: std::time::Duration::from_secs(args.max_gap),
+        ..TripOptions::default()
+    };
+
+    let rows = catalog
+        .period_stats(group_by, &trip_opts)?
+        .into_iter()
+        .map(|p| PeriodRow {
+            period: p.period,
+            start: p.start.format("%Y-%m-%d").to_string(),
+            trips: p.trips,
+            distance_m: p.distance_m,
+            drive_time_s: p.drive_time.as_secs_f64(),
+            clips: p.clips,
+            saved_events: p.saved_events,
+            sentry_events: p.sentry_events,
+        });
+
+    match args.format {
+        OutputFormat::Json => {
+            let rows: Vec<PeriodRow> = rows.collect();
+            write_json_export(out, &rows)?;
+        }
+        OutputFormat::Csv => {
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(period_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.period,
+                    r.start,
+                    r.trips.to_string(),
+                    fmt_f64(r.distance_m),
+                    fmt_f64(r.drive_time_s),
+                    r.clips.to_string(),
+                    r.saved_events.to_string(),
+                    r.sentry_events.to_string(),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_clip_stats(args: &StatsArgs, catalog: &Catalog, out: &mut dyn Write) -> Result<(), Error> {
+    let mut rows = Vec::new();
+    for clip in catalog.telemetry_clips() {
+        let file = clip.path.display().to_string();
+        rows.push(match clip_summary(&clip.path) {
+            Ok(summary) => ClipStatsRow::new(file, &summary, None),
+            Err(tesla_sei::Error::Io(e)) => return Err(e.into()),
+            Err(e) => ClipStatsRow::new(file, &ClipSummary::default(), Some(e.to_string())),
+        });
+    }
+
+    match args.format {
+        OutputFormat::Json => write_json_export(out, &rows)?,
+        OutputFormat::Csv => {
+            let opt = |v: Option<f64>| v.map(fmt_f64).unwrap_or_default();
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(clip_stats_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.file,
+                    r.frames.to_string(),
+                    opt(r.duration_s),
+                    fmt_f64(r.distance_m),
+                    opt(r.min_speed_mps),
+                    opt(r.max_speed_mps),
+                    opt(r.mean_speed_mps),
+                    fmt_f64(r.park_s),
+                    fmt_f64(r.drive_s),
+                    fmt_f64(r.reverse_s),
+                    fmt_f64(r.neutral_s),
+                    opt(r.autopilot_pct),
+                    opt(r.max_horizontal_g),
+                    opt(r.min_latitude_deg),
+                    opt(r.min_longitude_deg),
+                    opt(r.max_latitude_deg),
+                    opt(r.max_longitude_deg),
+                    r.error.unwrap_or_default(),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run_corpus(args: &CorpusArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let catalog = Catalog::scan(&args.path)?;
+
+    let mut rows: Vec<CorpusRow> = Vec::new();
+    let mut total = DecodeStats::default();
+    for clip in catalog.clips() {
+        let file = clip.path.display().to_string();
+        let mut extractor = match extract::extractor_from_path(&clip.path) {
+            Ok(e) => e,
+            Err(tesla_sei::Error::Io(e)) => return Err(e.into()),
+            Err(e) => {
+                rows.push(CorpusRow::new(
+                    file,
+                    &DecodeStats::default(),
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+        // Drain every event so each sample is decoded exactly once.
+        let mut error = None;
+        for event in &mut extractor {
+            if let Err(e) = event {
+                error = Some(e.to_string());
+                break;
+            }
+        }
+        total.merge(extractor.decode_stats());
+        rows.push(CorpusRow::new(file, extractor.decode_stats(), error));
+    }
+    rows.push(CorpusRow::new("TOTAL".to_string(), &total, None));
+
+    match args.format {
+        OutputFormat::Json => write_json_export(out, &rows)?,
+        OutputFormat::Csv => {
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(corpus_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.file,
+                    r.samples.to_string(),
+                    r.messages.to_string(),
+                    r.decoded.to_string(),
+                    r.undecoded.to_string(),
+                    r.by_magic_prefix.to_string(),
+                    r.by_uuid_skip.to_string(),
+                    r.by_raw_payload.to_string(),
+                    r.by_tag_scan.to_string(),
+                    r.trailing_byte_stripped.to_string(),
+                    r.rejected_empty.to_string(),
+                    r.error.unwrap_or_default(),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! The compare command.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use tesla_sei::{compare_cameras, parse_clip_name, CompareOptions};
+
+use crate::cli::args::CompareArgs;
+use crate::cli::error::Error;
+use crate::cli::input::expand_inputs;
+
+pub(crate) fn run_compare(args: &CompareArgs, out: &mut dyn Write) -> Result<bool, Error> {
+    let inputs = expand_inputs(&args.inputs)?;
+    // One group per recorded minute, with the front camera first as the reference.
+    let mut groups: std::collections::BTreeMap<String, Vec<PathBuf>> = Default::default();
+    for file in &inputs.files {
+        let key = parse_clip_name(file).map_or(String::new(), |n| {
+            n.start.format("%Y-%m-%d_%H-%M-%S").to_string()
+        });
+        groups.entry(key).or_default().push(file.clone());
+    }
+    let opts = CompareOptions {
+        gps_tolerance_m: args.gps_tolerance,
+    };
+
+    let mut consistent = true;
+    for (minute, mut group) in groups {
+        group.sort_by_key(|f| {
+            let camera = parse_clip_name(f).and_then(|n| n.camera);
+            (camera.as_deref() != Some("front"), f.clone())
+        });
+        if !minute.is_empty() {
+            writeln!(out, "{minute}:")?;
+        }
+        let comparison = match compare_cameras(&group, &opts) {
+            Ok(c) => c,
+            Err(e) => {
+                writeln!(out, "  error: {e}")?;
+                consistent = false;
+                continue;
+            }
+        };
+        for c in &comparison.cameras {
+            let seq = match (c.first_frame_seq_no, c.last_frame_seq_no) {
+                (Some(first), Some(last)) => format!("{first}-{last}"),
+                _ => "-".to_string(),
+            };
+            let gps = c
+                .max_gps_delta_m
+                .map_or("-".to_string(), |d| format!("{d:.1} m"));
+            writeln!(
+                out,
+                "  {}: {} frames in {} samples, frame_seq_no {seq}, {} shared, max GPS delta {gps}",
+                c.path.display(),
+                c.frames,
+                c.samples,
+                c.shared_frames,
+            )?;
+        }
+        for d in &comparison.discrepancies {
+            writeln!(out, "  {}: {}", d.path.display(), d.kind)?;
+        }
+        if comparison.cameras.len() < 2 {
+            writeln!(out, "  only one camera, nothing to compare against")?;
+        }
+        consistent &= comparison.is_consistent();
+    }
+    writeln!(
+        out,
+        "{}",
+        if consistent {
+            "cameras agree"
+        } else {
+            "cameras disagree"
+        }
+    )?;
+    Ok(consistent)
+}
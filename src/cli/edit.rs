@@ -0,0 +1,51 @@
+//! Commands that write a modified copy of a clip: redact and strip.
+
+use tesla_sei::{redact_gps, strip_sei, GpsRedaction, RedactOptions, StripOptions};
+
+use crate::cli::args::{RedactArgs, StripArgs};
+use crate::cli::error::Error;
+
+pub(crate) fn run_redact(args: &RedactArgs) -> Result<(), Error> {
+    if args.strip_all_sei {
+        return run_strip(&StripArgs {
+            input: args.input.clone(),
+            output: args.output.clone(),
+            telemetry_only: false,
+        });
+    }
+    let redaction = match args.jitter {
+        Some(radius_m) => GpsRedaction::Jitter {
+            radius_m,
+            heading_deg: args.heading_jitter,
+        },
+        None => GpsRedaction::Zero,
+    };
+    let opts = RedactOptions {
+        redaction,
+        seed: args.seed,
+    };
+    let summary = redact_gps(&args.input, &args.output, &opts)?;
+    println!(
+        "redacted {} SEI messages in {} samples",
+        summary.sei_messages_changed, summary.samples_changed
+    );
+    if summary.user_data_removed > 0 {
+        println!(
+            "removed {} location entries from the user data",
+            summary.user_data_removed
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn run_strip(args: &StripArgs) -> Result<(), Error> {
+    let opts = StripOptions {
+        telemetry_only: args.telemetry_only,
+    };
+    let summary = strip_sei(&args.input, &args.output, &opts)?;
+    println!(
+        "removed {} SEI messages from {} samples",
+        summary.sei_messages_removed, summary.samples_changed
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Errors of the command line: the library's, plus the clips `--strict` gave up on.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(thiserror::Error)]
+pub(crate) enum Error {
+    #[error(transparent)]
+    Lib(#[from] tesla_sei::Error),
+
+    /// A clip `--strict` gave up on; its exit status tells the failures apart.
+    #[error("{}: {failure}", file.display())]
+    ClipFailed { file: PathBuf, failure: ClipFailure },
+}
+
+// Why `--strict` gave up on a clip.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ClipFailure {
+    #[error("no SEI telemetry found")]
+    NoTelemetry,
+    #[error(transparent)]
+    Unreadable(tesla_sei::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Lib(e.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Lib(e.into())
+    }
+}
+
+// `main` prints the error it returns with `Debug`; keep the library's form for its errors.
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Lib(e) => fmt::Debug::fmt(e, f),
+            Error::ClipFailed { file, failure } => f
+                .debug_struct("ClipFailed")
+                .field("file", file)
+                .field("failure", failure)
+                .finish(),
+        }
+    }
+}
@@ -0,0 +1,924 @@
+//! The extract command: reading each input's telemetry and handing it to the output writer.
+
+use clap::ValueEnum;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Condvar, Mutex};
+use tempfile::NamedTempFile;
+
+use tesla_sei::annexb::{annexb_extractor_from_path, codec_from_extension};
+use tesla_sei::extract::{self, ExtractOptions, SampleTableRepair};
+use tesla_sei::geo::GeoPoint;
+use tesla_sei::info::VideoCodec;
+use tesla_sei::recover::recovery_extractor_from_path;
+use tesla_sei::schema::write_json_export;
+use tesla_sei::{parse_clip_name, pb};
+
+use crate::cli::args::{
+    AngleUnit, ColumnStyle, Compression, ExtractArgs, ExtractFormat, RangeBound, SpeedUnit,
+};
+use crate::cli::error::{ClipFailure, Error};
+use crate::cli::exit_status;
+use crate::cli::follow::{check_follow, follow_metadata};
+use crate::cli::formats::csv::write_csv;
+use crate::cli::formats::geojson::write_geojson;
+use crate::cli::formats::gpx::write_gpx;
+use crate::cli::formats::html_map::write_html_map;
+use crate::cli::formats::influx::write_influx;
+use crate::cli::formats::json::{write_compact_json, write_ndjson, SeiRecord};
+use crate::cli::formats::kml::{write_kml, write_kmz};
+#[cfg(feature = "mqtt")]
+use crate::cli::formats::mqtt::publish_mqtt;
+#[cfg(feature = "parquet")]
+use crate::cli::formats::parquet::write_parquet;
+use crate::cli::formats::pb::write_pb;
+#[cfg(feature = "sqlite")]
+use crate::cli::formats::sqlite::write_sqlite;
+use crate::cli::formats::srt::write_srt;
+use crate::cli::input::{expand_inputs, Inputs};
+use crate::cli::output::{should_write_to_stdout, with_extract_output};
+use crate::cli::records::{ClipStart, Sei, TimedMetadata};
+
+fn resolve_format(cli: &ExtractArgs) -> ExtractFormat {
+    if cli.csv {
+        ExtractFormat::Csv
+    } else if cli.json {
+        ExtractFormat::Json
+    } else {
+        cli.format
+    }
+}
+
+pub(crate) struct InputEvents {
+    pub(crate) start: Option<ClipStart>,
+    pub(crate) events: Box<dyn Iterator<Item = Result<TimedMetadata, Error>>>,
+}
+
+// A record with its frame index for `--start`/`--end`: the MP4 sample index, or for raw and
+// recovered streams the record's position in the stream. Both count frames skipped by thinning.
+type IndexedMetadata = (usize, TimedMetadata);
+
+// An input's records before `--start`/`--end` are applied.
+pub(crate) struct IndexedEvents {
+    pub(crate) start: Option<ClipStart>,
+    pub(crate) events: Box<dyn Iterator<Item = Result<IndexedMetadata, Error>>>,
+}
+
+// Decoded messages from either an MP4 or a raw elementary stream, limited to `--start`/`--end`.
+fn read_metadata(args: &ExtractArgs, input: &Path) -> Result<InputEvents, Error> {
+    let opened = if args.follow {
+        follow_metadata(args, input)
+    } else {
+        open_metadata(args, input)
+    };
+    let IndexedEvents { start, mut events } = match opened {
+        Err(e) if args.strict => return Err(clip_failed(input, e)),
+        opened => opened?,
+    };
+    if args.strict {
+        events = strict_events(events, input);
+    }
+    let events = if args.start.is_some() || args.end.is_some() {
+        select_range(events, args.start, args.end, input)
+    } else {
+        Box::new(events.map(|item| item.map(|(_, event)| event)))
+    };
+    Ok(InputEvents { start, events })
+}
+
+const EXIT_NO_TELEMETRY: u8 = 2;
+
+const EXIT_CORRUPT: u8 = 3;
+
+const EXIT_IO: u8 = 4;
+
+// Name the clip in its errors, and fail a clip that decodes no messages at all.
+fn strict_events<T: 'static>(
+    events: Box<dyn Iterator<Item = Result<T, Error>>>,
+    input: &Path,
+) -> Box<dyn Iterator<Item = Result<T, Error>>> {
+    let input = input.to_path_buf();
+    let mut decoded = 0usize;
+    let mut done = false;
+    let mut events = events;
+    Box::new(std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        match events.next() {
+            Some(Ok(event)) => {
+                decoded += 1;
+                Some(Ok(event))
+            }
+            Some(Err(e)) => {
+                done = true;
+                Some(Err(clip_failed(&input, e)))
+            }
+            None => {
+                done = true;
+                (decoded == 0).then(|| {
+                    Err(Error::ClipFailed {
+                        file: input.clone(),
+                        failure: ClipFailure::NoTelemetry,
+                    })
+                })
+            }
+        }
+    }))
+}
+
+// Name the clip in an error that stopped it, unless it already is.
+fn clip_failed(input: &Path, e: Error) -> Error {
+    match e {
+        Error::Lib(e) => Error::ClipFailed {
+            file: input.to_path_buf(),
+            failure: ClipFailure::Unreadable(e),
+        },
+        e @ Error::ClipFailed { .. } => e,
+    }
+}
+
+// Exit status for an error that stopped a `--strict` run.
+fn strict_exit_code(e: &Error) -> u8 {
+    let e = match e {
+        Error::ClipFailed {
+            failure: ClipFailure::NoTelemetry,
+            ..
+        } => return EXIT_NO_TELEMETRY,
+        Error::ClipFailed {
+            failure: ClipFailure::Unreadable(e),
+            ..
+        }
+        | Error::Lib(e) => e,
+    };
+    match e {
+        tesla_sei::Error::Io(io) => match io.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => EXIT_CORRUPT,
+            // Bad arguments rather than a bad clip.
+            io::ErrorKind::InvalidInput => 1,
+            _ => EXIT_IO,
+        },
+        _ => EXIT_CORRUPT,
+    }
+}
+
+// Frames are shown at most this many frames out of decode order (the largest decoded picture
+// buffer H.264 and H.265 allow), so once this many in a row are past a time `--end`, no later
+// one can be inside it.
+const MAX_REORDER_FRAMES: usize = 16;
+
+// Keep the records between two bounds, inclusive. Frame indices are the records' own (see
+// `IndexedMetadata`); times compare presentation times, so B-frame clips keep exactly the
+// frames shown in that window. Decoding stops once the records are past `end`; an MP4 clip
+// starts at an index `start` before the records get here (see `open_metadata`).
+fn select_range(
+    events: Box<dyn Iterator<Item = Result<IndexedMetadata, Error>>>,
+    start: Option<RangeBound>,
+    end: Option<RangeBound>,
+    input: &Path,
+) -> Box<dyn Iterator<Item = Result<TimedMetadata, Error>>> {
+    let file = input.display().to_string();
+    let mut past_end = 0;
+    Box::new(
+        events
+            // Errors carry no index and never end the range.
+            .take_while(move |item| match (item, end) {
+                (Ok((i, _)), Some(RangeBound::Index(n))) => *i <= n,
+                (Ok((_, (offset, _))), Some(RangeBound::Time(t))) => {
+                    past_end = if offset.is_some_and(|o| o > t) {
+                        past_end + 1
+                    } else {
+                        0
+                    };
+                    past_end < MAX_REORDER_FRAMES
+                }
+                _ => true,
+            })
+            .filter_map(move |item| {
+                let (i, (offset, m)) = match item {
+                    Ok(event) => event,
+                    Err(e) => return Some(Err(e)),
+                };
+                let position = |bound| match bound {
+                    RangeBound::Index(n) => Ok(i.cmp(&n)),
+                    RangeBound::Time(t) => offset.map(|offset| offset.cmp(&t)).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "{file} has no frame timing; give --start/--end as frame indices"
+                            ),
+                        )
+                    }),
+                };
+                let after_start = start.map_or(Ok(true), |b| position(b).map(|o| o.is_ge()));
+                let before_end = end.map_or(Ok(true), |b| position(b).map(|o| o.is_le()));
+                match (after_start, before_end) {
+                    (Ok(true), Ok(true)) => Some(Ok((offset, m))),
+                    (Err(e), _) | (_, Err(e)) => Some(Err(e.into())),
+                    _ => None,
+                }
+            }),
+    )
+}
+
+// Thinned by `--every`/`--hz`: MP4 samples in between are skipped unread; raw and recovered
+// streams have to be parsed in full, so their records are thinned after decoding.
+fn open_metadata(args: &ExtractArgs, input: &Path) -> Result<IndexedEvents, Error> {
+    let named_start = parse_clip_name(input).map(|n| ClipStart::Local(n.start));
+    let untimed = |events: Box<dyn Iterator<Item = Result<pb::SeiMetadata, tesla_sei::Error>>>| {
+        let stride = frame_stride(args, None, input)?;
+        Ok(IndexedEvents {
+            start: named_start,
+            events: Box::new(
+                events
+                    .enumerate()
+                    .step_by(stride)
+                    .map(|(i, m)| Ok((i, (None, m?)))),
+            ),
+        })
+    };
+    let stream_codec = args
+        .annexb
+        .map(VideoCodec::from)
+        .or_else(|| codec_from_extension(input));
+    match stream_codec {
+        Some(codec) => {
+            tracing::info!("{}: reading a raw {codec} stream", input.display());
+            let extractor = annexb_extractor_from_path(input, codec)?;
+            untimed(Box::new(extractor.map(|e| e.map(|e| e.metadata))))
+        }
+        None => {
+            let opts = ExtractOptions {
+                repair: args.repair.map(SampleTableRepair::from),
+                ..ExtractOptions::default()
+            };
+            match extract::extractor_from_path_with_options(input, &opts) {
+                Ok(mut extractor) => {
+                    tracing::info!("{}: {} samples", input.display(), extractor.total_samples());
+                    let stride = frame_stride(args, extractor.frame_rate(), input)?;
+                    extractor.set_sample_stride(stride);
+                    // Samples before an index `--start` are never read; `select_range` drops
+                    // the ones before a time.
+                    if let Some(RangeBound::Index(n)) = args.start {
+                        extractor.seek_sample(n.min(extractor.total_samples()))?;
+                    }
+                    let progress = clip_progress(args, input, extractor.total_samples());
+                    Ok(IndexedEvents {
+                        start: extractor
+                            .creation_time()
+                            .map(ClipStart::Utc)
+                            .or(named_start),
+                        events: Box::new(extractor.map(move |e| {
+                            if let (Some(bar), Ok(e)) = (&progress, &e) {
+                                bar.set_position(e.sample_index as u64 + 1);
+                            }
+                            let e = e?;
+                            Ok((e.sample_index, (e.presentation_time, e.metadata)))
+                        })),
+                    })
+                }
+                Err(tesla_sei::Error::NoTracksFound) if args.recover => {
+                    tracing::info!(
+                        "{}: no usable track, scanning for telemetry",
+                        input.display()
+                    );
+                    let extractor = recovery_extractor_from_path(input)?;
+                    untimed(Box::new(extractor.map(|e| e.map(|e| e.metadata))))
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+}
+
+// A progress bar over the clip's samples, on stderr, when output goes to a file; stdout may be a
+// terminal too. The bar clears itself once the clip's events are dropped, and indicatif draws
+// nothing when stderr isn't a terminal.
+fn clip_progress(args: &ExtractArgs, input: &Path, total_samples: usize) -> Option<ProgressBar> {
+    // Clips read in parallel would fight over the one line.
+    if should_write_to_stdout(&args.output) || args.follow || args.jobs.get() > 1 {
+        return None;
+    }
+    let style = ProgressStyle::with_template(
+        "{msg} [{bar:40}] {pos}/{len} samples ({percent}%), ETA {eta}",
+    )
+    .expect("valid progress template")
+    .progress_chars("=> ");
+    let name = input.file_name().unwrap_or(input.as_os_str());
+    Some(
+        ProgressBar::new(total_samples as u64)
+            .with_style(style)
+            .with_message(name.to_string_lossy().into_owned())
+            .with_finish(ProgressFinish::AndClear),
+    )
+}
+
+// Frames per kept frame for `--every`/`--hz`; `--hz` needs the input's frame rate.
+pub(crate) fn frame_stride(
+    args: &ExtractArgs,
+    frame_rate: Option<f64>,
+    input: &Path,
+) -> Result<usize, Error> {
+    match (args.every, args.hz) {
+        (Some(every), _) => Ok(every.get()),
+        (None, Some(hz)) => match frame_rate {
+            Some(rate) => Ok((rate / hz).round().max(1.0) as usize),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} has no frame rate to thin to --hz; use --every",
+                    input.display()
+                ),
+            )
+            .into()),
+        },
+        (None, None) => Ok(1),
+    }
+}
+
+// Open one input of the extract command. A single input fails the run on error, as it always
+// has; in a batch, a clip that can't be read is reported and skipped as a whole (`None`), and
+// `all_read` is cleared. A batch clip's records are put in display order (presentation time,
+// then frame sequence number), so a merged drive reads front to back.
+pub(crate) fn read_input(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    file: &Path,
+    all_read: &mut bool,
+) -> Result<Option<InputEvents>, Error> {
+    if !inputs.batch {
+        let mut input = read_metadata(args, file)?;
+        if args.dedupe {
+            input.events = dedupe_events(input.events);
+        }
+        input.events = frame_filter(args, input.events);
+        return Ok(Some(input));
+    }
+    let read = match inputs.read_ahead.take(file) {
+        Some(read) => read,
+        None => read_clip(args, file),
+    };
+    match read {
+        Ok((start, mut events)) => {
+            events.sort_by_key(|(offset, m)| (*offset, m.frame_seq_no));
+            let mut events: Box<dyn Iterator<Item = _>> = Box::new(events.into_iter().map(Ok));
+            if args.dedupe {
+                events = dedupe_events(events);
+            }
+            let events = frame_filter(args, events);
+            Ok(Some(InputEvents { start, events }))
+        }
+        Err(e) if args.strict => Err(e),
+        Err(e) => {
+            tracing::warn!("skipping {}: {e}", file.display());
+            *all_read = false;
+            Ok(None)
+        }
+    }
+}
+
+// A whole batch clip: its start and every record, in decode order.
+type ClipRead = Result<(Option<ClipStart>, Vec<TimedMetadata>), Error>;
+
+fn read_clip(args: &ExtractArgs, file: &Path) -> ClipRead {
+    let input = read_metadata(args, file)?;
+    Ok((input.start, input.events.collect::<Result<Vec<_>, _>>()?))
+}
+
+// `--jobs`: worker threads read the clips of a batch ahead of the writer, which takes them
+// back in input order through `read_input`. Workers stay at most a few clips ahead, so memory
+// stays bounded however large the batch.
+#[derive(Debug, Default)]
+struct ReadAhead {
+    queue: Mutex<Option<ReadAheadQueue>>,
+    changed: Condvar,
+}
+
+#[derive(Debug)]
+struct ReadAheadQueue {
+    files: Vec<PathBuf>,
+    // Next file for a worker to claim, and next for the writer to take.
+    claimed: usize,
+    taken: usize,
+    window: usize,
+    done: HashMap<usize, ClipRead>,
+    stopped: bool,
+}
+
+impl ReadAhead {
+    // Start `jobs` workers on `files`. They stop once every file is read or the returned guard
+    // is dropped, e.g. when the writer fails part way.
+    fn start<'scope, 'env>(
+        &'env self,
+        scope: &'scope std::thread::Scope<'scope, 'env>,
+        args: &'env ExtractArgs,
+        files: Vec<PathBuf>,
+        jobs: usize,
+    ) -> impl Drop + 'env {
+        *self.queue.lock().unwrap() = Some(ReadAheadQueue {
+            files,
+            claimed: 0,
+            taken: 0,
+            window: jobs * 2,
+            done: HashMap::new(),
+            stopped: false,
+        });
+        for _ in 0..jobs {
+            scope.spawn(move || self.work(args));
+        }
+        StopReadAhead(self)
+    }
+
+    fn work(&self, args: &ExtractArgs) {
+        loop {
+            let (index, file) = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    let q = queue.as_mut().expect("started");
+                    if q.stopped || q.claimed == q.files.len() {
+                        return;
+                    }
+                    if q.claimed < q.taken + q.window {
+                        q.claimed += 1;
+                        break (q.claimed - 1, q.files[q.claimed - 1].clone());
+                    }
+                    queue = self.changed.wait(queue).unwrap();
+                }
+            };
+            let read = read_clip(args, &file);
+            if let Some(q) = self.queue.lock().unwrap().as_mut() {
+                q.done.insert(index, read);
+            }
+            self.changed.notify_all();
+        }
+    }
+
+    // The next clip, once a worker has read it; `None` if nothing is being read ahead or
+    // `file` isn't the next one.
+    fn take(&self, file: &Path) -> Option<ClipRead> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.as_ref()?.files.get(queue.as_ref()?.taken)? != file {
+            return None;
+        }
+        loop {
+            let q = queue.as_mut().expect("checked above");
+            if let Some(read) = q.done.remove(&q.taken) {
+                q.taken += 1;
+                self.changed.notify_all();
+                return Some(read);
+            }
+            queue = self.changed.wait(queue).unwrap();
+        }
+    }
+}
+
+struct StopReadAhead<'a>(&'a ReadAhead);
+
+impl Drop for StopReadAhead<'_> {
+    fn drop(&mut self) {
+        if let Some(q) = self.0.queue.lock().unwrap().as_mut() {
+            q.stopped = true;
+        }
+        self.0.changed.notify_all();
+    }
+}
+
+// Drop each frame whose telemetry matches the one before it in everything but frame_seq_no.
+// Each clip keeps its first frame.
+fn dedupe_events(
+    events: Box<dyn Iterator<Item = Result<TimedMetadata, Error>>>,
+) -> Box<dyn Iterator<Item = Result<TimedMetadata, Error>>> {
+    let mut last: Option<pb::SeiMetadata> = None;
+    Box::new(events.filter(move |item| {
+        let Ok((_, m)) = item else {
+            return true;
+        };
+        let telemetry = pb::SeiMetadata {
+            frame_seq_no: 0,
+            ..*m
+        };
+        if last.as_ref() == Some(&telemetry) {
+            return false;
+        }
+        last = Some(telemetry);
+        true
+    }))
+}
+
+// `--min-speed`/`--moving-only` and `--bbox`. Frames are still in m/s here, so each speed is
+// converted to the `--units` unit the threshold is given in.
+fn frame_filter(
+    args: &ExtractArgs,
+    events: Box<dyn Iterator<Item = Result<TimedMetadata, Error>>>,
+) -> Box<dyn Iterator<Item = Result<TimedMetadata, Error>>> {
+    if args.min_speed.is_none() && !args.moving_only && args.bbox.is_none() {
+        return events;
+    }
+    let (min_speed, moving_only, unit) = (args.min_speed, args.moving_only, args.speed_unit());
+    let (bbox, exclude) = (args.bbox, args.exclude);
+    Box::new(events.filter(move |item| {
+        let Ok((_, m)) = item else {
+            return true;
+        };
+        let fast_enough = match min_speed {
+            Some(min) => unit.convert(m.vehicle_speed_mps) >= min,
+            None => !moving_only || m.vehicle_speed_mps != 0.0,
+        };
+        // A frame without a fix is neither inside nor outside the box.
+        let placed = bbox.is_none_or(|bbox| match GeoPoint::from_metadata(m) {
+            Some(fix) => bbox.contains(&fix) != exclude,
+            None => exclude,
+        });
+        fast_enough && placed
+    }))
+}
+
+// The clips of one extract run, and the `--jobs` workers reading a batch of them ahead.
+pub(crate) struct ExtractInputs {
+    pub(crate) files: Vec<PathBuf>,
+    pub(crate) batch: bool,
+    read_ahead: ReadAhead,
+    // Kept so that a copy of stdin lives as long as the run.
+    _stdin_copy: Option<NamedTempFile>,
+}
+
+impl ExtractInputs {
+    fn single(file: PathBuf) -> Self {
+        ExtractInputs {
+            files: vec![file],
+            batch: false,
+            read_ahead: ReadAhead::default(),
+            _stdin_copy: None,
+        }
+    }
+}
+
+// `expand_inputs` for the extract command, with a batch's clips in chronological order so that
+// a drive split across one-minute clips comes out as one timeline.
+fn extract_inputs(args: &ExtractArgs) -> Result<ExtractInputs, Error> {
+    let Inputs {
+        mut files,
+        batch,
+        stdin_copy,
+    } = expand_inputs(&args.inputs)?;
+    if batch {
+        // Clips whose start can't be told keep their relative order, after the others.
+        files.sort_by_cached_key(|file| {
+            let start = clip_sort_time(file);
+            (start.is_none(), start)
+        });
+    }
+    Ok(ExtractInputs {
+        files,
+        batch,
+        read_ahead: ReadAhead::default(),
+        _stdin_copy: stdin_copy,
+    })
+}
+
+// When a clip starts: from the TeslaCam file name, or else the MP4 creation time (UTC).
+fn clip_sort_time(file: &Path) -> Option<chrono::NaiveDateTime> {
+    if let Some(name) = parse_clip_name(file) {
+        return Some(name.start);
+    }
+    let extractor = extract::extractor_from_path(file).ok()?;
+    extractor.creation_time().map(|t| t.naive_utc())
+}
+
+// Returns false if any input in a batch couldn't be read; those are reported on stderr and
+// skipped.
+fn run_with_writer(
+    args: &ExtractArgs,
+    format: ExtractFormat,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let enum_strings = args.enum_strings;
+    match format {
+        ExtractFormat::Csv => return write_csv(args, inputs, out),
+        ExtractFormat::Ndjson => return write_ndjson(args, inputs, out),
+        ExtractFormat::Influx => return write_influx(args, inputs, out),
+        ExtractFormat::Pb => return write_pb(args, inputs, out),
+        #[cfg(feature = "parquet")]
+        ExtractFormat::Parquet => return write_parquet(args, inputs, out),
+        #[cfg(feature = "sqlite")]
+        ExtractFormat::Sqlite => unreachable!("SQLite output is written to a path by write_sqlite"),
+        ExtractFormat::Gpx => return write_gpx(args, inputs, out),
+        ExtractFormat::Kml => return write_kml(args, inputs, out),
+        ExtractFormat::Kmz => {
+            let mut kml = Vec::new();
+            let all_read = write_kml(args, inputs, &mut kml)?;
+            write_kmz(&kml, out)?;
+            return Ok(all_read);
+        }
+        ExtractFormat::Geojson => return write_geojson(args, inputs, out),
+        ExtractFormat::HtmlMap => return write_html_map(args, inputs, out),
+        ExtractFormat::Srt => return write_srt(args, inputs, out),
+        ExtractFormat::Json => {}
+    }
+
+    if !args.pretty_json() {
+        return write_compact_json(args, inputs, out);
+    }
+    let names: Vec<String> = inputs
+        .files
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect();
+    let mut records: Vec<SeiRecord> = Vec::new();
+    let mut all_read = true;
+
+    for (file, source_file) in inputs.files.iter().zip(&names) {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        for item in input.events {
+            let (offset, msg) = item?;
+            records.push(SeiRecord {
+                source_file: inputs.batch.then_some(source_file.as_str()),
+                timestamp: args.frame_timestamp(input.start, offset),
+                sei: Sei::from_pb(args.convert_units(msg), enum_strings),
+                fields: &args.fields,
+                speed_unit: args.speed_unit(),
+                column_style: args.column_style,
+            });
+        }
+    }
+
+    write_json_export(out, &records)?;
+
+    Ok(all_read)
+}
+
+pub(crate) fn run_extract(args: &ExtractArgs) -> Result<ExitCode, Error> {
+    let format = resolve_format(args);
+    let tabular = matches!(
+        format,
+        ExtractFormat::Csv | ExtractFormat::Json | ExtractFormat::Ndjson
+    );
+    let column_options = [
+        ("--fields", !args.fields.is_empty()),
+        ("--timestamp", args.timestamp),
+        ("--steering-units", args.steering_units != AngleUnit::Deg),
+        ("--column-style", args.column_style != ColumnStyle::Snake),
+    ];
+    if let Some((flag, _)) = column_options.iter().find(|(_, given)| *given && !tabular) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{flag} applies to csv, json and ndjson output"),
+        )
+        .into());
+    }
+    if args.units.is_some() && !tabular && !matches!(format, ExtractFormat::Srt) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--units applies to csv, json, ndjson and srt output",
+        )
+        .into());
+    }
+    if (args.compact || args.pretty)
+        && !matches!(format, ExtractFormat::Json | ExtractFormat::Geojson)
+    {
+        let flag = if args.compact {
+            "--compact"
+        } else {
+            "--pretty"
+        };
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{flag} applies to json and geojson output"),
+        )
+        .into());
+    }
+    if args.column_style == ColumnStyle::Tesla && args.speed_unit() != SpeedUnit::Mps {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--column-style tesla keeps Tesla's vehicleSpeedMps, so it needs --units mps",
+        )
+        .into());
+    }
+    match extract_all(args, format) {
+        Ok(all_read) => Ok(exit_status(all_read)),
+        Err(e) if args.strict => {
+            eprintln!("error: {e}");
+            Ok(ExitCode::from(strict_exit_code(&e)))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// The usual file extension of an output format.
+fn format_extension(args: &ExtractArgs, format: ExtractFormat) -> &'static str {
+    match format {
+        ExtractFormat::Json => "json",
+        ExtractFormat::Csv if args.tsv || args.delimiter == b'\t' => "tsv",
+        ExtractFormat::Csv => "csv",
+        ExtractFormat::Ndjson => "ndjson",
+        #[cfg(feature = "parquet")]
+        ExtractFormat::Parquet => "parquet",
+        #[cfg(feature = "sqlite")]
+        ExtractFormat::Sqlite => "sqlite",
+        ExtractFormat::Influx => "lp",
+        ExtractFormat::Pb => "pb",
+        ExtractFormat::Gpx => "gpx",
+        ExtractFormat::Kml => "kml",
+        ExtractFormat::Kmz => "kmz",
+        ExtractFormat::Geojson => "geojson",
+        ExtractFormat::HtmlMap => "html",
+        ExtractFormat::Srt => "srt",
+    }
+}
+
+// Fill in an --output-template for one input. Placeholders without a value (a clip with no
+// TeslaCam camera suffix, or no known start) become empty.
+fn render_output_template(
+    template: &str,
+    args: &ExtractArgs,
+    format: ExtractFormat,
+    file: &Path,
+) -> PathBuf {
+    let camera = parse_clip_name(file).and_then(|n| n.camera);
+    let start = clip_sort_time(file);
+    let value = |name: &str| -> String {
+        match name {
+            "stem" => file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            "ext" => {
+                let compression = args.compress.unwrap_or(Compression::None);
+                format!("{}{}", format_extension(args, format), compression.suffix())
+            }
+            "format" => format
+                .to_possible_value()
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_default(),
+            "camera" => camera.clone().unwrap_or_default(),
+            "date" => start
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            "time" => start
+                .map(|t| t.format("%H-%M-%S").to_string())
+                .unwrap_or_default(),
+            _ => unreachable!("placeholders are checked by parse_output_template"),
+        }
+    };
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let len = rest[open..]
+            .find('}')
+            .expect("checked by parse_output_template");
+        out.push_str(&rest[..open]);
+        out.push_str(&value(&rest[open + 1..open + len]));
+        rest = &rest[open + len + 1..];
+    }
+    out.push_str(rest);
+    PathBuf::from(out)
+}
+
+// --output-template: each input on its own, as if it had been the only one given. An input
+// that can't be read is skipped with a warning (and its output file removed), as in a batch.
+fn write_templated(
+    args: &ExtractArgs,
+    format: ExtractFormat,
+    inputs: &ExtractInputs,
+) -> Result<bool, Error> {
+    let template = args.output_template.as_deref().unwrap_or_default();
+    let files = &inputs.files;
+    let outputs: Vec<PathBuf> = files
+        .iter()
+        .map(|file| render_output_template(template, args, format, file))
+        .collect();
+    let mut seen = HashMap::new();
+    for (file, output) in files.iter().zip(&outputs) {
+        if let Some(other) = seen.insert(output, file) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "--output-template names {} for both {} and {}",
+                    output.display(),
+                    other.display(),
+                    file.display()
+                ),
+            )
+            .into());
+        }
+    }
+
+    // Each clip goes to its own file, so with --jobs the clips are simply shared out.
+    let write_one = |index: usize| -> Result<bool, Error> {
+        let (file, output) = (&files[index], &outputs[index]);
+        if let Some(dir) = output.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut all_read = true;
+        let written = with_extract_output(args, &Some(output.clone()), |out| {
+            let inputs = ExtractInputs::single(file.clone());
+            all_read = run_with_writer(args, format, &inputs, out)?;
+            Ok(())
+        });
+        match written {
+            Ok(()) => tracing::info!("wrote {}", output.display()),
+            Err(_) => {
+                let _ = std::fs::remove_file(output);
+            }
+        }
+        written.map(|()| all_read)
+    };
+    let results: Box<dyn Iterator<Item = Result<bool, Error>>> = if args.jobs.get() > 1 {
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let results = Mutex::new(HashMap::new());
+        std::thread::scope(|scope| {
+            for _ in 0..args.jobs.get().min(files.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    if index >= files.len() {
+                        break;
+                    }
+                    let result = write_one(index);
+                    results.lock().unwrap().insert(index, result);
+                });
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        Box::new((0..files.len()).map(move |i| results.remove(&i).expect("every clip is written")))
+    } else {
+        // Lazily, so --strict stops at the first failure.
+        Box::new((0..files.len()).map(write_one))
+    };
+
+    let mut all_read = true;
+    for (file, result) in files.iter().zip(results) {
+        match result {
+            Ok(read) => all_read &= read,
+            Err(e) if args.strict => return Err(e),
+            Err(e) => {
+                tracing::warn!("skipping {}: {e}", file.display());
+                all_read = false;
+            }
+        }
+    }
+    Ok(all_read)
+}
+
+// Returns false if any input in a batch couldn't be read.
+fn extract_all(args: &ExtractArgs, format: ExtractFormat) -> Result<bool, Error> {
+    let inputs = extract_inputs(args)?;
+    check_follow(args, format, inputs.batch)?;
+    let jobs = args.jobs.get();
+    if jobs > 1 && inputs.batch && args.output_template.is_none() {
+        return std::thread::scope(|scope| {
+            let _read_ahead = inputs
+                .read_ahead
+                .start(scope, args, inputs.files.clone(), jobs);
+            write_extract_output(args, format, &inputs)
+        });
+    }
+    write_extract_output(args, format, &inputs)
+}
+
+fn write_extract_output(
+    args: &ExtractArgs,
+    format: ExtractFormat,
+    inputs: &ExtractInputs,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    match format {
+        #[cfg(feature = "mqtt")]
+        _ if args.mqtt.is_some() => all_read = publish_mqtt(args, inputs)?,
+        #[cfg(feature = "sqlite")]
+        ExtractFormat::Sqlite if args.output_template.is_some() => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--output-template doesn't apply to sqlite output",
+            )
+            .into());
+        }
+        #[cfg(feature = "sqlite")]
+        ExtractFormat::Sqlite if args.compression(&args.output) != Compression::None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--compress doesn't apply to sqlite output",
+            )
+            .into());
+        }
+        #[cfg(feature = "sqlite")]
+        ExtractFormat::Sqlite => all_read = write_sqlite(args, inputs)?,
+        _ if args.output_template.is_some() => all_read = write_templated(args, format, inputs)?,
+        _ => {
+            with_extract_output(args, &args.output, |out| {
+                all_read = run_with_writer(args, format, inputs, out)?;
+                Ok(())
+            })?;
+            if let Some(output) = &args.output {
+                tracing::info!("wrote {}", output.display());
+            }
+        }
+    }
+    Ok(all_read)
+}
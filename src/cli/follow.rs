@@ -0,0 +1,151 @@
+//! `--follow`: extracting a clip that is still being written.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tesla_sei::extract::{self, ExtractOptions, SampleTableRepair};
+use tesla_sei::parse_clip_name;
+use tesla_sei::recover::recovery_extractor_from_path;
+
+use crate::cli::args::{ExtractArgs, ExtractFormat};
+use crate::cli::error::Error;
+use crate::cli::extract::{frame_stride, IndexedEvents};
+use crate::cli::records::{ClipStart, TimedMetadata};
+
+// `--follow` reads a single clip and needs an output that is written as frames arrive.
+pub(crate) fn check_follow(
+    args: &ExtractArgs,
+    format: ExtractFormat,
+    batch: bool,
+) -> Result<(), Error> {
+    if !args.follow {
+        return Ok(());
+    }
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let streaming = matches!(
+        format,
+        ExtractFormat::Csv | ExtractFormat::Ndjson | ExtractFormat::Influx | ExtractFormat::Pb
+    );
+    #[cfg(feature = "mqtt")]
+    let streaming = streaming || args.mqtt.is_some();
+    if !streaming {
+        return Err(invalid("--follow needs csv, ndjson, influx or pb output").into());
+    }
+    if batch {
+        return Err(invalid("--follow takes a single clip").into());
+    }
+    Ok(())
+}
+
+const FOLLOW_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+// How long a clip without an index may stay the same size before the writer counts as gone.
+const FOLLOW_SETTLE_TIME: std::time::Duration = std::time::Duration::from_secs(10);
+
+// `--follow`: until the clip has an index, its `mdat` is re-scanned whenever it grows, as for
+// `--recover`. Frames come without presentation times until then; `--hz` needs the frame rate
+// and so can't be used. A clip that stops growing without an index is an error.
+pub(crate) fn follow_metadata(args: &ExtractArgs, input: &Path) -> Result<IndexedEvents, Error> {
+    let stride = frame_stride(args, None, input)?;
+    let events = FollowEvents {
+        path: input.to_path_buf(),
+        opts: ExtractOptions {
+            repair: args.repair.map(SampleTableRepair::from),
+            ..ExtractOptions::default()
+        },
+        scanned_len: None,
+        grew_at: std::time::Instant::now(),
+        last_offset: None,
+        pending: std::collections::VecDeque::new(),
+        finished: false,
+    };
+    Ok(IndexedEvents {
+        start: parse_clip_name(input).map(|n| ClipStart::Local(n.start)),
+        events: Box::new(
+            events
+                .enumerate()
+                .step_by(stride)
+                .map(|(i, item)| item.map(|event| (i, event))),
+        ),
+    })
+}
+
+// Events of a clip that may still be growing; blocks between scans.
+struct FollowEvents {
+    path: PathBuf,
+    opts: ExtractOptions,
+    // File length at the last scan, to skip rescanning a clip that hasn't changed, and when that
+    // length was first seen.
+    scanned_len: Option<u64>,
+    grew_at: std::time::Instant,
+    // File offset of the last event queued; each scan starts from the top and skips up to it.
+    last_offset: Option<u64>,
+    pending: std::collections::VecDeque<TimedMetadata>,
+    // The clip has its index, so nothing more will be appended.
+    finished: bool,
+}
+
+impl FollowEvents {
+    fn scan(&mut self) -> Result<(), Error> {
+        let len = std::fs::metadata(&self.path)?.len();
+        let growing = self.scanned_len != Some(len);
+        if growing {
+            self.scanned_len = Some(len);
+            self.grew_at = std::time::Instant::now();
+        } else if self.grew_at.elapsed() < FOLLOW_SETTLE_TIME {
+            return Ok(());
+        }
+
+        // Events keyed by file offset: the sample's for the regular extractor, the NAL unit's
+        // when recovering. A sample starts before the SEI NAL unit inside it, so either kind
+        // resumes correctly after the other.
+        let new: Vec<(u64, TimedMetadata)> =
+            match extract::extractor_from_path_with_options(&self.path, &self.opts) {
+                Ok(extractor) => {
+                    self.finished = true;
+                    extractor
+                        .map(|e| e.map(|e| (e.file_offset, (e.presentation_time, e.metadata))))
+                        .collect::<Result<_, _>>()?
+                }
+                Err(tesla_sei::Error::Io(e)) => return Err(e.into()),
+                // A clip being written has no `moov` yet (or only part of one), so the regular
+                // extractor failing just means "not finished" while the file still grows.
+                Err(_) if growing => recovery_extractor_from_path(&self.path)?
+                    .map(|e| e.map(|e| (e.file_offset, (None, e.metadata))))
+                    .collect::<Result<_, _>>()?,
+                // The writer stopped without finishing the clip.
+                Err(e) => return Err(e.into()),
+            };
+        let resume_after = self.last_offset;
+        for (offset, event) in new {
+            if resume_after.is_some_and(|last| offset <= last) {
+                continue;
+            }
+            self.last_offset = self.last_offset.max(Some(offset));
+            self.pending.push_back(event);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for FollowEvents {
+    type Item = Result<TimedMetadata, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.finished {
+                return None;
+            }
+            if let Err(e) = self.scan() {
+                self.finished = true;
+                return Some(Err(e));
+            }
+            if self.pending.is_empty() && !self.finished {
+                std::thread::sleep(FOLLOW_POLL_INTERVAL);
+            }
+        }
+    }
+}
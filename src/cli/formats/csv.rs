@@ -0,0 +1,122 @@
+//! CSV and TSV output.
+
+use std::io::{self, Write};
+
+use tesla_sei::pb;
+
+use crate::cli::args::{selected_fields, CsvDialect, ExtractArgs};
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::{autopilot_state_string, fmt_f32, fmt_f64, gear_state_string};
+
+impl CsvDialect {
+    pub(crate) fn writer<'a>(&self, out: &'a mut dyn Write) -> CsvWriter<'a> {
+        let quote = if self.quote_all {
+            csv::QuoteStyle::Always
+        } else {
+            csv::QuoteStyle::Necessary
+        };
+        let writer = csv::WriterBuilder::new()
+            .delimiter(if self.tsv { b'\t' } else { self.delimiter })
+            .quote_style(quote)
+            .from_writer(out);
+        CsvWriter(writer)
+    }
+}
+
+// `csv::Writer` with its errors as plain `io::Error`s, which `?` turns into our `Error`.
+pub(crate) struct CsvWriter<'a>(csv::Writer<&'a mut dyn Write>);
+
+impl CsvWriter<'_> {
+    pub(crate) fn record<I>(&mut self, fields: I) -> io::Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        self.0
+            .write_record(fields)
+            .map_err(|e| match e.into_kind() {
+                csv::ErrorKind::Io(e) => e,
+                kind => io::Error::other(format!("CSV write failed: {kind:?}")),
+            })
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+// Rows are written as they are decoded (lower memory, easy to stream). A batch gets a leading
+// `source_file` column.
+pub(crate) fn write_csv(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    let mut csv = args.csv_dialect().writer(out);
+    let fields = selected_fields(&args.fields);
+    let leading = [
+        inputs.batch.then(|| args.column_style.apply("source_file")),
+        args.timestamp.then(|| "timestamp".to_string()),
+    ];
+    let header = fields
+        .iter()
+        .map(|f| f.column(args.speed_unit(), args.column_style));
+    csv.record(leading.into_iter().flatten().chain(header))?;
+
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let source_file = file.display().to_string();
+        for item in input.events {
+            let (offset, msg) = item?;
+            let timestamp = args.frame_timestamp(input.start, offset);
+            let record = sei_csv_record(&args.convert_units(msg), args.enum_strings);
+            let leading = [
+                inputs.batch.then_some(source_file.as_str()),
+                timestamp.as_ref().map(|t| t.as_deref().unwrap_or_default()),
+            ];
+            let selected = fields.iter().map(|&f| record[f as usize].as_str());
+            csv.record(leading.into_iter().flatten().chain(selected))?;
+            if args.follow {
+                csv.flush()?;
+            }
+        }
+    }
+    csv.flush()?;
+    Ok(all_read)
+}
+
+fn sei_csv_record(msg: &pb::SeiMetadata, enum_strings: bool) -> Vec<String> {
+    let gear = if enum_strings {
+        gear_state_string(msg.gear_state)
+    } else {
+        msg.gear_state.to_string()
+    };
+    let autopilot = if enum_strings {
+        autopilot_state_string(msg.autopilot_state)
+    } else {
+        msg.autopilot_state.to_string()
+    };
+
+    vec![
+        msg.version.to_string(),
+        gear,
+        msg.frame_seq_no.to_string(),
+        fmt_f32(msg.vehicle_speed_mps),
+        fmt_f32(msg.accelerator_pedal_position),
+        fmt_f32(msg.steering_wheel_angle),
+        msg.blinker_on_left.to_string(),
+        msg.blinker_on_right.to_string(),
+        msg.brake_applied.to_string(),
+        autopilot,
+        fmt_f64(msg.latitude_deg),
+        fmt_f64(msg.longitude_deg),
+        fmt_f64(msg.heading_deg),
+        fmt_f64(msg.linear_acceleration_mps2_x),
+        fmt_f64(msg.linear_acceleration_mps2_y),
+        fmt_f64(msg.linear_acceleration_mps2_z),
+    ]
+}
@@ -0,0 +1,73 @@
+//! GeoJSON output.
+
+use serde_json::Value;
+use std::io::Write;
+
+use tesla_sei::geo::has_gps_fix;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::Sei;
+
+// A GeoJSON FeatureCollection with one LineString feature per input. GeoJSON has no per-vertex
+// properties, so speed, heading, gear and autopilot state are parallel arrays under
+// `coordinateProperties` (the convention of togeojson and Mapbox tools), one entry per
+// coordinate. Points without a GPS fix are left out.
+pub(crate) fn write_geojson(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let enum_strings = args.enum_strings;
+    let mut all_read = true;
+    let mut features = Vec::new();
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let mut coordinates = Vec::new();
+        let mut times = Vec::new();
+        let mut speed = Vec::new();
+        let mut heading = Vec::new();
+        let mut gear = Vec::new();
+        let mut autopilot = Vec::new();
+        for item in input.events {
+            let (offset, m) = item?;
+            if !has_gps_fix(&m) {
+                continue;
+            }
+            coordinates.push(serde_json::json!([m.longitude_deg, m.latitude_deg]));
+            times.push(args.frame_time(input.start, offset));
+            speed.push(m.vehicle_speed_mps);
+            heading.push(m.heading_deg);
+            let sei = Sei::from_pb(m, enum_strings);
+            gear.push(sei.gear_state);
+            autopilot.push(sei.autopilot_state);
+        }
+        let mut point_props = serde_json::Map::new();
+        if times.iter().all(Option::is_some) {
+            point_props.insert("time".into(), serde_json::json!(times));
+        }
+        point_props.insert("vehicle_speed_mps".into(), serde_json::json!(speed));
+        point_props.insert("heading_deg".into(), serde_json::json!(heading));
+        point_props.insert("gear_state".into(), Value::Array(gear));
+        point_props.insert("autopilot_state".into(), Value::Array(autopilot));
+        features.push(serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "LineString", "coordinates": coordinates },
+            "properties": {
+                "source_file": file.display().to_string(),
+                "coordinateProperties": point_props,
+            },
+        }));
+    }
+    let collection = serde_json::json!({ "type": "FeatureCollection", "features": features });
+    if args.pretty_json() {
+        serde_json::to_writer_pretty(&mut *out, &collection)?;
+    } else {
+        serde_json::to_writer(&mut *out, &collection)?;
+    }
+    writeln!(out)?;
+    Ok(all_read)
+}
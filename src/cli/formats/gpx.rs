@@ -0,0 +1,65 @@
+//! GPX track output.
+
+use std::io::Write;
+
+use tesla_sei::geo::has_gps_fix;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::formats::xml_escape;
+
+// A GPX 1.1 document with one track per input. Speed and heading go in Garmin's
+// TrackPointExtension, which mapping tools read; points without a GPS fix are left out.
+pub(crate) fn write_gpx(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<gpx version="1.1" creator="tesla-sei" xmlns="http://www.topografix.com/GPX/1/1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v2">"#
+    )?;
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        writeln!(out, "  <trk>")?;
+        writeln!(out, "    <name>{}</name>", xml_escape(&name))?;
+        writeln!(out, "    <trkseg>")?;
+        for item in input.events {
+            let (offset, m) = item?;
+            if !has_gps_fix(&m) {
+                continue;
+            }
+            writeln!(
+                out,
+                r#"      <trkpt lat="{}" lon="{}">"#,
+                m.latitude_deg, m.longitude_deg
+            )?;
+            if let Some(time) = args.frame_time(input.start, offset) {
+                writeln!(out, "        <time>{time}</time>")?;
+            }
+            writeln!(out, "        <extensions><gpxtpx:TrackPointExtension>")?;
+            writeln!(
+                out,
+                "          <gpxtpx:speed>{}</gpxtpx:speed>",
+                m.vehicle_speed_mps
+            )?;
+            writeln!(
+                out,
+                "          <gpxtpx:course>{}</gpxtpx:course>",
+                m.heading_deg.rem_euclid(360.0)
+            )?;
+            writeln!(out, "        </gpxtpx:TrackPointExtension></extensions>")?;
+            writeln!(out, "      </trkpt>")?;
+        }
+        writeln!(out, "    </trkseg>")?;
+        writeln!(out, "  </trk>")?;
+    }
+    writeln!(out, "</gpx>")?;
+    Ok(all_read)
+}
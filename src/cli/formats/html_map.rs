@@ -0,0 +1,57 @@
+//! A self-contained HTML page mapping the drive.
+
+use std::io::Write;
+
+use tesla_sei::geo::has_gps_fix;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::formats::xml_escape;
+
+const HTML_MAP_TEMPLATE: &str = include_str!("html_map.html");
+
+// A page that opens in any browser: the points are embedded as JSON, and Leaflet (checked
+// against its published SRI hashes) and the OpenStreetMap tiles are fetched from their CDNs.
+pub(crate) fn write_html_map(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    let mut clips = Vec::new();
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let mut points = Vec::new();
+        for item in input.events {
+            let (offset, m) = item?;
+            if !has_gps_fix(&m) {
+                continue;
+            }
+            points.push(serde_json::json!([
+                m.latitude_deg,
+                m.longitude_deg,
+                m.vehicle_speed_mps,
+                offset.map(|o| o.as_secs_f64()),
+                args.frame_time(input.start, offset),
+            ]));
+        }
+        clips.push(serde_json::json!({ "file": file.display().to_string(), "points": points }));
+    }
+    let title = match inputs.files.as_slice() {
+        [file] => file
+            .file_name()
+            .unwrap_or(file.as_os_str())
+            .to_string_lossy(),
+        _ => "Drive map".into(),
+    };
+    // `</script>` in a file name would end the script element early.
+    let data = serde_json::to_string(&clips)?.replace("</", "<\\/");
+    let page = HTML_MAP_TEMPLATE
+        .replace("{{TITLE}}", &xml_escape(&title))
+        .replace("{{DATA}}", &data);
+    out.write_all(page.as_bytes())?;
+    Ok(all_read)
+}
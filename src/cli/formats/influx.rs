@@ -0,0 +1,111 @@
+//! InfluxDB line protocol output.
+
+use std::io::{self, Write};
+
+use tesla_sei::parse_clip_name;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::{autopilot_state_string, gear_state_string, ClipStart};
+
+// Measurement `tesla_sei`, tagged with the clip's file name and (for TeslaCam names) camera.
+// Timestamps are nanoseconds since the epoch from the MP4 creation time plus the frame's
+// presentation time; without a UTC start they are left off, and InfluxDB uses the write time.
+pub(crate) fn write_influx(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let mut tags = String::new();
+        if let Some(camera) = parse_clip_name(file).and_then(|n| n.camera) {
+            tags.push_str(&format!(",camera={}", influx_tag(&camera)));
+        }
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        tags.push_str(&format!(",clip={}", influx_tag(&name)));
+
+        for item in input.events {
+            let (offset, m) = item?;
+            let strings = args.enum_strings;
+            write!(out, "tesla_sei{tags} version={}i", m.version)?;
+            let gear = strings.then(|| gear_state_string(m.gear_state));
+            write_enum(out, "gear_state", m.gear_state, gear.as_deref())?;
+            write!(out, ",frame_seq_no={}i", m.frame_seq_no)?;
+            for (name, v) in [
+                ("vehicle_speed_mps", m.vehicle_speed_mps),
+                ("accelerator_pedal_position", m.accelerator_pedal_position),
+                ("steering_wheel_angle", m.steering_wheel_angle),
+            ] {
+                write_float(out, name, v)?;
+            }
+            write!(
+                out,
+                ",blinker_on_left={},blinker_on_right={},brake_applied={}",
+                m.blinker_on_left, m.blinker_on_right, m.brake_applied
+            )?;
+            let autopilot = strings.then(|| autopilot_state_string(m.autopilot_state));
+            write_enum(
+                out,
+                "autopilot_state",
+                m.autopilot_state,
+                autopilot.as_deref(),
+            )?;
+            for (name, v) in [
+                ("latitude_deg", m.latitude_deg),
+                ("longitude_deg", m.longitude_deg),
+                ("heading_deg", m.heading_deg),
+                ("linear_acceleration_mps2_x", m.linear_acceleration_mps2_x),
+                ("linear_acceleration_mps2_y", m.linear_acceleration_mps2_y),
+                ("linear_acceleration_mps2_z", m.linear_acceleration_mps2_z),
+            ] {
+                write_float(out, name, v)?;
+            }
+            let time = match (input.start, offset) {
+                (Some(ClipStart::Utc(t)), Some(o)) => chrono::Duration::from_std(o)
+                    .ok()
+                    .and_then(|o| (t + o).timestamp_nanos_opt()),
+                _ => None,
+            };
+            match time {
+                Some(ns) => writeln!(out, " {ns}")?,
+                None => writeln!(out)?,
+            }
+            if args.follow {
+                out.flush()?;
+            }
+        }
+    }
+    Ok(all_read)
+}
+
+// `,name=value`, or nothing for NaN and infinities: line protocol has no token for them, and
+// one fails the whole write, so such fields are left out of the point.
+fn write_float<T>(out: &mut dyn Write, name: &str, v: T) -> io::Result<()>
+where
+    T: Into<f64> + Copy + std::fmt::Display,
+{
+    if v.into().is_finite() {
+        write!(out, ",{name}={v}")?;
+    }
+    Ok(())
+}
+
+// `,name=value` for an enum field: its name as a string field with `-e`, else its raw value.
+fn write_enum(out: &mut dyn Write, name: &str, value: i32, string: Option<&str>) -> io::Result<()> {
+    match string {
+        Some(string) => write!(out, ",{name}=\"{string}\""),
+        None => write!(out, ",{name}={value}i"),
+    }
+}
+
+// Tag values escape commas, equals signs and spaces.
+fn influx_tag(s: &str) -> String {
+    s.replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
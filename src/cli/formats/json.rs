@@ -0,0 +1,103 @@
+//! JSON and NDJSON output.
+
+use serde::ser::SerializeMap;
+use serde::Serialize;
+use std::io::Write;
+
+use tesla_sei::schema::JsonExportWriter;
+
+use crate::cli::args::{selected_fields, ColumnStyle, ExtractArgs, SeiField, SpeedUnit};
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::Sei;
+
+// One telemetry record as JSON: `source_file` first in a batch, then the selected fields.
+pub(crate) struct SeiRecord<'a> {
+    pub(crate) source_file: Option<&'a str>,
+    // `Some` with --timestamp; the inner `None` serializes as null.
+    pub(crate) timestamp: Option<Option<String>>,
+    pub(crate) sei: Sei,
+    pub(crate) fields: &'a [SeiField],
+    pub(crate) speed_unit: SpeedUnit,
+    pub(crate) column_style: ColumnStyle,
+}
+
+impl Serialize for SeiRecord<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(source_file) = self.source_file {
+            map.serialize_entry(&self.column_style.apply("source_file"), source_file)?;
+        }
+        if let Some(timestamp) = &self.timestamp {
+            map.serialize_entry("timestamp", timestamp)?;
+        }
+        for &field in selected_fields(self.fields) {
+            self.sei
+                .serialize_field(field, self.speed_unit, self.column_style, &mut map)?;
+        }
+        map.end()
+    }
+}
+
+// `--format json` without pretty-printing: each record is written as soon as it is decoded, so
+// memory stays flat however large the export.
+pub(crate) fn write_compact_json(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    let mut json = JsonExportWriter::new(out)?;
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let source_file = file.display().to_string();
+        for item in input.events {
+            let (offset, msg) = item?;
+            json.write_record(&SeiRecord {
+                source_file: inputs.batch.then_some(source_file.as_str()),
+                timestamp: args.frame_timestamp(input.start, offset),
+                sei: Sei::from_pb(args.convert_units(msg), args.enum_strings),
+                fields: &args.fields,
+                speed_unit: args.speed_unit(),
+                column_style: args.column_style,
+            })?;
+        }
+    }
+    json.finish()?;
+    Ok(all_read)
+}
+
+// Unlike `--format json`, nothing is buffered, so long clips can be piped into `jq` and other
+// stream processors. Records carry no schema envelope.
+pub(crate) fn write_ndjson(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let source_file = file.display().to_string();
+        for item in input.events {
+            let (offset, msg) = item?;
+            let record = SeiRecord {
+                source_file: inputs.batch.then_some(source_file.as_str()),
+                timestamp: args.frame_timestamp(input.start, offset),
+                sei: Sei::from_pb(args.convert_units(msg), args.enum_strings),
+                fields: &args.fields,
+                speed_unit: args.speed_unit(),
+                column_style: args.column_style,
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+            if args.follow {
+                out.flush()?;
+            }
+        }
+    }
+    Ok(all_read)
+}
@@ -0,0 +1,105 @@
+//! KML and KMZ output.
+
+use std::io::{self, Write};
+
+use tesla_sei::geo::has_gps_fix;
+use tesla_sei::ThumbnailOptions;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::formats::xml_escape;
+use crate::cli::records::autopilot_state_string;
+
+// A KML 2.2 document with one folder per input: the drive path as a line, plus a placemark
+// wherever autopilot engages or disengages and at the start of each harsh g-force: horizontal
+// acceleration in any direction (braking, accelerating, swerving or cornering) past the
+// threshold `thumbnail_hints` uses for its harsh-event frame.
+pub(crate) fn write_kml(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let harsh_accel_mps2 = ThumbnailOptions::default().harsh_accel_mps2;
+    let mut all_read = true;
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(out, "<Document>")?;
+    writeln!(out, "  <name>tesla-sei</name>")?;
+    writeln!(
+        out,
+        r#"  <Style id="path"><LineStyle><color>ff0000ff</color><width>4</width></LineStyle></Style>"#
+    )?;
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let mut path = Vec::new();
+        let mut placemarks = Vec::new();
+        let mut autopilot = None;
+        let mut harsh = false;
+        for item in input.events {
+            let (offset, m) = item?;
+            if !has_gps_fix(&m) {
+                continue;
+            }
+            let coords = format!("{},{},0", m.longitude_deg, m.latitude_deg);
+            let accel = m
+                .linear_acceleration_mps2_x
+                .hypot(m.linear_acceleration_mps2_y);
+            let mut notable = Vec::new();
+            if autopilot.is_some_and(|prev| prev != m.autopilot_state) {
+                notable.push(match m.autopilot_state {
+                    0 => "Autopilot disengaged".to_string(),
+                    state => format!("Autopilot engaged ({})", autopilot_state_string(state)),
+                });
+            }
+            if accel >= harsh_accel_mps2 && !harsh {
+                notable.push(format!("Harsh g-force ({accel:.1} m/s²)"));
+            }
+            autopilot = Some(m.autopilot_state);
+            harsh = accel >= harsh_accel_mps2;
+            let time = args.frame_time(input.start, offset);
+            for label in notable {
+                let mut placemark = format!("    <Placemark><name>{}</name>", xml_escape(&label));
+                if let Some(time) = &time {
+                    placemark.push_str(&format!("<TimeStamp><when>{time}</when></TimeStamp>"));
+                }
+                placemark.push_str(&format!(
+                    "<Point><coordinates>{coords}</coordinates></Point></Placemark>"
+                ));
+                placemarks.push(placemark);
+            }
+            path.push(coords);
+        }
+        writeln!(out, "  <Folder>")?;
+        writeln!(out, "    <name>{}</name>", xml_escape(&name))?;
+        if !path.is_empty() {
+            writeln!(
+                out,
+                "    <Placemark><name>Path</name><styleUrl>#path</styleUrl><LineString><tessellate>1</tessellate><coordinates>{}</coordinates></LineString></Placemark>",
+                path.join(" ")
+            )?;
+        }
+        for placemark in placemarks {
+            writeln!(out, "{placemark}")?;
+        }
+        writeln!(out, "  </Folder>")?;
+    }
+    writeln!(out, "</Document>")?;
+    writeln!(out, "</kml>")?;
+    Ok(all_read)
+}
+
+// Google Earth opens the first `.kml` entry of a KMZ; `doc.kml` is the conventional name.
+pub(crate) fn write_kmz(kml: &[u8], out: &mut dyn Write) -> Result<(), Error> {
+    let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+    let opts = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("doc.kml", opts).map_err(io::Error::other)?;
+    zip.write_all(kml)?;
+    let bytes = zip.finish().map_err(io::Error::other)?.into_inner();
+    out.write_all(&bytes)?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Output writers of the extract command, one module per format.
+
+pub(crate) mod csv;
+pub(crate) mod geojson;
+pub(crate) mod gpx;
+pub(crate) mod html_map;
+pub(crate) mod influx;
+pub(crate) mod json;
+pub(crate) mod kml;
+#[cfg(feature = "mqtt")]
+pub(crate) mod mqtt;
+#[cfg(feature = "parquet")]
+pub(crate) mod parquet;
+pub(crate) mod pb;
+#[cfg(feature = "sqlite")]
+pub(crate) mod sqlite;
+pub(crate) mod srt;
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
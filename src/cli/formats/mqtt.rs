@@ -0,0 +1,138 @@
+//! Publishing frames to an MQTT broker.
+
+use std::io;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::formats::json::SeiRecord;
+use crate::cli::records::Sei;
+
+// Each event is published with QoS 1 as a JSON object carrying its `source_file`. Returns once
+// the broker has acknowledged every message.
+pub(crate) fn publish_mqtt(args: &ExtractArgs, inputs: &ExtractInputs) -> Result<bool, Error> {
+    use rumqttc::{Client, Event, MqttOptions, Outgoing, Packet, QoS};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    let url = args.mqtt.as_deref().unwrap_or_default();
+    let Some(broker) = parse_mqtt_url(url) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expected mqtt://[user:password@]host[:port], got {url}"),
+        )
+        .into());
+    };
+
+    let client_id = format!("tesla-sei-{}", std::process::id());
+    let mut opts = MqttOptions::new(client_id, broker.host, broker.port);
+    opts.set_keep_alive(Duration::from_secs(30));
+    if let Some((user, password)) = broker.credentials {
+        opts.set_credentials(user, password);
+    }
+    let (client, mut connection) = Client::new(opts, 64);
+
+    // The connection is driven on its own thread, which reports acknowledgements and the first
+    // connection error (rumqttc would otherwise keep reconnecting).
+    let (acks_tx, acks) = mpsc::channel();
+    let driver = std::thread::spawn(move || {
+        for event in connection.iter() {
+            let ack = match event {
+                Ok(Event::Incoming(Packet::PubAck(_))) => Ok(()),
+                Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                Ok(_) => continue,
+                Err(e) => Err(e),
+            };
+            let failed = ack.is_err();
+            if acks_tx.send(ack).is_err() || failed {
+                break;
+            }
+        }
+    });
+    let failed = |e| io::Error::other(format!("MQTT connection to {url} failed: {e}"));
+    // Why publishing stopped, once the driver thread has gone.
+    let gone = |acks: &mpsc::Receiver<Result<(), rumqttc::ConnectionError>>| {
+        let error = acks.try_iter().find_map(Result::err);
+        error.map_or_else(|| io::Error::other("MQTT connection closed"), failed)
+    };
+
+    let mut all_read = true;
+    let (mut published, mut acked) = (0usize, 0usize);
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let clip_started = Instant::now();
+        let source_file = file.display().to_string();
+        for item in input.events {
+            let (offset, msg) = item?;
+            if args.realtime
+                && let Some(offset) = offset
+            {
+                let due = clip_started + offset;
+                std::thread::sleep(due.saturating_duration_since(Instant::now()));
+            }
+            let payload = serde_json::to_vec(&SeiRecord {
+                source_file: Some(&source_file),
+                timestamp: args.frame_timestamp(input.start, offset),
+                sei: Sei::from_pb(args.convert_units(msg), args.enum_strings),
+                fields: &args.fields,
+                speed_unit: args.speed_unit(),
+                column_style: args.column_style,
+            })?;
+            if client
+                .publish(args.mqtt_topic.as_str(), QoS::AtLeastOnce, false, payload)
+                .is_err()
+            {
+                return Err(gone(&acks).into());
+            }
+            published += 1;
+            for ack in acks.try_iter() {
+                ack.map_err(failed)?;
+                acked += 1;
+            }
+        }
+    }
+
+    while acked < published {
+        match acks.recv() {
+            Ok(ack) => ack.map_err(failed)?,
+            Err(_) => return Err(gone(&acks).into()),
+        }
+        acked += 1;
+    }
+    let _ = client.disconnect();
+    let _ = driver.join();
+    Ok(all_read)
+}
+
+struct MqttBroker {
+    host: String,
+    port: u16,
+    // User name and password.
+    credentials: Option<(String, String)>,
+}
+
+// `mqtt://[user:password@]host[:port]`; `tcp://` also works.
+fn parse_mqtt_url(url: &str) -> Option<MqttBroker> {
+    let rest = url
+        .strip_prefix("mqtt://")
+        .or_else(|| url.strip_prefix("tcp://"))?;
+    let rest = rest.trim_end_matches('/');
+    let (credentials, address) = match rest.rsplit_once('@') {
+        Some((userinfo, address)) => {
+            let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+            (Some((user.to_string(), password.to_string())), address)
+        }
+        None => (None, rest),
+    };
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (address, 1883),
+    };
+    (!host.is_empty()).then(|| MqttBroker {
+        host: host.to_string(),
+        port,
+        credentials,
+    })
+}
@@ -0,0 +1,137 @@
+//! Apache Parquet output.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use arrow_array::builder::{StringBuilder, TimestampMicrosecondBuilder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int32Type;
+use arrow_array::{ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use tesla_sei::arrow::{metadata_to_record_batch, SEI_SCHEMA};
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::{autopilot_state_string, gear_state_string, ClipStart, TimedMetadata};
+
+const PARQUET_ROW_GROUP_ROWS: usize = 64 * 1024;
+
+// The clip's file name and UTC timestamp, then `SEI_SCHEMA` from `presentation_time_s` on, with
+// the two enums as strings for `-e`.
+fn parquet_schema(enum_strings: bool) -> SchemaRef {
+    let mut fields = vec![
+        Field::new("source_file", DataType::Utf8, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            true,
+        ),
+    ];
+    for field in SEI_SCHEMA.fields() {
+        match field.name().as_str() {
+            "sample_index" | "presentation_index" => {}
+            "gear_state" | "autopilot_state" if enum_strings => {
+                fields.push(Field::new(field.name(), DataType::Utf8, false));
+            }
+            _ => fields.push(field.as_ref().clone()),
+        }
+    }
+    Arc::new(Schema::new(fields))
+}
+
+// Rows buffered for the next row group.
+#[derive(Default)]
+struct RowGroup {
+    source_files: StringBuilder,
+    timestamps: TimestampMicrosecondBuilder,
+    records: Vec<TimedMetadata>,
+}
+
+impl RowGroup {
+    fn push(&mut self, source_file: &str, start: Option<ClipStart>, record: TimedMetadata) {
+        let timestamp = match (start, record.0) {
+            (Some(ClipStart::Utc(t)), Some(o)) => chrono::Duration::from_std(o)
+                .ok()
+                .map(|o| (t + o).timestamp_micros()),
+            _ => None,
+        };
+        self.source_files.append_value(source_file);
+        self.timestamps.append_option(timestamp);
+        self.records.push(record);
+    }
+
+    fn record_batch(
+        &mut self,
+        schema: &SchemaRef,
+        enum_strings: bool,
+    ) -> Result<RecordBatch, ArrowError> {
+        let metadata = metadata_to_record_batch(self.records.drain(..))?;
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(self.source_files.finish()),
+            Arc::new(self.timestamps.finish().with_timezone("UTC")),
+        ];
+        for (field, column) in metadata.schema().fields().iter().zip(metadata.columns()) {
+            let to_string = match field.name().as_str() {
+                "gear_state" if enum_strings => gear_state_string,
+                "autopilot_state" if enum_strings => autopilot_state_string,
+                _ => {
+                    columns.push(column.clone());
+                    continue;
+                }
+            };
+            let values = column.as_primitive::<Int32Type>().values();
+            let strings: StringArray = values.iter().map(|&v| Some(to_string(v))).collect();
+            columns.push(Arc::new(strings));
+        }
+        RecordBatch::try_new(schema.clone(), columns)
+    }
+}
+
+// Typed columns (enums as numbers unless `-e`), one row per frame, with the clip's file name
+// and, for MP4s with a creation time, a UTC timestamp. The writer spools each finished row
+// group into memory and hands it on, so memory stays bounded by the row group size.
+pub(crate) fn write_parquet(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let pq = io::Error::other;
+    let schema = parquet_schema(args.enum_strings);
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_max_row_group_size(PARQUET_ROW_GROUP_ROWS)
+        .build();
+    let mut writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(props)).map_err(pq)?;
+    let mut flush_row_group = |writer: &mut ArrowWriter<Vec<u8>>, rows: &mut RowGroup| {
+        let batch = rows
+            .record_batch(&schema, args.enum_strings)
+            .map_err(io::Error::other)?;
+        writer.write(&batch).map_err(pq)?;
+        writer.flush().map_err(pq)?;
+        out.write_all(&std::mem::take(writer.inner_mut()))
+    };
+
+    let mut all_read = true;
+    let mut rows = RowGroup::default();
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        let source_file = file.display().to_string();
+        for item in input.events {
+            rows.push(&source_file, input.start, item?);
+            if rows.records.len() == PARQUET_ROW_GROUP_ROWS {
+                flush_row_group(&mut writer, &mut rows)?;
+            }
+        }
+    }
+    if !rows.records.is_empty() {
+        flush_row_group(&mut writer, &mut rows)?;
+    }
+    out.write_all(&writer.into_inner().map_err(pq)?)?;
+    Ok(all_read)
+}
@@ -0,0 +1,35 @@
+//! Length-delimited protobuf output.
+
+use prost::Message;
+use std::io::Write;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+
+// The messages exactly as decoded, so nothing is lost to unit conversion or rounding. Batches
+// are concatenated without a marker between clips.
+pub(crate) fn write_pb(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let mut all_read = true;
+    let mut buf = Vec::new();
+    for file in &inputs.files {
+        let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+            continue;
+        };
+        for item in input.events {
+            let (_, msg) = item?;
+            buf.clear();
+            msg.encode_length_delimited(&mut buf)
+                .expect("a Vec grows to fit the message");
+            out.write_all(&buf)?;
+            if args.follow {
+                out.flush()?;
+            }
+        }
+    }
+    Ok(all_read)
+}
@@ -0,0 +1,211 @@
+//! SQLite output.
+
+use std::io;
+use std::path::Path;
+
+use tesla_sei::parse_clip_name;
+
+use crate::cli::args::ExtractArgs;
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::output::should_write_to_stdout;
+use crate::cli::records::{autopilot_state_string, gear_state_string, ClipStart};
+
+// A database replaces whatever is at `--output`, once it has been written in full: it is built
+// in a temporary file next to the output and renamed over it, so a failed run leaves the previous
+// database alone.
+pub(crate) fn write_sqlite(args: &ExtractArgs, inputs: &ExtractInputs) -> Result<bool, Error> {
+    let Some(path) = args
+        .output
+        .as_ref()
+        .filter(|_| !should_write_to_stdout(&args.output))
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--format sqlite needs --output FILE",
+        )
+        .into());
+    };
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = path.with_file_name(name);
+    match std::fs::remove_file(&tmp) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    match fill_sqlite(args, &tmp, inputs) {
+        Ok(all_read) => {
+            std::fs::rename(&tmp, path)?;
+            Ok(all_read)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            Err(e)
+        }
+    }
+}
+
+// Write the database to `path`, which must not exist yet. Batches also get a `clips` table (one
+// row per clip read, with its camera, TeslaCam folder (`SavedClips`, ...) and start times) that
+// telemetry rows reference by `clip_id`. Everything is inserted in one transaction.
+fn fill_sqlite(args: &ExtractArgs, path: &Path, inputs: &ExtractInputs) -> Result<bool, Error> {
+    use rusqlite::types::Value as SqlValue;
+    use tesla_sei::{CatalogClip, ClipSource};
+
+    let sql = io::Error::other;
+    let mut conn = rusqlite::Connection::open(path).map_err(sql)?;
+    let tx = conn.transaction().map_err(sql)?;
+    let enum_type = if args.enum_strings { "TEXT" } else { "INTEGER" };
+    if inputs.batch {
+        tx.execute_batch(
+            "CREATE TABLE clips (
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL,
+                camera TEXT,
+                source TEXT,
+                local_start TEXT,
+                creation_time TEXT,
+                frames INTEGER NOT NULL DEFAULT 0
+            );",
+        )
+        .map_err(sql)?;
+    }
+    tx.execute_batch(&format!(
+        "CREATE TABLE telemetry (
+            {clip_id}
+            presentation_time_s REAL,
+            version INTEGER NOT NULL,
+            gear_state {enum_type} NOT NULL,
+            frame_seq_no INTEGER NOT NULL,
+            vehicle_speed_mps REAL NOT NULL,
+            accelerator_pedal_position REAL NOT NULL,
+            steering_wheel_angle REAL NOT NULL,
+            blinker_on_left INTEGER NOT NULL,
+            blinker_on_right INTEGER NOT NULL,
+            brake_applied INTEGER NOT NULL,
+            autopilot_state {enum_type} NOT NULL,
+            latitude_deg REAL NOT NULL,
+            longitude_deg REAL NOT NULL,
+            heading_deg REAL NOT NULL,
+            linear_acceleration_mps2_x REAL NOT NULL,
+            linear_acceleration_mps2_y REAL NOT NULL,
+            linear_acceleration_mps2_z REAL NOT NULL
+        );",
+        clip_id = if inputs.batch {
+            "clip_id INTEGER NOT NULL REFERENCES clips(id),"
+        } else {
+            ""
+        }
+    ))
+    .map_err(sql)?;
+
+    let mut all_read = true;
+    {
+        let columns = if inputs.batch { 18 } else { 17 };
+        let placeholders = vec!["?"; columns].join(", ");
+        let mut insert = tx
+            .prepare(&format!("INSERT INTO telemetry VALUES ({placeholders})"))
+            .map_err(sql)?;
+        let mut clip_statements = if inputs.batch {
+            let insert = tx
+                .prepare(
+                    "INSERT INTO clips (path, camera, source, local_start, creation_time)
+                     VALUES (?, ?, ?, ?, ?)",
+                )
+                .map_err(sql)?;
+            let count_frames = tx
+                .prepare("UPDATE clips SET frames = ? WHERE id = ?")
+                .map_err(sql)?;
+            Some((insert, count_frames))
+        } else {
+            None
+        };
+
+        for file in &inputs.files {
+            let Some(input) = read_input(args, inputs, file, &mut all_read)? else {
+                continue;
+            };
+            let mut clip_id = None;
+            if let Some((insert_clip, _)) = &mut clip_statements {
+                let clip = CatalogClip {
+                    path: file.clone(),
+                    name: parse_clip_name(file),
+                };
+                let creation_time = match input.start {
+                    Some(ClipStart::Utc(t)) => Some(t.to_rfc3339()),
+                    _ => None,
+                };
+                insert_clip
+                    .execute(rusqlite::params![
+                        file.display().to_string(),
+                        clip.camera(),
+                        match clip.source() {
+                            ClipSource::Recent => Some("RecentClips"),
+                            ClipSource::Saved => Some("SavedClips"),
+                            ClipSource::Sentry => Some("SentryClips"),
+                            ClipSource::Other => None,
+                        },
+                        clip.start()
+                            .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()),
+                        creation_time,
+                    ])
+                    .map_err(sql)?;
+                clip_id = Some(tx.last_insert_rowid());
+            }
+
+            let mut frames = 0;
+            for item in input.events {
+                let (offset, m) = item?;
+                let (gear, autopilot) = if args.enum_strings {
+                    (
+                        SqlValue::Text(gear_state_string(m.gear_state)),
+                        SqlValue::Text(autopilot_state_string(m.autopilot_state)),
+                    )
+                } else {
+                    (
+                        SqlValue::Integer(m.gear_state.into()),
+                        SqlValue::Integer(m.autopilot_state.into()),
+                    )
+                };
+                let mut row = vec![
+                    offset.map_or(SqlValue::Null, |o| SqlValue::Real(o.as_secs_f64())),
+                    SqlValue::Integer(m.version.into()),
+                    gear,
+                    SqlValue::Integer(m.frame_seq_no as i64),
+                    SqlValue::Real(m.vehicle_speed_mps.into()),
+                    SqlValue::Real(m.accelerator_pedal_position.into()),
+                    SqlValue::Real(m.steering_wheel_angle.into()),
+                    SqlValue::Integer(m.blinker_on_left.into()),
+                    SqlValue::Integer(m.blinker_on_right.into()),
+                    SqlValue::Integer(m.brake_applied.into()),
+                    autopilot,
+                    SqlValue::Real(m.latitude_deg),
+                    SqlValue::Real(m.longitude_deg),
+                    SqlValue::Real(m.heading_deg),
+                    SqlValue::Real(m.linear_acceleration_mps2_x),
+                    SqlValue::Real(m.linear_acceleration_mps2_y),
+                    SqlValue::Real(m.linear_acceleration_mps2_z),
+                ];
+                if let Some(id) = clip_id {
+                    row.insert(0, SqlValue::Integer(id));
+                }
+                insert
+                    .execute(rusqlite::params_from_iter(row))
+                    .map_err(sql)?;
+                frames += 1;
+            }
+            if let (Some(id), Some((_, count_frames))) = (clip_id, &mut clip_statements) {
+                count_frames
+                    .execute(rusqlite::params![frames, id])
+                    .map_err(sql)?;
+            }
+        }
+    }
+    if inputs.batch {
+        tx.execute_batch("CREATE INDEX telemetry_clip ON telemetry (clip_id);")
+            .map_err(sql)?;
+    }
+    tx.commit().map_err(sql)?;
+    Ok(all_read)
+}
@@ -0,0 +1,120 @@
+//! SubRip subtitles, and the cue timing shared with the overlay.
+
+use std::io::{self, Write};
+
+use tesla_sei::pb;
+
+use crate::cli::args::{ExtractArgs, SpeedUnit};
+use crate::cli::error::Error;
+use crate::cli::extract::{read_input, ExtractInputs};
+use crate::cli::records::autopilot_state_string;
+
+// SubRip subtitles for one clip, cued at each frame's presentation time.
+pub(crate) fn write_srt(
+    args: &ExtractArgs,
+    inputs: &ExtractInputs,
+    out: &mut dyn Write,
+) -> Result<bool, Error> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let [file] = inputs.files.as_slice() else {
+        return Err(invalid("--format srt takes a single clip".into()).into());
+    };
+    let Some(input) = read_input(args, inputs, file, &mut true)? else {
+        unreachable!("a single input is never skipped");
+    };
+
+    let mut frames = Vec::new();
+    for item in input.events {
+        let (offset, m) = item?;
+        let Some(offset) = offset else {
+            return Err(invalid(format!(
+                "{} has no frame timing to align subtitles to",
+                file.display()
+            ))
+            .into());
+        };
+        frames.push((offset, srt_text(&m, args.units)));
+    }
+    for (n, (start, end, text)) in subtitle_cues(frames).iter().enumerate() {
+        writeln!(out, "{}", n + 1)?;
+        writeln!(out, "{} --> {}", srt_time(*start), srt_time(*end))?;
+        writeln!(out, "{text}")?;
+        writeln!(out)?;
+    }
+    Ok(true)
+}
+
+pub(crate) type Cue<T> = (std::time::Duration, std::time::Duration, T);
+
+// Turn per-frame content into (start, end, content) cues. Consecutive frames with equal
+// content share a cue, which keeps the file small and the overlay steady; each cue lasts until
+// the next different content, the last one for one more frame interval.
+pub(crate) fn subtitle_cues<T: PartialEq>(
+    mut frames: Vec<(std::time::Duration, T)>,
+) -> Vec<Cue<T>> {
+    // Frames come in decode order; with B-frames that isn't display order.
+    frames.sort_by_key(|(offset, _)| *offset);
+    let last_interval = match frames.as_slice() {
+        [.., (a, _), (b, _)] => *b - *a,
+        _ => std::time::Duration::from_secs(1),
+    };
+    let ends: Vec<_> = frames
+        .iter()
+        .skip(1)
+        .map(|(next, _)| *next)
+        .chain(frames.last().map(|(last, _)| *last + last_interval))
+        .collect();
+    let mut cues: Vec<Cue<T>> = Vec::new();
+    for ((start, content), end) in frames.into_iter().zip(ends) {
+        match cues.last_mut() {
+            Some(cue) if cue.2 == content => cue.1 = end,
+            _ => cues.push((start, end, content)),
+        }
+    }
+    cues
+}
+
+// The speed is shown in `unit`, or in both km/h and mph when --units wasn't given.
+fn srt_text(m: &pb::SeiMetadata, unit: Option<SpeedUnit>) -> String {
+    let speed = match unit {
+        Some(unit) => format!("{:.0} {}", unit.convert(m.vehicle_speed_mps), unit.label()),
+        None => format!(
+            "{:.0} km/h ({:.0} mph)",
+            SpeedUnit::Kmh.convert(m.vehicle_speed_mps),
+            SpeedUnit::Mph.convert(m.vehicle_speed_mps)
+        ),
+    };
+    let gear = match pb::sei_metadata::Gear::try_from(m.gear_state) {
+        Ok(pb::sei_metadata::Gear::Park) => "P",
+        Ok(pb::sei_metadata::Gear::Drive) => "D",
+        Ok(pb::sei_metadata::Gear::Reverse) => "R",
+        Ok(pb::sei_metadata::Gear::Neutral) => "N",
+        Err(_) => "?",
+    };
+    let autopilot = match pb::sei_metadata::AutopilotState::try_from(m.autopilot_state) {
+        Ok(pb::sei_metadata::AutopilotState::None) => "off".to_string(),
+        Ok(pb::sei_metadata::AutopilotState::SelfDriving) => "FSD".to_string(),
+        Ok(pb::sei_metadata::AutopilotState::Autosteer) => "Autosteer".to_string(),
+        Ok(pb::sei_metadata::AutopilotState::Tacc) => "TACC".to_string(),
+        Err(_) => autopilot_state_string(m.autopilot_state),
+    };
+    let blinkers = match (m.blinker_on_left, m.blinker_on_right) {
+        (true, true) => "  <<  >>",
+        (true, false) => "  <<",
+        (false, true) => "  >>",
+        (false, false) => "",
+    };
+    format!("{speed}  {gear}\nAutopilot: {autopilot}{blinkers}")
+}
+
+// `HH:MM:SS,mmm`
+fn srt_time(t: std::time::Duration) -> String {
+    let ms = t.as_millis();
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
@@ -0,0 +1,80 @@
+//! Expanding input arguments into clips, and the stdin copy that stands in for `-`.
+
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use tempfile::NamedTempFile;
+
+use tesla_sei::Catalog;
+
+use crate::cli::error::Error;
+
+// The clips named by some inputs.
+pub(crate) struct Inputs {
+    pub(crate) files: Vec<PathBuf>,
+    // Whether they make a batch (a directory, a pattern or several inputs) whose records need a
+    // `source_file` column.
+    pub(crate) batch: bool,
+    // `-` as an input: stdin copied to a temporary file, since clips are read with seeks. It is
+    // removed when the inputs are dropped.
+    pub(crate) stdin_copy: Option<NamedTempFile>,
+}
+
+fn copy_stdin() -> Result<NamedTempFile, Error> {
+    // The extension keeps the copy from being taken for a raw Annex B stream.
+    let copy = tempfile::Builder::new()
+        .prefix("tesla-sei-stdin-")
+        .suffix(".mp4")
+        .tempfile()?;
+    let mut file = BufWriter::new(copy.as_file());
+    let len = io::copy(&mut io::stdin().lock(), &mut file)?;
+    file.flush()?;
+    drop(file);
+    tracing::info!("read {len} bytes from stdin");
+    Ok(copy)
+}
+
+pub(crate) fn expand_inputs(inputs: &[PathBuf]) -> Result<Inputs, Error> {
+    let mut files = Vec::new();
+    let mut batch = inputs.len() > 1;
+    let mut stdin_copy = None;
+    for input in inputs {
+        if input.as_os_str() == "-" {
+            if stdin_copy.is_none() {
+                stdin_copy = Some(copy_stdin()?);
+            }
+            files.extend(stdin_copy.as_ref().map(|copy| copy.path().to_path_buf()));
+            continue;
+        }
+        if input.is_dir() {
+            let catalog = Catalog::scan(input)?;
+            tracing::info!("{}: {} clips", input.display(), catalog.clips().len());
+            files.extend(catalog.clips().iter().map(|c| c.path.clone()));
+            batch = true;
+            continue;
+        }
+        let pattern = input.to_string_lossy();
+        if input.exists() || !pattern.contains(['*', '?', '[']) {
+            files.push(input.clone());
+            continue;
+        }
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let mut matched: Vec<PathBuf> = glob::glob(&pattern)
+            .map_err(|e| invalid(format!("invalid pattern {pattern}: {e}")))?
+            .filter_map(Result::ok)
+            .filter(|p| p.is_file())
+            .collect();
+        if matched.is_empty() {
+            return Err(invalid(format!("no files match {pattern}")).into());
+        }
+        matched.sort();
+        tracing::info!("{pattern}: {} files", matched.len());
+        files.append(&mut matched);
+        batch = true;
+    }
+    Ok(Inputs {
+        files,
+        batch,
+        stdin_copy,
+    })
+}
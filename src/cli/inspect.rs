@@ -0,0 +1,392 @@
+//! Commands for looking inside one clip: info, events, migrate, boxes, debug-sei and raw-sei.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, Write};
+
+use tesla_sei::extract;
+use tesla_sei::info::{dump_box_tree, quick_look, BoxNode};
+use tesla_sei::schema::{read_json_export, write_json_export};
+use tesla_sei::{
+    driving_events_from_extractor, parse_clip_name, BlinkerSide, DecodeHeuristic, DrivingEvent,
+    DrivingEventKind, DrivingEventOptions,
+};
+
+use crate::cli::args::{
+    BoxesArgs, DebugSeiArgs, EventsArgs, InfoArgs, MigrateArgs, OutputFormat, RawSeiArgs,
+    TimeZoneArg,
+};
+use crate::cli::error::Error;
+use crate::cli::input::expand_inputs;
+use crate::cli::records::{
+    autopilot_state_string, clip_start, fmt_f64, gear_state_string, hex, ClipStart,
+};
+
+#[derive(Debug, Serialize)]
+struct EventRow {
+    file: String,
+    sample_index: usize,
+    /// Seconds into the clip.
+    time_s: Option<f64>,
+    /// Absolute time, as for extract --timestamp.
+    timestamp: Option<String>,
+    event: &'static str,
+    /// Peak of a hard braking or rapid acceleration run, in g.
+    peak_g: Option<f64>,
+    /// The blinker side, the autopilot mode engaged, or the gears changed between.
+    detail: Option<String>,
+}
+
+impl EventRow {
+    fn new(
+        file: String,
+        start: Option<ClipStart>,
+        zone: Option<TimeZoneArg>,
+        e: &DrivingEvent,
+    ) -> Self {
+        let (event, peak_g, detail) = match e.kind {
+            DrivingEventKind::HardBraking { peak_g } => ("hard_braking", Some(peak_g), None),
+            DrivingEventKind::RapidAcceleration { peak_g } => {
+                ("rapid_acceleration", Some(peak_g), None)
+            }
+            DrivingEventKind::AutopilotEngaged { state } => (
+                "autopilot_engaged",
+                None,
+                Some(autopilot_state_string(state)),
+            ),
+            DrivingEventKind::AutopilotDisengaged => ("autopilot_disengaged", None, None),
+            DrivingEventKind::Blinker { side } => (
+                "blinker",
+                None,
+                Some(match side {
+                    BlinkerSide::Left => "left".to_string(),
+                    BlinkerSide::Right => "right".to_string(),
+                }),
+            ),
+            DrivingEventKind::GearChange { from, to } => (
+                "gear_change",
+                None,
+                Some(format!(
+                    "{} -> {}",
+                    gear_state_string(from),
+                    gear_state_string(to)
+                )),
+            ),
+        };
+        EventRow {
+            file,
+            sample_index: e.sample_index,
+            time_s: e.presentation_time.map(|t| t.as_secs_f64()),
+            timestamp: start
+                .zip(e.presentation_time)
+                .and_then(|(start, t)| start.format_at(t, zone)),
+            event,
+            peak_g,
+            detail,
+        }
+    }
+}
+
+fn event_csv_header() -> &'static str {
+    "file,sample_index,time_s,timestamp,event,peak_g,detail"
+}
+
+#[derive(Debug, Default, Serialize)]
+struct InfoRow {
+    file: String,
+    /// Camera from the TeslaCam file name.
+    camera: Option<String>,
+    /// Whether the MP4 structure could be parsed for extraction.
+    valid: bool,
+    codec: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    frame_rate: Option<f64>,
+    duration_s: Option<f64>,
+    samples: Option<usize>,
+    /// Whether telemetry was found in the samples probed at each end of the clip.
+    has_telemetry: Option<bool>,
+    /// The SEI payload `version` field, which identifies the firmware's telemetry variant.
+    sei_version: Option<u32>,
+    /// Why the clip isn't valid, if it isn't.
+    error: Option<String>,
+}
+
+fn info_csv_header() -> &'static str {
+    "file,camera,valid,codec,width,height,frame_rate,duration_s,samples,has_telemetry,sei_version,error"
+}
+
+#[derive(Debug, Serialize)]
+struct RawSeiRow {
+    sample_index: usize,
+    file_offset: u64,
+    nal_type: u8,
+    payload_type: u32,
+    /// Payload bytes (emulation prevention removed) as lowercase hex.
+    payload: String,
+}
+
+fn raw_sei_csv_header() -> &'static str {
+    "sample_index,file_offset,nal_type,payload_type,payload"
+}
+
+pub(crate) fn run_info(args: &InfoArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let inputs = expand_inputs(&args.inputs)?;
+    let rows: Vec<InfoRow> = inputs
+        .files
+        .iter()
+        .map(|file| {
+            let row = InfoRow {
+                file: file.display().to_string(),
+                camera: parse_clip_name(file).and_then(|n| n.camera),
+                ..InfoRow::default()
+            };
+            match quick_look(file) {
+                Ok(look) => InfoRow {
+                    valid: true,
+                    codec: Some(look.track.codec.to_string()),
+                    width: look.track.resolution.map(|r| r.width),
+                    height: look.track.resolution.map(|r| r.height),
+                    frame_rate: look.track.frame_rate,
+                    duration_s: look.duration.map(|d| d.as_secs_f64()),
+                    samples: Some(look.track.sample_count),
+                    has_telemetry: Some(look.has_telemetry),
+                    sei_version: look.firmware_variant,
+                    ..row
+                },
+                Err(e) => InfoRow {
+                    error: Some(e.to_string()),
+                    ..row
+                },
+            }
+        })
+        .collect();
+
+    match args.format {
+        OutputFormat::Json => write_json_export(out, &rows)?,
+        OutputFormat::Csv => {
+            let opt = |v: Option<String>| v.unwrap_or_default();
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(info_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.file,
+                    opt(r.camera),
+                    r.valid.to_string(),
+                    opt(r.codec),
+                    opt(r.width.map(|v| v.to_string())),
+                    opt(r.height.map(|v| v.to_string())),
+                    opt(r.frame_rate.map(fmt_f64)),
+                    opt(r.duration_s.map(fmt_f64)),
+                    opt(r.samples.map(|v| v.to_string())),
+                    opt(r.has_telemetry.map(|v| v.to_string())),
+                    opt(r.sei_version.map(|v| v.to_string())),
+                    opt(r.error),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+// Returns false if any clip couldn't be read; those are reported on stderr and skipped.
+pub(crate) fn run_events(args: &EventsArgs, out: &mut dyn Write) -> Result<bool, Error> {
+    let inputs = expand_inputs(&args.inputs)?;
+    let opts = DrivingEventOptions {
+        hard_braking_g: args.braking_g,
+        rapid_acceleration_g: args.acceleration_g,
+    };
+    let mut all_read = true;
+    let mut rows: Vec<EventRow> = Vec::new();
+    for file in &inputs.files {
+        let read = extract::extractor_from_path(file).and_then(|mut extractor| {
+            let events = driving_events_from_extractor(&mut extractor, &opts)?;
+            Ok((clip_start(file, &extractor), events))
+        });
+        let (start, events) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                tracing::warn!("skipping {}: {e}", file.display());
+                all_read = false;
+                continue;
+            }
+        };
+        let name = file.display().to_string();
+        rows.extend(
+            events
+                .iter()
+                .map(|e| EventRow::new(name.clone(), start, args.timezone, e)),
+        );
+    }
+
+    match args.format {
+        OutputFormat::Json => write_json_export(out, &rows)?,
+        OutputFormat::Csv => {
+            let opt = |v: Option<String>| v.unwrap_or_default();
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(event_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.file,
+                    r.sample_index.to_string(),
+                    opt(r.time_s.map(fmt_f64)),
+                    opt(r.timestamp),
+                    r.event.to_string(),
+                    opt(r.peak_g.map(fmt_f64)),
+                    opt(r.detail),
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(all_read)
+}
+
+pub(crate) fn run_migrate(args: &MigrateArgs, out: &mut dyn Write) -> Result<(), Error> {
+    // Records are carried through as raw JSON so every export kind (telemetry, near, routes,
+    // stats) migrates the same way.
+    let (_version, records) = read_json_export::<Value>(File::open(&args.input)?)?;
+    Ok(write_json_export(out, &records)?)
+}
+
+pub(crate) fn run_boxes(args: &BoxesArgs, out: &mut dyn Write) -> Result<(), Error> {
+    fn write_nodes(out: &mut dyn Write, nodes: &[BoxNode], depth: usize) -> io::Result<()> {
+        for node in nodes {
+            write!(
+                out,
+                "{:indent$}{} offset={} size={}",
+                "",
+                node.box_type,
+                node.offset,
+                node.size,
+                indent = depth * 2
+            )?;
+            if let Some(e) = &node.error {
+                write!(out, " ERROR: {e}")?;
+            }
+            writeln!(out)?;
+            write_nodes(out, &node.children, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    let tree = dump_box_tree(File::open(&args.input)?)?;
+    write_nodes(out, &tree, 0)?;
+    Ok(())
+}
+
+pub(crate) fn run_debug_sei(args: &DebugSeiArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let mut extractor = extract::extractor_from_path(&args.input)?;
+    let samples: Vec<usize> = if args.samples.is_empty() {
+        (0..extractor.total_samples()).collect()
+    } else {
+        args.samples.clone()
+    };
+    for sample_index in samples {
+        let messages: Vec<_> = extractor
+            .read_sample_raw_sei(sample_index)?
+            .into_iter()
+            .filter(|m| {
+                args.payload_types.is_empty() || args.payload_types.contains(&m.payload_type)
+            })
+            .collect();
+        let Some(first) = messages.first() else {
+            writeln!(out, "sample {sample_index}: no SEI")?;
+            continue;
+        };
+        writeln!(
+            out,
+            "sample {sample_index} offset {}: {} SEI messages",
+            first.file_offset,
+            messages.len()
+        )?;
+        for m in &messages {
+            let uuid = m.uuid().map_or("-".to_string(), |u| hex(&u));
+            writeln!(
+                out,
+                "  nal_type {} payload_type {} size {} uuid {uuid}",
+                m.nal_type,
+                m.payload_type,
+                m.payload.len()
+            )?;
+            let candidates = m.decode_candidates();
+            let used = candidates.iter().position(|c| c.decodes);
+            let notes: Vec<String> = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let name = match c.heuristic {
+                        DecodeHeuristic::MagicPrefix => "magic_prefix",
+                        DecodeHeuristic::UuidSkip => "uuid_skip",
+                        DecodeHeuristic::RawPayload => "raw_payload",
+                        DecodeHeuristic::TagScan => "tag_scan",
+                    };
+                    let result = match (Some(i) == used, c.decodes) {
+                        (true, _) => " (used)",
+                        (false, true) => " (decodes)",
+                        (false, false) => "",
+                    };
+                    format!("{name}@{}{result}", c.offset)
+                })
+                .collect();
+            writeln!(out, "  candidates: {}", notes.join(", "))?;
+            // A '>' in front of a byte marks a candidate offset.
+            let marked: Vec<usize> = candidates.iter().map(|c| c.offset).collect();
+            for (row, chunk) in m.payload.chunks(16).enumerate() {
+                let mut line = format!("    {:04x} ", row * 16);
+                for (i, b) in chunk.iter().enumerate() {
+                    let at = row * 16 + i;
+                    line.push(if marked.contains(&at) { '>' } else { ' ' });
+                    line.push_str(&format!("{b:02x}"));
+                }
+                line.push_str(&" ".repeat((16 - chunk.len()) * 3));
+                let ascii: String = chunk
+                    .iter()
+                    .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+                    .collect();
+                writeln!(out, "{line}  |{ascii}|")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn run_raw_sei(args: &RawSeiArgs, out: &mut dyn Write) -> Result<(), Error> {
+    let mut extractor = extract::extractor_from_path(&args.input)?;
+    let mut rows = Vec::new();
+    for message in extractor.iter_raw_sei() {
+        let m = message?;
+        if !args.payload_types.is_empty() && !args.payload_types.contains(&m.payload_type) {
+            continue;
+        }
+        rows.push(RawSeiRow {
+            sample_index: m.sample_index,
+            file_offset: m.file_offset,
+            nal_type: m.nal_type,
+            payload_type: m.payload_type,
+            payload: hex(&m.payload),
+        });
+    }
+
+    match args.format {
+        OutputFormat::Json => write_json_export(out, &rows)?,
+        OutputFormat::Csv => {
+            let mut csv = args.csv_dialect.writer(out);
+            csv.record(raw_sei_csv_header().split(','))?;
+            for r in rows {
+                csv.record([
+                    r.sample_index.to_string(),
+                    r.file_offset.to_string(),
+                    r.nal_type.to_string(),
+                    r.payload_type.to_string(),
+                    r.payload,
+                ])?;
+            }
+            csv.flush()?;
+        }
+    }
+    Ok(())
+}
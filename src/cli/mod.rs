@@ -0,0 +1,30 @@
+//! The `tesla-sei` command line: argument definitions, the extract pipeline and its output
+//! formats, and the other subcommands. `main.rs` only parses the arguments and dispatches.
+
+pub(crate) mod args;
+pub(crate) mod catalog;
+pub(crate) mod compare;
+pub(crate) mod edit;
+pub(crate) mod error;
+pub(crate) mod extract;
+pub(crate) mod follow;
+pub(crate) mod formats;
+pub(crate) mod input;
+pub(crate) mod inspect;
+pub(crate) mod output;
+pub(crate) mod overlay;
+pub(crate) mod records;
+#[cfg(any(feature = "grpc", feature = "rest"))]
+pub(crate) mod serve;
+pub(crate) mod validate;
+
+use std::process::ExitCode;
+
+// How a run that got to the end exits: 1 if a clip couldn't be read or failed a check.
+pub(crate) fn exit_status(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
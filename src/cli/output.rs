@@ -0,0 +1,108 @@
+//! Where command output goes: stdout or a file, optionally compressed.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::cli::args::{Compression, ExtractArgs};
+use crate::cli::error::Error;
+
+pub(crate) fn should_write_to_stdout(output: &Option<PathBuf>) -> bool {
+    match output {
+        None => true,
+        Some(p) => p.as_os_str() == "-",
+    }
+}
+
+impl ExtractArgs {
+    // `--compact` or `--pretty`, else pretty only for people reading stdout.
+    pub(crate) fn pretty_json(&self) -> bool {
+        self.pretty
+            || !self.compact
+                && self.output_template.is_none()
+                && should_write_to_stdout(&self.output)
+    }
+}
+
+pub(crate) fn with_output(
+    output: &Option<PathBuf>,
+    run: impl FnOnce(&mut dyn Write) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if should_write_to_stdout(output) {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        run(&mut out)?;
+        out.flush()?;
+    } else {
+        let path = output.as_ref().unwrap();
+        let file = File::create(path)?;
+        let mut out = BufWriter::new(file);
+        run(&mut out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// `with_output` for extract, writing through the `--compress` encoder.
+pub(crate) fn with_extract_output(
+    args: &ExtractArgs,
+    output: &Option<PathBuf>,
+    run: impl FnOnce(&mut dyn Write) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let compression = args.compression(output);
+    with_output(output, |out| {
+        let mut out = Encoder::new(out, compression)?;
+        run(&mut out)?;
+        out.finish()
+    })
+}
+
+enum Encoder<'a> {
+    Plain(&'a mut dyn Write),
+    Gzip(flate2::write::GzEncoder<&'a mut dyn Write>),
+    Zstd(zstd::Encoder<'static, &'a mut dyn Write>),
+}
+
+impl<'a> Encoder<'a> {
+    fn new(out: &'a mut dyn Write, compression: Compression) -> Result<Self, Error> {
+        Ok(match compression {
+            Compression::None => Encoder::Plain(out),
+            Compression::Gzip => {
+                Encoder::Gzip(flate2::write::GzEncoder::new(out, Default::default()))
+            }
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(out, 0)?),
+        })
+    }
+
+    // Write the compressed stream's trailer; the output is truncated without it.
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            Encoder::Plain(_) => {}
+            Encoder::Gzip(gzip) => {
+                gzip.finish()?;
+            }
+            Encoder::Zstd(zstd) => {
+                zstd.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Encoder<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(out) => out.write(buf),
+            Encoder::Gzip(gzip) => gzip.write(buf),
+            Encoder::Zstd(zstd) => zstd.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(out) => out.flush(),
+            Encoder::Gzip(gzip) => gzip.flush(),
+            Encoder::Zstd(zstd) => zstd.flush(),
+        }
+    }
+}
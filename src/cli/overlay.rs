@@ -0,0 +1,209 @@
+//! The overlay command: an ASS subtitle HUD, optionally burned into the video.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use tesla_sei::extract;
+use tesla_sei::pb;
+
+use crate::cli::args::{OverlayArgs, SpeedUnit};
+use crate::cli::error::Error;
+use crate::cli::formats::srt::{subtitle_cues, Cue};
+use crate::cli::output::with_output;
+use crate::cli::records::autopilot_state_string;
+
+// What the HUD shows at one frame; frames that look the same share a subtitle event.
+#[derive(Debug, PartialEq)]
+struct HudState {
+    speed: i64,
+    gear: i32,
+    autopilot: i32,
+    left: bool,
+    right: bool,
+    brake: bool,
+}
+
+pub(crate) fn run_overlay(args: &OverlayArgs) -> Result<(), Error> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let mut extractor = extract::extractor_from_path(&args.input)?;
+    let (width, height) = extractor
+        .resolution()
+        .map_or((1280, 960), |r| (r.width, r.height));
+    let mut frames = Vec::new();
+    while let Some(event) = extractor.next_event()? {
+        let Some(offset) = event.presentation_time else {
+            return Err(invalid(format!(
+                "{} has no frame timing to align the overlay to",
+                args.input.display()
+            ))
+            .into());
+        };
+        let m = &event.metadata;
+        frames.push((
+            offset,
+            HudState {
+                speed: args.units.convert(m.vehicle_speed_mps).round() as i64,
+                gear: m.gear_state,
+                autopilot: m.autopilot_state,
+                left: m.blinker_on_left,
+                right: m.blinker_on_right,
+                brake: m.brake_applied,
+            },
+        ));
+    }
+    let cues = subtitle_cues(frames);
+
+    let ass_path = match (&args.output, &args.burn) {
+        (Some(path), _) => Some(path.clone()),
+        (None, Some(video)) => Some(video.with_extension("ass")),
+        (None, None) => None,
+    };
+    if args.burn.is_some() && ass_path.as_deref() == Some(Path::new("-")) {
+        return Err(invalid("--burn needs the .ass file on disk; give -o FILE".into()).into());
+    }
+    with_output(&ass_path, |out| {
+        write_ass(out, width, height, args.units, &cues)?;
+        Ok(())
+    })?;
+
+    if let (Some(video), Some(ass_path)) = (&args.burn, &ass_path) {
+        burn_subtitles(&args.ffmpeg, &args.input, ass_path, video)?;
+    }
+    Ok(())
+}
+
+// Advanced SubStation Alpha script with a HUD along the bottom of the frame: speed in the
+// middle flanked by the blinker arrows, the gear selector (current gear highlighted) and the
+// autopilot mode below, and a red BRAKE tag while the pedal is down. Sizes scale with the
+// clip's resolution through PlayResX/PlayResY.
+fn write_ass(
+    out: &mut dyn Write,
+    width: u32,
+    height: u32,
+    unit: SpeedUnit,
+    cues: &[Cue<HudState>],
+) -> io::Result<()> {
+    let big = height / 12;
+    let small = height / 28;
+    writeln!(out, "[Script Info]")?;
+    writeln!(out, "ScriptType: v4.00+")?;
+    writeln!(out, "PlayResX: {width}")?;
+    writeln!(out, "PlayResY: {height}")?;
+    writeln!(out, "ScaledBorderAndShadow: yes")?;
+    writeln!(out)?;
+    writeln!(out, "[V4+ Styles]")?;
+    writeln!(
+        out,
+        "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, \
+         BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+         BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding"
+    )?;
+    // Colours are &HAABBGGRR.
+    for (name, size, colour, margin_v) in [
+        ("Speed", big, "&H00FFFFFF", small * 3),
+        ("Info", small, "&H00FFFFFF", small),
+    ] {
+        writeln!(
+            out,
+            "Style: {name},Arial,{size},{colour},&H00FFFFFF,&H00000000,&H80000000,-1,0,0,0,\
+             100,100,0,0,1,2,1,2,10,10,{margin_v},1"
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "[Events]")?;
+    writeln!(
+        out,
+        "Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text"
+    )?;
+
+    const DIM: &str = "{\\c&H808080&}";
+    const LIT: &str = "{\\c&HFFFFFF&}";
+    const GREEN: &str = "{\\c&H00D000&}";
+    const HIDDEN: &str = "{\\alpha&HFF&}";
+    const SHOWN: &str = "{\\alpha&H00&}";
+    for (start, end, hud) in cues {
+        let (start, end) = (ass_time(*start), ass_time(*end));
+        // Hidden arrows keep the speed centred whichever blinker is on.
+        let arrow = |on: bool| if on { SHOWN } else { HIDDEN };
+        writeln!(
+            out,
+            "Dialogue: 0,{start},{end},Speed,,0,0,0,,{GREEN}{}◀{LIT}{SHOWN} {} {GREEN}{}▶\\N\
+             {LIT}{SHOWN}{{\\fs{small}}}{}",
+            arrow(hud.left),
+            hud.speed,
+            arrow(hud.right),
+            unit.label()
+        )?;
+        let gears: Vec<String> = [
+            ("P", pb::sei_metadata::Gear::Park),
+            ("R", pb::sei_metadata::Gear::Reverse),
+            ("N", pb::sei_metadata::Gear::Neutral),
+            ("D", pb::sei_metadata::Gear::Drive),
+        ]
+        .iter()
+        .map(|(label, gear)| {
+            let colour = if hud.gear == *gear as i32 { LIT } else { DIM };
+            format!("{colour}{label}")
+        })
+        .collect();
+        let autopilot = match pb::sei_metadata::AutopilotState::try_from(hud.autopilot) {
+            Ok(pb::sei_metadata::AutopilotState::None) => String::new(),
+            Ok(pb::sei_metadata::AutopilotState::SelfDriving) => "FSD".to_string(),
+            Ok(pb::sei_metadata::AutopilotState::Autosteer) => "Autosteer".to_string(),
+            Ok(pb::sei_metadata::AutopilotState::Tacc) => "TACC".to_string(),
+            Err(_) => autopilot_state_string(hud.autopilot),
+        };
+        let mut info = gears.join(" ");
+        if !autopilot.is_empty() {
+            info.push_str(&format!("   {{\\c&HFF9E3E&}}{autopilot}"));
+        }
+        if hud.brake {
+            info.push_str("   {\\c&H3030FF&}BRAKE");
+        }
+        writeln!(out, "Dialogue: 0,{start},{end},Info,,0,0,0,,{info}")?;
+    }
+    Ok(())
+}
+
+// ASS times are H:MM:SS.cc.
+fn ass_time(t: std::time::Duration) -> String {
+    let cs = t.as_millis() / 10;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        cs / 360_000,
+        cs / 6000 % 60,
+        cs / 100 % 60,
+        cs % 100
+    )
+}
+
+fn burn_subtitles(ffmpeg: &Path, input: &Path, ass: &Path, output: &Path) -> Result<(), Error> {
+    // Quote the path for the filter graph; a quote inside is closed, escaped and reopened.
+    let ass = ass
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace('\'', "'\\''");
+    let status = std::process::Command::new(ffmpeg)
+        .arg("-y")
+        .arg("-i")
+        .arg(input)
+        .arg("-vf")
+        .arg(format!("ass=filename='{ass}'"))
+        .args(["-c:a", "copy"])
+        .arg(output)
+        .status()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                e.kind(),
+                format!(
+                    "{} not found; install ffmpeg or pass --ffmpeg PATH",
+                    ffmpeg.display()
+                ),
+            ),
+            _ => e,
+        })?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {status}", ffmpeg.display())).into());
+    }
+    Ok(())
+}
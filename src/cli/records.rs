@@ -0,0 +1,236 @@
+//! Telemetry records as the extract outputs present them: field values, enum names and times.
+
+use serde::ser::SerializeMap;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use tesla_sei::extract::SeiExtractor;
+use tesla_sei::{parse_clip_name, pb};
+
+use crate::cli::args::{ColumnStyle, ExtractArgs, SeiField, SpeedUnit, TimeZoneArg};
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Sei {
+    version: u32,
+    pub(crate) gear_state: Value,
+    frame_seq_no: u64,
+    vehicle_speed_mps: f32,
+    accelerator_pedal_position: f32,
+    steering_wheel_angle: f32,
+    blinker_on_left: bool,
+    blinker_on_right: bool,
+    brake_applied: bool,
+    pub(crate) autopilot_state: Value,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    heading_deg: f64,
+    linear_acceleration_mps2_x: f64,
+    linear_acceleration_mps2_y: f64,
+    linear_acceleration_mps2_z: f64,
+}
+
+impl Sei {
+    pub(crate) fn serialize_field<M: SerializeMap>(
+        &self,
+        field: SeiField,
+        speed_unit: SpeedUnit,
+        style: ColumnStyle,
+        map: &mut M,
+    ) -> Result<(), M::Error> {
+        let name = field.column(speed_unit, style);
+        match field {
+            SeiField::Version => map.serialize_entry(&name, &self.version),
+            SeiField::GearState => map.serialize_entry(&name, &self.gear_state),
+            SeiField::FrameSeqNo => map.serialize_entry(&name, &self.frame_seq_no),
+            SeiField::VehicleSpeedMps => map.serialize_entry(&name, &self.vehicle_speed_mps),
+            SeiField::AcceleratorPedalPosition => {
+                map.serialize_entry(&name, &self.accelerator_pedal_position)
+            }
+            SeiField::SteeringWheelAngle => map.serialize_entry(&name, &self.steering_wheel_angle),
+            SeiField::BlinkerOnLeft => map.serialize_entry(&name, &self.blinker_on_left),
+            SeiField::BlinkerOnRight => map.serialize_entry(&name, &self.blinker_on_right),
+            SeiField::BrakeApplied => map.serialize_entry(&name, &self.brake_applied),
+            SeiField::AutopilotState => map.serialize_entry(&name, &self.autopilot_state),
+            SeiField::LatitudeDeg => map.serialize_entry(&name, &self.latitude_deg),
+            SeiField::LongitudeDeg => map.serialize_entry(&name, &self.longitude_deg),
+            SeiField::HeadingDeg => map.serialize_entry(&name, &self.heading_deg),
+            SeiField::LinearAccelerationMps2X => {
+                map.serialize_entry(&name, &self.linear_acceleration_mps2_x)
+            }
+            SeiField::LinearAccelerationMps2Y => {
+                map.serialize_entry(&name, &self.linear_acceleration_mps2_y)
+            }
+            SeiField::LinearAccelerationMps2Z => {
+                map.serialize_entry(&name, &self.linear_acceleration_mps2_z)
+            }
+        }
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn gear_state_string(v: i32) -> String {
+    match pb::sei_metadata::Gear::try_from(v) {
+        Ok(e) => e.as_str_name().to_string(),
+        Err(_) => format!("UNKNOWN({v})"),
+    }
+}
+
+pub(crate) fn autopilot_state_string(v: i32) -> String {
+    match pb::sei_metadata::AutopilotState::try_from(v) {
+        Ok(e) => e.as_str_name().to_string(),
+        Err(_) => format!("UNKNOWN({v})"),
+    }
+}
+
+pub(crate) fn fmt_f32(v: f32) -> String {
+    // Print with high decimal precision for downstream ML/analysis.
+    // Cast to f64 to expose the exact stored f32 value (common desire for telemetry).
+    format!("{:.15}", v as f64)
+}
+
+pub(crate) fn fmt_f64(v: f64) -> String {
+    format!("{:.15}", v)
+}
+
+impl From<pb::SeiMetadata> for Sei {
+    fn from(m: pb::SeiMetadata) -> Self {
+        Sei {
+            version: m.version,
+            gear_state: Value::Number(Number::from(m.gear_state)),
+            frame_seq_no: m.frame_seq_no,
+            vehicle_speed_mps: m.vehicle_speed_mps,
+            accelerator_pedal_position: m.accelerator_pedal_position,
+            steering_wheel_angle: m.steering_wheel_angle,
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: Value::Number(Number::from(m.autopilot_state)),
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+        }
+    }
+}
+
+impl Sei {
+    pub(crate) fn from_pb(m: pb::SeiMetadata, enum_strings: bool) -> Self {
+        if !enum_strings {
+            return m.into();
+        }
+
+        Sei {
+            version: m.version,
+            gear_state: Value::String(gear_state_string(m.gear_state)),
+            frame_seq_no: m.frame_seq_no,
+            vehicle_speed_mps: m.vehicle_speed_mps,
+            accelerator_pedal_position: m.accelerator_pedal_position,
+            steering_wheel_angle: m.steering_wheel_angle,
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: Value::String(autopilot_state_string(m.autopilot_state)),
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+        }
+    }
+}
+
+// One decoded message and its presentation time, when the input has a timeline.
+pub(crate) type TimedMetadata = (Option<std::time::Duration>, pb::SeiMetadata);
+
+// Wall-clock time of a clip's first frame.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ClipStart {
+    // From the MP4 header.
+    Utc(chrono::DateTime<chrono::Utc>),
+    // From a TeslaCam file name, in the car's local time.
+    Local(chrono::NaiveDateTime),
+}
+
+impl ClipStart {
+    // ISO 8601 time of the frame `offset` into the clip. Without a zone, UTC times get a `Z`
+    // suffix and file-name times none; with one, both are given in that zone with its offset,
+    // file-name times taken to be that zone's local time.
+    pub(crate) fn format_at(
+        self,
+        offset: std::time::Duration,
+        zone: Option<TimeZoneArg>,
+    ) -> Option<String> {
+        let offset = chrono::Duration::from_std(offset).ok()?;
+        let Some(zone) = zone else {
+            return Some(match self {
+                ClipStart::Utc(t) => (t + offset).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+                ClipStart::Local(t) => (t + offset).format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+            });
+        };
+        match zone {
+            TimeZoneArg::Utc => self.in_zone(&chrono::Utc, offset, "%Y-%m-%dT%H:%M:%S%.3fZ"),
+            TimeZoneArg::Local => self.in_zone(&chrono::Local, offset, ZONED_TIME_FORMAT),
+            TimeZoneArg::Named(tz) => self.in_zone(&tz, offset, ZONED_TIME_FORMAT),
+        }
+    }
+
+    fn in_zone<Tz: chrono::TimeZone>(
+        self,
+        zone: &Tz,
+        offset: chrono::Duration,
+        format: &str,
+    ) -> Option<String>
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        let start = match self {
+            ClipStart::Utc(t) => t.with_timezone(zone),
+            // A time skipped by a DST change doesn't exist; a repeated one takes its first
+            // occurrence.
+            ClipStart::Local(t) => zone.from_local_datetime(&t).earliest()?,
+        };
+        Some((start + offset).format(format).to_string())
+    }
+}
+
+const ZONED_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+impl ExtractArgs {
+    // ISO 8601 time of a frame `offset` into a clip starting at `start`, in the --timezone.
+    pub(crate) fn frame_time(
+        &self,
+        start: Option<ClipStart>,
+        offset: Option<std::time::Duration>,
+    ) -> Option<String> {
+        let (start, offset) = start.zip(offset)?;
+        start.format_at(offset, self.timezone)
+    }
+
+    // The `--timestamp` value of a frame, if the column is wanted.
+    pub(crate) fn frame_timestamp(
+        &self,
+        start: Option<ClipStart>,
+        offset: Option<std::time::Duration>,
+    ) -> Option<Option<String>> {
+        self.timestamp.then(|| self.frame_time(start, offset))
+    }
+}
+
+// When a clip starts: the MP4 creation time, or else the TeslaCam file name's local time.
+pub(crate) fn clip_start<R: Read + Seek>(
+    file: &Path,
+    extractor: &SeiExtractor<R>,
+) -> Option<ClipStart> {
+    extractor
+        .creation_time()
+        .map(ClipStart::Utc)
+        .or_else(|| parse_clip_name(file).map(|n| ClipStart::Local(n.start)))
+}
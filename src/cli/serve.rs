@@ -0,0 +1,47 @@
+//! The gRPC and REST servers.
+
+use std::io;
+
+#[cfg(feature = "rest")]
+use crate::cli::args::ServeArgs;
+#[cfg(feature = "grpc")]
+use crate::cli::args::ServeGrpcArgs;
+use crate::cli::error::Error;
+
+#[cfg(feature = "grpc")]
+pub(crate) fn run_serve_grpc(args: &ServeGrpcArgs) -> Result<(), Error> {
+    let options = tesla_sei::grpc::GrpcOptions {
+        root: args.root.clone(),
+        max_upload: args.max_upload_mb.saturating_mul(1024 * 1024),
+        ..Default::default()
+    };
+    if let Some(root) = &options.root
+        && !root.is_dir()
+    {
+        return Err(io::Error::other(format!("{}: not a directory", root.display())).into());
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    tracing::info!("serving gRPC on {}", args.listen);
+    Ok(runtime.block_on(tesla_sei::grpc::serve(args.listen, options))?)
+}
+
+#[cfg(feature = "rest")]
+pub(crate) fn run_serve(args: &ServeArgs) -> Result<(), Error> {
+    let options = tesla_sei::rest::RestOptions {
+        root: args.root.clone(),
+        max_upload: args.max_upload_mb.saturating_mul(1024 * 1024),
+        ..Default::default()
+    };
+    if let Some(root) = &options.root
+        && !root.is_dir()
+    {
+        return Err(io::Error::other(format!("{}: not a directory", root.display())).into());
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    tracing::info!("serving HTTP on {}", args.listen);
+    Ok(runtime.block_on(tesla_sei::rest::serve(args.listen, options))?)
+}
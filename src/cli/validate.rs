@@ -0,0 +1,251 @@
+//! The validate command.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use tesla_sei::extract::{self, ExtractOptions, SampleTableRepair, SeiExtractor};
+use tesla_sei::info::{dump_box_tree, BoxNode};
+
+use crate::cli::args::{ReportFormat, ValidateArgs};
+use crate::cli::error::Error;
+
+#[derive(Debug, Default, Serialize)]
+struct ValidateReport {
+    file: String,
+    ok: bool,
+    samples: usize,
+    samples_with_telemetry: usize,
+    /// SEI messages of the telemetry payload type examined, and how many decoded.
+    sei_messages: usize,
+    decoded_messages: usize,
+    /// `decoded_messages / sei_messages`; `None` without SEI.
+    decode_rate: Option<f64>,
+    /// Skipped or repeated frame_seq_no values.
+    frame_seq_breaks: usize,
+    problems: Vec<ValidateProblem>,
+}
+
+#[derive(Debug, Serialize)]
+struct ValidateProblem {
+    /// `container`, `sample_tables`, `sei_syntax`, `telemetry` or `frame_sequence`.
+    check: &'static str,
+    sample_index: Option<usize>,
+    file_offset: Option<u64>,
+    message: String,
+}
+
+impl ValidateProblem {
+    fn new(check: &'static str, message: String) -> Self {
+        ValidateProblem {
+            check,
+            sample_index: None,
+            file_offset: None,
+            message,
+        }
+    }
+}
+
+// Returns whether the file is clean.
+pub(crate) fn run_validate(args: &ValidateArgs, out: &mut dyn Write) -> Result<bool, Error> {
+    let report = validate_clip(&args.input)?;
+    match args.format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &report)?;
+            writeln!(out)?;
+        }
+        ReportFormat::Text => {
+            for p in &report.problems {
+                match (p.check, p.sample_index, p.file_offset) {
+                    ("sei_syntax", Some(sample), Some(offset)) => {
+                        writeln!(out, "sample {sample} offset {offset} {}", p.message)?
+                    }
+                    (check, Some(sample), _) => {
+                        writeln!(out, "{check}: sample {sample}: {}", p.message)?
+                    }
+                    (check, None, Some(offset)) => {
+                        writeln!(out, "{check}: offset {offset}: {}", p.message)?
+                    }
+                    (check, None, None) => writeln!(out, "{check}: {}", p.message)?,
+                }
+            }
+            let violations: Vec<usize> = report
+                .problems
+                .iter()
+                .filter(|p| p.check == "sei_syntax")
+                .filter_map(|p| p.sample_index)
+                .collect();
+            let mut samples = violations.clone();
+            samples.dedup();
+            writeln!(
+                out,
+                "{} violations in {} of {} samples",
+                violations.len(),
+                samples.len(),
+                report.samples
+            )?;
+            writeln!(
+                out,
+                "telemetry in {} of {} samples, {} of {} SEI messages decoded, {} frame sequence breaks",
+                report.samples_with_telemetry,
+                report.samples,
+                report.decoded_messages,
+                report.sei_messages,
+                report.frame_seq_breaks
+            )?;
+            writeln!(
+                out,
+                "{}",
+                if report.ok {
+                    "ok".to_string()
+                } else {
+                    format!("{} problems", report.problems.len())
+                }
+            )?;
+        }
+    }
+    Ok(report.ok)
+}
+
+// Run every check on one clip. Problems with the file's contents go in the report; only IO
+// errors are returned.
+fn validate_clip(input: &Path) -> Result<ValidateReport, Error> {
+    fn box_errors(nodes: &[BoxNode], problems: &mut Vec<ValidateProblem>) {
+        for node in nodes {
+            if let Some(e) = &node.error {
+                problems.push(ValidateProblem {
+                    file_offset: Some(node.offset),
+                    ..ValidateProblem::new("container", format!("{}: {e}", node.box_type))
+                });
+            }
+            box_errors(&node.children, problems);
+        }
+    }
+
+    let mut report = ValidateReport {
+        file: input.display().to_string(),
+        ..ValidateReport::default()
+    };
+    box_errors(&dump_box_tree(File::open(input)?)?, &mut report.problems);
+
+    let opened = match extract::extractor_from_path(input) {
+        Err(e @ tesla_sei::Error::Mp4InconsistentSampleTables { .. }) => {
+            // Carry on with the samples the tables agree on, so the other checks still run.
+            let opts = ExtractOptions {
+                repair: Some(SampleTableRepair::Truncate),
+                ..ExtractOptions::default()
+            };
+            let repaired = extract::extractor_from_path_with_options(input, &opts);
+            if let Ok(extractor) = &repaired {
+                report.problems.push(ValidateProblem::new(
+                    "sample_tables",
+                    format!(
+                        "{e}; {} samples unaccounted for",
+                        extractor.dropped_samples()
+                    ),
+                ));
+            }
+            repaired
+        }
+        opened => opened,
+    };
+    let mut extractor = match opened {
+        Ok(extractor) => extractor,
+        Err(tesla_sei::Error::Io(e)) => return Err(e.into()),
+        Err(e) => {
+            report
+                .problems
+                .push(ValidateProblem::new("container", e.to_string()));
+            return Ok(report);
+        }
+    };
+    match check_samples(&mut extractor, &mut report) {
+        // A clip cut off mid-write still lists the samples it never got to.
+        Err(Error::Lib(tesla_sei::Error::Io(e))) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            report.problems.push(ValidateProblem::new(
+                "container",
+                "sample data runs past the end of the file".to_string(),
+            ))
+        }
+        result => result?,
+    }
+
+    report.ok = report.problems.is_empty();
+    Ok(report)
+}
+
+// The checks that read sample data: SEI syntax, telemetry presence and decode rate, frame
+// sequence continuity.
+fn check_samples(
+    extractor: &mut SeiExtractor<File>,
+    report: &mut ValidateReport,
+) -> Result<(), Error> {
+    report.samples = extractor.total_samples();
+
+    for v in extractor.validate_sei()? {
+        let nal_type = v.nal_type.map_or("-".to_string(), |t| t.to_string());
+        report.problems.push(ValidateProblem {
+            sample_index: Some(v.sample_index),
+            file_offset: Some(v.file_offset),
+            ..ValidateProblem::new("sei_syntax", format!("nal_type {nal_type}: {}", v.kind))
+        });
+    }
+
+    let mut frames = Vec::new();
+    while let Some(event) = extractor.next_event()? {
+        frames.push(event);
+    }
+    let mut with_telemetry: Vec<usize> = frames.iter().map(|e| e.sample_index).collect();
+    with_telemetry.sort_unstable();
+    with_telemetry.dedup();
+    report.samples_with_telemetry = with_telemetry.len();
+    let stats = extractor.decode_stats();
+    report.sei_messages = stats.messages;
+    report.decoded_messages = stats.decoded;
+    report.decode_rate = (stats.messages > 0).then(|| stats.decoded as f64 / stats.messages as f64);
+
+    if frames.is_empty() {
+        report.problems.push(ValidateProblem::new(
+            "telemetry",
+            "no telemetry".to_string(),
+        ));
+    } else if report.samples_with_telemetry < report.samples {
+        report.problems.push(ValidateProblem::new(
+            "telemetry",
+            format!(
+                "{} of {} samples carry no telemetry",
+                report.samples - report.samples_with_telemetry,
+                report.samples
+            ),
+        ));
+    }
+    if stats.undecoded > 0 {
+        report.problems.push(ValidateProblem::new(
+            "telemetry",
+            format!(
+                "{} of {} SEI messages didn't decode",
+                stats.undecoded, stats.messages
+            ),
+        ));
+    }
+
+    // Order-agnostic: B-frame encodes may carry the numbers in decode or display order.
+    frames.sort_by_key(|e| (e.metadata.frame_seq_no, e.sample_index));
+    for pair in frames.windows(2) {
+        let (a, b) = (pair[0].metadata.frame_seq_no, pair[1].metadata.frame_seq_no);
+        let message = if a == b {
+            format!("frame_seq_no {b} repeats")
+        } else if b - a > 1 {
+            format!("frame_seq_no skips from {a} to {b}")
+        } else {
+            continue;
+        };
+        report.frame_seq_breaks += 1;
+        report.problems.push(ValidateProblem {
+            sample_index: Some(pair[1].sample_index),
+            ..ValidateProblem::new("frame_sequence", message)
+        });
+    }
+    Ok(())
+}
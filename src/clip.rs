@@ -0,0 +1,44 @@
+#[cfg(feature = "chrono")]
+use std::path::Path;
+
+#[cfg(feature = "chrono")]
+use chrono::NaiveDateTime;
+
+/// Metadata recovered from a TeslaCam clip filename.
+///
+/// TeslaCam names clips `YYYY-MM-DD_HH-MM-SS-<camera>.mp4`, where the timestamp is the
+/// car's local wall-clock time at the start of the clip (no timezone is recorded).
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipName {
+    /// Local start time of the clip.
+    pub start: NaiveDateTime,
+    /// Camera suffix (e.g. `front`, `back`, `left_repeater`), if present.
+    pub camera: Option<String>,
+}
+
+#[cfg(feature = "chrono")]
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+const TIMESTAMP_LEN: usize = 19;
+
+/// Parse a TeslaCam clip filename (or a path ending in one).
+///
+/// Returns `None` if the file stem does not start with a TeslaCam timestamp.
+#[cfg(feature = "chrono")]
+pub fn parse_clip_name(path: impl AsRef<Path>) -> Option<ClipName> {
+    let stem = path.as_ref().file_stem()?.to_str()?;
+    let ts = stem.get(..TIMESTAMP_LEN)?;
+    let start = NaiveDateTime::parse_from_str(ts, TIMESTAMP_FORMAT).ok()?;
+
+    let camera = clip_camera(stem).map(str::to_string);
+
+    Some(ClipName { start, camera })
+}
+
+// The camera suffix of a TeslaCam file stem (`front` from `2024-01-01_12-00-00-front`), without
+// checking the timestamp, so it works without the `chrono` feature.
+pub(crate) fn clip_camera(stem: &str) -> Option<&str> {
+    stem.get(TIMESTAMP_LEN..)?
+        .strip_prefix('-')
+        .filter(|c| !c.is_empty())
+}
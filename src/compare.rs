@@ -0,0 +1,252 @@
+//! Cross-checks of the camera files of one recorded minute.
+//!
+//! Every camera of a TeslaCam event carries the same vehicle telemetry, stamped with the same
+//! frame sequence numbers. [`compare_cameras`] lines the files up by `frame_seq_no` and reports
+//! where they disagree: frames one camera has and another lacks, gaps in the sequence, GPS fixes
+//! that don't match, samples without telemetry. A clean set has none of these; a set that was
+//! edited, re-encoded or partially lost usually has several.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::extract::extractor_from_path;
+use crate::geo::GeoPoint;
+use crate::pb::SeiMetadata;
+use crate::Error;
+
+/// Tolerances for [`compare_cameras`].
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Largest distance, in meters, between two cameras' GPS fixes for the same frame that still
+    /// counts as agreement.
+    pub gps_tolerance_m: f64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        // The cameras copy one fix from the vehicle bus, so any distance at all is suspicious;
+        // the tolerance only absorbs rounding by re-muxing tools.
+        CompareOptions {
+            gps_tolerance_m: 1.0,
+        }
+    }
+}
+
+/// One camera file's telemetry, measured against the reference (the first file).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraReport {
+    pub path: PathBuf,
+    /// Video samples in the track.
+    pub samples: usize,
+    /// Distinct frame sequence numbers decoded.
+    pub frames: usize,
+    /// Samples that carry no decodable telemetry.
+    pub samples_without_telemetry: usize,
+    pub first_frame_seq_no: Option<u64>,
+    pub last_frame_seq_no: Option<u64>,
+    /// Places where the sequence skips one or more numbers.
+    pub frame_seq_gaps: usize,
+    /// Frames whose sequence number the reference also has.
+    pub shared_frames: usize,
+    /// Largest distance between this camera's and the reference's GPS fix for a shared frame;
+    /// `None` if no shared frame has a fix in both.
+    pub max_gps_delta_m: Option<f64>,
+    /// Shared frames whose telemetry, apart from the GPS position, differs from the reference's.
+    pub mismatched_frames: usize,
+}
+
+/// What [`compare_cameras`] found wrong with a camera file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiscrepancyKind {
+    /// Samples without decodable telemetry.
+    MissingTelemetry { samples: usize },
+    /// The frame sequence skips numbers this many times.
+    FrameSeqGaps { gaps: usize },
+    /// No frame sequence number in common with the reference.
+    NoSharedFrames,
+    /// Frames the reference has and this camera lacks.
+    MissingFrames { frames: usize },
+    /// Frames this camera has and the reference lacks.
+    ExtraFrames { frames: usize },
+    /// GPS fixes for the same frame further apart than [`CompareOptions::gps_tolerance_m`].
+    GpsDisagreement { frames: usize, max_m: f64 },
+    /// Shared frames whose other telemetry differs from the reference's.
+    TelemetryMismatch { frames: usize },
+}
+
+impl fmt::Display for DiscrepancyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingTelemetry { samples } => {
+                write!(f, "{samples} samples without telemetry")
+            }
+            Self::FrameSeqGaps { gaps } => write!(f, "{gaps} gaps in the frame sequence"),
+            Self::NoSharedFrames => write!(f, "no frames in common with the reference"),
+            Self::MissingFrames { frames } => {
+                write!(f, "{frames} frames of the reference are missing")
+            }
+            Self::ExtraFrames { frames } => write!(f, "{frames} frames the reference lacks"),
+            Self::GpsDisagreement { frames, max_m } => write!(
+                f,
+                "GPS disagrees with the reference on {frames} frames (up to {max_m:.1} m)"
+            ),
+            Self::TelemetryMismatch { frames } => {
+                write!(f, "telemetry differs from the reference on {frames} frames")
+            }
+        }
+    }
+}
+
+/// A discrepancy found in one camera file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub path: PathBuf,
+    pub kind: DiscrepancyKind,
+}
+
+/// Result of [`compare_cameras`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraComparison {
+    /// One report per input, in input order; the first is the reference.
+    pub cameras: Vec<CameraReport>,
+    /// Everything that didn't line up, grouped by input.
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl CameraComparison {
+    /// Whether the camera files agree completely.
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Compare the telemetry of the camera files of one recorded minute.
+///
+/// The first path is the reference the others are measured against; put the front camera
+/// first if it's present. Any file that can't be read fails the whole comparison.
+pub fn compare_cameras<P: AsRef<Path>>(
+    paths: &[P],
+    opts: &CompareOptions,
+) -> Result<CameraComparison, Error> {
+    let mut streams = Vec::with_capacity(paths.len());
+    for path in paths {
+        streams.push(read_camera(path.as_ref())?);
+    }
+
+    let mut comparison = CameraComparison::default();
+    let Some(reference) = streams.first() else {
+        return Ok(comparison);
+    };
+    for (i, (path, stream)) in paths.iter().zip(&streams).enumerate() {
+        let path = path.as_ref().to_path_buf();
+        let mut found = Vec::new();
+        let missing_telemetry = stream.samples.saturating_sub(stream.samples_with_telemetry);
+        if missing_telemetry > 0 {
+            found.push(DiscrepancyKind::MissingTelemetry {
+                samples: missing_telemetry,
+            });
+        }
+        let seq: Vec<u64> = stream.frames.keys().copied().collect();
+        let gaps = seq.windows(2).filter(|w| w[1] - w[0] > 1).count();
+        if gaps > 0 {
+            found.push(DiscrepancyKind::FrameSeqGaps { gaps });
+        }
+
+        let mut shared = 0;
+        let mut max_gps_delta_m: Option<f64> = None;
+        let mut gps_disagreements = 0;
+        let mut mismatched = 0;
+        for (seq_no, m) in &stream.frames {
+            let Some(r) = reference.frames.get(seq_no) else {
+                continue;
+            };
+            shared += 1;
+            if let (Some(a), Some(b)) = (GeoPoint::from_metadata(m), GeoPoint::from_metadata(r)) {
+                let d = a.distance_m(&b);
+                max_gps_delta_m = Some(max_gps_delta_m.map_or(d, |x| x.max(d)));
+                if d > opts.gps_tolerance_m {
+                    gps_disagreements += 1;
+                }
+            }
+            let without_position = |m: &SeiMetadata| SeiMetadata {
+                latitude_deg: 0.0,
+                longitude_deg: 0.0,
+                ..*m
+            };
+            if without_position(m) != without_position(r) {
+                mismatched += 1;
+            }
+        }
+        // The reference trivially agrees with itself.
+        if i > 0 {
+            if shared == 0 && !(stream.frames.is_empty() && reference.frames.is_empty()) {
+                found.push(DiscrepancyKind::NoSharedFrames);
+            } else {
+                if reference.frames.len() > shared {
+                    found.push(DiscrepancyKind::MissingFrames {
+                        frames: reference.frames.len() - shared,
+                    });
+                }
+                if stream.frames.len() > shared {
+                    found.push(DiscrepancyKind::ExtraFrames {
+                        frames: stream.frames.len() - shared,
+                    });
+                }
+            }
+            if gps_disagreements > 0 {
+                found.push(DiscrepancyKind::GpsDisagreement {
+                    frames: gps_disagreements,
+                    max_m: max_gps_delta_m.unwrap_or_default(),
+                });
+            }
+            if mismatched > 0 {
+                found.push(DiscrepancyKind::TelemetryMismatch { frames: mismatched });
+            }
+        }
+
+        comparison.cameras.push(CameraReport {
+            path: path.clone(),
+            samples: stream.samples,
+            frames: stream.frames.len(),
+            samples_without_telemetry: missing_telemetry,
+            first_frame_seq_no: seq.first().copied(),
+            last_frame_seq_no: seq.last().copied(),
+            frame_seq_gaps: gaps,
+            shared_frames: shared,
+            max_gps_delta_m,
+            mismatched_frames: mismatched,
+        });
+        comparison
+            .discrepancies
+            .extend(found.into_iter().map(|kind| Discrepancy {
+                path: path.clone(),
+                kind,
+            }));
+    }
+    Ok(comparison)
+}
+
+struct CameraStream {
+    samples: usize,
+    samples_with_telemetry: usize,
+    /// Telemetry by frame sequence number; the first message wins if a number repeats.
+    frames: BTreeMap<u64, SeiMetadata>,
+}
+
+fn read_camera(path: &Path) -> Result<CameraStream, Error> {
+    let mut extractor = extractor_from_path(path)?;
+    let mut frames = BTreeMap::new();
+    let mut with_telemetry = BTreeSet::new();
+    while let Some(event) = extractor.next_event()? {
+        with_telemetry.insert(event.sample_index);
+        frames
+            .entry(event.metadata.frame_seq_no)
+            .or_insert(event.metadata);
+    }
+    Ok(CameraStream {
+        samples: extractor.total_samples(),
+        samples_with_telemetry: with_telemetry.len(),
+        frames,
+    })
+}
@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::extract::{extractor_from_reader, SeiExtractor};
+use crate::geo::STANDARD_GRAVITY_MPS2;
+use crate::pb::sei_metadata::AutopilotState;
+use crate::pb::SeiMetadata;
+use crate::Error;
+
+/// What happened at a [`DrivingEvent`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrivingEventKind {
+    /// Deceleration along the car beyond [`DrivingEventOptions::hard_braking_g`]. `peak_g` is
+    /// the strongest deceleration before it eased off again, as a positive number.
+    HardBraking { peak_g: f64 },
+    /// Forward acceleration beyond [`DrivingEventOptions::rapid_acceleration_g`], with its peak.
+    RapidAcceleration { peak_g: f64 },
+    /// Autopilot engaged, or switched to another mode while engaged. `state` is the raw
+    /// `AutopilotState` value.
+    AutopilotEngaged { state: i32 },
+    /// Autopilot went back to `NONE`.
+    AutopilotDisengaged,
+    /// A turn signal came on.
+    Blinker { side: BlinkerSide },
+    /// The gear changed; raw `Gear` values.
+    GearChange { from: i32, to: i32 },
+}
+
+/// Which turn signal a [`DrivingEventKind::Blinker`] event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlinkerSide {
+    Left,
+    Right,
+}
+
+/// A notable moment found by [`driving_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrivingEvent {
+    pub kind: DrivingEventKind,
+    /// 0-based sample index (decode order) of the frame where the event starts.
+    pub sample_index: usize,
+    /// When that frame is displayed, relative to the start of the clip.
+    pub presentation_time: Option<Duration>,
+}
+
+/// Thresholds for [`driving_events`].
+#[derive(Debug, Clone)]
+pub struct DrivingEventOptions {
+    /// Longitudinal deceleration, in g, that counts as hard braking.
+    pub hard_braking_g: f64,
+    /// Longitudinal acceleration, in g, that counts as rapid acceleration.
+    pub rapid_acceleration_g: f64,
+}
+
+impl Default for DrivingEventOptions {
+    fn default() -> Self {
+        DrivingEventOptions {
+            // Insurance telematics usually flag braking around 0.3-0.45 g; normal stops stay
+            // below 0.25 g.
+            hard_braking_g: 0.4,
+            rapid_acceleration_g: 0.35,
+        }
+    }
+}
+
+/// Find hard braking, rapid acceleration, autopilot, blinker and gear events in an on-disk MP4.
+pub fn driving_events(
+    path: impl AsRef<Path>,
+    opts: &DrivingEventOptions,
+) -> Result<Vec<DrivingEvent>, Error> {
+    driving_events_reader(File::open(path)?, opts)
+}
+
+/// Find driving events in any seekable reader.
+///
+/// Frames are examined in display order and events are returned in that order. Each event is
+/// reported once, at the frame where it starts: a braking or acceleration run above its
+/// threshold yields one event carrying the run's peak, and a blinker yields one event when it
+/// comes on. State at the first frame (the gear the clip starts in, autopilot already engaged)
+/// is not an event.
+pub fn driving_events_reader<R: Read + Seek>(
+    reader: R,
+    opts: &DrivingEventOptions,
+) -> Result<Vec<DrivingEvent>, Error> {
+    driving_events_from_extractor(&mut extractor_from_reader(reader)?, opts)
+}
+
+/// Find driving events in the remaining samples of an already opened extractor, for callers
+/// that also want its [`creation_timestamp`](SeiExtractor::creation_timestamp).
+pub fn driving_events_from_extractor<R: Read + Seek>(
+    extractor: &mut SeiExtractor<R>,
+    opts: &DrivingEventOptions,
+) -> Result<Vec<DrivingEvent>, Error> {
+    let mut frames = Vec::new();
+    while let Some(event) = extractor.next_event()? {
+        frames.push(event);
+    }
+    frames.sort_by_key(|e| e.presentation_index);
+
+    let mut events: Vec<DrivingEvent> = Vec::new();
+    let mut prev: Option<&SeiMetadata> = None;
+    // Index into `events` of the braking or acceleration run in progress, and whether it is
+    // braking.
+    let mut run: Option<(usize, bool)> = None;
+    for frame in &frames {
+        let m = &frame.metadata;
+        let at = |kind| DrivingEvent {
+            kind,
+            sample_index: frame.sample_index,
+            presentation_time: frame.presentation_time,
+        };
+
+        let g = m.linear_acceleration_mps2_x / STANDARD_GRAVITY_MPS2;
+        let harsh = if g <= -opts.hard_braking_g {
+            Some((true, -g))
+        } else if g >= opts.rapid_acceleration_g {
+            Some((false, g))
+        } else {
+            None
+        };
+        run = match (harsh, run) {
+            (Some((braking, g)), Some((i, run_braking))) if braking == run_braking => {
+                if let DrivingEventKind::HardBraking { peak_g }
+                | DrivingEventKind::RapidAcceleration { peak_g } = &mut events[i].kind
+                {
+                    *peak_g = peak_g.max(g);
+                }
+                run
+            }
+            (Some((braking, peak_g)), _) => {
+                events.push(at(if braking {
+                    DrivingEventKind::HardBraking { peak_g }
+                } else {
+                    DrivingEventKind::RapidAcceleration { peak_g }
+                }));
+                Some((events.len() - 1, braking))
+            }
+            (None, _) => None,
+        };
+
+        if let Some(prev) = prev {
+            let none = AutopilotState::None as i32;
+            if m.autopilot_state != prev.autopilot_state {
+                events.push(at(if m.autopilot_state == none {
+                    DrivingEventKind::AutopilotDisengaged
+                } else {
+                    DrivingEventKind::AutopilotEngaged {
+                        state: m.autopilot_state,
+                    }
+                }));
+            }
+            if m.blinker_on_left && !prev.blinker_on_left {
+                events.push(at(DrivingEventKind::Blinker {
+                    side: BlinkerSide::Left,
+                }));
+            }
+            if m.blinker_on_right && !prev.blinker_on_right {
+                events.push(at(DrivingEventKind::Blinker {
+                    side: BlinkerSide::Right,
+                }));
+            }
+            if m.gear_state != prev.gear_state {
+                events.push(at(DrivingEventKind::GearChange {
+                    from: prev.gear_state,
+                    to: m.gear_state,
+                }));
+            }
+        }
+        prev = Some(m);
+    }
+    Ok(events)
+}
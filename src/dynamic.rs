@@ -0,0 +1,56 @@
+//! Decode telemetry against a protobuf schema loaded at runtime.
+//!
+//! The generated [`pb::SeiMetadata`](crate::pb::SeiMetadata) is fixed when the crate is built.
+//! When Tesla extends the message, compile the updated `dashcam.proto` into a descriptor set
+//! (`protoc --include_imports --descriptor_set_out=dashcam.bin dashcam.proto`) and load it with
+//! [`DynamicSchema::from_descriptor_set`]; [`DynamicSchema::decode`] then reads every field of
+//! [`SeiEvent::raw_metadata`](crate::SeiEvent::raw_metadata), new ones included, as a
+//! [`DynamicMessage`].
+//!
+//! The extractor still uses the built-in schema to find telemetry payloads, so this only helps
+//! while newer payloads stay compatible with it, which protobuf's field-addition rules ensure.
+
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+
+use crate::Error;
+
+const BUNDLED_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/dashcam.bin"));
+
+/// Name of the telemetry message in `dashcam.proto`, which declares no package.
+pub const SEI_METADATA_MESSAGE: &str = "SeiMetadata";
+
+/// A telemetry message layout, resolved from a protobuf descriptor set.
+#[derive(Debug, Clone)]
+pub struct DynamicSchema {
+    message: MessageDescriptor,
+}
+
+impl DynamicSchema {
+    /// The schema this crate was built with (`proto/dashcam.proto`).
+    pub fn bundled() -> Self {
+        Self::from_descriptor_set(BUNDLED_DESCRIPTOR_SET, SEI_METADATA_MESSAGE)
+            .expect("bundled descriptor set is valid")
+    }
+
+    /// Load `message_name` (fully qualified, e.g. [`SEI_METADATA_MESSAGE`]) from an encoded
+    /// `FileDescriptorSet`.
+    pub fn from_descriptor_set(bytes: &[u8], message_name: &str) -> Result<Self, Error> {
+        let pool = DescriptorPool::decode(bytes)?;
+        let message =
+            pool.get_message_by_name(message_name)
+                .ok_or_else(|| Error::MessageNotFound {
+                    name: message_name.to_string(),
+                })?;
+        Ok(DynamicSchema { message })
+    }
+
+    /// The resolved message descriptor, e.g. to list its fields.
+    pub fn descriptor(&self) -> &MessageDescriptor {
+        &self.message
+    }
+
+    /// Decode protobuf bytes, typically an event's `raw_metadata`.
+    pub fn decode(&self, raw_metadata: &[u8]) -> Result<DynamicMessage, Error> {
+        Ok(DynamicMessage::decode(self.message.clone(), raw_metadata)?)
+    }
+}
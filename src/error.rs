@@ -8,6 +8,15 @@ pub enum Error {
     #[error(transparent)]
     Io(#[from] io::Error),
 
+    /// Malformed JSON (e.g. when reading an export back in).
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// A JSON export was written in a schema version this release can't read.
+    #[error("unsupported output schema version {found} (this release reads up to {supported})")]
+    UnsupportedSchemaVersion { found: u64, supported: u32 },
+
     /// No usable video track/sample tables were found in the MP4.
     #[error("no video tracks with sample tables found")]
     NoTracksFound,
@@ -35,6 +44,56 @@ pub enum Error {
         chunk_offsets: usize,
     },
 
+    /// The input's codec can't be used for SEI extraction (e.g. an unrecognised elementary stream).
+    #[error("unsupported codec: {codec}")]
+    UnsupportedCodec { codec: String },
+
+    /// A protobuf descriptor set supplied at runtime is malformed.
+    #[cfg(feature = "reflect")]
+    #[error(transparent)]
+    Descriptor(#[from] prost_reflect::DescriptorError),
+
+    /// The message a runtime schema should decode isn't in its descriptor set.
+    #[cfg(feature = "reflect")]
+    #[error("message {name} not found in descriptor set")]
+    MessageNotFound { name: String },
+
+    /// Telemetry bytes don't decode against a runtime schema.
+    #[cfg(feature = "reflect")]
+    #[error(transparent)]
+    ProtobufDecode(#[from] prost::DecodeError),
+
+    /// An HTTP request for a remote clip failed.
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// The server answered a byte-range request with something other than `206 Partial
+    /// Content`, so the clip can't be read without downloading all of it.
+    #[cfg(feature = "http")]
+    #[error("server does not support byte-range requests: {url}")]
+    RangeNotSupported { url: String },
+
+    /// An S3 request for a remote clip failed.
+    #[cfg(feature = "s3")]
+    #[error("S3 request failed: {}", aws_sdk_s3::error::DisplayErrorContext(.0.as_ref()))]
+    S3(#[source] Box<aws_sdk_s3::Error>),
+
+    /// Watching a directory for new clips failed.
+    #[cfg(feature = "watch")]
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
+
+    /// The gRPC server failed to bind or stopped on a transport error.
+    #[cfg(feature = "grpc")]
+    #[error(transparent)]
+    Grpc(#[from] tonic::transport::Error),
+
+    /// Building a Polars `DataFrame` failed.
+    #[cfg(feature = "polars")]
+    #[error(transparent)]
+    Polars(#[from] polars::error::PolarsError),
+
     /// Requested sample index is outside the available range.
     #[error("sample index out of range: {sample_index} (total_samples={total_samples})")]
     SampleIndexOutOfRange {
@@ -1,11 +1,27 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use std::time::Duration;
 
-use crate::mp4::{build_sample_offsets, parse_mp4, CodecConfig, TrackSampleTables};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+
+use crate::info::{CodecProfile, Resolution, TrackInfo, UserDataEntry, UserDataValue, VideoCodec};
+use crate::mp4::{
+    build_presentation_times, build_sample_codec_indices, build_sample_offsets, edit_list_offset,
+    infer_chunk_samples, mp4_time_to_unix, parse_mp4, stsc_gap_chunks, truncate_to_chunks,
+    CodecConfig, MediaHeader, MovieHeader, TrackHeader, TrackSampleTables,
+};
 use crate::pb;
-use crate::sei::decode_sei_from_sample;
+use crate::pic_timing::{clock_timestamps_from_sample, track_clock_timestamps, ClockTimestamp};
+use crate::sei::{
+    decode_candidates, decode_sei_from_sample, raw_sei_from_sample, user_data_uuid,
+    DecodeCandidate, DecodeStats, DecodedSei, UnknownField, DEFAULT_SEI_PAYLOAD_TYPES,
+};
+use crate::sps::SpsInfo;
+use crate::validate::{validate_sample, SeiViolation};
+use crate::warning::Warning;
 use crate::Error;
 
 /// A single decoded SEI telemetry event.
@@ -18,8 +34,101 @@ pub struct SeiEvent {
     pub sample_index: usize,
     /// Absolute file offset where the MP4 sample begins.
     pub file_offset: u64,
+    /// The 0-based position of this sample in presentation (display) order.
+    ///
+    /// Samples are stored in decode order; on encodes with B-frames (`ctts` present) this differs
+    /// from `sample_index`. Sort by this field to line telemetry up with displayed frames.
+    pub presentation_index: usize,
+    /// When the sample is displayed, relative to the start of the clip.
+    ///
+    /// Accounts for composition offsets (`ctts`) and edit lists (`elst`), so it matches the
+    /// position players show. `None` if the track has no usable timescale.
+    pub presentation_time: Option<Duration>,
     /// The decoded protobuf message.
     pub metadata: pb::SeiMetadata,
+    /// The 16-byte UUID that starts the `user_data_unregistered` SEI payload the message was
+    /// decoded from. Tells Tesla's telemetry apart from other user data injected by
+    /// post-processing tools. `None` for other payload types and AV1 metadata OBUs.
+    pub uuid: Option<[u8; 16]>,
+    /// Clock timestamps from `pic_timing` (H.264) or `time_code` (H.265) SEI messages in the
+    /// same sample. Empty for most encodes, which don't send them.
+    pub clock_timestamps: Vec<ClockTimestamp>,
+    /// Fields of the protobuf message this crate's schema doesn't know, e.g. added by newer
+    /// firmware. Empty while the schema is current.
+    pub unknown_fields: Vec<UnknownField>,
+    /// The protobuf bytes `metadata` was decoded from, for decoding against a newer schema
+    /// (`reflect` feature, see the `dynamic` module).
+    #[cfg(feature = "reflect")]
+    pub raw_metadata: Vec<u8>,
+}
+
+/// One SEI message of any payload type, as returned by [`SeiExtractor::iter_raw_sei`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawSeiMessage {
+    /// The 0-based sample index in the selected track.
+    pub sample_index: usize,
+    /// Absolute file offset where the MP4 sample begins.
+    pub file_offset: u64,
+    /// NAL unit type carrying the message: 6 for H.264, 39 (prefix) or 40 (suffix) for H.265.
+    pub nal_type: u8,
+    /// SEI payload type, e.g. 1 (pic_timing), 4 (user_data_registered_itu_t_t35) or
+    /// 5 (user_data_unregistered, which Tesla uses).
+    pub payload_type: u32,
+    /// The payload bytes with emulation prevention removed; for type 5 this still starts with
+    /// the 16-byte UUID.
+    pub payload: Vec<u8>,
+}
+
+impl RawSeiMessage {
+    /// The 16-byte UUID of a `user_data_unregistered` (type 5) message; `None` for other payload
+    /// types or a payload too short to hold one.
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        user_data_uuid(self.payload_type, &self.payload)
+    }
+
+    /// Every offset the telemetry decoder would try on this payload, in the order it tries them,
+    /// and whether each decodes. For working out why a new firmware's payloads stop decoding.
+    pub fn decode_candidates(&self) -> Vec<DecodeCandidate> {
+        decode_candidates(self.payload_type, &self.payload)
+    }
+}
+
+/// How [`ExtractOptions::repair`] reconciles sample tables that disagree with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleTableRepair {
+    /// Keep the samples the chunk tables (`stsc`/`stco`) account for and drop the rest.
+    Truncate,
+    /// Ignore `stsc` and work out how many samples each chunk holds from the chunk offsets and
+    /// sample sizes, assuming a chunk's samples run back to back until the next chunk starts.
+    ///
+    /// Recovers more samples than [`SampleTableRepair::Truncate`] when `stsc` is what's damaged,
+    /// but relies on the track's chunks not being interleaved with other tracks' data (true for
+    /// Tesla clips, which are video only). Samples that still fit nowhere are dropped.
+    InferChunks,
+}
+
+/// Options for [`extractor_from_reader_with_options`].
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Repair inconsistent sample tables instead of failing with
+    /// [`Error::Mp4InconsistentSampleTables`]. Off by default: a repaired file may be missing
+    /// samples, see [`SeiExtractor::dropped_samples`].
+    pub repair: Option<SampleTableRepair>,
+    /// SEI payload types to try decoding telemetry from; `None` tries every type.
+    ///
+    /// Defaults to `[5]` (user_data_unregistered, which Tesla uses), so clips dense with
+    /// pic_timing or encoder SEI don't pay for decode attempts on those. AV1 metadata OBUs count
+    /// as type 5. [`SeiExtractor::iter_raw_sei`] ignores this filter.
+    pub sei_payload_types: Option<Vec<u32>>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            repair: None,
+            sei_payload_types: Some(DEFAULT_SEI_PAYLOAD_TYPES.to_vec()),
+        }
+    }
 }
 
 /// Streaming extractor that yields per-sample/per-frame telemetry as it is decoded.
@@ -30,12 +139,37 @@ pub struct SeiExtractor<R: Read + Seek> {
     reader: R,
     sample_sizes: Vec<u32>,
     sample_offsets: Vec<u64>,
-    codec: CodecConfig,
+    presentation_times: Vec<i64>,
+    presentation_index: Vec<usize>,
+    // One per stsd sample entry; `sample_codecs` maps each sample to one when there are several.
+    codecs: Vec<CodecConfig>,
+    sample_codecs: Vec<usize>,
+    codec: VideoCodec,
+    profile: Option<CodecProfile>,
+    resolution: Option<Resolution>,
+    frame_rate: Option<f64>,
+    movie_header: Option<MovieHeader>,
+    track_header: Option<TrackHeader>,
+    media_header: Option<MediaHeader>,
+    handler_name: Option<String>,
+    sps: Option<SpsInfo>,
+    // Whether samples are worth searching for clock timestamps; `None` until the first
+    // telemetry sample read decides it for H.265.
+    clock_timestamps: Option<bool>,
+    user_data: Vec<UserDataEntry>,
+    decode_stats: DecodeStats,
+    dropped_samples: usize,
+    warnings: Vec<Warning>,
+    // Samples already reported as `Warning::UndecodableSei`, so rereading one doesn't repeat it.
+    undecodable_samples: HashSet<usize>,
+    sei_payload_types: Option<Vec<u32>>,
+    sample_stride: usize,
 
     next_sample_index: usize,
     pending_offset: u64,
     pending_sample_index: usize,
-    pending: VecDeque<pb::SeiMetadata>,
+    pending_clock_timestamps: Vec<ClockTimestamp>,
+    pending: VecDeque<DecodedSei>,
 }
 
 /// Create an extractor from an on-disk MP4 path.
@@ -47,32 +181,115 @@ pub fn extractor_from_path(path: impl AsRef<Path>) -> Result<SeiExtractor<File>,
 /// Create an extractor from any seekable reader.
 ///
 /// This is the most flexible entry point for integrating into other Rust projects.
-pub fn extractor_from_reader<R: Read + Seek>(mut reader: R) -> Result<SeiExtractor<R>, Error> {
-    let mp4 = parse_mp4(&mut reader)?;
+pub fn extractor_from_reader<R: Read + Seek>(reader: R) -> Result<SeiExtractor<R>, Error> {
+    extractor_from_reader_with_options(reader, &ExtractOptions::default())
+}
+
+/// Create an extractor from an on-disk MP4 path with non-default options.
+pub fn extractor_from_path_with_options(
+    path: impl AsRef<Path>,
+    opts: &ExtractOptions,
+) -> Result<SeiExtractor<File>, Error> {
+    extractor_from_reader_with_options(File::open(path)?, opts)
+}
+
+/// Create an extractor from any seekable reader with non-default options.
+pub fn extractor_from_reader_with_options<R: Read + Seek>(
+    mut reader: R,
+    opts: &ExtractOptions,
+) -> Result<SeiExtractor<R>, Error> {
+    let mut mp4 = parse_mp4(&mut reader)?;
 
     if mp4.tracks.is_empty() {
         return Err(Error::NoTracksFound);
     }
 
     // Tesla clips sometimes contain multiple video tracks (e.g., a tiny preview track).
-    // Pick the track with the most samples.
-    let (_track_index, track) = mp4
-        .tracks
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, t)| t.sample_sizes.len())
-        .unwrap();
+    // Pick the highest-resolution track, then the one with the most samples.
+    let (_track_index, track) = select_largest_track(&mp4.tracks).unwrap();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        track = _track_index,
+        of = mp4.tracks.len(),
+        samples = track.sample_sizes.len(),
+        "selected video track"
+    );
+
+    let mut warnings = std::mem::take(&mut mp4.warnings);
+    let stsc_gaps = stsc_gap_chunks(track);
+    if stsc_gaps > 0 {
+        warnings.push(Warning::StscGapsFilled { chunks: stsc_gaps });
+    }
 
-    let sample_offsets = build_sample_offsets(track)?;
+    let total_samples = track.sample_sizes.len();
+    let repaired: TrackSampleTables;
+    let (track, sample_offsets) = match (build_sample_offsets(track), opts.repair) {
+        (Err(Error::Mp4InconsistentSampleTables { .. }), Some(mode)) => {
+            repaired = match mode {
+                SampleTableRepair::Truncate => truncate_to_chunks(track),
+                SampleTableRepair::InferChunks => {
+                    let data_end = reader.seek(SeekFrom::End(0))?;
+                    infer_chunk_samples(track, data_end)
+                }
+            };
+            (&repaired, build_sample_offsets(&repaired)?)
+        }
+        (offsets, _) => (track, offsets?),
+    };
+    let dropped_samples = total_samples - track.sample_sizes.len();
+    if dropped_samples > 0 {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(dropped_samples, "repaired inconsistent sample tables");
+        warnings.push(Warning::SamplesDropped {
+            count: dropped_samples,
+        });
+    }
+    // The edit list shifts every sample equally, so it doesn't affect presentation order.
+    let movie_timescale = mp4.movie_header.as_ref().map(|h| h.timescale);
+    let edit_offset = edit_list_offset(track, movie_timescale);
+    let presentation_times: Vec<i64> = build_presentation_times(track)
+        .into_iter()
+        .map(|pts| pts + edit_offset)
+        .collect();
+    let presentation_index = presentation_ranks(&presentation_times);
+    let sample_codecs = if track.codecs.len() > 1 {
+        build_sample_codec_indices(track)
+    } else {
+        Vec::new()
+    };
 
     Ok(SeiExtractor {
         reader,
         sample_sizes: track.sample_sizes.clone(),
         sample_offsets,
-        codec: track.codec.clone(),
+        presentation_times,
+        presentation_index,
+        codecs: track.codecs.clone(),
+        sample_codecs,
+        codec: track.video_codec(),
+        profile: track.decoder_config.as_ref().map(|c| c.profile),
+        resolution: track.resolution(),
+        frame_rate: track.frame_rate(),
+        movie_header: mp4.movie_header.clone(),
+        track_header: track.track_header.clone(),
+        media_header: track.media_header.clone(),
+        handler_name: track.handler_name.clone(),
+        sps: track.sps().copied(),
+        clock_timestamps: track_clock_timestamps(
+            track.codecs.first().unwrap_or(&CodecConfig::Unknown),
+            track.sps(),
+        ),
+        user_data: mp4.user_data.clone(),
+        decode_stats: DecodeStats::default(),
+        dropped_samples,
+        warnings,
+        undecodable_samples: HashSet::new(),
+        sei_payload_types: opts.sei_payload_types.clone(),
+        sample_stride: 1,
         next_sample_index: 0,
         pending_offset: 0,
         pending_sample_index: 0,
+        pending_clock_timestamps: Vec::new(),
         pending: VecDeque::new(),
     })
 }
@@ -83,6 +300,156 @@ impl<R: Read + Seek> SeiExtractor<R> {
         self.sample_offsets.len()
     }
 
+    /// Media timescale (ticks per second) of the selected track, from `mdhd`.
+    pub fn timescale(&self) -> Option<u32> {
+        self.media_header
+            .as_ref()
+            .map(|h| h.timescale)
+            .filter(|&ts| ts != 0)
+    }
+
+    /// Duration of the selected track (`mdhd`), falling back to the movie duration (`mvhd`).
+    pub fn duration(&self) -> Option<Duration> {
+        let media = self
+            .media_header
+            .as_ref()
+            .and_then(|h| ticks_to_duration(h.duration, h.timescale));
+        media.or_else(|| {
+            self.movie_header
+                .as_ref()
+                .and_then(|h| ticks_to_duration(h.duration, h.timescale))
+        })
+    }
+
+    /// Creation time recorded in the movie header (`mvhd`), falling back to `mdhd`, as Unix
+    /// seconds.
+    pub fn creation_timestamp(&self) -> Option<i64> {
+        let movie = self.movie_header.as_ref().map(|h| h.creation_time);
+        let media = self.media_header.as_ref().map(|h| h.creation_time);
+        first_header_timestamp([movie, media])
+    }
+
+    /// Modification time recorded in the movie header (`mvhd`), falling back to `mdhd`, as Unix
+    /// seconds.
+    pub fn modification_timestamp(&self) -> Option<i64> {
+        let movie = self.movie_header.as_ref().map(|h| h.modification_time);
+        let media = self.media_header.as_ref().map(|h| h.modification_time);
+        first_header_timestamp([movie, media])
+    }
+
+    /// Creation time recorded in the movie header (`mvhd`), falling back to `mdhd`.
+    #[cfg(feature = "chrono")]
+    pub fn creation_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.creation_timestamp()?, 0)
+    }
+
+    /// Modification time recorded in the movie header (`mvhd`), falling back to `mdhd`.
+    #[cfg(feature = "chrono")]
+    pub fn modification_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.modification_timestamp()?, 0)
+    }
+
+    /// Summary of the selected video track.
+    pub fn track_info(&self) -> TrackInfo {
+        TrackInfo {
+            track_id: self.track_id(),
+            handler_name: self.handler_name.clone(),
+            codec: self.codec,
+            profile: self.profile,
+            resolution: self.resolution,
+            frame_rate: self.frame_rate,
+            sample_count: self.total_samples(),
+            timescale: self.timescale(),
+            duration: self.duration(),
+        }
+    }
+
+    /// Frame size of the selected track.
+    ///
+    /// Taken from the SPS in `avcC`/`hvcC` (with cropping applied) when it parses, so it stays
+    /// correct even when the visual sample entry or `tkhd` are missing or wrong; those are the
+    /// fallbacks, in that order.
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolution
+    }
+
+    /// Frames per second of the selected track.
+    ///
+    /// Taken from the SPS VUI timing info when signalled, falling back to the average sample
+    /// duration in `stts`.
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.frame_rate
+    }
+
+    /// Key/value items from the movie's user-data boxes (`udta`, `meta`/`ilst`).
+    ///
+    /// Useful for vendor metadata such as firmware or camera identifiers, when present.
+    pub fn user_data(&self) -> &[UserDataEntry] {
+        &self.user_data
+    }
+
+    /// Value of the first user-data item with `key`, if any.
+    pub fn user_data_value(&self, key: &str) -> Option<&UserDataValue> {
+        self.user_data
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| &e.value)
+    }
+
+    /// How many samples [`ExtractOptions::repair`] dropped to make the sample tables consistent;
+    /// 0 unless a repair was needed.
+    pub fn dropped_samples(&self) -> usize {
+        self.dropped_samples
+    }
+
+    /// Problems tolerated so far: those found opening the clip, then SEI messages that didn't
+    /// decode in the samples read since (each sample is reported once, however often it's read).
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Remove and return the warnings collected so far, e.g. to log them while a long clip is
+    /// still being decoded.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Which decode heuristics fired so far, over every sample this extractor has decoded.
+    pub fn decode_stats(&self) -> &DecodeStats {
+        &self.decode_stats
+    }
+
+    /// MP4 track ID of the selected track (`tkhd`).
+    pub fn track_id(&self) -> Option<u32> {
+        self.track_header.as_ref().map(|h| h.track_id)
+    }
+
+    /// Handler name of the selected track (`hdlr`), if the muxer wrote one.
+    pub fn handler_name(&self) -> Option<&str> {
+        self.handler_name.as_deref()
+    }
+
+    /// Presentation time of `sample_index` relative to the start of the clip.
+    ///
+    /// Composition offsets (`ctts`) and edit lists (`elst`) are applied. Returns `None` if the
+    /// index is out of range or the track has no usable timescale. Samples that fall before the
+    /// start of the presentation (e.g. cut by an edit list) are clamped to zero.
+    pub fn presentation_time(&self, sample_index: usize) -> Option<Duration> {
+        let pts = *self.presentation_times.get(sample_index)?;
+        ticks_to_duration(pts.max(0) as u64, self.timescale()?)
+    }
+
+    /// Sample indices of the selected track, sorted into presentation (display) order.
+    ///
+    /// Identical to `0..total_samples()` unless the track carries composition offsets (`ctts`).
+    pub fn presentation_order(&self) -> Vec<usize> {
+        let mut order = vec![0; self.presentation_index.len()];
+        for (sample_index, &rank) in self.presentation_index.iter().enumerate() {
+            order[rank] = sample_index;
+        }
+        order
+    }
+
     /// Pull the next event (convenience wrapper around `Iterator::next`).
     pub fn next_event(&mut self) -> Result<Option<SeiEvent>, Error> {
         self.next().transpose()
@@ -105,9 +472,19 @@ impl<R: Read + Seek> SeiExtractor<R> {
         self.pending.clear();
         self.pending_offset = 0;
         self.pending_sample_index = 0;
+        self.pending_clock_timestamps.clear();
         Ok(())
     }
 
+    /// Make the iterator decode only every `stride`th sample from the cursor on (1, the default,
+    /// decodes all of them; 0 is treated as 1).
+    ///
+    /// Skipped samples are never read, so thinning a clip to a map or plot rate saves the I/O
+    /// and decoding, not just the output. [`seek_sample`](Self::seek_sample) keeps the stride.
+    pub fn set_sample_stride(&mut self, stride: usize) {
+        self.sample_stride = stride.max(1);
+    }
+
     /// Decode telemetry events for an arbitrary `sample_index` without changing the iterator
     /// cursor.
     ///
@@ -122,44 +499,195 @@ impl<R: Read + Seek> SeiExtractor<R> {
             });
         }
 
-        let off = self.sample_offsets[sample_index];
-        let sz = self.sample_sizes[sample_index] as usize;
-        let mut buf = vec![0u8; sz];
-        self.reader.seek(SeekFrom::Start(off))?;
-        self.reader.read_exact(&mut buf)?;
-
-        let decoded = decode_sei_from_sample(&self.codec, &buf);
+        let (off, buf) = self.read_sample(sample_index)?;
+        let codec = self.sample_codec(sample_index).clone();
+        let decoded = self.decode_sample(sample_index, off, &codec, &buf);
+        let clock_timestamps = if decoded.is_empty() {
+            Vec::new()
+        } else {
+            self.sample_clock_timestamps(&codec, &buf)
+        };
         let events = decoded
             .into_iter()
-            .map(|metadata| SeiEvent {
+            .map(|d| SeiEvent {
                 sample_index,
                 file_offset: off,
-                metadata,
+                presentation_index: self.presentation_index[sample_index],
+                presentation_time: self.presentation_time(sample_index),
+                metadata: d.metadata,
+                uuid: d.uuid,
+                clock_timestamps: clock_timestamps.clone(),
+                unknown_fields: d.unknown_fields,
+                #[cfg(feature = "reflect")]
+                raw_metadata: d.raw_metadata,
             })
             .collect();
 
         Ok(events)
     }
 
+    /// Every SEI message in `sample_index`, whatever its payload type, without decoding any.
+    ///
+    /// Doesn't change the iterator cursor. AV1 tracks carry no SEI and always yield nothing.
+    pub fn read_sample_raw_sei(
+        &mut self,
+        sample_index: usize,
+    ) -> Result<Vec<RawSeiMessage>, Error> {
+        let total = self.sample_offsets.len();
+        if sample_index >= total {
+            return Err(Error::SampleIndexOutOfRange {
+                sample_index,
+                total_samples: total,
+            });
+        }
+
+        let (off, buf) = self.read_sample(sample_index)?;
+        let messages = raw_sei_from_sample(self.sample_codec(sample_index), &buf)
+            .into_iter()
+            .map(|(nal_type, payload_type, payload)| RawSeiMessage {
+                sample_index,
+                file_offset: off,
+                nal_type,
+                payload_type,
+                payload,
+            })
+            .collect();
+        Ok(messages)
+    }
+
+    /// Iterate every SEI message in the track (pic_timing, other vendors' user data, ...), not
+    /// just Tesla's telemetry, from the first sample on.
+    ///
+    /// For research tools; nothing is decoded. Doesn't change the event iterator's cursor.
+    pub fn iter_raw_sei(&mut self) -> RawSeiIter<'_, R> {
+        RawSeiIter {
+            extractor: self,
+            next_sample_index: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Check the SEI NAL units of `sample_index` strictly against the H.264/H.265 syntax:
+    /// NAL framing, emulation prevention, message sizes and `rbsp_trailing_bits`.
+    ///
+    /// An empty list means the sample is clean. Doesn't change the iterator cursor.
+    pub fn validate_sample_sei(&mut self, sample_index: usize) -> Result<Vec<SeiViolation>, Error> {
+        let total = self.sample_offsets.len();
+        if sample_index >= total {
+            return Err(Error::SampleIndexOutOfRange {
+                sample_index,
+                total_samples: total,
+            });
+        }
+
+        let (off, buf) = self.read_sample(sample_index)?;
+        let violations = validate_sample(self.sample_codec(sample_index), &buf)
+            .into_iter()
+            .map(|(nal_offset, nal_type, kind)| SeiViolation {
+                sample_index,
+                file_offset: off + nal_offset as u64,
+                nal_type,
+                kind,
+            })
+            .collect();
+        Ok(violations)
+    }
+
+    /// [`validate_sample_sei`](Self::validate_sample_sei) over every sample of the track.
+    pub fn validate_sei(&mut self) -> Result<Vec<SeiViolation>, Error> {
+        let mut out = Vec::new();
+        for sample_index in 0..self.total_samples() {
+            out.extend(self.validate_sample_sei(sample_index)?);
+        }
+        Ok(out)
+    }
+
+    // The underlying reader, for the async extractor to load samples into.
+    #[cfg(feature = "async")]
+    pub(crate) fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    // (file offset, size) of a sample, if the index is in range.
+    #[cfg(feature = "async")]
+    pub(crate) fn sample_location(&self, sample_index: usize) -> Option<(u64, u32)> {
+        Some((
+            *self.sample_offsets.get(sample_index)?,
+            self.sample_sizes[sample_index],
+        ))
+    }
+
+    // Returns (file offset, bytes) of a sample; the index must be in range.
+    fn read_sample(&mut self, sample_index: usize) -> Result<(u64, Vec<u8>), Error> {
+        let off = self.sample_offsets[sample_index];
+        let sz = self.sample_sizes[sample_index] as usize;
+        let mut buf = vec![0u8; sz];
+        self.reader.seek(SeekFrom::Start(off))?;
+        self.reader.read_exact(&mut buf)?;
+        Ok((off, buf))
+    }
+
+    // Codec configuration (NAL length size) for the sample's stsd entry.
+    fn sample_codec(&self, sample_index: usize) -> &CodecConfig {
+        let entry = self.sample_codecs.get(sample_index).copied().unwrap_or(0);
+        self.codecs.get(entry).unwrap_or(&CodecConfig::Unknown)
+    }
+
+    // Decode a sample's telemetry, recording a warning the first time any of its SEI messages
+    // doesn't decode.
+    fn decode_sample(
+        &mut self,
+        sample_index: usize,
+        file_offset: u64,
+        codec: &CodecConfig,
+        buf: &[u8],
+    ) -> Vec<DecodedSei> {
+        let undecoded = self.decode_stats.undecoded;
+        let decoded = decode_sei_from_sample(
+            codec,
+            buf,
+            self.sei_payload_types.as_deref(),
+            &mut self.decode_stats,
+        );
+        let messages = self.decode_stats.undecoded - undecoded;
+        if messages > 0 && self.undecodable_samples.insert(sample_index) {
+            self.warnings.push(Warning::UndecodableSei {
+                sample_index,
+                file_offset,
+                messages,
+            });
+        }
+        decoded
+    }
+
+    // Clock timestamps of a sample with telemetry, skipping the SEI walk on tracks without them.
+    fn sample_clock_timestamps(&mut self, codec: &CodecConfig, buf: &[u8]) -> Vec<ClockTimestamp> {
+        if self.clock_timestamps == Some(false) {
+            return Vec::new();
+        }
+        let timestamps = clock_timestamps_from_sample(codec, buf, self.sps.as_ref());
+        // Encoders that send time_code send it with every frame, so the first sample settles it.
+        self.clock_timestamps.get_or_insert(!timestamps.is_empty());
+        timestamps
+    }
+
     fn read_next_sample_into_pending(&mut self) -> Result<bool, Error> {
         while self.pending.is_empty() && self.next_sample_index < self.sample_offsets.len() {
             let sample_index = self.next_sample_index;
-            let off = self.sample_offsets[sample_index];
-            let sz = self.sample_sizes[sample_index] as usize;
-
-            let mut buf = vec![0u8; sz];
-            self.reader.seek(SeekFrom::Start(off))?;
-            self.reader.read_exact(&mut buf)?;
-
-            self.next_sample_index += 1;
+            let (off, buf) = self.read_sample(sample_index)?;
+            self.next_sample_index = sample_index.saturating_add(self.sample_stride);
 
-            let decoded = decode_sei_from_sample(&self.codec, &buf);
+            let codec = self.sample_codec(sample_index).clone();
+            let decoded = self.decode_sample(sample_index, off, &codec, &buf);
             if decoded.is_empty() {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(sample_index, offset = off, "no telemetry in sample");
                 continue;
             }
 
             self.pending_offset = off;
             self.pending_sample_index = sample_index;
+            self.pending_clock_timestamps = self.sample_clock_timestamps(&codec, &buf);
             self.pending = decoded.into();
             return Ok(true);
         }
@@ -176,15 +704,53 @@ impl<R: Read + Seek> Iterator for SeiExtractor<R> {
             return Some(Err(e));
         }
 
-        let metadata = self.pending.pop_front()?;
+        let decoded = self.pending.pop_front()?;
         Some(Ok(SeiEvent {
             sample_index: self.pending_sample_index,
             file_offset: self.pending_offset,
-            metadata,
+            presentation_index: self.presentation_index[self.pending_sample_index],
+            presentation_time: self.presentation_time(self.pending_sample_index),
+            metadata: decoded.metadata,
+            uuid: decoded.uuid,
+            clock_timestamps: self.pending_clock_timestamps.clone(),
+            unknown_fields: decoded.unknown_fields,
+            #[cfg(feature = "reflect")]
+            raw_metadata: decoded.raw_metadata,
         }))
     }
 }
 
+/// Iterator over every SEI message of a track, created by [`SeiExtractor::iter_raw_sei`].
+pub struct RawSeiIter<'a, R: Read + Seek> {
+    extractor: &'a mut SeiExtractor<R>,
+    next_sample_index: usize,
+    pending: VecDeque<RawSeiMessage>,
+}
+
+impl<R: Read + Seek> Iterator for RawSeiIter<'_, R> {
+    type Item = Result<RawSeiMessage, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && self.next_sample_index < self.extractor.total_samples() {
+            match self.extractor.read_sample_raw_sei(self.next_sample_index) {
+                Ok(messages) => self.pending = messages.into(),
+                Err(e) => {
+                    // Stop after an IO error rather than retrying the same sample forever.
+                    self.next_sample_index = usize::MAX;
+                    return Some(Err(e));
+                }
+            }
+            self.next_sample_index += 1;
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Decode every event of the MP4 at `path` in one call.
+pub fn extract_all(path: impl AsRef<Path>) -> Result<Vec<SeiEvent>, Error> {
+    extractor_from_path(path)?.collect()
+}
+
 /// Convenience helper that iterates all decoded events and invokes a callback.
 ///
 /// This can be more ergonomic than manually writing a `for` loop when integrating in apps.
@@ -199,11 +765,108 @@ pub fn for_each_sei_metadata<R: Read + Seek>(
     Ok(())
 }
 
-// Keep this around for future improvements, such as exposing track selection options.
-#[allow(dead_code)]
-fn _select_largest_track<'a>(tracks: &'a [TrackSampleTables]) -> Option<(usize, &'a TrackSampleTables)> {
-    tracks
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, t)| t.sample_sizes.len())
+pub(crate) fn ticks_to_duration(ticks: u64, timescale: u32) -> Option<Duration> {
+    if timescale == 0 {
+        return None;
+    }
+    let secs = ticks / timescale as u64;
+    let rem = ticks % timescale as u64;
+    Some(Duration::from_secs(secs) + Duration::from_nanos(rem * 1_000_000_000 / timescale as u64))
+}
+
+fn first_header_timestamp(times: [Option<u64>; 2]) -> Option<i64> {
+    times.into_iter().flatten().find_map(mp4_time_to_unix)
+}
+
+// Rank each sample by presentation time; ties keep decode order.
+fn presentation_ranks(presentation_times: &[i64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..presentation_times.len()).collect();
+    order.sort_by_key(|&i| (presentation_times[i], i));
+
+    let mut ranks = vec![0; order.len()];
+    for (rank, sample_index) in order.into_iter().enumerate() {
+        ranks[sample_index] = rank;
+    }
+    ranks
+}
+
+// Keep this separate for future improvements, such as exposing track selection options.
+pub(crate) fn select_largest_track(
+    tracks: &[TrackSampleTables],
+) -> Option<(usize, &TrackSampleTables)> {
+    tracks.iter().enumerate().max_by_key(|(_, t)| {
+        let pixels = t.resolution().map_or(0, |r| r.pixels());
+        (pixels, t.sample_sizes.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn mp4_box(typ: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut b = ((8 + payload.len()) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(typ);
+        b.extend_from_slice(payload);
+        b
+    }
+
+    fn full_box(typ: &[u8; 4], fields: &[u32]) -> Vec<u8> {
+        let payload: Vec<u8> = fields.iter().flat_map(|v| v.to_be_bytes()).collect();
+        mp4_box(typ, &payload)
+    }
+
+    // A one-sample H.264 clip whose sample holds `sample` (already length-prefixed).
+    fn single_sample_clip(sample: &[u8]) -> Vec<u8> {
+        let mdat = mp4_box(b"mdat", sample);
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(b"vide");
+        hdlr.extend_from_slice(&[0u8; 13]);
+        let mut stsd = 0u32.to_be_bytes().to_vec();
+        stsd.extend_from_slice(&1u32.to_be_bytes());
+        stsd.extend(mp4_box(b"avc1", &[0u8; 78]));
+
+        let stbl_with = |chunk_offset: u32| {
+            let children = [
+                mp4_box(b"stsd", &stsd),
+                full_box(b"stsz", &[0, 0, 1, sample.len() as u32]),
+                full_box(b"stco", &[0, 1, chunk_offset]),
+                full_box(b"stsc", &[0, 1, 1, 1, 1]),
+            ]
+            .concat();
+            let minf = mp4_box(b"minf", &mp4_box(b"stbl", &children));
+            let mdia = mp4_box(b"mdia", &[mp4_box(b"hdlr", &hdlr), minf].concat());
+            mp4_box(b"moov", &mp4_box(b"trak", &mdia))
+        };
+        // The moov size doesn't depend on the chunk offset, so measure it first.
+        let moov = stbl_with(0);
+        let moov = stbl_with((moov.len() + 8) as u32);
+        [moov, mdat].concat()
+    }
+
+    #[test]
+    fn rereading_a_sample_warns_once() {
+        // A user_data_unregistered SEI whose payload isn't telemetry.
+        let mut nal = vec![0x06, 0x05, 32];
+        nal.extend_from_slice(&[0x11; 16]);
+        nal.extend_from_slice(&[0xFF; 16]);
+        nal.push(0x80);
+        let mut sample = (nal.len() as u32).to_be_bytes().to_vec();
+        sample.extend(nal);
+
+        let clip = single_sample_clip(&sample);
+        let mut extractor = extractor_from_reader(Cursor::new(clip)).unwrap();
+        assert!(extractor.read_sample_events(0).unwrap().is_empty());
+        assert!(extractor.read_sample_events(0).unwrap().is_empty());
+        assert_eq!(
+            extractor.warnings(),
+            [Warning::UndecodableSei {
+                sample_index: 0,
+                file_offset: extractor.sample_offsets[0],
+                messages: 1,
+            }]
+        );
+    }
 }
@@ -0,0 +1,236 @@
+//! C ABI for embedding the extractor in C, C++ and Swift dashcam viewers (`ffi` feature).
+//!
+//! `include/tesla_sei.h` declares everything here; it is generated by cbindgen from this file
+//! with the `cbindgen.toml` at the crate root. Build the shared library with
+//! `cargo rustc --release --lib --no-default-features --features ffi --crate-type cdylib`
+//! (or `staticlib`).
+//!
+//! ```c
+//! TeslaSeiExtractor *extractor;
+//! if (tesla_sei_open("clip.mp4", &extractor) != TESLA_SEI_STATUS_OK) {
+//!     fprintf(stderr, "%s\n", tesla_sei_last_error());
+//!     return 1;
+//! }
+//! TeslaSeiTelemetry t;
+//! TeslaSeiStatus status;
+//! while ((status = tesla_sei_next(extractor, &t)) == TESLA_SEI_STATUS_OK) {
+//!     printf("%llu %.1f m/s\n", (unsigned long long)t.metadata.frame_seq_no,
+//!            t.metadata.vehicle_speed_mps);
+//! }
+//! tesla_sei_close(extractor);
+//! ```
+//!
+//! Functions never unwind into the caller: a panic is caught and reported as
+//! [`TeslaSeiStatus::Panic`]. An extractor may move between threads but must not be used by two
+//! at once.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fs::File;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use crate::extract::{extractor_from_path, SeiEvent, SeiExtractor};
+use crate::flat::SeiMetadataFlat;
+use crate::Error;
+
+/// Result of a `tesla_sei_*` call. Negative values are errors; [`tesla_sei_last_error`]
+/// describes the most recent one.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeslaSeiStatus {
+    Ok = 0,
+    /// `tesla_sei_next` found no more telemetry.
+    End = 1,
+    /// A required pointer was null, or the path isn't valid for this platform.
+    InvalidArgument = -1,
+    /// The file couldn't be opened or read.
+    Io = -2,
+    /// The file isn't a usable MP4: malformed boxes, no video track, broken sample tables.
+    InvalidMp4 = -3,
+    /// The video track's codec carries no SEI this library can read.
+    UnsupportedCodec = -4,
+    /// The library panicked; the extractor should be closed.
+    Panic = -5,
+}
+
+impl From<&Error> for TeslaSeiStatus {
+    fn from(e: &Error) -> Self {
+        match e {
+            Error::Io(_) => TeslaSeiStatus::Io,
+            Error::UnsupportedCodec { .. } => TeslaSeiStatus::UnsupportedCodec,
+            _ => TeslaSeiStatus::InvalidMp4,
+        }
+    }
+}
+
+/// One frame's telemetry plus where the frame sits in the clip.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TeslaSeiTelemetry {
+    /// The `SeiMetadata` fields, with enums as their raw values.
+    pub metadata: SeiMetadataFlat,
+    /// 0-based sample index, in decode order.
+    pub sample_index: u64,
+    /// 0-based position in display order.
+    pub presentation_index: u64,
+    /// Display time from the start of the clip, in microseconds; -1 if the track has no timing.
+    pub presentation_time_us: i64,
+}
+
+impl From<&SeiEvent> for TeslaSeiTelemetry {
+    fn from(event: &SeiEvent) -> Self {
+        TeslaSeiTelemetry {
+            metadata: (&event.metadata).into(),
+            sample_index: event.sample_index as u64,
+            presentation_index: event.presentation_index as u64,
+            presentation_time_us: event
+                .presentation_time
+                .map_or(-1, |t| i64::try_from(t.as_micros()).unwrap_or(i64::MAX)),
+        }
+    }
+}
+
+/// An open clip. Opaque to C.
+pub struct TeslaSeiExtractor {
+    inner: SeiExtractor<File>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// Remember `message` for `tesla_sei_last_error` and hand back `status`.
+fn fail(status: TeslaSeiStatus, message: impl ToString) -> TeslaSeiStatus {
+    // Interior NULs would cut the message short in C anyway.
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+    status
+}
+
+// Run `body`, turning a panic into `TeslaSeiStatus::Panic`.
+fn guarded(body: impl FnOnce() -> TeslaSeiStatus) -> TeslaSeiStatus {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        fail(TeslaSeiStatus::Panic, format!("panic: {message}"))
+    })
+}
+
+#[cfg(unix)]
+fn path_from_c(path: &CStr) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(std::ffi::OsStr::from_bytes(path.to_bytes()).into())
+}
+
+// Elsewhere paths are taken to be UTF-8.
+#[cfg(not(unix))]
+fn path_from_c(path: &CStr) -> Option<PathBuf> {
+    path.to_str().ok().map(PathBuf::from)
+}
+
+/// Open an MP4 clip. On success `*out` holds an extractor to pass to [`tesla_sei_next`] and
+/// finally [`tesla_sei_close`]; on failure it is set to null.
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `out` must point to writable memory for a
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tesla_sei_open(
+    path: *const c_char,
+    out: *mut *mut TeslaSeiExtractor,
+) -> TeslaSeiStatus {
+    guarded(|| {
+        if out.is_null() {
+            return fail(TeslaSeiStatus::InvalidArgument, "out is null");
+        }
+        // SAFETY: the caller passes a writable `out`.
+        unsafe { *out = ptr::null_mut() };
+        if path.is_null() {
+            return fail(TeslaSeiStatus::InvalidArgument, "path is null");
+        }
+        // SAFETY: the caller passes a NUL-terminated `path`.
+        let path = unsafe { CStr::from_ptr(path) };
+        let Some(path) = path_from_c(path) else {
+            return fail(TeslaSeiStatus::InvalidArgument, "path is not valid UTF-8");
+        };
+        match extractor_from_path(&path) {
+            Ok(inner) => {
+                let extractor = Box::new(TeslaSeiExtractor { inner });
+                // SAFETY: as above.
+                unsafe { *out = Box::into_raw(extractor) };
+                TeslaSeiStatus::Ok
+            }
+            Err(e) => fail((&e).into(), format!("{}: {e}", path.display())),
+        }
+    })
+}
+
+/// Decode the next frame's telemetry into `*out`, in decode order. Returns
+/// [`TeslaSeiStatus::Ok`] with `*out` filled in, [`TeslaSeiStatus::End`] once the clip is
+/// exhausted, or an error.
+///
+/// # Safety
+///
+/// `extractor` must come from [`tesla_sei_open`] and not yet be closed; `out` must point to
+/// writable memory for a `TeslaSeiTelemetry`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tesla_sei_next(
+    extractor: *mut TeslaSeiExtractor,
+    out: *mut TeslaSeiTelemetry,
+) -> TeslaSeiStatus {
+    guarded(|| {
+        // SAFETY: the caller passes a live extractor from `tesla_sei_open`, or null.
+        let Some(extractor) = (unsafe { extractor.as_mut() }) else {
+            return fail(TeslaSeiStatus::InvalidArgument, "extractor is null");
+        };
+        if out.is_null() {
+            return fail(TeslaSeiStatus::InvalidArgument, "out is null");
+        }
+        match extractor.inner.next_event() {
+            Ok(Some(event)) => {
+                // SAFETY: the caller passes a writable `out`.
+                unsafe { out.write(TeslaSeiTelemetry::from(&event)) };
+                TeslaSeiStatus::Ok
+            }
+            Ok(None) => TeslaSeiStatus::End,
+            Err(e) => fail((&e).into(), e),
+        }
+    })
+}
+
+/// Number of video samples in the clip, for progress reporting; 0 if `extractor` is null.
+///
+/// # Safety
+///
+/// `extractor` must come from [`tesla_sei_open`] and not yet be closed, or be null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tesla_sei_total_samples(extractor: *const TeslaSeiExtractor) -> u64 {
+    // SAFETY: the caller passes a live extractor from `tesla_sei_open`, or null.
+    unsafe { extractor.as_ref() }.map_or(0, |e| e.inner.total_samples() as u64)
+}
+
+/// Close an extractor and free it. Null is ignored.
+///
+/// # Safety
+///
+/// `extractor` must come from [`tesla_sei_open`] and not be used again afterwards, or be null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tesla_sei_close(extractor: *mut TeslaSeiExtractor) {
+    if !extractor.is_null() {
+        // SAFETY: the caller passes an extractor from `tesla_sei_open` exactly once.
+        drop(unsafe { Box::from_raw(extractor) });
+    }
+}
+
+/// Description of the most recent error on this thread, or null if there was none. The string
+/// stays valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn tesla_sei_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
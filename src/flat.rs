@@ -0,0 +1,89 @@
+//! A fixed-layout copy of [`SeiMetadata`] for crossing language and process boundaries.
+//!
+//! The generated protobuf type carries its enums as open `i32`s behind prost accessors and may
+//! grow fields with the schema. [`SeiMetadataFlat`] pins the current fields down as plain
+//! numbers and bools in a `#[repr(C)]` struct, so the C ABI, the wasm and Node bindings, and
+//! anything that copies telemetry through shared memory agree on one layout instead of each
+//! flattening it their own way.
+
+use crate::pb::SeiMetadata;
+
+/// The `SeiMetadata` fields in proto order, with enums as their raw values.
+///
+/// The layout is fixed (`repr(C)`), so it can be handed to C as is or copied between processes
+/// built from the same release.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SeiMetadataFlat {
+    pub version: u32,
+    /// `Gear`: 0 park, 1 drive, 2 reverse, 3 neutral.
+    pub gear_state: i32,
+    pub frame_seq_no: u64,
+    pub vehicle_speed_mps: f32,
+    pub accelerator_pedal_position: f32,
+    pub steering_wheel_angle: f32,
+    pub blinker_on_left: bool,
+    pub blinker_on_right: bool,
+    pub brake_applied: bool,
+    /// `AutopilotState`: 0 none, 1 self driving, 2 autosteer, 3 TACC.
+    pub autopilot_state: i32,
+    /// 0.0, 0.0 without a GPS fix.
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    pub heading_deg: f64,
+    pub linear_acceleration_mps2_x: f64,
+    pub linear_acceleration_mps2_y: f64,
+    pub linear_acceleration_mps2_z: f64,
+}
+
+impl From<&SeiMetadata> for SeiMetadataFlat {
+    fn from(m: &SeiMetadata) -> Self {
+        SeiMetadataFlat {
+            version: m.version,
+            gear_state: m.gear_state,
+            frame_seq_no: m.frame_seq_no,
+            vehicle_speed_mps: m.vehicle_speed_mps,
+            accelerator_pedal_position: m.accelerator_pedal_position,
+            steering_wheel_angle: m.steering_wheel_angle,
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: m.autopilot_state,
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+        }
+    }
+}
+
+impl From<SeiMetadata> for SeiMetadataFlat {
+    fn from(m: SeiMetadata) -> Self {
+        (&m).into()
+    }
+}
+
+impl From<SeiMetadataFlat> for SeiMetadata {
+    fn from(f: SeiMetadataFlat) -> Self {
+        SeiMetadata {
+            version: f.version,
+            gear_state: f.gear_state,
+            frame_seq_no: f.frame_seq_no,
+            vehicle_speed_mps: f.vehicle_speed_mps,
+            accelerator_pedal_position: f.accelerator_pedal_position,
+            steering_wheel_angle: f.steering_wheel_angle,
+            blinker_on_left: f.blinker_on_left,
+            blinker_on_right: f.blinker_on_right,
+            brake_applied: f.brake_applied,
+            autopilot_state: f.autopilot_state,
+            latitude_deg: f.latitude_deg,
+            longitude_deg: f.longitude_deg,
+            heading_deg: f.heading_deg,
+            linear_acceleration_mps2_x: f.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: f.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: f.linear_acceleration_mps2_z,
+        }
+    }
+}
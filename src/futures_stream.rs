@@ -0,0 +1,191 @@
+//! Runtime-agnostic event streams.
+//!
+//! The `async_extract` module needs a Tokio runtime (`spawn_blocking`,
+//! Tokio channels). The streams here implement plain `futures_core::Stream` and need no runtime:
+//! the sync extractor runs on a dedicated thread, or on whatever blocking pool the caller's
+//! spawner hands it to (`smol::unblock`, `async_std::task::spawn_blocking`, ...), and events
+//! are handed over through a small bounded channel that wakes the consuming task directly.
+
+use std::collections::VecDeque;
+use std::io::{Read, Seek};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use crate::extract::{extractor_from_path, extractor_from_reader, SeiEvent, SeiExtractor};
+use crate::Error;
+
+/// A blocking extraction job. A spawner passed to the `*_with_spawner` functions runs it
+/// somewhere it may block, e.g. `|job| smol::unblock(job).detach()`; if the job is dropped
+/// without running, the stream just ends.
+pub type BlockingJob = Box<dyn FnOnce() + Send + 'static>;
+
+/// Stream of events from [`event_stream_from_path`] and friends.
+///
+/// Dropping the stream stops extraction before the next event is sent.
+pub struct EventStream {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Signalled when the queue has room or the stream was dropped.
+    space: Condvar,
+}
+
+struct State {
+    queue: VecDeque<Result<SeiEvent, Error>>,
+    capacity: usize,
+    stream_dropped: bool,
+    worker_done: bool,
+    waker: Option<Waker>,
+}
+
+/// Stream the events of the MP4 at `path`, extracting on a new thread.
+///
+/// `buffer` is the channel capacity, as for the Tokio `stream_from_path`.
+pub fn event_stream_from_path(path: impl Into<PathBuf>, buffer: usize) -> EventStream {
+    event_stream_from_path_with_spawner(path, buffer, spawn_thread)
+}
+
+/// Like [`event_stream_from_path`], but hands the extraction job to `spawner`.
+pub fn event_stream_from_path_with_spawner(
+    path: impl Into<PathBuf>,
+    buffer: usize,
+    spawner: impl FnOnce(BlockingJob),
+) -> EventStream {
+    let path = path.into();
+    spawn_extraction(move || extractor_from_path(path), buffer, spawner)
+}
+
+/// Stream the events of any seekable reader, extracting on a new thread.
+pub fn event_stream_from_reader<R>(reader: R, buffer: usize) -> EventStream
+where
+    R: Read + Seek + Send + 'static,
+{
+    event_stream_from_reader_with_spawner(reader, buffer, spawn_thread)
+}
+
+/// Like [`event_stream_from_reader`], but hands the extraction job to `spawner`.
+pub fn event_stream_from_reader_with_spawner<R>(
+    reader: R,
+    buffer: usize,
+    spawner: impl FnOnce(BlockingJob),
+) -> EventStream
+where
+    R: Read + Seek + Send + 'static,
+{
+    spawn_extraction(move || extractor_from_reader(reader), buffer, spawner)
+}
+
+fn spawn_thread(job: BlockingJob) {
+    std::thread::Builder::new()
+        .name("tesla-sei-extract".into())
+        .spawn(job)
+        .expect("failed to spawn extraction thread");
+}
+
+fn spawn_extraction<R, F>(open: F, buffer: usize, spawner: impl FnOnce(BlockingJob)) -> EventStream
+where
+    R: Read + Seek,
+    F: FnOnce() -> Result<SeiExtractor<R>, Error> + Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            capacity: buffer.max(1),
+            stream_dropped: false,
+            worker_done: false,
+            waker: None,
+        }),
+        space: Condvar::new(),
+    });
+
+    let sender = Sender(shared.clone());
+    spawner(Box::new(move || {
+        let extractor = match open() {
+            Ok(e) => e,
+            Err(err) => {
+                sender.send(Err(err));
+                return;
+            }
+        };
+        for item in extractor {
+            if !sender.send(item) {
+                break;
+            }
+        }
+    }));
+
+    EventStream { shared }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic while holding the lock leaves the queue consistent, so ignore poisoning.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// The worker's end of the channel; dropping it (including on panic) ends the stream.
+struct Sender(Arc<Shared>);
+
+impl Sender {
+    // Blocks while the queue is full; false once the stream has been dropped.
+    fn send(&self, item: Result<SeiEvent, Error>) -> bool {
+        let mut state = self.0.lock();
+        while state.queue.len() >= state.capacity && !state.stream_dropped {
+            state = self.0.space.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        if state.stream_dropped {
+            return false;
+        }
+        state.queue.push_back(item);
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        true
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.worker_done = true;
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<SeiEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.lock();
+        if let Some(item) = state.queue.pop_front() {
+            drop(state);
+            self.shared.space.notify_one();
+            return Poll::Ready(Some(item));
+        }
+        if state.worker_done {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.shared.lock().stream_dropped = true;
+        self.shared.space.notify_all();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::pb;
+
+/// Mean Earth radius (IUGG), in meters.
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Standard gravity, for expressing accelerations in g.
+pub const STANDARD_GRAVITY_MPS2: f64 = 9.806_65;
+
+/// A WGS84 coordinate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+}
+
+impl GeoPoint {
+    /// Position from decoded metadata, or `None` if it has no GPS fix.
+    pub fn from_metadata(m: &pb::SeiMetadata) -> Option<Self> {
+        has_gps_fix(m).then_some(GeoPoint {
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+        })
+    }
+
+    /// Great-circle distance to `other`, in meters.
+    pub fn distance_m(&self, other: &GeoPoint) -> f64 {
+        haversine_m(
+            self.latitude_deg,
+            self.longitude_deg,
+            other.latitude_deg,
+            other.longitude_deg,
+        )
+    }
+}
+
+/// Great-circle distance between two WGS84 coordinates, in meters.
+pub fn haversine_m(lat1_deg: f64, lon1_deg: f64, lat2_deg: f64, lon2_deg: f64) -> f64 {
+    let (lat1, lat2) = (lat1_deg.to_radians(), lat2_deg.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2_deg - lon1_deg).to_radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+/// Whether the metadata carries a usable GPS fix.
+///
+/// The car reports `0.0, 0.0` while it has no fix, so that exact position is treated as missing.
+pub fn has_gps_fix(m: &pb::SeiMetadata) -> bool {
+    !(m.latitude_deg == 0.0 && m.longitude_deg == 0.0)
+        && m.latitude_deg.is_finite()
+        && m.longitude_deg.is_finite()
+}
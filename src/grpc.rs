@@ -0,0 +1,199 @@
+//! gRPC service that streams a clip's telemetry back as `SeiMetadata` messages (`grpc` feature).
+//!
+//! The `TelemetryExtractor` service is defined in `proto/extractor.proto` on top of
+//! `proto/dashcam.proto`, so services in other languages generate their stubs from those two
+//! files. `tesla-sei serve-grpc` runs it; [`serve`] does the same from Rust, and
+//! [`ExtractorService::into_server`] adds it to an existing tonic server.
+
+use std::io::{self, Cursor};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::async_extract::{stream_from_path, stream_from_reader};
+use crate::extract::SeiEvent;
+use crate::pb::SeiMetadata;
+use crate::Error;
+
+/// Code generated from `proto/extractor.proto`: the service trait, server and client.
+pub mod proto {
+    tonic::include_proto!("tesla_sei.v1");
+}
+
+use proto::telemetry_extractor_server::{TelemetryExtractor, TelemetryExtractorServer};
+use proto::{ClipChunk, ExtractPathRequest};
+
+/// Options for [`ExtractorService`].
+#[derive(Debug, Clone)]
+pub struct GrpcOptions {
+    /// Folder `ExtractPath` requests are resolved against. `None` disables `ExtractPath`, so
+    /// clients can only upload clips.
+    pub root: Option<PathBuf>,
+    /// Largest clip `ExtractUpload` accepts, in bytes.
+    pub max_upload: usize,
+    /// Events buffered per call, as for [`stream_from_path`].
+    pub buffer: usize,
+}
+
+impl Default for GrpcOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_upload: 256 * 1024 * 1024,
+            buffer: 64,
+        }
+    }
+}
+
+/// Telemetry stream returned by both calls.
+pub type TelemetryStream = Pin<Box<dyn Stream<Item = Result<SeiMetadata, Status>> + Send>>;
+
+/// The `TelemetryExtractor` service. Each call runs the extractor on Tokio's blocking pool.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractorService {
+    options: GrpcOptions,
+}
+
+impl ExtractorService {
+    pub fn new(options: GrpcOptions) -> Self {
+        Self { options }
+    }
+
+    /// Wrap the service for `tonic::transport::Server::add_service`.
+    pub fn into_server(self) -> TelemetryExtractorServer<Self> {
+        TelemetryExtractorServer::new(self)
+    }
+
+    // Resolve a requested path inside the root folder, refusing anything that leads out of it
+    // (`..`, absolute paths, symlinks).
+    async fn resolve(&self, requested: &str) -> Result<PathBuf, Status> {
+        let Some(root) = &self.options.root else {
+            return Err(Status::failed_precondition(
+                "this server has no clip folder; upload the clip with ExtractUpload instead",
+            ));
+        };
+        let root = tokio::fs::canonicalize(root).await?;
+        // Name the path as requested, not as it resolves on this machine.
+        let path = tokio::fs::canonicalize(root.join(requested))
+            .await
+            .map_err(|e| io::Error::new(e.kind(), format!("{requested}: {e}")))?;
+        if !path.starts_with(&root) {
+            return Err(Status::permission_denied(format!(
+                "{requested} is outside the clip folder"
+            )));
+        }
+        Ok(path)
+    }
+}
+
+#[tonic::async_trait]
+impl TelemetryExtractor for ExtractorService {
+    type ExtractPathStream = TelemetryStream;
+    type ExtractUploadStream = TelemetryStream;
+
+    async fn extract_path(
+        &self,
+        request: Request<ExtractPathRequest>,
+    ) -> Result<Response<TelemetryStream>, Status> {
+        let path = self.resolve(&request.get_ref().path).await?;
+        let events = stream_from_path(path, self.options.buffer);
+        Ok(Response::new(telemetry(events)))
+    }
+
+    async fn extract_upload(
+        &self,
+        request: Request<Streaming<ClipChunk>>,
+    ) -> Result<Response<TelemetryStream>, Status> {
+        let mut chunks = request.into_inner();
+        let mut clip = Vec::new();
+        while let Some(chunk) = chunks.message().await? {
+            if clip.len() + chunk.data.len() > self.options.max_upload {
+                return Err(Status::resource_exhausted(format!(
+                    "clip is larger than the {} byte upload limit",
+                    self.options.max_upload
+                )));
+            }
+            clip.extend_from_slice(&chunk.data);
+        }
+        let events = stream_from_reader(Cursor::new(clip), self.options.buffer);
+        Ok(Response::new(telemetry(events)))
+    }
+}
+
+fn telemetry(events: ReceiverStream<Result<SeiEvent, Error>>) -> TelemetryStream {
+    Box::pin(events.map(|event| event.map(|e| e.metadata).map_err(status)))
+}
+
+fn status(e: Error) -> Status {
+    match &e {
+        Error::Io(io) => match io.kind() {
+            io::ErrorKind::NotFound => Status::not_found(e.to_string()),
+            io::ErrorKind::PermissionDenied => Status::permission_denied(e.to_string()),
+            // A clip cut short reads past its end.
+            io::ErrorKind::UnexpectedEof => Status::invalid_argument(e.to_string()),
+            _ => Status::internal(e.to_string()),
+        },
+        Error::UnsupportedCodec { .. } => Status::failed_precondition(e.to_string()),
+        _ => Status::invalid_argument(e.to_string()),
+    }
+}
+
+/// Serve [`ExtractorService`] on `addr` until the process ends.
+pub async fn serve(addr: SocketAddr, options: GrpcOptions) -> Result<(), Error> {
+    Server::builder()
+        .add_service(ExtractorService::new(options).into_server())
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tonic::Code;
+
+    use super::*;
+
+    #[test]
+    fn resolve_stays_inside_root() {
+        let dir = std::env::temp_dir().join(format!("tesla-sei-grpc-{}", std::process::id()));
+        let root = dir.join("clips");
+        fs::create_dir_all(root.join("event")).unwrap();
+        fs::write(root.join("event/inside.mp4"), b"").unwrap();
+        fs::write(dir.join("outside.mp4"), b"").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("outside.mp4"), root.join("link.mp4")).unwrap();
+        let service = ExtractorService::new(GrpcOptions {
+            root: Some(root.clone()),
+            ..GrpcOptions::default()
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let resolve = |requested: &str| runtime.block_on(service.resolve(requested));
+        let outside = dir.join("outside.mp4").display().to_string();
+        let mut escapes = vec!["../outside.mp4", "event/../../outside.mp4", "..", &outside];
+        if cfg!(unix) {
+            escapes.push("link.mp4");
+        }
+        for requested in escapes {
+            let status = resolve(requested).unwrap_err();
+            assert_eq!(status.code(), Code::PermissionDenied, "{requested}");
+        }
+        let inside = resolve("event/../event/inside.mp4").unwrap();
+        assert_eq!(
+            inside,
+            fs::canonicalize(root.join("event/inside.mp4")).unwrap()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
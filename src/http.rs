@@ -0,0 +1,212 @@
+//! Read clips over HTTP byte-range requests.
+//!
+//! [`HttpRangeReader`] implements `Read + Seek` on top of `Range: bytes=` GET requests, so any
+//! `*_reader` entry point ([`extractor_from_reader`],
+//! [`quick_look_reader`](crate::quick_look_reader), ...) can process a clip on a web server or
+//! behind a presigned URL while fetching only the boxes and samples it touches.
+//!
+//! The reader uses reqwest's blocking client. Don't create or read it on a Tokio runtime thread
+//! (reqwest panics there); wrap the work in `tokio::task::spawn_blocking` instead.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+
+use crate::extract::{extractor_from_reader, SeiExtractor};
+use crate::Error;
+
+/// Options for [`HttpRangeReader::with_options`].
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Bytes fetched per request. Reads that miss the buffer fetch at least this much, so
+    /// neighbouring box headers and samples are served without another round trip.
+    pub read_ahead: usize,
+    /// Per-request timeout; `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            read_ahead: 256 * 1024,
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// A seekable reader over a remote file, fetched in byte ranges on demand.
+#[derive(Debug)]
+pub struct HttpRangeReader {
+    client: Client,
+    url: String,
+    len: u64,
+    pos: u64,
+    read_ahead: usize,
+    buf: Vec<u8>,
+    buf_start: u64,
+    requests: u64,
+}
+
+impl HttpRangeReader {
+    /// Open `url` with default options.
+    pub fn new(url: impl Into<String>) -> Result<Self, Error> {
+        Self::with_options(url, &HttpOptions::default())
+    }
+
+    /// Open `url`, learning its length from the first range response.
+    ///
+    /// The length is probed with a ranged GET rather than a HEAD request, since presigned URLs
+    /// are usually signed for GET only. The probe's bytes (the start of the file, where `ftyp`
+    /// and often `moov` live) are kept as the first read-ahead buffer.
+    pub fn with_options(url: impl Into<String>, opts: &HttpOptions) -> Result<Self, Error> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = opts.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Self::with_client(builder.build()?, url, opts)
+    }
+
+    /// Like [`with_options`](Self::with_options) but with a caller-configured client (proxies,
+    /// default headers, TLS roots); `opts.timeout` is ignored.
+    pub fn with_client(
+        client: Client,
+        url: impl Into<String>,
+        opts: &HttpOptions,
+    ) -> Result<Self, Error> {
+        let mut reader = HttpRangeReader {
+            client,
+            url: url.into(),
+            len: 0,
+            pos: 0,
+            read_ahead: opts.read_ahead.max(1),
+            buf: Vec::new(),
+            buf_start: 0,
+            requests: 0,
+        };
+
+        let end = reader.read_ahead as u64 - 1;
+        let resp = reader.get_range(0, end)?;
+        // An empty file can't satisfy any range.
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(reader);
+        }
+        let resp = resp.error_for_status()?;
+        let range = match resp.status() {
+            StatusCode::PARTIAL_CONTENT => content_range(&resp),
+            _ => None,
+        };
+        let Some((0, _, total)) = range else {
+            return Err(Error::RangeNotSupported { url: reader.url });
+        };
+        reader.len = total;
+        reader.buf = resp.bytes()?.to_vec();
+        Ok(reader)
+    }
+
+    /// The URL being read.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Total length of the remote file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the remote file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of HTTP requests issued so far, the length probe included.
+    pub fn request_count(&self) -> u64 {
+        self.requests
+    }
+
+    fn get_range(&mut self, start: u64, end: u64) -> reqwest::Result<Response> {
+        self.requests += 1;
+        self.client
+            .get(&self.url)
+            .header(RANGE, format!("bytes={start}-{end}"))
+            .send()
+    }
+
+    fn fill(&mut self, min_len: usize) -> io::Result<()> {
+        let want = min_len.max(self.read_ahead) as u64;
+        let end = (self.pos + want).min(self.len) - 1;
+        let resp = self.get_range(self.pos, end).map_err(io::Error::other)?;
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            let status = resp.status();
+            return Err(io::Error::other(format!(
+                "range request for bytes {}-{end} of {} returned {status}",
+                self.pos, self.url
+            )));
+        }
+        if content_range(&resp).map(|(start, _, _)| start) != Some(self.pos) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected Content-Range from {}", self.url),
+            ));
+        }
+        self.buf = resp.bytes().map_err(io::Error::other)?.to_vec();
+        self.buf_start = self.pos;
+        if self.buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("empty range response from {}", self.url),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Read for HttpRangeReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        let buf_end = self.buf_start + self.buf.len() as u64;
+        if self.pos < self.buf_start || self.pos >= buf_end {
+            self.fill(out.len())?;
+        }
+        let at = (self.pos - self.buf_start) as usize;
+        let n = out.len().min(self.buf.len() - at);
+        out[..n].copy_from_slice(&self.buf[at..at + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpRangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        let Some(target) = target else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to a negative position",
+            ));
+        };
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+/// Create an extractor reading `url` through an [`HttpRangeReader`] with default options.
+pub fn extractor_from_url(url: &str) -> Result<SeiExtractor<HttpRangeReader>, Error> {
+    extractor_from_reader(HttpRangeReader::new(url)?)
+}
+
+// (first byte, last byte, complete length) from `Content-Range: bytes a-b/len`.
+fn content_range(resp: &Response) -> Option<(u64, u64, u64)> {
+    let value = resp.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?, total.parse().ok()?))
+}
@@ -0,0 +1,314 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::extract::{
+    extractor_from_reader, select_largest_track, ticks_to_duration, SeiExtractor,
+};
+use crate::geo::GeoPoint;
+use crate::mp4::{parse_mp4, read_box_tree};
+use crate::Error;
+
+/// Frame dimensions of a video track, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Resolution {
+    /// Total pixel count, used to rank tracks (e.g. main camera vs. preview).
+    pub fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// One key/value entry from the MP4 user-data boxes (`udta`, `meta`/`ilst`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDataEntry {
+    /// Item key: a four-character code such as `©too`, a QuickTime `keys` name such as
+    /// `com.apple.quicktime.make`, or `mean:name` for iTunes-style freeform (`----`) items.
+    pub key: String,
+    pub value: UserDataValue,
+}
+
+/// Value of a [`UserDataEntry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserDataValue {
+    Text(String),
+    Integer(i64),
+    /// Anything that isn't text or a plain integer (images, binary blobs, unknown types).
+    Binary(Vec<u8>),
+}
+
+impl fmt::Display for UserDataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UserDataValue::Text(s) => f.write_str(s),
+            UserDataValue::Integer(v) => write!(f, "{v}"),
+            UserDataValue::Binary(b) => {
+                for byte in b {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Video codec of a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoCodec {
+    /// H.264 (`avc1`).
+    Avc,
+    /// H.265 (`hvc1`/`hev1`).
+    Hevc,
+    /// AV1 (`av01`).
+    Av1,
+    Unknown,
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VideoCodec::Avc => "h264",
+            VideoCodec::Hevc => "h265",
+            VideoCodec::Av1 => "av1",
+            VideoCodec::Unknown => "unknown",
+        })
+    }
+}
+
+/// Profile, tier and level signalled in a track's decoder configuration (`avcC`/`hvcC`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CodecProfile {
+    /// `profile_idc`, e.g. 100 (H.264 High), 1 (HEVC Main) or 2 (HEVC Main 10).
+    pub profile_idc: u8,
+    /// HEVC `general_profile_space`; always 0 for H.264.
+    pub profile_space: u8,
+    /// HEVC high tier; always `false` for H.264.
+    pub high_tier: bool,
+    /// `level_idc`: the level times 10 for H.264 (e.g. 41), times 30 for HEVC (e.g. 123).
+    pub level_idc: u8,
+}
+
+/// Summary of the video track selected for extraction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackInfo {
+    /// MP4 track ID (`tkhd`).
+    pub track_id: Option<u32>,
+    /// Handler name from `hdlr`, e.g. `VideoHandler`; tells a clip's main camera track apart
+    /// from auxiliary ones when the muxer names them.
+    pub handler_name: Option<String>,
+    pub codec: VideoCodec,
+    /// Profile and level from `avcC`/`hvcC`, when present.
+    pub profile: Option<CodecProfile>,
+    pub resolution: Option<Resolution>,
+    /// Frames per second, from the SPS timing info or else the average sample duration.
+    pub frame_rate: Option<f64>,
+    pub sample_count: usize,
+    /// Media timescale (ticks per second).
+    pub timescale: Option<u32>,
+    pub duration: Option<Duration>,
+}
+
+/// A cheap overview of a clip, for file-manager previews and archive indexers.
+///
+/// Produced by [`quick_look`], which only decodes a handful of samples at each end of the clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickLook {
+    pub track: TrackInfo,
+    pub duration: Option<Duration>,
+    /// Whether any SEI telemetry was found in the probed samples.
+    pub has_telemetry: bool,
+    /// First GPS fix near the start of the clip.
+    pub first_gps: Option<GeoPoint>,
+    /// Last GPS fix near the end of the clip.
+    pub last_gps: Option<GeoPoint>,
+    /// The SEI payload `version` field, which identifies the firmware's telemetry variant.
+    pub firmware_variant: Option<u32>,
+}
+
+/// Parsed structure of an MP4 file, as seen by the extractor.
+///
+/// Produced by [`mp4_info`], which reads only the box headers and sample tables: no sample data
+/// is decoded and per-sample offsets aren't built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mp4Info {
+    /// Movie timescale (`mvhd`).
+    pub timescale: Option<u32>,
+    /// Movie duration (`mvhd`).
+    pub duration: Option<Duration>,
+    /// Video tracks in file order; other track types are skipped by the parser.
+    pub tracks: Vec<Mp4TrackInfo>,
+    /// Key/value items from the movie's user-data boxes.
+    pub user_data: Vec<UserDataEntry>,
+}
+
+impl Mp4Info {
+    /// The track the extractor decodes (highest resolution, then most samples).
+    pub fn selected_track(&self) -> Option<&Mp4TrackInfo> {
+        self.tracks.iter().find(|t| t.selected)
+    }
+}
+
+/// Sample-table details of one video track in an [`Mp4Info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mp4TrackInfo {
+    /// Codec, resolution and timing, as [`SeiExtractor::track_info`] would report them.
+    pub track: TrackInfo,
+    /// Number of chunks (`stco`/`co64` entries).
+    pub chunk_count: usize,
+    /// Number of sample entries in `stsd`; more than one means the codec configuration changes
+    /// mid-track.
+    pub sample_entry_count: usize,
+    /// Whether the track has composition offsets (`ctts`), i.e. B-frames.
+    pub has_composition_offsets: bool,
+    /// Number of edit list (`elst`) entries.
+    pub edit_count: usize,
+    /// Whether this is the track the extractor would select.
+    pub selected: bool,
+}
+
+/// Inspect the structure of an on-disk MP4.
+pub fn mp4_info(path: impl AsRef<Path>) -> Result<Mp4Info, Error> {
+    mp4_info_reader(File::open(path)?)
+}
+
+/// Inspect the structure of an MP4 from any seekable reader.
+///
+/// Unlike [`extractor_from_reader`] this succeeds on files with no video tracks (`tracks` is
+/// empty) and doesn't validate that the sample tables agree with each other.
+pub fn mp4_info_reader<R: Read + Seek>(mut reader: R) -> Result<Mp4Info, Error> {
+    let mp4 = parse_mp4(&mut reader)?;
+    let selected = select_largest_track(&mp4.tracks).map(|(i, _)| i);
+
+    let tracks = mp4
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let media = t.media_header.as_ref();
+            Mp4TrackInfo {
+                track: TrackInfo {
+                    track_id: t.track_header.as_ref().map(|h| h.track_id),
+                    handler_name: t.handler_name.clone(),
+                    codec: t.video_codec(),
+                    profile: t.decoder_config.as_ref().map(|c| c.profile),
+                    resolution: t.resolution(),
+                    frame_rate: t.frame_rate(),
+                    sample_count: t.sample_sizes.len(),
+                    timescale: media.map(|h| h.timescale).filter(|&ts| ts != 0),
+                    duration: media.and_then(|h| ticks_to_duration(h.duration, h.timescale)),
+                },
+                chunk_count: t.chunk_offsets.len(),
+                sample_entry_count: t.codecs.len(),
+                has_composition_offsets: !t.ctts.is_empty(),
+                edit_count: t.elst.len(),
+                selected: selected == Some(i),
+            }
+        })
+        .collect();
+
+    let movie = mp4.movie_header.as_ref();
+    Ok(Mp4Info {
+        timescale: movie.map(|h| h.timescale).filter(|&ts| ts != 0),
+        duration: movie.and_then(|h| ticks_to_duration(h.duration, h.timescale)),
+        tracks,
+        user_data: mp4.user_data,
+    })
+}
+
+/// One box in the tree returned by [`dump_box_tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxNode {
+    /// Four-character code, with non-printable bytes shown as `.`.
+    pub box_type: String,
+    /// Absolute file offset of the box header.
+    pub offset: u64,
+    /// Size as declared in the header (header included); a size of 0 ("to the end") is resolved.
+    pub size: u64,
+    /// 8, or 16 for boxes with a 64-bit size.
+    pub header_len: u64,
+    /// Child boxes, for the container types the parser descends into.
+    pub children: Vec<BoxNode>,
+    /// What is wrong with this box, if anything: a size smaller than its header (which ends the
+    /// walk of the enclosing container) or one that runs past the enclosing box or file.
+    pub error: Option<String>,
+}
+
+/// List the box structure of an MP4: offsets, sizes and nesting.
+///
+/// Meant for debugging malformed files, so it doesn't fail on bad boxes; see
+/// [`BoxNode::error`]. Only IO errors are returned.
+pub fn dump_box_tree<R: Read + Seek>(mut reader: R) -> Result<Vec<BoxNode>, Error> {
+    Ok(read_box_tree(&mut reader)?)
+}
+
+/// How many samples [`quick_look`] decodes from each end of the clip while looking for telemetry
+/// and GPS fixes.
+pub const QUICK_LOOK_PROBE_SAMPLES: usize = 64;
+
+/// Build a [`QuickLook`] for an on-disk MP4.
+pub fn quick_look(path: impl AsRef<Path>) -> Result<QuickLook, Error> {
+    quick_look_reader(File::open(path)?)
+}
+
+/// Build a [`QuickLook`] from any seekable reader.
+///
+/// IO is limited to the MP4 headers plus at most [`QUICK_LOOK_PROBE_SAMPLES`] samples from each
+/// end of the track; probing stops as soon as a GPS fix is found.
+pub fn quick_look_reader<R: Read + Seek>(reader: R) -> Result<QuickLook, Error> {
+    let mut extractor = extractor_from_reader(reader)?;
+    let total = extractor.total_samples();
+
+    let mut probe = Probe::default();
+    let head = 0..total.min(QUICK_LOOK_PROBE_SAMPLES);
+    let first_gps = probe.scan(&mut extractor, head)?;
+    let tail = total.saturating_sub(QUICK_LOOK_PROBE_SAMPLES)..total;
+    let last_gps = probe.scan(&mut extractor, tail.rev())?;
+
+    let track = extractor.track_info();
+    Ok(QuickLook {
+        duration: track.duration,
+        track,
+        has_telemetry: probe.has_telemetry,
+        first_gps,
+        last_gps,
+        firmware_variant: probe.firmware_variant,
+    })
+}
+
+#[derive(Default)]
+struct Probe {
+    has_telemetry: bool,
+    firmware_variant: Option<u32>,
+}
+
+impl Probe {
+    // Decode samples in the given order and return the first GPS fix encountered.
+    fn scan<R: Read + Seek>(
+        &mut self,
+        extractor: &mut SeiExtractor<R>,
+        samples: impl Iterator<Item = usize>,
+    ) -> Result<Option<GeoPoint>, Error> {
+        for sample_index in samples {
+            for event in extractor.read_sample_events(sample_index)? {
+                self.has_telemetry = true;
+                self.firmware_variant.get_or_insert(event.metadata.version);
+                if let Some(p) = GeoPoint::from_metadata(&event.metadata) {
+                    return Ok(Some(p));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
@@ -9,36 +9,229 @@
 //! ## Quick start (sync)
 //! - Open a file and iterate decoded events:
 //!   - Use [`extractor_from_path`] and iterate the returned [`SeiExtractor`].
+//!   - Or collect a whole clip at once with [`extract_all`].
+//! - [`SeiExtractor::validate_sei`] checks every SEI NAL unit strictly and reports syntax
+//!   violations per sample, e.g. for files from third-party muxers.
+//! - Problems the extractor tolerates (clamped boxes, gaps in `stsc`, SEI that doesn't decode)
+//!   are collected as [`Warning`]s; read them with [`SeiExtractor::warnings`] to log a clip's
+//!   data-quality issues without failing on them.
 //! - For GUI scrubbing, use [`SeiExtractor::read_sample_events`] or [`SeiExtractor::seek_sample`].
+//! - Raw `.h264`/`.h265` elementary streams (no MP4 container) go through
+//!   [`annexb_extractor_from_path`] instead.
+//! - [`SeiMetadataFlat`] is a `#[repr(C)]` copy of the telemetry with enums as `i32`, the
+//!   layout shared by the C ABI, the wasm and Node bindings, and shared-memory consumers.
+//!
+//! ## Previews and indexing
+//! - [`quick_look`] returns track info, duration, first/last GPS fix and the firmware variant
+//!   while reading only the MP4 headers and a few samples at each end of the clip.
+//! - [`thumbnail_hints`] suggests meaningful preview frames (harsh event, max speed, mid-drive)
+//!   instead of frame zero.
+//! - [`clip_summary`] totals a clip's telemetry: distance, speed range, time per gear,
+//!   autopilot share, peak horizontal g and GPS bounding box.
+//! - [`driving_events`] lists notable moments (hard braking, rapid acceleration, autopilot
+//!   engaging and disengaging, blinkers, gear changes) with their sample indices and times.
+//! - [`compare_cameras`] cross-checks the camera files of one recorded minute (shared frame
+//!   sequence numbers, GPS agreement, samples without telemetry) to spot tampered or partially
+//!   corrupted clip sets.
+//! - [`mp4_info`] describes the parsed container (tracks, sample and chunk counts, codecs)
+//!   without decoding anything; [`dump_box_tree`] lists raw box offsets and sizes for debugging
+//!   malformed files.
+//!
+//! ## Rewriting
+//! - [`inject_sei`] writes a copy of a clip with `SeiMetadata` muxed into chosen samples, e.g.
+//!   to build test fixtures.
+//! - [`redact_gps`] zeroes or jitters the GPS position in a clip's telemetry before sharing it;
+//!   [`strip_sei`] removes the telemetry (or all SEI) instead.
 //!
 //! ## Quick start (async)
-//! - Use [`stream_from_path`] to get a Tokio `Stream` of events.
+//! - Use [`stream_from_path`] to get a Tokio `Stream` of events, or [`extract_all_async`] for
+//!   the whole clip in one await.
+//! - [`async_extractor_from_path`] / [`async_extractor_from_reader`] read through Tokio's
+//!   `AsyncRead + AsyncSeek` instead (e.g. `tokio::fs::File` or a network source) without
+//!   occupying a blocking thread; [`AsyncSeiExtractor::into_stream`] turns one into a `Stream`.
 //! - To start from a scrubbed position, use [`stream_from_path_from_sample`].
+//! - [`stream_from_path_with_handle`] also returns an [`ExtractionHandle`] that aborts the
+//!   worker promptly and reports whether extraction completed, was cancelled, or failed.
+//! - [`stream_from_path_with_progress`] also returns a `watch` channel of [`Progress`]
+//!   (samples processed out of the total) for progress bars.
+//! - [`stream_from_path_metered`] exposes channel occupancy and blocked-send counters to detect
+//!   slow consumers, and can grow its buffer adaptively.
+//! - [`SeiStreamExt::time_windows`] groups a stream's events into fixed time buckets with
+//!   min/max/mean speed, for dashboards.
+//! - [`merge_cameras`] extracts the camera files of one recorded minute concurrently and yields
+//!   their events camera-tagged and ordered by frame sequence number.
+//!
+//! ## Catalog queries
+//! - Use [`Catalog::scan`] on a TeslaCam folder, then e.g. [`Catalog::passes_near`] to find every
+//!   pass through an area across all clips.
+//! - [`Catalog::trips`] assembles consecutive clips into drives, and [`route::cluster_routes`]
+//!   groups those drives by route (e.g. "all my commutes").
+//! - [`Catalog::period_stats`] aggregates distance, drive time, and event counts per day or week.
 //!
 //! ## Features
 //! - `async` (default): enables Tokio stream helpers.
+//! - `futures`: `futures_stream` streams implement plain `futures_core::Stream` and run the
+//!   extractor on their own thread or a caller-supplied spawner, for async-std/smol apps that
+//!   don't run Tokio.
+//! - `arrow`: `arrow::events_to_record_batch` collects events (and
+//!   `arrow::metadata_to_record_batch` bare messages) into an Arrow `RecordBatch` with typed
+//!   columns, for DataFusion, Polars and pyarrow.
+//! - `polars`: `SeiExtractor::to_dataframe` decodes a clip into a Polars `DataFrame`, one row
+//!   per frame.
+//! - `cli` (default): the `tesla-sei` binary; implies `chrono`, `serde` and `tracing`.
+//! - `chrono`: wall-clock times ([`SeiExtractor::creation_time`]), TeslaCam clip names, and the
+//!   catalog, trip, route, and report modules.
+//! - `serde`: the versioned JSON export format in [`schema`], and `Serialize`/`Deserialize` on
+//!   [`pb::SeiMetadata`] and its enums.
+//! - `player`: telemetry keyed by playback time for mpv/GStreamer overlays (`player` module);
+//!   implies `serde`.
+//! - `reflect`: decode telemetry against a descriptor set loaded at runtime (`dynamic` module),
+//!   for firmware that extends the schema before this crate catches up.
+//! - `http`: `http::HttpRangeReader` reads a clip on a web server or behind a presigned URL
+//!   through byte-range requests, so it can be passed to any `*_reader` entry point without
+//!   downloading the whole file.
+//! - `s3`: `s3::S3RangeReader` does the same for S3 objects with ranged `GetObject` requests,
+//!   feeding the async extractor (`s3::async_extractor_from_s3`); implies `async`.
+//! - `watch`: `watch::watch_dir` follows a TeslaCam folder (including network mounts, by
+//!   polling) and streams the telemetry of each clip as the car finishes writing it; implies
+//!   `async`.
+//! - `grpc`: a tonic service (`grpc` module, `proto/extractor.proto`) that streams a clip's
+//!   telemetry back as `SeiMetadata` messages, for services written in other languages; the CLI
+//!   serves it with `serve-grpc`. Implies `async`.
+//! - `rest`: an HTTP API (`rest` module, axum) that answers clip uploads and clips in a folder
+//!   with their telemetry as NDJSON, for web viewers; the CLI serves it with `serve`. Implies
+//!   `async` and `serde`.
+//! - `ffi`: a C ABI (`ffi` module) to embed the extractor in C, C++ and Swift applications,
+//!   declared by the cbindgen-generated `include/tesla_sei.h`.
+//! - `tracing`: diagnostics as `tracing` events (the MP4 box walk, which video track was picked,
+//!   sample table repairs), for whatever subscriber the application installs. The CLI enables
+//!   it for `-v`.
+//! - `wasm`: wasm-bindgen wrappers (`wasm` module) that decode a clip passed in as bytes, for
+//!   browser-based viewers that read telemetry client-side.
+//!
+//! With `default-features = false` only the extractor core remains, depending on just prost and
+//! std, for size-sensitive binaries and wasm.
 
 pub mod pb {
     include!(concat!(env!("OUT_DIR"), "/dashcam.rs"));
 }
 
+pub mod annexb;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod error;
+pub mod info;
 
 mod mp4;
 mod sei;
+mod sps;
 
 pub mod extract;
 
+#[cfg(feature = "chrono")]
+pub mod catalog;
+#[cfg(any(feature = "async", feature = "chrono"))]
+pub mod clip;
+pub mod compare;
+pub mod driving;
+#[cfg(feature = "reflect")]
+pub mod dynamic;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "futures")]
+pub mod futures_stream;
+pub mod flat;
+pub mod geo;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod pic_timing;
+#[cfg(feature = "player")]
+pub mod player;
+#[cfg(feature = "polars")]
+pub mod polars;
+pub mod recover;
+#[cfg(feature = "chrono")]
+pub mod report;
+#[cfg(feature = "rest")]
+pub mod rest;
+pub mod rewrite;
+#[cfg(feature = "chrono")]
+pub mod route;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(feature = "serde")]
+pub mod schema;
+pub mod summary;
+pub mod thumbnail;
+pub mod validate;
+pub mod warning;
+#[cfg(feature = "chrono")]
+pub mod trip;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+
 #[cfg(feature = "async")]
 pub mod async_extract;
 
 pub use extract::{
-    extractor_from_path, extractor_from_reader, for_each_sei_metadata, SeiEvent, SeiExtractor,
+    extract_all, extractor_from_path, extractor_from_path_with_options, extractor_from_reader,
+    extractor_from_reader_with_options, for_each_sei_metadata, ExtractOptions, RawSeiIter,
+    RawSeiMessage, SampleTableRepair, SeiEvent, SeiExtractor,
 };
 
+pub use annexb::{
+    annexb_extractor_from_path, annexb_extractor_from_reader, AnnexBEvent, AnnexBExtractor,
+};
+#[cfg(feature = "chrono")]
+pub use catalog::{Catalog, CatalogClip, ClipSource, Pass};
+#[cfg(feature = "chrono")]
+pub use clip::{parse_clip_name, ClipName};
+pub use recover::{
+    recovery_extractor_from_path, recovery_extractor_from_reader, RecoveredEvent,
+    RecoveryExtractor,
+};
+pub use rewrite::{
+    inject_sei, inject_sei_reader, redact_gps, redact_gps_reader, strip_sei, strip_sei_reader,
+    GpsRedaction, InjectOptions, RedactOptions, RewriteSummary, StripOptions, TESLA_SEI_UUID,
+};
+pub use compare::{
+    compare_cameras, CameraComparison, CameraReport, CompareOptions, Discrepancy, DiscrepancyKind,
+};
+pub use driving::{
+    driving_events, driving_events_from_extractor, driving_events_reader, BlinkerSide,
+    DrivingEvent, DrivingEventKind, DrivingEventOptions,
+};
+pub use summary::{clip_summary, clip_summary_reader, ClipSummary, GearTimes, GeoBounds};
+pub use thumbnail::{
+    thumbnail_hints, thumbnail_hints_reader, HintKind, ThumbnailHint, ThumbnailOptions,
+};
+#[cfg(feature = "chrono")]
+pub use trip::{Trip, TripOptions};
+
 pub use error::Error;
+pub use flat::SeiMetadataFlat;
+pub use validate::{SeiViolation, SeiViolationKind};
+pub use warning::Warning;
+pub use pic_timing::ClockTimestamp;
+pub use sei::{DecodeCandidate, DecodeHeuristic, DecodeStats, UnknownField};
+pub use info::{
+    dump_box_tree, mp4_info, mp4_info_reader, quick_look, quick_look_reader, BoxNode, CodecProfile,
+    Mp4Info, Mp4TrackInfo, QuickLook, Resolution, TrackInfo, UserDataEntry, UserDataValue,
+    VideoCodec,
+};
 
 #[cfg(feature = "async")]
 pub use async_extract::{
-    stream_from_path, stream_from_path_from_sample, stream_from_reader, stream_from_reader_from_sample,
+    async_extractor_from_path, async_extractor_from_reader,
+    async_extractor_from_reader_with_options, extract_all_async, merge_cameras, stream_from_path,
+    stream_from_path_from_sample, stream_from_path_metered, stream_from_path_with_handle,
+    stream_from_path_with_progress, stream_from_reader, stream_from_reader_from_sample,
+    stream_from_reader_metered, stream_from_reader_with_handle, stream_from_reader_with_progress,
+    AsyncSeiExtractor, AsyncSeiStream, BackpressureStats, CameraEvent, ExtractionHandle,
+    ExtractionOutcome, MergedCameraStream, MeteredStream, Progress, SeiStreamExt, StreamMetrics,
+    StreamOptions, TimeWindow, TimeWindows,
 };
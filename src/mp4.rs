@@ -1,6 +1,8 @@
-use std::env;
 use std::io::{self, Read, Seek, SeekFrom};
 
+use crate::info::{BoxNode, CodecProfile, Resolution, UserDataEntry, UserDataValue, VideoCodec};
+use crate::sps::{parse_avc_sps, parse_hevc_sps, SpsInfo};
+use crate::warning::Warning;
 use crate::Error;
 
 // -----------------------------
@@ -8,40 +10,158 @@ use crate::Error;
 // -----------------------------
 #[derive(Debug, Clone)]
 pub(crate) struct TrackSampleTables {
-    // stsz
+    // stsz/stz2
     pub(crate) sample_sizes: Vec<u32>,
     // stco/co64
     pub(crate) chunk_offsets: Vec<u64>,
     // stsc
     pub(crate) stsc: Vec<StscEntry>,
-    // codec config (avcC/hvcC)
-    pub(crate) codec: CodecConfig,
+    // stts
+    pub(crate) stts: Vec<SttsEntry>,
+    // ctts (empty when the track has no composition offsets)
+    pub(crate) ctts: Vec<CttsEntry>,
+    // codec config (avcC/hvcC), one per stsd sample entry; stsc sample_description_index is
+    // 1-based into this
+    pub(crate) codecs: Vec<CodecConfig>,
+    // avcC/hvcC details of the first sample entry
+    pub(crate) decoder_config: Option<DecoderConfig>,
+    // width/height from the visual sample entry (avc1/hvc1/hev1)
+    pub(crate) visual_size: Option<(u16, u16)>,
+    // edts/elst (empty when the track has no edit list)
+    pub(crate) elst: Vec<ElstEntry>,
+    // tkhd
+    pub(crate) track_header: Option<TrackHeader>,
+    // mdhd
+    pub(crate) media_header: Option<MediaHeader>,
+    // hdlr name, e.g. "VideoHandler"
+    pub(crate) handler_name: Option<String>,
+}
+
+// elst
+#[derive(Debug, Clone)]
+pub(crate) struct ElstEntry {
+    // In movie (mvhd) timescale units.
+    pub(crate) segment_duration: u64,
+    // In media (mdhd) timescale units; -1 marks an empty edit (a gap before media starts).
+    pub(crate) media_time: i64,
+}
+
+// mvhd
+#[derive(Debug, Clone)]
+pub(crate) struct MovieHeader {
+    pub(crate) creation_time: u64,
+    pub(crate) modification_time: u64,
+    pub(crate) timescale: u32,
+    pub(crate) duration: u64,
+}
+
+// tkhd
+#[derive(Debug, Clone)]
+pub(crate) struct TrackHeader {
+    pub(crate) track_id: u32,
+    // Presentation size; stored as 16.16 fixed point, integer part kept.
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+// mdhd
+#[derive(Debug, Clone)]
+pub(crate) struct MediaHeader {
+    pub(crate) creation_time: u64,
+    pub(crate) modification_time: u64,
+    pub(crate) timescale: u32,
+    pub(crate) duration: u64,
+}
+
+impl TrackSampleTables {
+    // Cropped frame size from the SPS, falling back to the sample entry and then the tkhd
+    // presentation size.
+    pub(crate) fn resolution(&self) -> Option<Resolution> {
+        let sps = self.sps().map(|s| (s.width, s.height));
+        let entry = self.visual_size.map(|(w, h)| (w as u32, h as u32));
+        let tkhd = self.track_header.as_ref().map(|h| (h.width, h.height));
+        [sps, entry, tkhd]
+            .into_iter()
+            .flatten()
+            .find(|&(w, h)| w != 0 && h != 0)
+            .map(|(width, height)| Resolution { width, height })
+    }
+
+    // Frame rate from the SPS timing info, falling back to the average sample duration.
+    pub(crate) fn frame_rate(&self) -> Option<f64> {
+        if let Some(fps) = self.sps().and_then(|s| s.frame_rate) {
+            return Some(fps);
+        }
+        let timescale = self.media_header.as_ref()?.timescale;
+        let (samples, ticks) = self.stts.iter().fold((0u64, 0u64), |(n, t), e| {
+            let n_e = e.sample_count as u64;
+            (n + n_e, t + n_e * e.sample_delta as u64)
+        });
+        if samples == 0 || ticks == 0 || timescale == 0 {
+            return None;
+        }
+        Some(samples as f64 * timescale as f64 / ticks as f64)
+    }
+
+    // Codec of the first sample entry.
+    pub(crate) fn video_codec(&self) -> VideoCodec {
+        match self.codecs.first().unwrap_or(&CodecConfig::Unknown) {
+            CodecConfig::Avc { .. } => VideoCodec::Avc,
+            CodecConfig::Hevc { .. } => VideoCodec::Hevc,
+            CodecConfig::Av1 => VideoCodec::Av1,
+            CodecConfig::Unknown => VideoCodec::Unknown,
+        }
+    }
+
+    pub(crate) fn sps(&self) -> Option<&SpsInfo> {
+        self.decoder_config.as_ref()?.sps.as_ref()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct StscEntry {
     pub(crate) first_chunk: u32,
     pub(crate) samples_per_chunk: u32,
-    #[allow(dead_code)]
     pub(crate) sample_description_index: u32,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct SttsEntry {
+    pub(crate) sample_count: u32,
+    pub(crate) sample_delta: u32,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CttsEntry {
+    pub(crate) sample_count: u32,
+    // Version 0 stores this unsigned, version 1 signed; widened so both fit.
+    pub(crate) sample_offset: i64,
+}
+
+// Parsed decoder configuration record (avcC / hvcC).
+#[derive(Debug, Clone)]
+pub(crate) struct DecoderConfig {
+    pub(crate) profile: CodecProfile,
+    // The first SPS that parsed.
+    pub(crate) sps: Option<SpsInfo>,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum CodecConfig {
     Avc { nal_len_size: usize },  // from avcC lengthSizeMinusOne + 1
     Hevc { nal_len_size: usize }, // from hvcC (same idea)
+    Av1,                          // samples are size-delimited OBUs, no length prefix to configure
     Unknown,
 }
 
 #[derive(Debug)]
 pub(crate) struct Mp4 {
+    pub(crate) movie_header: Option<MovieHeader>,
     pub(crate) tracks: Vec<TrackSampleTables>,
-}
-
-fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
-    let mut b = [0u8; 1];
-    r.read_exact(&mut b)?;
-    Ok(b[0])
+    // moov/udta and moov/meta key/value items
+    pub(crate) user_data: Vec<UserDataEntry>,
+    // Problems the parse worked around, e.g. clamped boxes
+    pub(crate) warnings: Vec<Warning>,
 }
 
 fn read_be_u32<R: Read>(r: &mut R) -> io::Result<u32> {
@@ -96,26 +216,13 @@ fn fourcc_to_string(t: [u8; 4]) -> String {
         .collect()
 }
 
-fn trace_enabled() -> bool {
-    matches!(
-        env::var("TESLA_SEI_TRACE_MP4").as_deref(),
-        Ok("1") | Ok("true") | Ok("TRUE") | Ok("yes") | Ok("YES")
-    )
-}
-
-fn trace_box(ctx: &str, start: u64, hdr: &BoxHeader, limit: u64) {
-    if trace_enabled() {
-        eprintln!(
-            "[mp4] {ctx}: pos={start} typ={} size={} header={} limit={}",
-            fourcc_to_string(hdr.typ),
-            hdr.size,
-            hdr.header_len,
-            limit
-        );
-    }
-}
-
-fn safe_box_end(ctx: &str, start: u64, hdr: &BoxHeader, limit: u64) -> Result<u64, Error> {
+fn safe_box_end(
+    ctx: &str,
+    start: u64,
+    hdr: &BoxHeader,
+    limit: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<u64, Error> {
     // ISO-BMFF: size==0 means "extends to end of file" (or end of the containing box).
     let mut size = hdr.size;
     if size == 0 {
@@ -134,6 +241,13 @@ fn safe_box_end(ctx: &str, start: u64, hdr: &BoxHeader, limit: u64) -> Result<u6
 
     // Clamp to containing limit to avoid seeking past boundaries on malformed files.
     if end > limit {
+        warnings.push(Warning::BoxClamped {
+            context: ctx.to_string(),
+            box_type: fourcc_to_string(hdr.typ),
+            offset: start,
+            size,
+            container_end: limit,
+        });
         end = limit;
     }
 
@@ -150,8 +264,99 @@ fn safe_box_end(ctx: &str, start: u64, hdr: &BoxHeader, limit: u64) -> Result<u6
     Ok(end)
 }
 
+// Nesting limit for `read_box_tree`, so crafted files can't recurse without bound.
+const MAX_BOX_TREE_DEPTH: usize = 16;
+
+// Walk every box in the file, descending into the containers this crate knows about. Unlike
+// `parse_mp4` this never fails on malformed boxes: problems are recorded on the node and the
+// walk of that container stops.
+pub(crate) fn read_box_tree<R: Read + Seek>(f: &mut R) -> io::Result<Vec<BoxNode>> {
+    let file_len = f.seek(SeekFrom::End(0))?;
+    read_box_children(f, None, 0, file_len, 0)
+}
+
+fn read_box_children<R: Read + Seek>(
+    f: &mut R,
+    parent: Option<[u8; 4]>,
+    mut pos: u64,
+    end: u64,
+    depth: usize,
+) -> io::Result<Vec<BoxNode>> {
+    let mut nodes = Vec::new();
+    while pos + 8 <= end {
+        f.seek(SeekFrom::Start(pos))?;
+        let hdr = match read_box_header(f) {
+            Ok(hdr) => hdr,
+            // A 64-bit size cut off by the end of the file.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        let size = if hdr.size == 0 { end - pos } else { hdr.size };
+        let mut node = BoxNode {
+            box_type: fourcc_to_string(hdr.typ),
+            offset: pos,
+            size,
+            header_len: hdr.header_len,
+            children: Vec::new(),
+            error: None,
+        };
+
+        if size < hdr.header_len {
+            node.error = Some(format!("size {size} < header_len {}", hdr.header_len));
+            nodes.push(node);
+            break;
+        }
+        let box_end = pos.saturating_add(size).min(end);
+        if pos.saturating_add(size) > end {
+            node.error = Some(format!(
+                "extends {} bytes past its container",
+                pos.saturating_add(size) - end
+            ));
+        }
+
+        let payload_start = pos + hdr.header_len;
+        if depth < MAX_BOX_TREE_DEPTH
+            && let Some(first) = first_child_offset(f, parent, hdr.typ, payload_start, box_end)?
+        {
+            node.children = read_box_children(f, Some(hdr.typ), first, box_end, depth + 1)?;
+        }
+        nodes.push(node);
+        pos = box_end;
+    }
+    Ok(nodes)
+}
+
+// Where the child boxes of a container start, or `None` for leaf boxes.
+fn first_child_offset<R: Read + Seek>(
+    f: &mut R,
+    parent: Option<[u8; 4]>,
+    typ: [u8; 4],
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Option<u64>> {
+    const CONTAINERS: [&str; 13] = [
+        "moov", "trak", "mdia", "minf", "stbl", "edts", "udta", "dinf", "mvex", "moof", "traf",
+        "mfra", "ilst",
+    ];
+    let offset = match typ {
+        t if CONTAINERS.iter().any(|c| fourcc(c) == t) => payload_start,
+        // ilst items hold their `data` boxes directly.
+        _ if parent == Some(fourcc("ilst")) => payload_start,
+        t if t == fourcc("meta") => meta_children_start(f, payload_start, end)?,
+        // FullBox header + entry_count
+        t if t == fourcc("stsd") || t == fourcc("dref") => payload_start + 8,
+        // VisualSampleEntry fields precede the codec configuration boxes.
+        t if parent == Some(fourcc("stsd")) && is_visual_sample_entry(t) => payload_start + 78,
+        _ => return Ok(None),
+    };
+    Ok(Some(offset))
+}
+
 pub(crate) fn parse_mp4<R: Read + Seek>(f: &mut R) -> Result<Mp4, Error> {
     let mut tracks: Vec<TrackSampleTables> = Vec::new();
+    let mut movie_header: Option<MovieHeader> = None;
+    let mut user_data: Vec<UserDataEntry> = Vec::new();
+    let mut warnings: Vec<Warning> = Vec::new();
 
     let file_len = f.seek(SeekFrom::End(0))?;
     let mut pos = 0u64;
@@ -161,19 +366,37 @@ pub(crate) fn parse_mp4<R: Read + Seek>(f: &mut R) -> Result<Mp4, Error> {
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("top", start, &hdr, file_len);
-        let end = safe_box_end("top", start, &hdr, file_len)?;
+        let end = safe_box_end("top", start, &hdr, file_len, &mut warnings)?;
         let payload_start = start + hdr.header_len;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            box_type = %fourcc_to_string(hdr.typ),
+            offset = start,
+            size = end - start,
+            "top-level box"
+        );
 
         if hdr.typ == fourcc("moov") {
             // parse moov children
-            parse_moov(f, payload_start, end, &mut tracks)?;
+            movie_header = parse_moov(
+                f,
+                payload_start,
+                end,
+                &mut tracks,
+                &mut user_data,
+                &mut warnings,
+            )?;
         }
 
         pos = end;
     }
 
-    Ok(Mp4 { tracks })
+    Ok(Mp4 {
+        movie_header,
+        tracks,
+        user_data,
+        warnings,
+    })
 }
 
 fn parse_moov<R: Read + Seek>(
@@ -181,51 +404,92 @@ fn parse_moov<R: Read + Seek>(
     mut pos: u64,
     end: u64,
     tracks: &mut Vec<TrackSampleTables>,
-) -> Result<(), Error> {
+    user_data: &mut Vec<UserDataEntry>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<MovieHeader>, Error> {
+    let mut movie_header: Option<MovieHeader> = None;
+
     while pos + 8 <= end {
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("moov", start, &hdr, end);
-        let box_end = safe_box_end("moov", start, &hdr, end)?;
+        let box_end = safe_box_end("moov", start, &hdr, end, warnings)?;
         let payload_start = start + hdr.header_len;
 
-        if hdr.typ == fourcc("trak") {
-            if let Some(t) = parse_trak(f, payload_start, box_end)? {
-                tracks.push(t);
-            }
+        if hdr.typ == fourcc("mvhd") {
+            movie_header = Some(parse_mvhd(f, payload_start)?);
+        } else if hdr.typ == fourcc("udta") {
+            parse_udta(f, payload_start, box_end, user_data, warnings)?;
+        } else if hdr.typ == fourcc("meta") {
+            parse_meta(f, payload_start, box_end, user_data, warnings)?;
+        } else if hdr.typ == fourcc("trak")
+            && let Some(t) = parse_trak(f, payload_start, box_end, warnings)?
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                offset = start,
+                samples = t.sample_sizes.len(),
+                codec = ?t.video_codec(),
+                "video track"
+            );
+            tracks.push(t);
         }
 
         pos = box_end;
     }
-    Ok(())
+    Ok(movie_header)
 }
 
 fn parse_trak<R: Read + Seek>(
     f: &mut R,
     mut pos: u64,
     end: u64,
+    warnings: &mut Vec<Warning>,
 ) -> Result<Option<TrackSampleTables>, Error> {
     // We only care about video tracks. We'll detect by presence of stsd avc1/hvc1/etc.
+    let mut track_header: Option<TrackHeader> = None;
+    let mut elst: Vec<ElstEntry> = Vec::new();
+    let mut tables: Option<TrackSampleTables> = None;
+
     while pos + 8 <= end {
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("trak", start, &hdr, end);
-        let box_end = safe_box_end("trak", start, &hdr, end)?;
+        let box_end = safe_box_end("trak", start, &hdr, end, warnings)?;
         let payload_start = start + hdr.header_len;
 
-        if hdr.typ == fourcc("mdia") {
-            return parse_mdia(f, payload_start, box_end);
+        match hdr.typ {
+            t if t == fourcc("tkhd") => {
+                track_header = Some(parse_tkhd(f, payload_start)?);
+            }
+            t if t == fourcc("edts") => {
+                elst = parse_edts(f, payload_start, box_end, warnings)?;
+            }
+            t if t == fourcc("mdia") => {
+                tables = parse_mdia(f, payload_start, box_end, warnings)?;
+            }
+            _ => {}
         }
 
         pos = box_end;
     }
-    Ok(None)
+
+    Ok(tables.map(|mut t| {
+        t.track_header = track_header;
+        t.elst = elst;
+        t
+    }))
 }
 
-fn parse_mdia<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Option<TrackSampleTables>, Error> {
+fn parse_mdia<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<TrackSampleTables>, Error> {
     let mut handler_type: Option<[u8; 4]> = None;
+    let mut handler_name: Option<String> = None;
+    let mut media_header: Option<MediaHeader> = None;
     let mut stbl_tables: Option<TrackSampleTables> = None;
     let mut minf_err: Option<Error> = None;
 
@@ -233,20 +497,23 @@ fn parse_mdia<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Optio
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("mdia", start, &hdr, end);
-        let box_end = safe_box_end("mdia", start, &hdr, end)?;
+        let box_end = safe_box_end("mdia", start, &hdr, end, warnings)?;
         let payload_start = start + hdr.header_len;
 
         match hdr.typ {
+            t if t == fourcc("mdhd") => {
+                media_header = Some(parse_mdhd(f, payload_start)?);
+            }
             t if t == fourcc("hdlr") => {
                 // hdlr: version/flags (4) + pre_defined (4) + handler_type (4)
                 f.seek(SeekFrom::Start(payload_start + 8))?;
                 let mut ht = [0u8; 4];
                 f.read_exact(&mut ht)?;
                 handler_type = Some(ht);
+                handler_name = read_handler_name(f, payload_start, box_end)?;
             }
             t if t == fourcc("minf") => {
-                match parse_minf(f, payload_start, box_end) {
+                match parse_minf(f, payload_start, box_end, warnings) {
                     Ok(v) => stbl_tables = v,
                     Err(e) => minf_err = Some(e),
                 }
@@ -262,23 +529,63 @@ fn parse_mdia<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Optio
         if let Some(e) = minf_err {
             return Err(e);
         }
-        Ok(stbl_tables)
+        Ok(stbl_tables.map(|mut t| {
+            t.media_header = media_header;
+            t.handler_name = handler_name;
+            t
+        }))
     } else {
         Ok(None)
     }
 }
 
-fn parse_minf<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Option<TrackSampleTables>, Error> {
+// Handler names are short labels; anything longer is truncated.
+const MAX_HANDLER_NAME_LEN: u64 = 256;
+
+// hdlr name: after handler_type come 12 reserved bytes, then a null-terminated UTF-8 string.
+// QuickTime writes a counted (Pascal) string instead, so a leading length byte that matches the
+// rest of the box is stripped. Empty names are `None`.
+fn read_handler_name<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Option<String>> {
+    let name_start = payload_start + 24;
+    if name_start >= end {
+        return Ok(None);
+    }
+    f.seek(SeekFrom::Start(name_start))?;
+    let mut name = vec![0u8; (end - name_start).min(MAX_HANDLER_NAME_LEN) as usize];
+    f.read_exact(&mut name)?;
+
+    let mut name = name.as_slice();
+    if let Some((&len, rest)) = name.split_first()
+        && len as usize <= rest.len()
+        && rest[..len as usize].iter().all(|&b| b != 0)
+        && rest[len as usize..].iter().all(|&b| b == 0)
+    {
+        name = &rest[..len as usize];
+    }
+    let name = name.split(|&b| b == 0).next().unwrap_or_default();
+    let name = String::from_utf8_lossy(name).trim().to_string();
+    Ok((!name.is_empty()).then_some(name))
+}
+
+fn parse_minf<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<TrackSampleTables>, Error> {
     while pos + 8 <= end {
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("minf", start, &hdr, end);
-        let box_end = safe_box_end("minf", start, &hdr, end)?;
+        let box_end = safe_box_end("minf", start, &hdr, end, warnings)?;
         let payload_start = start + hdr.header_len;
 
         if hdr.typ == fourcc("stbl") {
-            return parse_stbl(f, payload_start, box_end).map(Some);
+            return parse_stbl(f, payload_start, box_end, warnings).map(Some);
         }
 
         pos = box_end;
@@ -286,27 +593,70 @@ fn parse_minf<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Optio
     Ok(None)
 }
 
-fn parse_stbl<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<TrackSampleTables, Error> {
+// Sample tables of an `stbl` node from `read_box_tree`.
+pub(crate) fn read_stbl<R: Read + Seek>(
+    f: &mut R,
+    stbl: &BoxNode,
+) -> Result<TrackSampleTables, Error> {
+    parse_stbl(
+        f,
+        stbl.offset + stbl.header_len,
+        stbl.offset + stbl.size,
+        &mut Vec::new(),
+    )
+}
+
+// Track ID of a `tkhd` node from `read_box_tree`.
+pub(crate) fn read_track_id<R: Read + Seek>(f: &mut R, tkhd: &BoxNode) -> io::Result<u32> {
+    Ok(parse_tkhd(f, tkhd.offset + tkhd.header_len)?.track_id)
+}
+
+// Chunk offsets of an `stco` or `co64` node from `read_box_tree`.
+pub(crate) fn read_chunk_offsets<R: Read + Seek>(
+    f: &mut R,
+    node: &BoxNode,
+) -> io::Result<Vec<u64>> {
+    let payload_start = node.offset + node.header_len;
+    if node.box_type == "co64" {
+        parse_co64(f, payload_start)
+    } else {
+        parse_stco(f, payload_start)
+    }
+}
+
+fn parse_stbl<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<TrackSampleTables, Error> {
     let mut sample_sizes: Option<Vec<u32>> = None;
     let mut chunk_offsets: Option<Vec<u64>> = None;
     let mut stsc: Option<Vec<StscEntry>> = None;
-    let mut codec: CodecConfig = CodecConfig::Unknown;
+    let mut stts: Vec<SttsEntry> = Vec::new();
+    let mut ctts: Vec<CttsEntry> = Vec::new();
+    let mut codecs: Vec<CodecConfig> = Vec::new();
+    let mut decoder_config: Option<DecoderConfig> = None;
+    let mut visual_size: Option<(u16, u16)> = None;
 
     while pos + 8 <= end {
         f.seek(SeekFrom::Start(pos))?;
         let hdr = read_box_header(f)?;
         let start = pos;
-        trace_box("stbl", start, &hdr, end);
-        let box_end = safe_box_end("stbl", start, &hdr, end)?;
+        let box_end = safe_box_end("stbl", start, &hdr, end, warnings)?;
         let payload_start = start + hdr.header_len;
 
         match hdr.typ {
             t if t == fourcc("stsd") => {
-                codec = parse_stsd_for_codec(f, payload_start, box_end)?;
+                (codecs, decoder_config) = parse_stsd_codecs(f, payload_start, box_end, warnings)?;
+                visual_size = parse_stsd_visual_size(f, payload_start, box_end)?;
             }
             t if t == fourcc("stsz") => {
                 sample_sizes = Some(parse_stsz(f, payload_start)?);
             }
+            t if t == fourcc("stz2") => {
+                sample_sizes = Some(parse_stz2(f, start, payload_start, box_end)?);
+            }
             t if t == fourcc("stco") => {
                 chunk_offsets = Some(parse_stco(f, payload_start)?);
             }
@@ -316,6 +666,12 @@ fn parse_stbl<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Track
             t if t == fourcc("stsc") => {
                 stsc = Some(parse_stsc(f, payload_start)?);
             }
+            t if t == fourcc("stts") => {
+                stts = parse_stts(f, payload_start)?;
+            }
+            t if t == fourcc("ctts") => {
+                ctts = parse_ctts(f, payload_start)?;
+            }
             _ => {}
         }
 
@@ -324,7 +680,7 @@ fn parse_stbl<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Track
 
     let mut missing: Vec<&'static str> = Vec::new();
     if sample_sizes.is_none() {
-        missing.push("stsz");
+        missing.push("stsz/stz2");
     }
     if chunk_offsets.is_none() {
         missing.push("stco/co64");
@@ -343,10 +699,426 @@ fn parse_stbl<R: Read + Seek>(f: &mut R, mut pos: u64, end: u64) -> Result<Track
         sample_sizes: sample_sizes.unwrap(),
         chunk_offsets: chunk_offsets.unwrap(),
         stsc: stsc.unwrap(),
-        codec,
+        stts,
+        ctts,
+        codecs,
+        decoder_config,
+        visual_size,
+        elst: Vec::new(),
+        track_header: None,
+        media_header: None,
+        handler_name: None,
+    })
+}
+
+// MP4 times count seconds since 1904-01-01 00:00:00 UTC.
+const MP4_EPOCH_OFFSET_SECS: i64 = 2_082_844_800;
+
+// Convert an MP4 header time to Unix seconds. Zero means "not set" in practice.
+pub(crate) fn mp4_time_to_unix(t: u64) -> Option<i64> {
+    if t == 0 {
+        return None;
+    }
+    i64::try_from(t).ok().map(|t| t - MP4_EPOCH_OFFSET_SECS)
+}
+
+// Read a version-dependent time/duration field: 32-bit for version 0, 64-bit for version 1.
+fn read_versioned_u64<R: Read>(r: &mut R, version: u32) -> io::Result<u64> {
+    if version == 1 {
+        read_be_u64(r)
+    } else {
+        Ok(read_be_u32(r)? as u64)
+    }
+}
+
+fn parse_mvhd<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<MovieHeader> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let version = read_be_u32(f)? >> 24;
+    let creation_time = read_versioned_u64(f, version)?;
+    let modification_time = read_versioned_u64(f, version)?;
+    let timescale = read_be_u32(f)?;
+    let duration = read_versioned_u64(f, version)?;
+    Ok(MovieHeader {
+        creation_time,
+        modification_time,
+        timescale,
+        duration,
     })
 }
 
+fn parse_tkhd<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<TrackHeader> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let version = read_be_u32(f)? >> 24;
+    let _creation_time = read_versioned_u64(f, version)?;
+    let _modification_time = read_versioned_u64(f, version)?;
+    let track_id = read_be_u32(f)?;
+    let _reserved = read_be_u32(f)?;
+    let _duration = read_versioned_u64(f, version)?;
+
+    // reserved (8) + layer (2) + alternate_group (2) + volume (2) + reserved (2) + matrix (36)
+    f.seek(SeekFrom::Current(52))?;
+    let width = read_be_u32(f)? >> 16;
+    let height = read_be_u32(f)? >> 16;
+
+    Ok(TrackHeader {
+        track_id,
+        width,
+        height,
+    })
+}
+
+fn parse_mdhd<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<MediaHeader> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let version = read_be_u32(f)? >> 24;
+    let creation_time = read_versioned_u64(f, version)?;
+    let modification_time = read_versioned_u64(f, version)?;
+    let timescale = read_be_u32(f)?;
+    let duration = read_versioned_u64(f, version)?;
+
+    Ok(MediaHeader {
+        creation_time,
+        modification_time,
+        timescale,
+        duration,
+    })
+}
+
+fn parse_edts<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<ElstEntry>, Error> {
+    while pos + 8 <= end {
+        f.seek(SeekFrom::Start(pos))?;
+        let hdr = read_box_header(f)?;
+        let start = pos;
+        let box_end = safe_box_end("edts", start, &hdr, end, warnings)?;
+        let payload_start = start + hdr.header_len;
+
+        if hdr.typ == fourcc("elst") {
+            return Ok(parse_elst(f, payload_start)?);
+        }
+
+        pos = box_end;
+    }
+    Ok(Vec::new())
+}
+
+fn parse_elst<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<ElstEntry>> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let version = read_be_u32(f)? >> 24;
+    let count = read_be_u32(f)?;
+    let mut v = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let segment_duration = read_versioned_u64(f, version)?;
+        let media_time = if version == 1 {
+            read_be_u64(f)? as i64
+        } else {
+            read_be_u32(f)? as i32 as i64
+        };
+        let _media_rate = read_be_u32(f)?;
+        v.push(ElstEntry {
+            segment_duration,
+            media_time,
+        });
+    }
+    Ok(v)
+}
+
+// Larger user-data values (e.g. cover art) are skipped rather than read into memory.
+const MAX_USER_DATA_VALUE_LEN: u64 = 64 * 1024;
+
+// User-data keys are four raw bytes, commonly with a leading 0xA9 ('©' in Latin-1).
+fn user_data_key(t: [u8; 4]) -> String {
+    t.iter().map(|&b| b as char).collect()
+}
+
+fn parse_udta<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    out: &mut Vec<UserDataEntry>,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), Error> {
+    while pos + 8 <= end {
+        f.seek(SeekFrom::Start(pos))?;
+        let hdr = read_box_header(f)?;
+        let start = pos;
+        let box_end = safe_box_end("udta", start, &hdr, end, warnings)?;
+        let payload_start = start + hdr.header_len;
+
+        if hdr.typ == fourcc("meta") {
+            parse_meta(f, payload_start, box_end, out, warnings)?;
+        } else if hdr.typ[0] == 0xA9 {
+            // QuickTime user-data text: size (2) + language (2) + text
+            if let Some(text) = read_udta_text(f, payload_start, box_end)? {
+                out.push(UserDataEntry {
+                    key: user_data_key(hdr.typ),
+                    value: UserDataValue::Text(text),
+                });
+            }
+        }
+
+        pos = box_end;
+    }
+    Ok(())
+}
+
+fn read_udta_text<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Option<String>> {
+    if payload_start + 4 > end {
+        return Ok(None);
+    }
+    f.seek(SeekFrom::Start(payload_start))?;
+    let mut b = [0u8; 4];
+    f.read_exact(&mut b)?;
+    let len = u16::from_be_bytes([b[0], b[1]]) as u64;
+    if len > MAX_USER_DATA_VALUE_LEN || payload_start + 4 + len > end {
+        return Ok(None);
+    }
+    let mut text = vec![0u8; len as usize];
+    f.read_exact(&mut text)?;
+    Ok(Some(String::from_utf8_lossy(&text).into_owned()))
+}
+
+// User-data keys for where a clip was recorded: QuickTime's `©xyz` text, 3GPP `loci`, and the
+// ISO 6709 string of a `meta`/`keys` table.
+const LOCATION_KEYS: [&str; 3] = ["\u{a9}xyz", "loci", "com.apple.quicktime.location.ISO6709"];
+
+// Boxes below `node` (from `read_box_tree`) that hold a location: `udta` entries and `ilst` items
+// with one of `LOCATION_KEYS`. `keys` is the key table of the enclosing `meta`.
+pub(crate) fn location_user_data<'a, R: Read + Seek>(
+    f: &mut R,
+    node: &'a BoxNode,
+    keys: &[String],
+    out: &mut Vec<&'a BoxNode>,
+) -> io::Result<()> {
+    let mut keys = keys.to_vec();
+    if let Some(table) = node.children.iter().find(|c| c.box_type == "keys") {
+        keys = parse_keys(
+            f,
+            table.offset + table.header_len,
+            table.offset + table.size,
+        )?;
+    }
+    for c in &node.children {
+        // `box_type` masks the 0xA9 of `©xyz`.
+        f.seek(SeekFrom::Start(c.offset + 4))?;
+        let mut typ = [0u8; 4];
+        f.read_exact(&mut typ)?;
+        let key = match node.box_type.as_str() {
+            "ilst" => keys
+                .get((u32::from_be_bytes(typ) as usize).wrapping_sub(1))
+                .cloned()
+                .unwrap_or_else(|| user_data_key(typ)),
+            "udta" => user_data_key(typ),
+            _ => String::new(),
+        };
+        if LOCATION_KEYS.contains(&key.as_str()) {
+            out.push(c);
+        } else {
+            location_user_data(f, c, &keys, out)?;
+        }
+    }
+    Ok(())
+}
+
+// ISO-BMFF `meta` is a FullBox, but QuickTime writes it as a plain box. Tell them apart by
+// checking whether a child box header starts right at the payload.
+fn meta_children_start<R: Read + Seek>(f: &mut R, payload_start: u64, end: u64) -> io::Result<u64> {
+    if payload_start + 8 <= end {
+        f.seek(SeekFrom::Start(payload_start + 4))?;
+        let mut typ = [0u8; 4];
+        f.read_exact(&mut typ)?;
+        if ![fourcc("hdlr"), fourcc("keys"), fourcc("ilst")].contains(&typ) {
+            return Ok(payload_start + 4);
+        }
+    }
+    Ok(payload_start)
+}
+
+fn parse_meta<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+    out: &mut Vec<UserDataEntry>,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), Error> {
+    let mut pos = meta_children_start(f, payload_start, end)?;
+    let mut keys: Vec<String> = Vec::new();
+    while pos + 8 <= end {
+        f.seek(SeekFrom::Start(pos))?;
+        let hdr = read_box_header(f)?;
+        let start = pos;
+        let box_end = safe_box_end("meta", start, &hdr, end, warnings)?;
+        let payload_start = start + hdr.header_len;
+
+        match hdr.typ {
+            t if t == fourcc("keys") => {
+                keys = parse_keys(f, payload_start, box_end)?;
+            }
+            t if t == fourcc("ilst") => {
+                parse_ilst(f, payload_start, box_end, &keys, out, warnings)?;
+            }
+            _ => {}
+        }
+
+        pos = box_end;
+    }
+    Ok(())
+}
+
+// QuickTime `keys`: version/flags (4) + entry_count (4), then (key_size, namespace, key) entries.
+fn parse_keys<R: Read + Seek>(f: &mut R, payload_start: u64, end: u64) -> io::Result<Vec<String>> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let _version_flags = read_be_u32(f)?;
+    let count = read_be_u32(f)?;
+
+    let mut pos = payload_start + 8;
+    let mut keys = Vec::new();
+    for _ in 0..count {
+        if pos + 8 > end {
+            break;
+        }
+        let key_size = read_be_u32(f)? as u64;
+        let _namespace = read_be_u32(f)?;
+        if key_size < 8 || pos + key_size > end || key_size - 8 > MAX_USER_DATA_VALUE_LEN {
+            break;
+        }
+        let mut key = vec![0u8; (key_size - 8) as usize];
+        f.read_exact(&mut key)?;
+        keys.push(String::from_utf8_lossy(&key).into_owned());
+        pos += key_size;
+    }
+    Ok(keys)
+}
+
+fn parse_ilst<R: Read + Seek>(
+    f: &mut R,
+    mut pos: u64,
+    end: u64,
+    keys: &[String],
+    out: &mut Vec<UserDataEntry>,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), Error> {
+    while pos + 8 <= end {
+        f.seek(SeekFrom::Start(pos))?;
+        let hdr = read_box_header(f)?;
+        let start = pos;
+        let box_end = safe_box_end("ilst", start, &hdr, end, warnings)?;
+        let payload_start = start + hdr.header_len;
+
+        // With a `keys` box, item types are 1-based indices into the key table.
+        let mut key = match keys.get((u32::from_be_bytes(hdr.typ) as usize).wrapping_sub(1)) {
+            Some(k) => k.clone(),
+            None => user_data_key(hdr.typ),
+        };
+
+        // Item children: `data` (value), plus `mean`/`name` for freeform `----` items.
+        let mut mean: Option<String> = None;
+        let mut name: Option<String> = None;
+        let mut values: Vec<UserDataValue> = Vec::new();
+        let mut p = payload_start;
+        while p + 8 <= box_end {
+            f.seek(SeekFrom::Start(p))?;
+            let child = read_box_header(f)?;
+            let child_end = safe_box_end("ilst", p, &child, box_end, warnings)?;
+            let child_payload = p + child.header_len;
+
+            match child.typ {
+                t if t == fourcc("data") => {
+                    values.extend(read_data_value(f, child_payload, child_end)?);
+                }
+                t if t == fourcc("mean") => mean = read_full_box_text(f, child_payload, child_end)?,
+                t if t == fourcc("name") => name = read_full_box_text(f, child_payload, child_end)?,
+                _ => {}
+            }
+
+            p = child_end;
+        }
+
+        if hdr.typ == fourcc("----") {
+            key = match (mean, name) {
+                (Some(m), Some(n)) => format!("{m}:{n}"),
+                (None, Some(n)) => n,
+                _ => key,
+            };
+        }
+        out.extend(values.into_iter().map(|value| UserDataEntry {
+            key: key.clone(),
+            value,
+        }));
+
+        pos = box_end;
+    }
+    Ok(())
+}
+
+fn read_full_box_text<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Option<String>> {
+    let start = payload_start + 4;
+    if start > end || end - start > MAX_USER_DATA_VALUE_LEN {
+        return Ok(None);
+    }
+    f.seek(SeekFrom::Start(start))?;
+    let mut text = vec![0u8; (end - start) as usize];
+    f.read_exact(&mut text)?;
+    Ok(Some(String::from_utf8_lossy(&text).into_owned()))
+}
+
+// `data`: type indicator (4) + locale (4) + value
+fn read_data_value<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Option<UserDataValue>> {
+    let value_start = payload_start + 8;
+    if value_start > end || end - value_start > MAX_USER_DATA_VALUE_LEN {
+        return Ok(None);
+    }
+    f.seek(SeekFrom::Start(payload_start))?;
+    let data_type = read_be_u32(f)? & 0x00FF_FFFF;
+    let _locale = read_be_u32(f)?;
+    let mut v = vec![0u8; (end - value_start) as usize];
+    f.read_exact(&mut v)?;
+
+    Ok(Some(match data_type {
+        // UTF-8
+        1 => UserDataValue::Text(String::from_utf8_lossy(&v).into_owned()),
+        // UTF-16 (big-endian)
+        2 => {
+            let units: Vec<u16> = v
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            UserDataValue::Text(String::from_utf16_lossy(&units))
+        }
+        // Big-endian signed / unsigned integer
+        21 | 22 if (1..=8).contains(&v.len()) => {
+            let mut n: u64 = 0;
+            for b in &v {
+                n = (n << 8) | *b as u64;
+            }
+            let bits = v.len() as u32 * 8;
+            let value = if data_type == 21 && bits < 64 {
+                // sign-extend
+                ((n << (64 - bits)) as i64) >> (64 - bits)
+            } else {
+                n as i64
+            };
+            UserDataValue::Integer(value)
+        }
+        _ => UserDataValue::Binary(v),
+    }))
+}
+
 fn parse_stsz<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<u32>> {
     f.seek(SeekFrom::Start(payload_start))?;
     let _version_flags = read_be_u32(f)?;
@@ -365,6 +1137,55 @@ fn parse_stsz<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<u
     Ok(sizes)
 }
 
+// stz2 (compact sample sizes): like stsz without the constant size, but entries are
+// field_size (4, 8 or 16) bits wide. 4-bit entries pack two per byte, high nibble first.
+fn parse_stz2<R: Read + Seek>(
+    f: &mut R,
+    start: u64,
+    payload_start: u64,
+    end: u64,
+) -> Result<Vec<u32>, Error> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let _version_flags = read_be_u32(f)?;
+    // reserved (24 bits) + field_size (8 bits)
+    let field_size = (read_be_u32(f)? & 0xFF) as u64;
+    let sample_count = read_be_u32(f)? as u64;
+
+    if ![4, 8, 16].contains(&field_size) {
+        return Err(Error::Mp4InvalidBox {
+            context: "stbl".to_string(),
+            box_type: "stz2".to_string(),
+            offset: start,
+            message: format!("invalid field_size {field_size}"),
+        });
+    }
+    let table_len = (sample_count * field_size).div_ceil(8);
+    if payload_start + 12 + table_len > end {
+        return Err(Error::Mp4InvalidBox {
+            context: "stbl".to_string(),
+            box_type: "stz2".to_string(),
+            offset: start,
+            message: format!("{sample_count} entries don't fit in the box"),
+        });
+    }
+
+    let mut table = vec![0u8; table_len as usize];
+    f.read_exact(&mut table)?;
+    let sizes = match field_size {
+        4 => table
+            .iter()
+            .flat_map(|&b| [(b >> 4) as u32, (b & 0x0F) as u32])
+            .take(sample_count as usize)
+            .collect(),
+        8 => table.iter().map(|&b| b as u32).collect(),
+        _ => table
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]) as u32)
+            .collect(),
+    };
+    Ok(sizes)
+}
+
 fn parse_stco<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<u64>> {
     f.seek(SeekFrom::Start(payload_start))?;
     let _version_flags = read_be_u32(f)?;
@@ -402,45 +1223,148 @@ fn parse_stsc<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<S
     Ok(v)
 }
 
-fn parse_stsd_for_codec<R: Read + Seek>(
+fn parse_stts<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<SttsEntry>> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let _version_flags = read_be_u32(f)?;
+    let count = read_be_u32(f)?;
+    let mut v = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        v.push(SttsEntry {
+            sample_count: read_be_u32(f)?,
+            sample_delta: read_be_u32(f)?,
+        });
+    }
+    Ok(v)
+}
+
+fn parse_ctts<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<Vec<CttsEntry>> {
+    f.seek(SeekFrom::Start(payload_start))?;
+    let version = read_be_u32(f)? >> 24;
+    let count = read_be_u32(f)?;
+    let mut v = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let sample_count = read_be_u32(f)?;
+        let raw = read_be_u32(f)?;
+        let sample_offset = if version == 0 {
+            raw as i64
+        } else {
+            raw as i32 as i64
+        };
+        v.push(CttsEntry {
+            sample_count,
+            sample_offset,
+        });
+    }
+    Ok(v)
+}
+
+fn is_visual_sample_entry(entry_type: [u8; 4]) -> bool {
+    entry_type == fourcc("avc1")
+        || entry_type == fourcc("hvc1")
+        || entry_type == fourcc("hev1")
+        || entry_type == fourcc("av01")
+}
+
+fn parse_stsd_visual_size<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    stsd_end: u64,
+) -> io::Result<Option<(u16, u16)>> {
+    // stsd: version/flags (4) + entry_count (4), then the first sample entry's size + type.
+    f.seek(SeekFrom::Start(payload_start + 4))?;
+    if read_be_u32(f)? == 0 {
+        return Ok(None);
+    }
+    let entry_pos = payload_start + 8;
+    let _entry_size = read_be_u32(f)?;
+    let mut entry_type = [0u8; 4];
+    f.read_exact(&mut entry_type)?;
+
+    // VisualSampleEntry: reserved (6) + data_reference_index (2) + pre_defined/reserved (16),
+    // then width (2) + height (2).
+    let size_pos = entry_pos + 8 + 24;
+    if !is_visual_sample_entry(entry_type) || size_pos + 4 > stsd_end {
+        return Ok(None);
+    }
+    f.seek(SeekFrom::Start(size_pos))?;
+    let mut b = [0u8; 4];
+    f.read_exact(&mut b)?;
+    let width = u16::from_be_bytes([b[0], b[1]]);
+    let height = u16::from_be_bytes([b[2], b[3]]);
+    Ok(Some((width, height)))
+}
+
+fn parse_stsd_codecs<R: Read + Seek>(
     f: &mut R,
     payload_start: u64,
     stsd_end: u64,
-) -> Result<CodecConfig, Error> {
+    warnings: &mut Vec<Warning>,
+) -> Result<(Vec<CodecConfig>, Option<DecoderConfig>), Error> {
     // stsd: version/flags (4) + entry_count (4) + sample entries...
     f.seek(SeekFrom::Start(payload_start))?;
     let _version_flags = read_be_u32(f)?;
     let entry_count = read_be_u32(f)?;
-    if entry_count == 0 {
-        return Ok(CodecConfig::Unknown);
-    }
 
-    // sample entry is itself a box-ish structure: size + type
-    let entry_pos = payload_start + 8;
-    f.seek(SeekFrom::Start(entry_pos))?;
-    let entry_size = read_be_u32(f)? as u64;
-    let mut entry_type = [0u8; 4];
-    f.read_exact(&mut entry_type)?;
+    // Each sample entry is itself a box-ish structure: size + type. Spliced clips can carry
+    // several, e.g. two avc1 entries with different avcC parameters.
+    let mut codecs = Vec::new();
+    let mut first_config: Option<DecoderConfig> = None;
+    let mut entry_pos = payload_start + 8;
+    for _ in 0..entry_count {
+        if entry_pos + 8 > stsd_end {
+            break;
+        }
+        f.seek(SeekFrom::Start(entry_pos))?;
+        let entry_size = read_be_u32(f)? as u64;
+        let mut entry_type = [0u8; 4];
+        f.read_exact(&mut entry_type)?;
+
+        let entry_end = if entry_size == 0 {
+            stsd_end
+        } else if entry_size < 8 {
+            break;
+        } else if entry_pos + entry_size > stsd_end {
+            warnings.push(Warning::BoxClamped {
+                context: "stsd".to_string(),
+                box_type: fourcc_to_string(entry_type),
+                offset: entry_pos,
+                size: entry_size,
+                container_end: stsd_end,
+            });
+            stsd_end
+        } else {
+            entry_pos + entry_size
+        };
+        let (codec, config) =
+            parse_sample_entry_codec(f, entry_type, entry_pos, entry_end, warnings)?;
+        if codecs.is_empty() {
+            first_config = config;
+        }
+        codecs.push(codec);
+        entry_pos = entry_end;
+    }
+    Ok((codecs, first_config))
+}
 
-    // We need avcC or hvcC inside this sample entry.
+fn parse_sample_entry_codec<R: Read + Seek>(
+    f: &mut R,
+    entry_type: [u8; 4],
+    entry_start: u64,
+    entry_end: u64,
+    warnings: &mut Vec<Warning>,
+) -> Result<(CodecConfig, Option<DecoderConfig>), Error> {
+    // We need avcC or hvcC inside this sample entry (av1C carries nothing we need).
     // Sample entry has a fixed header (6 reserved + 2 data_ref_idx) etc.
     // We'll just scan child boxes within the entry payload for avcC/hvcC.
-    let entry_start = entry_pos;
-    let entry_payload_start = entry_pos + 8;
-    let entry_end = if entry_size == 0 {
-        stsd_end
-    } else {
-        (entry_start + entry_size).min(stsd_end)
-    };
+    let entry_payload_start = entry_start + 8;
 
     // For video sample entries (avc1/hvc1/hev1), child boxes start after the fixed VisualSampleEntry header.
     // VisualSampleEntry is 78 bytes after the size+type header.
     let visual_sample_entry_len: u64 = 78;
-    let mut p = match entry_type {
-        t if t == fourcc("avc1") || t == fourcc("hvc1") || t == fourcc("hev1") => {
-            entry_payload_start.saturating_add(visual_sample_entry_len)
-        }
-        _ => entry_payload_start,
+    let mut p = if is_visual_sample_entry(entry_type) {
+        entry_payload_start.saturating_add(visual_sample_entry_len)
+    } else {
+        entry_payload_start
     };
     if p > entry_end {
         p = entry_payload_start;
@@ -450,58 +1374,204 @@ fn parse_stsd_for_codec<R: Read + Seek>(
         let hdr = read_box_header(f)?;
         let start = p;
         // Child boxes can also legally be size==0; treat as extending to end of sample entry.
-        let child_end = safe_box_end("stsd", start, &hdr, entry_end)?;
+        let child_end = safe_box_end("stsd", start, &hdr, entry_end, warnings)?;
         let payload = start + hdr.header_len;
 
         if hdr.typ == fourcc("avcC") {
-            let nal = parse_avcc_nal_len(f, payload)?;
-            return Ok(CodecConfig::Avc { nal_len_size: nal });
+            let (nal, config) = parse_avcc(f, payload, child_end)?;
+            return Ok((CodecConfig::Avc { nal_len_size: nal }, config));
         }
         if hdr.typ == fourcc("hvcC") {
-            let nal = parse_hvcc_nal_len(f, payload)?;
-            return Ok(CodecConfig::Hevc { nal_len_size: nal });
+            let record = read_config_record(f, payload, child_end)?;
+            let (nal, config) = parse_hvcc(&record, start)?;
+            return Ok((CodecConfig::Hevc { nal_len_size: nal }, config));
         }
 
         p = child_end;
     }
 
     // fallback: still accept video even if unknown; try 4-byte NAL lengths
-    Ok(match entry_type {
+    let codec = match entry_type {
         t if t == fourcc("avc1") => CodecConfig::Avc { nal_len_size: 4 },
         t if t == fourcc("hvc1") || t == fourcc("hev1") => CodecConfig::Hevc { nal_len_size: 4 },
+        t if t == fourcc("av01") => CodecConfig::Av1,
         _ => CodecConfig::Unknown,
-    })
+    };
+    Ok((codec, None))
 }
 
-fn parse_avcc_nal_len<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<usize> {
+// Decoder configuration records are small; anything larger is malformed.
+const MAX_CONFIG_RECORD_LEN: u64 = 64 * 1024;
+
+fn read_config_record<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<Vec<u8>> {
+    let len = end.saturating_sub(payload_start).min(MAX_CONFIG_RECORD_LEN);
+    f.seek(SeekFrom::Start(payload_start))?;
+    let mut record = vec![0u8; len as usize];
+    f.read_exact(&mut record)?;
+    Ok(record)
+}
+
+fn parse_avcc<R: Read + Seek>(
+    f: &mut R,
+    payload_start: u64,
+    end: u64,
+) -> io::Result<(usize, Option<DecoderConfig>)> {
     // avcC:
     // configurationVersion(1), AVCProfileIndication(1), profile_compat(1), AVCLevelIndication(1),
-    // lengthSizeMinusOne in low 2 bits of next byte
-    f.seek(SeekFrom::Start(payload_start + 4))?;
-    let b = read_u8(f)?;
-    let len_minus_one = (b & 0b11) as usize;
-    Ok(len_minus_one + 1)
+    // lengthSizeMinusOne in low 2 bits of next byte, then numOfSequenceParameterSets (low 5 bits)
+    // SPS and numOfPictureParameterSets PPS, each as (length u16, NAL unit)
+    let record = read_config_record(f, payload_start, end)?;
+    if record.len() < 5 {
+        return Ok((4, None));
+    }
+    let nal_len_size = (record[4] & 0b11) as usize + 1;
+
+    let mut nal_arrays: Vec<(u8, Vec<Vec<u8>>)> = Vec::new();
+    let mut i = 5usize;
+    for (nal_type, count_mask) in [(7u8, 0x1F), (8, 0xFF)] {
+        let Some(&count) = record.get(i) else {
+            break;
+        };
+        i += 1;
+        let mut nalus = Vec::new();
+        for _ in 0..count & count_mask {
+            // Each NAL unit is read to its declared length; a record cut short of that is
+            // corrupt, and its parameter sets aren't trusted.
+            let nal = record.get(i..i + 2).and_then(|len| {
+                let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+                record.get(i + 2..i + 2 + len)
+            });
+            let Some(nal) = nal else {
+                return Ok((nal_len_size, None));
+            };
+            nalus.push(nal.to_vec());
+            i += 2 + nal.len();
+        }
+        nal_arrays.push((nal_type, nalus));
+    }
+    let sps = first_sps(&nal_arrays, 7, parse_avc_sps);
+
+    let config = DecoderConfig {
+        profile: CodecProfile {
+            profile_idc: record[1],
+            profile_space: 0,
+            high_tier: false,
+            level_idc: record[3],
+        },
+        sps,
+    };
+    Ok((nal_len_size, Some(config)))
+}
+
+// Parse a HEVCDecoderConfigurationRecord (ISO/IEC 14496-15 8.3.3.1):
+//
+//   configurationVersion                          u8          [0]
+//   profile_space(2) tier(1) profile_idc(5)        u8          [1]
+//   general_profile_compatibility_flags           u32         [2..6]
+//   general_constraint_indicator_flags            48 bits     [6..12]
+//   general_level_idc                             u8          [12]
+//   min_spatial_segmentation_idc (+4 reserved)    u16         [13..15]
+//   parallelismType, chromaFormat,
+//   bitDepthLumaMinus8, bitDepthChromaMinus8      u8 each     [15..19]
+//   avgFrameRate                                  u16         [19..21]
+//   constantFrameRate(2) numTemporalLayers(3)
+//   temporalIdNested(1) lengthSizeMinusOne(2)     u8          [21]
+//   numOfArrays                                   u8          [22]
+//   arrays: completeness(1) reserved(1) NAL_unit_type(6), numNalus u16,
+//           then (nalUnitLength u16, nalUnit) per NAL
+//
+// Truncated or inconsistent records keep whatever parsed cleanly; an invalid length size falls
+// back to the common 4-byte prefix.
+// `offset` is the file offset of the hvcC box, for errors.
+fn parse_hvcc(record: &[u8], offset: u64) -> Result<(usize, Option<DecoderConfig>), Error> {
+    if record.len() < 13 {
+        return Ok((4, None));
+    }
+
+    let profile = CodecProfile {
+        profile_idc: record[1] & 0x1F,
+        profile_space: record[1] >> 6,
+        high_tier: record[1] & 0x20 != 0,
+        level_idc: record[12],
+    };
+
+    let nal_len_size = match record.get(21).map(|b| b & 0b11) {
+        // lengthSizeMinusOne == 2 (3-byte lengths) isn't allowed for HEVC, and guessing another
+        // size would misread every NAL unit.
+        Some(2) => {
+            return Err(Error::Mp4InvalidBox {
+                context: "stsd".to_string(),
+                box_type: "hvcC".to_string(),
+                offset,
+                message: "lengthSizeMinusOne 2 is not a valid NAL length size".to_string(),
+            });
+        }
+        Some(minus_one) => minus_one as usize + 1,
+        None => 4,
+    };
+
+    let mut nal_arrays: Vec<(u8, Vec<Vec<u8>>)> = Vec::new();
+    let num_arrays = record.get(22).copied().unwrap_or(0);
+    let mut i = 23usize;
+    'arrays: for _ in 0..num_arrays {
+        let Some(header) = record.get(i..i + 3) else {
+            break;
+        };
+        let nal_type = header[0] & 0x3F;
+        let num_nalus = u16::from_be_bytes([header[1], header[2]]);
+        i += 3;
+
+        let mut nalus = Vec::new();
+        for _ in 0..num_nalus {
+            let Some(len) = record.get(i..i + 2) else {
+                break 'arrays;
+            };
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+            i += 2;
+            let Some(nal) = record.get(i..i + len) else {
+                break 'arrays;
+            };
+            nalus.push(nal.to_vec());
+            i += len;
+        }
+        nal_arrays.push((nal_type, nalus));
+    }
+    let sps = first_sps(&nal_arrays, 33, parse_hevc_sps);
+
+    Ok((nal_len_size, Some(DecoderConfig { profile, sps })))
 }
 
-fn parse_hvcc_nal_len<R: Read + Seek>(f: &mut R, payload_start: u64) -> io::Result<usize> {
-    // hvcC: we only need lengthSizeMinusOne which is near the end of the fixed header.
-    // Per ISO/IEC 14496-15, lengthSizeMinusOne is in the byte after:
-    // configurationVersion (1) + ... + min_spatial_segmentation_idc(2) + parallelismType(1)
-    // + chromaFormat(1) + bitDepthLumaMinus8(1) + bitDepthChromaMinus8(1)
-    // + avgFrameRate(2) + constantFrameRate/numTemporalLayers/temporalIdNested (1)
-    // then lengthSizeMinusOne (2 bits) in next byte.
-    // That lands at offset 21 (0-based) for the common layout.
-    f.seek(SeekFrom::Start(payload_start + 21))?;
-    let b = read_u8(f)?;
-    let len_minus_one = (b & 0b11) as usize;
-    Ok(len_minus_one + 1)
+// The first SPS of type `nal_type` in a decoder configuration that parses.
+fn first_sps(
+    nal_arrays: &[(u8, Vec<Vec<u8>>)],
+    nal_type: u8,
+    parse: fn(&[u8]) -> Option<SpsInfo>,
+) -> Option<SpsInfo> {
+    nal_arrays
+        .iter()
+        .filter(|(t, _)| *t == nal_type)
+        .flat_map(|(_, nalus)| nalus)
+        .find_map(|nal| parse(nal))
 }
 
-// Turn stsc + stco + stsz into per-sample absolute file offsets.
-pub(crate) fn build_sample_offsets(t: &TrackSampleTables) -> Result<Vec<u64>, Error> {
-    // Expand chunk -> samples_per_chunk using stsc runs.
+// Expand stsc runs into per-chunk (samples_per_chunk, sample_description_index).
+pub(crate) fn chunk_runs(t: &TrackSampleTables) -> Vec<(u32, u32)> {
+    expand_stsc(t).0
+}
+
+// How many chunks `chunk_runs` gave the previous chunk's sample count because no stsc run
+// gave them any samples.
+pub(crate) fn stsc_gap_chunks(t: &TrackSampleTables) -> usize {
+    expand_stsc(t).1
+}
+
+fn expand_stsc(t: &TrackSampleTables) -> (Vec<(u32, u32)>, usize) {
     // MP4 chunks are 1-based in stsc.
-    let mut chunk_samples: Vec<u32> = vec![0; t.chunk_offsets.len()];
+    let mut chunks: Vec<(u32, u32)> = vec![(0, 0); t.chunk_offsets.len()];
 
     for i in 0..t.stsc.len() {
         let cur = &t.stsc[i];
@@ -513,22 +1583,110 @@ pub(crate) fn build_sample_offsets(t: &TrackSampleTables) -> Result<Vec<u64>, Er
 
         for chunk_idx_1based in cur.first_chunk..next_first {
             let idx0 = (chunk_idx_1based - 1) as usize;
-            if idx0 < chunk_samples.len() {
-                chunk_samples[idx0] = cur.samples_per_chunk;
+            if idx0 < chunks.len() {
+                chunks[idx0] = (cur.samples_per_chunk, cur.sample_description_index);
             }
         }
     }
 
     // Some files can be slightly malformed (or we parsed an unexpected stsc ordering).
     // Fill any zeros with the previous non-zero value so we still walk all chunks.
-    let mut last = 0u32;
-    for v in &mut chunk_samples {
-        if *v == 0 {
+    let mut last = (0u32, 0u32);
+    let mut gaps = 0;
+    for v in &mut chunks {
+        if v.0 == 0 {
             *v = last;
+            if last.0 != 0 {
+                gaps += 1;
+            }
         } else {
             last = *v;
         }
     }
+    (chunks, gaps)
+}
+
+// Per-sample 0-based index into `t.codecs`, from the stsc sample_description_index.
+//
+// Out-of-range indices (including 0) fall back to the first entry.
+pub(crate) fn build_sample_codec_indices(t: &TrackSampleTables) -> Vec<usize> {
+    let mut out = Vec::with_capacity(t.sample_sizes.len());
+    for (spc, sdi) in chunk_runs(t) {
+        let idx = (sdi as usize)
+            .checked_sub(1)
+            .filter(|&i| i < t.codecs.len())
+            .unwrap_or(0);
+        for _ in 0..spc {
+            if out.len() >= t.sample_sizes.len() {
+                return out;
+            }
+            out.push(idx);
+        }
+    }
+    out.resize(t.sample_sizes.len(), 0);
+    out
+}
+
+// Drop the samples that the chunk tables (stsc/stco) don't account for.
+pub(crate) fn truncate_to_chunks(t: &TrackSampleTables) -> TrackSampleTables {
+    let covered: u64 = chunk_runs(t).iter().map(|&(spc, _)| spc as u64).sum();
+    let mut repaired = t.clone();
+    repaired
+        .sample_sizes
+        .truncate(usize::try_from(covered).unwrap_or(usize::MAX));
+    repaired
+}
+
+// Rebuild stsc from the chunk offsets and sample sizes alone: each chunk's samples are assumed
+// to be stored back to back up to where the next chunk starts (or `data_end` for the last one).
+// Chunks that hold no sample are dropped, and samples that fit nowhere are truncated.
+//
+// This only holds for tracks whose chunks aren't interleaved with other tracks' data, which is
+// the case for Tesla clips (video only).
+pub(crate) fn infer_chunk_samples(t: &TrackSampleTables, data_end: u64) -> TrackSampleTables {
+    let runs = chunk_runs(t);
+    let mut chunk_offsets: Vec<u64> = Vec::new();
+    let mut stsc: Vec<StscEntry> = Vec::new();
+    let mut sample = 0usize;
+
+    for (i, &chunk_off) in t.chunk_offsets.iter().enumerate() {
+        let limit = t
+            .chunk_offsets
+            .get(i + 1)
+            .copied()
+            .filter(|&next| next > chunk_off)
+            .unwrap_or(data_end);
+
+        let mut off = chunk_off;
+        let mut count = 0u32;
+        while let Some(&size) = t.sample_sizes.get(sample)
+            && off + size as u64 <= limit
+        {
+            off += size as u64;
+            sample += 1;
+            count += 1;
+        }
+
+        if count > 0 {
+            chunk_offsets.push(chunk_off);
+            stsc.push(StscEntry {
+                first_chunk: chunk_offsets.len() as u32,
+                samples_per_chunk: count,
+                sample_description_index: runs.get(i).map_or(1, |&(_, sdi)| sdi),
+            });
+        }
+    }
+
+    let mut repaired = t.clone();
+    repaired.chunk_offsets = chunk_offsets;
+    repaired.stsc = stsc;
+    repaired.sample_sizes.truncate(sample);
+    repaired
+}
+
+// Turn stsc + stco + stsz into per-sample absolute file offsets.
+pub(crate) fn build_sample_offsets(t: &TrackSampleTables) -> Result<Vec<u64>, Error> {
+    let chunk_samples: Vec<u32> = chunk_runs(t).into_iter().map(|(spc, _)| spc).collect();
 
     // Now compute offsets by walking chunks in order.
     let mut sample_offsets = Vec::with_capacity(t.sample_sizes.len());
@@ -558,3 +1716,106 @@ pub(crate) fn build_sample_offsets(t: &TrackSampleTables) -> Result<Vec<u64>, Er
 
     Ok(sample_offsets)
 }
+
+// Turn stts + ctts into per-sample presentation times (media timescale units).
+//
+// Decode times come from stts; each sample's composition offset from ctts is added on top.
+// Missing stts entries fall back to a delta of 1 so ordering still works, and missing ctts
+// entries mean presentation order equals decode order.
+pub(crate) fn build_presentation_times(t: &TrackSampleTables) -> Vec<i64> {
+    let total = t.sample_sizes.len();
+
+    let mut deltas = t
+        .stts
+        .iter()
+        .flat_map(|e| std::iter::repeat_n(e.sample_delta, e.sample_count as usize));
+    let mut offsets = t
+        .ctts
+        .iter()
+        .flat_map(|e| std::iter::repeat_n(e.sample_offset, e.sample_count as usize));
+
+    let mut out = Vec::with_capacity(total);
+    let mut dts = 0i64;
+    for _ in 0..total {
+        out.push(dts + offsets.next().unwrap_or(0));
+        dts += deltas.next().unwrap_or(1) as i64;
+    }
+    out
+}
+
+// Offset (media timescale units) to add to composition times so they match the presentation
+// timeline players use.
+//
+// Only the common edit-list shapes are modelled: leading empty edits delay the track, and the
+// first non-empty edit's media_time marks which media time is shown first. Later segments
+// (e.g. spliced or repeated ranges) are ignored.
+pub(crate) fn edit_list_offset(t: &TrackSampleTables, movie_timescale: Option<u32>) -> i64 {
+    let media_timescale = t.media_header.as_ref().map_or(0, |h| h.timescale);
+
+    let mut offset = 0i64;
+    for e in &t.elst {
+        if e.media_time == -1 {
+            // Empty edit durations are in movie units; convert to media units.
+            if let Some(mts) = movie_timescale.filter(|&ts| ts != 0) {
+                let ticks = e.segment_duration as i128 * media_timescale as i128 / mts as i128;
+                offset += ticks as i64;
+            }
+        } else {
+            offset -= e.media_time;
+            break;
+        }
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::sps::tests::SPS_1280X960;
+
+    // An avcC payload with one SPS, whose length field says `declared` bytes, and no PPS.
+    fn avcc(declared: u16, sps: &[u8]) -> Vec<u8> {
+        let mut record = vec![1, 0x64, 0x00, 0x28, 0xFF, 0xE1];
+        record.extend_from_slice(&declared.to_be_bytes());
+        record.extend_from_slice(sps);
+        record
+    }
+
+    #[test]
+    fn avcc_sps() {
+        let record = avcc(SPS_1280X960.len() as u16, &SPS_1280X960);
+        let end = record.len() as u64;
+        let (nal_len_size, config) = parse_avcc(&mut Cursor::new(record), 0, end).unwrap();
+        assert_eq!(nal_len_size, 4);
+        let sps = config.unwrap().sps.unwrap();
+        assert_eq!((sps.width, sps.height), (1280, 960));
+    }
+
+    #[test]
+    fn truncated_avcc_has_no_decoder_config() {
+        // The record ends 8 bytes into a 20-byte SPS.
+        let record = avcc(SPS_1280X960.len() as u16, &SPS_1280X960[..8]);
+        let end = record.len() as u64;
+        let (nal_len_size, config) = parse_avcc(&mut Cursor::new(record), 0, end).unwrap();
+        assert_eq!(nal_len_size, 4);
+        assert!(config.is_none());
+    }
+
+    #[test]
+    fn hvcc_three_byte_lengths_are_rejected() {
+        // A 23-byte hvcC with no NAL arrays; byte 21 holds lengthSizeMinusOne.
+        let mut record = vec![0u8; 23];
+        record[0] = 1;
+        record[21] = 0xFC | 3;
+        assert_eq!(parse_hvcc(&record, 0).unwrap().0, 4);
+        record[21] = 0xFC | 1;
+        assert_eq!(parse_hvcc(&record, 0).unwrap().0, 2);
+        record[21] = 0xFC | 2;
+        assert!(matches!(
+            parse_hvcc(&record, 0),
+            Err(Error::Mp4InvalidBox { .. })
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Per-frame clock timestamps from H.264 `pic_timing` and H.265 `time_code` SEI messages.
+//!
+//! Some encoders stamp every frame with a wall-clock style `hh:mm:ss` plus frame count. These
+//! timestamps live in the bitstream itself, so they survive remuxes that rewrite or drop the MP4
+//! timing tables. H.264 carries them in `pic_timing` (payload type 1), whose layout depends on
+//! the SPS VUI/HRD parameters; H.265 carries them in `time_code` (payload type 136).
+
+use std::time::Duration;
+
+use crate::mp4::CodecConfig;
+use crate::sei::raw_sei_from_sample;
+use crate::sps::{BitReader, PicTimingLayout, SpsInfo};
+
+const SEI_PIC_TIMING: u32 = 1;
+const SEI_TIME_CODE: u32 = 136;
+
+/// One clock timestamp of a frame (or field), as signalled in the bitstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockTimestamp {
+    /// The hours part; `None` when the encoder left it out to mean "same as the previous
+    /// timestamp", as do `minutes` and `seconds`.
+    pub hours: Option<u8>,
+    /// The minutes part.
+    pub minutes: Option<u8>,
+    /// The seconds part.
+    pub seconds: Option<u8>,
+    /// Frame (or field, see `field_based`) count within the second.
+    pub n_frames: u16,
+    /// Offset in clock ticks (1 / VUI `time_scale`) added to the timestamp.
+    pub time_offset: i32,
+    /// Whether `n_frames` counts fields rather than frames.
+    pub field_based: bool,
+    /// `counting_type`: how `n_frames` relates to the source, e.g. 4 for NTSC drop-frame.
+    pub counting_type: u8,
+    /// Set when this timestamp doesn't continue from the previous one.
+    pub discontinuity: bool,
+    /// `cnt_dropped_flag`: frame counts were skipped ahead of this timestamp.
+    pub cnt_dropped: bool,
+    /// The timestamp as a duration since `00:00:00`. `None` when any of the time parts is left
+    /// out or the SPS signals no timing info.
+    pub time: Option<Duration>,
+}
+
+impl ClockTimestamp {
+    // Fill in `time` from the spec's clockTimestamp, which counts 1 / time_scale second ticks.
+    fn resolve(&mut self, timing: Option<(u32, u32)>) {
+        let (Some(h), Some(m), Some(s), Some((num_units_in_tick, time_scale))) =
+            (self.hours, self.minutes, self.seconds, timing)
+        else {
+            return;
+        };
+        if time_scale == 0 {
+            return;
+        }
+        let whole = (h as i64 * 60 + m as i64) * 60 + s as i64;
+        let ticks_per_count = num_units_in_tick as i64 * (1 + self.field_based as i64);
+        let ticks = whole * time_scale as i64
+            + self.n_frames as i64 * ticks_per_count
+            + self.time_offset as i64;
+        if ticks >= 0 {
+            self.time = Some(Duration::from_secs_f64(ticks as f64 / time_scale as f64));
+        }
+    }
+}
+
+// Whether a track's samples can carry clock timestamps, as far as its decoder configuration
+// tells: H.264 pic_timing only has them when the SPS VUI sets pic_struct_present_flag. `None`
+// for H.265, whose time_code messages nothing announces ahead of time.
+pub(crate) fn track_clock_timestamps(codec: &CodecConfig, sps: Option<&SpsInfo>) -> Option<bool> {
+    match codec {
+        CodecConfig::Avc { .. } => Some(
+            sps.and_then(|s| s.pic_timing)
+                .is_some_and(|layout| layout.pic_struct_present),
+        ),
+        CodecConfig::Hevc { .. } => None,
+        _ => Some(false),
+    }
+}
+
+// Clock timestamps from every pic_timing (H.264) or time_code (H.265) SEI message in a sample.
+pub(crate) fn clock_timestamps_from_sample(
+    codec: &CodecConfig,
+    sample: &[u8],
+    sps: Option<&SpsInfo>,
+) -> Vec<ClockTimestamp> {
+    let timing = sps.and_then(|s| s.timing);
+    let mut out = Vec::new();
+    for (_, payload_type, payload) in raw_sei_from_sample(codec, sample) {
+        let parsed = match (codec, payload_type) {
+            (CodecConfig::Avc { .. }, SEI_PIC_TIMING) => match sps.and_then(|s| s.pic_timing) {
+                Some(layout) => parse_avc_pic_timing(&payload, &layout),
+                // Without the SPS the message can't even be split into fields.
+                None => None,
+            },
+            (CodecConfig::Hevc { .. }, SEI_TIME_CODE) => parse_hevc_time_code(&payload),
+            _ => None,
+        };
+        for mut ts in parsed.unwrap_or_default() {
+            ts.resolve(timing);
+            out.push(ts);
+        }
+    }
+    out
+}
+
+// H.264 pic_timing().
+fn parse_avc_pic_timing(payload: &[u8], layout: &PicTimingLayout) -> Option<Vec<ClockTimestamp>> {
+    let mut r = BitReader::new(payload);
+    if let Some((cpb_removal_delay_len, dpb_output_delay_len)) = layout.delay_lengths {
+        r.skip((cpb_removal_delay_len + dpb_output_delay_len) as usize)?;
+    }
+    if !layout.pic_struct_present {
+        return Some(Vec::new());
+    }
+    // NumClockTS for the pic_struct.
+    let num_clock_ts = match r.bits(4)? {
+        0..=2 => 1,
+        3 | 4 | 7 => 2,
+        5 | 6 | 8 => 3,
+        _ => return None,
+    };
+
+    let mut out = Vec::new();
+    for _ in 0..num_clock_ts {
+        // clock_timestamp_flag
+        if !r.flag()? {
+            continue;
+        }
+        // ct_type
+        r.skip(2)?;
+        let mut ts = parse_clock_timestamp(&mut r, 8)?;
+        if layout.time_offset_length > 0 {
+            ts.time_offset = r.signed(layout.time_offset_length)?;
+        }
+        out.push(ts);
+    }
+    Some(out)
+}
+
+// H.265 time_code().
+fn parse_hevc_time_code(payload: &[u8]) -> Option<Vec<ClockTimestamp>> {
+    let mut r = BitReader::new(payload);
+    let num_clock_ts = r.bits(2)?;
+
+    let mut out = Vec::new();
+    for _ in 0..num_clock_ts {
+        // clock_timestamp_flag
+        if !r.flag()? {
+            continue;
+        }
+        let mut ts = parse_clock_timestamp(&mut r, 9)?;
+        let time_offset_length = r.bits(5)?;
+        if time_offset_length > 0 {
+            ts.time_offset = r.signed(time_offset_length)?;
+        }
+        out.push(ts);
+    }
+    Some(out)
+}
+
+// The clock timestamp fields H.264 and H.265 share, from the field-based flag up to the hours.
+fn parse_clock_timestamp(r: &mut BitReader, n_frames_bits: u32) -> Option<ClockTimestamp> {
+    let field_based = r.flag()?;
+    let counting_type = r.bits(5)? as u8;
+    let full_timestamp = r.flag()?;
+    let discontinuity = r.flag()?;
+    let cnt_dropped = r.flag()?;
+    let n_frames = r.bits(n_frames_bits)? as u16;
+
+    let (mut hours, mut minutes, mut seconds) = (None, None, None);
+    if full_timestamp {
+        seconds = Some(r.bits(6)? as u8);
+        minutes = Some(r.bits(6)? as u8);
+        hours = Some(r.bits(5)? as u8);
+    } else if r.flag()? {
+        seconds = Some(r.bits(6)? as u8);
+        if r.flag()? {
+            minutes = Some(r.bits(6)? as u8);
+            if r.flag()? {
+                hours = Some(r.bits(5)? as u8);
+            }
+        }
+    }
+
+    Some(ClockTimestamp {
+        hours,
+        minutes,
+        seconds,
+        n_frames,
+        time_offset: 0,
+        field_based,
+        counting_type,
+        discontinuity,
+        cnt_dropped,
+        time: None,
+    })
+}
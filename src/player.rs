@@ -0,0 +1,177 @@
+//! Telemetry keyed by playback time, for video player overlays.
+//!
+//! [`PlaybackTimeline`] does the sync work once: every event is placed at the presentation time
+//! the player shows its frame, so overlays only need to look up the current playback position.
+//!
+//! - mpv: [`PlaybackTimeline::write_ndjson`] writes one JSON object per line for a Lua/JS script
+//!   to load, and [`PlaybackTimeline::mpv_script_message`] builds a JSON IPC command that
+//!   delivers the record for a playback time to scripts via `script-message`.
+//! - GStreamer: each [`TimedTelemetry`] maps onto one `appsrc` buffer, with
+//!   [`TimedTelemetry::pts`]/[`TimedTelemetry::duration`] as the buffer PTS/duration and
+//!   [`TimedTelemetry::to_json`] as the payload (caps `application/x-tesla-sei+json`).
+
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::extract::{extractor_from_reader, SeiExtractor};
+use crate::pb;
+use crate::Error;
+
+/// Name scripts register with `mp.register_script_message` to receive
+/// [`PlaybackTimeline::mpv_script_message`] payloads.
+pub const MPV_SCRIPT_MESSAGE: &str = "tesla-sei-telemetry";
+
+/// One telemetry record placed on the playback timeline.
+#[derive(Debug, Clone)]
+pub struct TimedTelemetry {
+    /// Presentation time of the frame carrying the record.
+    pub pts: Duration,
+    /// How long the record stays current: until the next record, or the end of the clip.
+    pub duration: Duration,
+    /// 0-based sample index in the selected track.
+    pub sample_index: usize,
+    pub metadata: pb::SeiMetadata,
+}
+
+impl TimedTelemetry {
+    /// The record as a JSON object: `time` and `duration` in seconds, followed by the telemetry
+    /// fields (enums as numbers).
+    pub fn to_json(&self) -> String {
+        let m = &self.metadata;
+        let record = JsonRecord {
+            time: self.pts.as_secs_f64(),
+            duration: self.duration.as_secs_f64(),
+            sample_index: self.sample_index,
+            version: m.version,
+            gear_state: m.gear_state,
+            frame_seq_no: m.frame_seq_no,
+            vehicle_speed_mps: m.vehicle_speed_mps,
+            accelerator_pedal_position: m.accelerator_pedal_position,
+            steering_wheel_angle: m.steering_wheel_angle,
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: m.autopilot_state,
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+        };
+        // Plain numbers, bools and field names only; serialization can't fail.
+        serde_json::to_string(&record).unwrap_or_default()
+    }
+}
+
+// Serialized through a struct (rather than `json!`) so `f32` fields keep their short form.
+#[derive(Serialize)]
+struct JsonRecord {
+    time: f64,
+    duration: f64,
+    sample_index: usize,
+    version: u32,
+    gear_state: i32,
+    frame_seq_no: u64,
+    vehicle_speed_mps: f32,
+    accelerator_pedal_position: f32,
+    steering_wheel_angle: f32,
+    blinker_on_left: bool,
+    blinker_on_right: bool,
+    brake_applied: bool,
+    autopilot_state: i32,
+    latitude_deg: f64,
+    longitude_deg: f64,
+    heading_deg: f64,
+    linear_acceleration_mps2_x: f64,
+    linear_acceleration_mps2_y: f64,
+    linear_acceleration_mps2_z: f64,
+}
+
+/// A clip's telemetry sorted by presentation time.
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackTimeline {
+    entries: Vec<TimedTelemetry>,
+}
+
+impl PlaybackTimeline {
+    /// Decode every event of an on-disk MP4 onto the timeline.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_reader(File::open(path)?)
+    }
+
+    /// Decode every event from any seekable reader onto the timeline.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self, Error> {
+        Self::from_extractor(extractor_from_reader(reader)?)
+    }
+
+    /// Drain an extractor onto the timeline.
+    ///
+    /// Events without a presentation time (tracks with no usable timescale) are dropped.
+    pub fn from_extractor<R: Read + Seek>(mut extractor: SeiExtractor<R>) -> Result<Self, Error> {
+        let mut entries: Vec<TimedTelemetry> = Vec::new();
+        while let Some(event) = extractor.next_event()? {
+            let Some(pts) = event.presentation_time else {
+                continue;
+            };
+            entries.push(TimedTelemetry {
+                pts,
+                duration: Duration::ZERO,
+                sample_index: event.sample_index,
+                metadata: event.metadata,
+            });
+        }
+        // Stable, so several records in one sample keep their order.
+        entries.sort_by_key(|e| e.pts);
+
+        // Each record lasts until the next distinct presentation time (or the end of the clip).
+        let mut end = extractor.duration();
+        let mut group_pts: Option<Duration> = None;
+        for e in entries.iter_mut().rev() {
+            if group_pts != Some(e.pts) {
+                if let Some(p) = group_pts {
+                    end = Some(p);
+                }
+                group_pts = Some(e.pts);
+            }
+            e.duration = end.map_or(Duration::ZERO, |end| end.saturating_sub(e.pts));
+        }
+
+        Ok(PlaybackTimeline { entries })
+    }
+
+    /// All records, in presentation order.
+    pub fn entries(&self) -> &[TimedTelemetry] {
+        &self.entries
+    }
+
+    /// The record current at playback position `t`: the last one at or before `t`.
+    pub fn at(&self, t: Duration) -> Option<&TimedTelemetry> {
+        let idx = self.entries.partition_point(|e| e.pts <= t);
+        idx.checked_sub(1).map(|i| &self.entries[i])
+    }
+
+    /// Write the timeline as newline-delimited JSON, one [`TimedTelemetry::to_json`] per line.
+    pub fn write_ndjson(&self, out: &mut dyn Write) -> Result<(), Error> {
+        for e in &self.entries {
+            writeln!(out, "{}", e.to_json())?;
+        }
+        Ok(())
+    }
+
+    /// An mpv JSON IPC command that delivers the record current at `t` to scripts, or `None`
+    /// before the first record.
+    ///
+    /// Send it (newline-terminated) to mpv's `--input-ipc-server` socket; a script receives the
+    /// record JSON via `mp.register_script_message("tesla-sei-telemetry", ...)`.
+    pub fn mpv_script_message(&self, t: Duration) -> Option<String> {
+        let e = self.at(t)?;
+        let cmd = json!({ "command": ["script-message", MPV_SCRIPT_MESSAGE, e.to_json()] });
+        Some(cmd.to_string())
+    }
+}
@@ -0,0 +1,137 @@
+//! Telemetry as a Polars [`DataFrame`] (`polars` feature).
+//!
+//! [`SeiExtractor::to_dataframe`] decodes a clip straight into a frame with one typed column per
+//! field, named and typed like `arrow::SEI_SCHEMA` (`arrow` feature) lays out record batches:
+//!
+//! ```no_run
+//! let df = tesla_sei::extractor_from_path("clip.mp4")?.to_dataframe()?;
+//! # Ok::<(), tesla_sei::Error>(())
+//! ```
+
+use std::io::{Read, Seek};
+
+use polars::prelude::{Column, DataFrame};
+
+use crate::extract::{SeiEvent, SeiExtractor};
+use crate::Error;
+
+impl<R: Read + Seek> SeiExtractor<R> {
+    /// Decode the remaining events into a [`DataFrame`], one row per event in decode order.
+    ///
+    /// Columns are `sample_index`, `presentation_index`, `presentation_time_s` (null when the
+    /// track has no usable timing), then the `SeiMetadata` fields in proto order with enums as
+    /// their raw `i32` values. On a fresh extractor that is the whole clip; after iterating or
+    /// [`seek_sample`](Self::seek_sample) it starts from the current position.
+    pub fn to_dataframe(&mut self) -> Result<DataFrame, Error> {
+        let rows = self.total_samples();
+        events_to_dataframe(std::iter::from_fn(|| self.next_event().transpose()), rows)
+    }
+}
+
+// The frame for `to_dataframe`; `rows` is only a capacity hint.
+fn events_to_dataframe(
+    events: impl Iterator<Item = Result<SeiEvent, Error>>,
+    rows: usize,
+) -> Result<DataFrame, Error> {
+    let mut sample_index = Vec::with_capacity(rows);
+    let mut presentation_index = Vec::with_capacity(rows);
+    let mut presentation_time = Vec::with_capacity(rows);
+    let mut version = Vec::with_capacity(rows);
+    let mut gear_state = Vec::with_capacity(rows);
+    let mut frame_seq_no = Vec::with_capacity(rows);
+    let mut speed = Vec::with_capacity(rows);
+    let mut accelerator = Vec::with_capacity(rows);
+    let mut steering = Vec::with_capacity(rows);
+    let mut blinker_left = Vec::with_capacity(rows);
+    let mut blinker_right = Vec::with_capacity(rows);
+    let mut brake = Vec::with_capacity(rows);
+    let mut autopilot = Vec::with_capacity(rows);
+    let mut latitude = Vec::with_capacity(rows);
+    let mut longitude = Vec::with_capacity(rows);
+    let mut heading = Vec::with_capacity(rows);
+    let mut accel_x = Vec::with_capacity(rows);
+    let mut accel_y = Vec::with_capacity(rows);
+    let mut accel_z = Vec::with_capacity(rows);
+
+    for event in events {
+        let event = event?;
+        let m = &event.metadata;
+        sample_index.push(event.sample_index as u64);
+        presentation_index.push(event.presentation_index as u64);
+        presentation_time.push(event.presentation_time.map(|t| t.as_secs_f64()));
+        version.push(m.version);
+        gear_state.push(m.gear_state);
+        frame_seq_no.push(m.frame_seq_no);
+        speed.push(m.vehicle_speed_mps);
+        accelerator.push(m.accelerator_pedal_position);
+        steering.push(m.steering_wheel_angle);
+        blinker_left.push(m.blinker_on_left);
+        blinker_right.push(m.blinker_on_right);
+        brake.push(m.brake_applied);
+        autopilot.push(m.autopilot_state);
+        latitude.push(m.latitude_deg);
+        longitude.push(m.longitude_deg);
+        heading.push(m.heading_deg);
+        accel_x.push(m.linear_acceleration_mps2_x);
+        accel_y.push(m.linear_acceleration_mps2_y);
+        accel_z.push(m.linear_acceleration_mps2_z);
+    }
+
+    let df = DataFrame::new(vec![
+        Column::new("sample_index".into(), sample_index),
+        Column::new("presentation_index".into(), presentation_index),
+        Column::new("presentation_time_s".into(), presentation_time),
+        Column::new("version".into(), version),
+        Column::new("gear_state".into(), gear_state),
+        Column::new("frame_seq_no".into(), frame_seq_no),
+        Column::new("vehicle_speed_mps".into(), speed),
+        Column::new("accelerator_pedal_position".into(), accelerator),
+        Column::new("steering_wheel_angle".into(), steering),
+        Column::new("blinker_on_left".into(), blinker_left),
+        Column::new("blinker_on_right".into(), blinker_right),
+        Column::new("brake_applied".into(), brake),
+        Column::new("autopilot_state".into(), autopilot),
+        Column::new("latitude_deg".into(), latitude),
+        Column::new("longitude_deg".into(), longitude),
+        Column::new("heading_deg".into(), heading),
+        Column::new("linear_acceleration_mps2_x".into(), accel_x),
+        Column::new("linear_acceleration_mps2_y".into(), accel_y),
+        Column::new("linear_acceleration_mps2_z".into(), accel_z),
+    ])?;
+    Ok(df)
+}
+
+#[cfg(all(test, feature = "arrow"))]
+mod tests {
+    use polars::prelude::DataType;
+
+    use super::*;
+    use crate::arrow::SEI_SCHEMA;
+
+    #[test]
+    fn columns_match_arrow_schema() {
+        let df = events_to_dataframe(std::iter::empty(), 0).unwrap();
+        let columns: Vec<_> = df
+            .get_columns()
+            .iter()
+            .map(|c| (c.name().to_string(), c.dtype().clone()))
+            .collect();
+        let expected: Vec<_> = SEI_SCHEMA
+            .fields()
+            .iter()
+            .map(|f| {
+                let dtype = match f.data_type() {
+                    arrow_schema::DataType::Boolean => DataType::Boolean,
+                    arrow_schema::DataType::Int32 => DataType::Int32,
+                    arrow_schema::DataType::UInt32 => DataType::UInt32,
+                    arrow_schema::DataType::UInt64 => DataType::UInt64,
+                    arrow_schema::DataType::Float32 => DataType::Float32,
+                    arrow_schema::DataType::Float64 => DataType::Float64,
+                    other => panic!("no Polars dtype for {other}"),
+                };
+                (f.name().clone(), dtype)
+            })
+            .collect();
+        assert_eq!(columns, expected);
+    }
+}
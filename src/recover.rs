@@ -0,0 +1,256 @@
+//! Best-effort telemetry recovery from MP4 files without a usable `moov` box.
+//!
+//! A camera that loses power mid-recording leaves the `mdat` payload on disk but never writes the
+//! `moov` index, so the regular extractor fails with [`Error::NoTracksFound`]. Samples in `mdat`
+//! are runs of NAL units with 4-byte big-endian length prefixes, so they can be walked without
+//! the sample tables: [`RecoveryExtractor`] follows that length chain from the start of `mdat`
+//! (or of the file, when no `mdat` header survived) and resynchronises byte by byte wherever the
+//! chain breaks.
+//!
+//! Without the sample tables there are no sample indices or presentation times, and the codec is
+//! unknown: SEI NAL units of both H.264 and H.265 are tried until one decodes as telemetry, and
+//! from then on only that codec's SEI NAL units are read.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::mp4::{read_box_tree, CodecConfig};
+use crate::pb;
+use crate::sei::{decode_sei_from_nal, DecodeStats, UnknownField, DEFAULT_SEI_PAYLOAD_TYPES};
+use crate::Error;
+
+/// One decoded SEI payload found by scanning.
+#[derive(Debug, Clone)]
+pub struct RecoveredEvent {
+    /// The 0-based index of the NAL unit among those recovered.
+    pub nal_index: usize,
+    /// Absolute file offset of the NAL unit header (just past its length prefix).
+    pub file_offset: u64,
+    /// The decoded protobuf message.
+    pub metadata: pb::SeiMetadata,
+    /// UUID of the `user_data_unregistered` SEI payload, see [`crate::SeiEvent::uuid`].
+    pub uuid: Option<[u8; 16]>,
+    /// Fields newer than the schema, see [`crate::SeiEvent::unknown_fields`].
+    pub unknown_fields: Vec<UnknownField>,
+    /// The protobuf bytes `metadata` was decoded from, see [`crate::SeiEvent::raw_metadata`].
+    #[cfg(feature = "reflect")]
+    pub raw_metadata: Vec<u8>,
+}
+
+/// Open an on-disk MP4 for recovery.
+pub fn recovery_extractor_from_path(
+    path: impl AsRef<Path>,
+) -> Result<RecoveryExtractor<BufReader<File>>, Error> {
+    recovery_extractor_from_reader(BufReader::new(File::open(path)?))
+}
+
+/// Create a recovery extractor from any seekable reader.
+///
+/// Scanning starts at the payload of the first top-level `mdat` box, or at the start of the
+/// input if there is none, and runs to the end of the input: a truncated `mdat` often declares a
+/// size that no longer matches the file.
+pub fn recovery_extractor_from_reader<R: Read + Seek>(
+    mut reader: R,
+) -> Result<RecoveryExtractor<R>, Error> {
+    let start = read_box_tree(&mut reader)?
+        .iter()
+        .find(|b| b.box_type == "mdat")
+        .map_or(0, |b| b.offset + b.header_len);
+    let end = reader.seek(SeekFrom::End(0))?;
+
+    Ok(RecoveryExtractor {
+        reader,
+        pos: start,
+        end,
+        nal_index: 0,
+        codec: None,
+        pending: VecDeque::new(),
+        decode_stats: DecodeStats::default(),
+        resync_bytes: 0,
+    })
+}
+
+// Tesla clips never carry NAL units anywhere near this large; bigger lengths are misaligned reads.
+const MAX_NAL_LEN: u32 = 32 * 1024 * 1024;
+
+const RESYNC_WINDOW: usize = 64 * 1024;
+
+/// Streaming scanner over the length-prefixed NAL units of an MP4 without sample tables.
+///
+/// Implements `Iterator<Item = Result<RecoveredEvent, Error>>`.
+pub struct RecoveryExtractor<R: Read + Seek> {
+    reader: R,
+    // Offset of the next length prefix.
+    pos: u64,
+    end: u64,
+    nal_index: usize,
+    // Set by the first NAL unit that decodes as telemetry.
+    codec: Option<CodecConfig>,
+    pending: VecDeque<RecoveredEvent>,
+    decode_stats: DecodeStats,
+    resync_bytes: u64,
+}
+
+impl<R: Read + Seek> RecoveryExtractor<R> {
+    /// Which decode heuristics fired so far; `samples` counts NAL units.
+    pub fn decode_stats(&self) -> &DecodeStats {
+        &self.decode_stats
+    }
+
+    /// Bytes skipped so far while resynchronising on a broken length chain.
+    pub fn resync_bytes(&self) -> u64 {
+        self.resync_bytes
+    }
+
+    /// Pull the next decoded event, or `Ok(None)` once the input is exhausted.
+    pub fn next_event(&mut self) -> Result<Option<RecoveredEvent>, Error> {
+        let mut decoded = Vec::new();
+        while self.pending.is_empty() {
+            let Some(len) = self.next_nal_len()? else {
+                break;
+            };
+            let file_offset = self.pos + 4;
+            self.pos = file_offset + len as u64;
+
+            let nal_index = self.nal_index;
+            self.nal_index += 1;
+            self.decode_stats.samples += 1;
+
+            // Only SEI NAL units are worth reading in full.
+            let mut header = [0u8; 1];
+            self.reader.seek(SeekFrom::Start(file_offset))?;
+            self.reader.read_exact(&mut header)?;
+            let candidates = match &self.codec {
+                Some(codec) => std::slice::from_ref(codec),
+                None => &BOTH_CODECS,
+            };
+            if !candidates.iter().any(|c| is_sei_nal_header(c, header[0])) {
+                continue;
+            }
+            let mut nal = vec![0u8; len as usize];
+            self.reader.seek(SeekFrom::Start(file_offset))?;
+            self.reader.read_exact(&mut nal)?;
+
+            // Each config only decodes its own SEI NAL unit types.
+            let mut detected = None;
+            for codec in candidates {
+                decode_sei_from_nal(
+                    codec,
+                    &nal,
+                    Some(DEFAULT_SEI_PAYLOAD_TYPES),
+                    &mut self.decode_stats,
+                    &mut decoded,
+                );
+                if !decoded.is_empty() {
+                    detected = Some(codec.clone());
+                    break;
+                }
+            }
+            if self.codec.is_none() {
+                self.codec = detected;
+            }
+            self.pending
+                .extend(decoded.drain(..).map(|d| RecoveredEvent {
+                    nal_index,
+                    file_offset,
+                    metadata: d.metadata,
+                    uuid: d.uuid,
+                    unknown_fields: d.unknown_fields,
+                    #[cfg(feature = "reflect")]
+                    raw_metadata: d.raw_metadata,
+                }));
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    // Length of the NAL unit at `pos`, resynchronising first if the chain is broken there.
+    fn next_nal_len(&mut self) -> io::Result<Option<u32>> {
+        loop {
+            if let Some(len) = self.nal_len_at(self.pos)?
+                && self.chain_continues(self.pos + 4 + len as u64)?
+            {
+                return Ok(Some(len));
+            }
+            if !self.resync()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    // A plausible length prefix at `pos`: non-zero, inside the input, and followed by a NAL
+    // header with the forbidden_zero_bit clear.
+    fn nal_len_at(&mut self, pos: u64) -> io::Result<Option<u32>> {
+        if pos + 5 > self.end {
+            return Ok(None);
+        }
+        let mut buf = [0u8; 5];
+        self.reader.seek(SeekFrom::Start(pos))?;
+        self.reader.read_exact(&mut buf)?;
+        Ok(plausible_nal_len(&buf, pos, self.end))
+    }
+
+    // Whether the chain can go on after a NAL ending at `next`: another plausible prefix, or the
+    // end of the input (possibly mid-prefix, for a file cut off by power loss).
+    fn chain_continues(&mut self, next: u64) -> io::Result<bool> {
+        Ok(next + 5 > self.end || self.nal_len_at(next)?.is_some())
+    }
+
+    // Move `pos` forward to the next offset where two consecutive length prefixes line up.
+    // Returns `false` at the end of the input.
+    fn resync(&mut self) -> io::Result<bool> {
+        let mut window = vec![0u8; RESYNC_WINDOW];
+        let mut from = self.pos + 1;
+        while from + 5 <= self.end {
+            let len = (RESYNC_WINDOW as u64).min(self.end - from) as usize;
+            self.reader.seek(SeekFrom::Start(from))?;
+            self.reader.read_exact(&mut window[..len])?;
+
+            for i in 0..len.saturating_sub(4) {
+                let pos = from + i as u64;
+                if let Some(nal_len) = plausible_nal_len(&window[i..i + 5], pos, self.end)
+                    && self.chain_continues(pos + 4 + nal_len as u64)?
+                {
+                    self.resync_bytes += pos - self.pos;
+                    self.pos = pos;
+                    return Ok(true);
+                }
+            }
+            // Windows overlap by 4 bytes so no candidate prefix is split.
+            from += len.saturating_sub(4).max(1) as u64;
+        }
+        self.resync_bytes += self.end.saturating_sub(self.pos);
+        self.pos = self.end;
+        Ok(false)
+    }
+}
+
+const BOTH_CODECS: [CodecConfig; 2] = [
+    CodecConfig::Avc { nal_len_size: 4 },
+    CodecConfig::Hevc { nal_len_size: 4 },
+];
+
+// H.264 SEI (type 6) or H.265 prefix/suffix SEI (types 39/40), by the first NAL header byte.
+fn is_sei_nal_header(codec: &CodecConfig, b: u8) -> bool {
+    match codec {
+        CodecConfig::Avc { .. } => b & 0x1F == 6,
+        CodecConfig::Hevc { .. } => matches!((b >> 1) & 0x3F, 39 | 40),
+        CodecConfig::Av1 | CodecConfig::Unknown => false,
+    }
+}
+
+// `buf` holds a 4-byte length prefix and the first NAL header byte.
+fn plausible_nal_len(buf: &[u8], pos: u64, end: u64) -> Option<u32> {
+    let len = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let fits = pos + 4 + len as u64 <= end;
+    (len > 0 && len <= MAX_NAL_LEN && fits && buf[4] & 0x80 == 0).then_some(len)
+}
+
+impl<R: Read + Seek> Iterator for RecoveryExtractor<R> {
+    type Item = Result<RecoveredEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime};
+
+use crate::catalog::{Catalog, ClipSource};
+use crate::trip::TripOptions;
+use crate::Error;
+
+/// Reporting period for [`Catalog::period_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Calendar day.
+    Day,
+    /// ISO 8601 week (Monday to Sunday).
+    Week,
+}
+
+impl GroupBy {
+    /// First day of the period containing `t`.
+    pub fn period_start(&self, t: NaiveDateTime) -> NaiveDate {
+        let date = t.date();
+        match self {
+            GroupBy::Day => date,
+            GroupBy::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        }
+    }
+
+    /// Human-readable label for the period starting at `start`: `2024-03-15` or `2024-W11`.
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            GroupBy::Day => start.format("%Y-%m-%d").to_string(),
+            GroupBy::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+        }
+    }
+}
+
+/// Aggregate statistics for one reporting period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodStats {
+    /// Period label (see [`GroupBy::label`]).
+    pub period: String,
+    /// First day of the period.
+    pub start: NaiveDate,
+    /// Trips starting in this period.
+    pub trips: usize,
+    /// Distance driven on those trips, in meters.
+    pub distance_m: f64,
+    /// Recorded duration of those trips.
+    pub drive_time: Duration,
+    /// Recorded minutes (one per clip set, regardless of camera count).
+    pub clips: usize,
+    /// Saved events (`SavedClips` folders) in this period.
+    pub saved_events: usize,
+    /// Sentry Mode events (`SentryClips` folders) in this period.
+    pub sentry_events: usize,
+}
+
+impl PeriodStats {
+    fn new(group_by: GroupBy, start: NaiveDate) -> Self {
+        PeriodStats {
+            period: group_by.label(start),
+            start,
+            trips: 0,
+            distance_m: 0.0,
+            drive_time: Duration::ZERO,
+            clips: 0,
+            saved_events: 0,
+            sentry_events: 0,
+        }
+    }
+}
+
+fn period_entry(
+    periods: &mut BTreeMap<NaiveDate, PeriodStats>,
+    group_by: GroupBy,
+    t: NaiveDateTime,
+) -> &mut PeriodStats {
+    let start = group_by.period_start(t);
+    periods
+        .entry(start)
+        .or_insert_with(|| PeriodStats::new(group_by, start))
+}
+
+impl Catalog {
+    /// Aggregate distance, drive time, and event counts per day or week.
+    ///
+    /// Trips are attributed to the period they start in. Clips without a TeslaCam timestamp are
+    /// left out. Periods with no activity are omitted; the result is in chronological order.
+    pub fn period_stats(
+        &self,
+        group_by: GroupBy,
+        opts: &TripOptions,
+    ) -> Result<Vec<PeriodStats>, Error> {
+        let mut periods: BTreeMap<NaiveDate, PeriodStats> = BTreeMap::new();
+
+        for trip in self.trips(opts)? {
+            let Some(start) = trip.start else {
+                continue;
+            };
+            let p = period_entry(&mut periods, group_by, start);
+            p.trips += 1;
+            p.distance_m += trip.distance_m;
+            p.drive_time += trip.duration;
+        }
+
+        for clip in self.telemetry_clips() {
+            if let Some(start) = clip.start() {
+                period_entry(&mut periods, group_by, start).clips += 1;
+            }
+        }
+
+        let mut seen_events: HashSet<&Path> = HashSet::new();
+        for clip in self.clips() {
+            let (Some(start), Some(dir)) = (clip.start(), clip.event_dir()) else {
+                continue;
+            };
+            if !seen_events.insert(dir) {
+                continue;
+            }
+            let p = period_entry(&mut periods, group_by, start);
+            match clip.source() {
+                ClipSource::Saved => p.saved_events += 1,
+                ClipSource::Sentry => p.sentry_events += 1,
+                ClipSource::Recent | ClipSource::Other => {}
+            }
+        }
+
+        Ok(periods.into_values().collect())
+    }
+}
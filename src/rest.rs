@@ -0,0 +1,273 @@
+//! HTTP API serving telemetry as NDJSON, for web viewers (`rest` feature).
+//!
+//! [`router`] builds an axum `Router` with:
+//!
+//! - `POST /extract`: a `multipart/form-data` upload of one clip, answered with its telemetry.
+//! - `GET /clips`: the ids of the clips in the clip folder ([`RestOptions::root`]), as a JSON
+//!   array. A clip's id is its file name without `.mp4`, e.g. `2024-05-01_12-00-00-front`.
+//! - `GET /clips/{id}/telemetry`: that clip's telemetry.
+//!
+//! Telemetry is `application/x-ndjson`, one `SeiMetadata` per line in decode order, as
+//! `tesla-sei --format ndjson` prints it. It is streamed while the clip is decoded; a clip that
+//! fails part-way through ends the response early. `tesla-sei serve` runs the router; [`serve`]
+//! does the same from Rust.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Cursor};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use axum::body::{Body, Bytes};
+use axum::extract::multipart::{Multipart, MultipartError};
+use axum::extract::{DefaultBodyLimit, Path as UrlPath, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+
+use crate::async_extract::{stream_from_path, stream_from_reader};
+use crate::extract::SeiEvent;
+use crate::Error;
+
+/// Options for [`router`].
+#[derive(Debug, Clone)]
+pub struct RestOptions {
+    /// Folder (searched recursively) that `/clips` serves. `None` leaves only `POST /extract`.
+    pub root: Option<PathBuf>,
+    /// Largest request body `POST /extract` accepts, in bytes.
+    pub max_upload: usize,
+    /// Events buffered per request, as for [`stream_from_path`].
+    pub buffer: usize,
+}
+
+impl Default for RestOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_upload: 256 * 1024 * 1024,
+            buffer: 64,
+        }
+    }
+}
+
+/// Build the API's routes.
+pub fn router(options: RestOptions) -> Router {
+    let max_upload = options.max_upload;
+    Router::new()
+        .route(
+            "/extract",
+            post(extract_upload).layer(DefaultBodyLimit::max(max_upload)),
+        )
+        .route("/clips", get(list_clips))
+        .route("/clips/{id}/telemetry", get(clip_telemetry))
+        .with_state(Arc::new(ApiState {
+            options,
+            clips: Mutex::default(),
+        }))
+}
+
+// Shared by every request.
+struct ApiState {
+    options: RestOptions,
+    // Clip ids in the clip folder and the files they name, as of the last scan. Listing the
+    // clips rescans the folder; looking one up rescans only when the id isn't known yet.
+    clips: Mutex<Arc<BTreeMap<String, PathBuf>>>,
+}
+
+/// Serve [`router`] on `addr` until the process ends.
+pub async fn serve(addr: SocketAddr, options: RestOptions) -> Result<(), Error> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(options)).await?;
+    Ok(())
+}
+
+// An error response: a status code and a plain-text message.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, self.1).into_response()
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let status = match &e {
+            Error::Io(io) => match io.kind() {
+                io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                // A clip cut short reads past its end.
+                io::ErrorKind::UnexpectedEof => StatusCode::UNPROCESSABLE_ENTITY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            Error::UnsupportedCodec { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        ApiError(status, e.to_string())
+    }
+}
+
+impl From<MultipartError> for ApiError {
+    fn from(e: MultipartError) -> Self {
+        ApiError(e.status(), e.body_text())
+    }
+}
+
+async fn extract_upload(
+    State(state): State<Arc<ApiState>>,
+    mut multipart: Multipart,
+) -> Result<Response, ApiError> {
+    // The clip is the first part; its field name doesn't matter.
+    let Some(field) = multipart.next_field().await? else {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "expected the clip as a multipart/form-data part".into(),
+        ));
+    };
+    let clip = field.bytes().await?;
+    ndjson(stream_from_reader(Cursor::new(clip), state.options.buffer)).await
+}
+
+async fn list_clips(State(state): State<Arc<ApiState>>) -> Result<Response, ApiError> {
+    let clips = scan_clips(&state).await?;
+    Ok(Json(clips.keys().collect::<Vec<_>>()).into_response())
+}
+
+// Only ids found by scanning the clip folder name a file, so no id reaches outside it.
+async fn clip_telemetry(
+    State(state): State<Arc<ApiState>>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Response, ApiError> {
+    let known = state.clips.lock().unwrap().get(&id).cloned();
+    let path = match known {
+        Some(path) => path,
+        None => match scan_clips(&state).await?.get(&id) {
+            Some(path) => path.clone(),
+            None => return Err(ApiError(StatusCode::NOT_FOUND, format!("no clip {id}"))),
+        },
+    };
+    ndjson(stream_from_path(path, state.options.buffer)).await
+}
+
+// Walk the clip folder and remember what's in it. Paths are sorted first, so an id that occurs
+// twice resolves to the same clip each time.
+async fn scan_clips(state: &ApiState) -> Result<Arc<BTreeMap<String, PathBuf>>, ApiError> {
+    let Some(root) = state.options.root.clone() else {
+        return Err(ApiError(
+            StatusCode::NOT_FOUND,
+            "this server has no clip folder; POST the clip to /extract instead".into(),
+        ));
+    };
+    let found = tokio::task::spawn_blocking(move || {
+        let mut paths = Vec::new();
+        collect_mp4s(&root, &mut paths)?;
+        paths.sort();
+        let mut clips = BTreeMap::new();
+        for path in paths {
+            if let Some(id) = clip_id(&path) {
+                clips.entry(id.to_string()).or_insert(path);
+            }
+        }
+        Ok(clips)
+    })
+    .await;
+    let clips = Arc::new(
+        found
+            .map_err(io::Error::other)
+            .and_then(|clips| clips)
+            .map_err(Error::from)?,
+    );
+    *state.clips.lock().unwrap() = clips.clone();
+    Ok(clips)
+}
+
+// Respond with the events as NDJSON. Errors up to the first event (the clip can't be opened or
+// has no usable index) become an error status instead of an empty 200.
+async fn ndjson(mut events: ReceiverStream<Result<SeiEvent, Error>>) -> Result<Response, ApiError> {
+    let first = events.next().await.transpose()?;
+    let lines = tokio_stream::iter(first.map(Ok))
+        .chain(events)
+        .map(|event| {
+            let mut line = serde_json::to_vec(&event?.metadata)?;
+            line.push(b'\n');
+            Ok::<_, Error>(Bytes::from(line))
+        });
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+// The clip's file name without `.mp4`.
+fn clip_id(path: &Path) -> Option<&str> {
+    path.file_stem()?.to_str()
+}
+
+// Symlinked folders aren't followed, so a link back up the tree can't loop.
+fn collect_mp4s(path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_mp4s(&path, out)?;
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_ids_stay_inside_root() {
+        let dir = std::env::temp_dir().join(format!("tesla-sei-rest-{}", std::process::id()));
+        let root = dir.join("clips");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("inside.mp4"), b"").unwrap();
+        fs::write(dir.join("outside.mp4"), b"").unwrap();
+        let state = Arc::new(ApiState {
+            options: RestOptions {
+                root: Some(root.clone()),
+                ..RestOptions::default()
+            },
+            clips: Mutex::default(),
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let status = |id: &str| {
+            let id = UrlPath(id.to_string());
+            match runtime.block_on(clip_telemetry(State(state.clone()), id)) {
+                Ok(response) => response.status(),
+                Err(ApiError(status, _)) => status,
+            }
+        };
+        for id in [
+            "../outside",
+            "..",
+            "/etc/passwd",
+            &dir.join("outside").display().to_string(),
+        ] {
+            assert_eq!(status(id), StatusCode::NOT_FOUND, "{id}");
+        }
+        // The empty file is found, and fails to decode.
+        assert_eq!(status("inside"), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            state.clips.lock().unwrap().keys().collect::<Vec<_>>(),
+            ["inside"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
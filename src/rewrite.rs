@@ -0,0 +1,1275 @@
+//! Rewrite the SEI carried in an MP4's video samples.
+//!
+//! [`inject_sei`] adds telemetry to a clip, e.g. to build test fixtures or to carry telemetry
+//! over to re-encoded footage. [`redact_gps`] hides where a clip was recorded so it can be shared,
+//! and [`strip_sei`] removes the telemetry altogether.
+//!
+//! A rewrite copies every box byte for byte except the edited samples, the headers of the boxes
+//! holding them, and the sample size (`stsz`/`stz2`) and chunk offset (`stco`/`co64`) tables,
+//! which are regenerated to match the new sample sizes. Media data keeps its layout, so other
+//! tracks' samples and bytes no track references come through as they were. Fragmented MP4s
+//! (`moof`) are not supported.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use prost::Message;
+
+use crate::extract::select_largest_track;
+use crate::geo::EARTH_RADIUS_M;
+use crate::info::BoxNode;
+use crate::mp4::{
+    build_sample_codec_indices, build_sample_offsets, location_user_data, parse_mp4, read_box_tree,
+    read_chunk_offsets, read_stbl, read_track_id, CodecConfig, TrackSampleTables,
+};
+use crate::pb;
+use crate::sei::{
+    build_sei_nal, is_vcl_nal, nal_units, protobuf_fields, sei_nal_header, sei_nal_messages,
+    telemetry_span,
+};
+use crate::Error;
+
+/// UUID written in front of injected telemetry: `0x42` bytes ending in `0x69`, the marker Tesla's
+/// own viewer scans for.
+pub const TESLA_SEI_UUID: [u8; 16] = [
+    0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x69,
+];
+
+/// What a rewrite changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewriteSummary {
+    /// Samples whose bytes changed.
+    pub samples_changed: usize,
+    /// SEI messages added.
+    pub sei_messages_added: usize,
+    /// SEI messages removed; a replaced message counts as one removed and one added.
+    pub sei_messages_removed: usize,
+    /// SEI messages whose payload was rewritten in place.
+    pub sei_messages_changed: usize,
+    /// Location entries removed from the movie's user data (`©xyz`, `loci` and the like).
+    pub user_data_removed: usize,
+}
+
+/// Options for [`inject_sei`].
+#[derive(Debug, Clone)]
+pub struct InjectOptions {
+    /// Remove the telemetry already in a sample before adding the new messages. On by default;
+    /// when off, the new messages are added alongside the old ones.
+    pub replace_existing: bool,
+    /// UUID in front of each injected payload.
+    pub uuid: [u8; 16],
+}
+
+impl Default for InjectOptions {
+    fn default() -> Self {
+        Self {
+            replace_existing: true,
+            uuid: TESLA_SEI_UUID,
+        }
+    }
+}
+
+/// Write a copy of the MP4 at `input` to `output` with telemetry injected; see
+/// [`inject_sei_reader`].
+pub fn inject_sei(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    metadata: impl IntoIterator<Item = (usize, pb::SeiMetadata)>,
+    opts: &InjectOptions,
+) -> Result<RewriteSummary, Error> {
+    with_files(input.as_ref(), output.as_ref(), |reader, writer| {
+        inject_sei_reader(reader, writer, metadata, opts)
+    })
+}
+
+/// Copy an MP4 from `reader` to `writer`, adding a `user_data_unregistered` SEI message for each
+/// `(sample_index, metadata)` pair.
+///
+/// Sample indices are in decode order in the track the extractor selects, like
+/// [`SeiEvent::sample_index`](crate::SeiEvent::sample_index); a sample may get several messages.
+/// A sample's new messages go into one SEI NAL unit in front of its first slice.
+pub fn inject_sei_reader<R: Read + Seek, W: Write>(
+    reader: R,
+    writer: W,
+    metadata: impl IntoIterator<Item = (usize, pb::SeiMetadata)>,
+    opts: &InjectOptions,
+) -> Result<RewriteSummary, Error> {
+    let mut rewriter = Rewriter::open(reader)?;
+    let (total_samples, codec) = rewriter.selected_track().ok_or(Error::NoTracksFound)?;
+    if sei_nal_header(codec).is_none() {
+        return Err(unsupported_codec(codec));
+    }
+
+    let mut per_sample: HashMap<usize, Vec<pb::SeiMetadata>> = HashMap::new();
+    for (sample_index, m) in metadata {
+        if sample_index >= total_samples {
+            return Err(Error::SampleIndexOutOfRange {
+                sample_index,
+                total_samples,
+            });
+        }
+        per_sample.entry(sample_index).or_default().push(m);
+    }
+
+    let mut summary = RewriteSummary::default();
+    let wanted = |selected: bool, index: usize| selected && per_sample.contains_key(&index);
+    rewriter.edit_where(&mut summary, wanted, |sample, summary| {
+        let Some(messages) = per_sample.get(&sample.index) else {
+            return Ok(Vec::new());
+        };
+        let header = sei_nal_header(sample.codec).ok_or_else(|| unsupported_codec(sample.codec))?;
+
+        let mut splices = if opts.replace_existing {
+            edit_sei_messages(sample, summary, |payload_type, payload| {
+                !is_telemetry(payload_type, payload)
+            })?
+        } else {
+            Vec::new()
+        };
+
+        let payloads: Vec<Vec<u8>> = messages
+            .iter()
+            .map(|m| [opts.uuid.as_slice(), &m.encode_to_vec()].concat())
+            .collect();
+        let sei: Vec<(u32, &[u8])> = payloads.iter().map(|p| (5, p.as_slice())).collect();
+        let nal = build_sei_nal(header, &sei);
+        summary.sei_messages_added += sei.len();
+
+        // In front of the first slice, after any access unit delimiter and parameter sets.
+        let at = nal_units(sample.data, sample.nal_len_size)
+            .into_iter()
+            .find(|(_, nal)| is_vcl_nal(sample.codec, nal))
+            .map_or(sample.data.len(), |(prefix, _)| prefix);
+        splices.push(Splice {
+            range: at..at,
+            data: length_prefixed(&nal, sample.nal_len_size)?,
+        });
+        Ok(splices)
+    })?;
+    rewriter.write(writer)?;
+    Ok(summary)
+}
+
+/// How [`redact_gps`] hides positions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GpsRedaction {
+    /// Set latitude, longitude and heading to 0, which readers take as "no GPS fix".
+    Zero,
+    /// Keep the route's shape but move it: every fix in the clip is shifted by the same random
+    /// offset, between `radius_m / 2` and `radius_m` meters in a random direction, and each
+    /// heading gets up to `heading_deg` degrees of noise either way.
+    ///
+    /// The offset is shared so averaging many fixes (e.g. while parked) can't undo it.
+    Jitter { radius_m: f64, heading_deg: f64 },
+}
+
+/// Options for [`redact_gps`].
+#[derive(Debug, Clone)]
+pub struct RedactOptions {
+    pub redaction: GpsRedaction,
+    /// Seed for [`GpsRedaction::Jitter`], for reproducible output. Random by default.
+    pub seed: Option<u64>,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            redaction: GpsRedaction::Zero,
+            seed: None,
+        }
+    }
+}
+
+/// Write a copy of the MP4 at `input` to `output` with GPS positions redacted; see
+/// [`redact_gps_reader`].
+pub fn redact_gps(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    opts: &RedactOptions,
+) -> Result<RewriteSummary, Error> {
+    with_files(input.as_ref(), output.as_ref(), |reader, writer| {
+        redact_gps_reader(reader, writer, opts)
+    })
+}
+
+/// Copy an MP4 from `reader` to `writer`, redacting `latitude_deg`, `longitude_deg` and
+/// `heading_deg` in every telemetry message of every video track.
+///
+/// The fields are overwritten inside the encoded payloads, so everything else in them, fields
+/// this crate doesn't know included, stays as it was; the coded video is untouched. Messages
+/// without a GPS fix are left alone when jittering. Location entries in the movie's user data
+/// (`udta` `©xyz` or `loci`, and the `meta` ISO 6709 location) are removed either way.
+pub fn redact_gps_reader<R: Read + Seek, W: Write>(
+    reader: R,
+    writer: W,
+    opts: &RedactOptions,
+) -> Result<RewriteSummary, Error> {
+    let mut rewriter = Rewriter::open(reader)?;
+    let mut rng = SplitMix64(opts.seed.unwrap_or_else(random_seed));
+    let redaction = match opts.redaction {
+        GpsRedaction::Zero => Redaction::Zero,
+        GpsRedaction::Jitter {
+            radius_m,
+            heading_deg,
+        } => {
+            let distance = radius_m * (0.25 + 0.75 * rng.next_f64()).sqrt();
+            let bearing = std::f64::consts::TAU * rng.next_f64();
+            Redaction::Jitter {
+                north_m: distance * bearing.cos(),
+                east_m: distance * bearing.sin(),
+                heading_deg,
+            }
+        }
+    };
+
+    let mut summary = RewriteSummary {
+        user_data_removed: rewriter.drop_location_user_data()?,
+        ..Default::default()
+    };
+    rewriter.edit(&mut summary, |sample, summary| {
+        edit_sei_messages(sample, summary, |payload_type, payload| {
+            if let Some(span) = telemetry_span(payload_type, payload) {
+                redact_metadata(&mut payload[span], &redaction, &mut rng);
+            }
+            true
+        })
+    })?;
+    rewriter.write(writer)?;
+    Ok(summary)
+}
+
+enum Redaction {
+    Zero,
+    Jitter {
+        north_m: f64,
+        east_m: f64,
+        heading_deg: f64,
+    },
+}
+
+// latitude_deg, longitude_deg and heading_deg in proto/dashcam.proto; all doubles (wire type 1,
+// fixed64).
+const POSITION_FIELDS: [u32; 3] = [11, 12, 13];
+
+// Overwrite the position fields of an encoded SeiMetadata in place. Fields the encoder left out
+// (i.e. 0) stay out.
+fn redact_metadata(msg: &mut [u8], redaction: &Redaction, rng: &mut SplitMix64) {
+    let mut fields: [Option<Range<usize>>; 3] = Default::default();
+    for (number, wire_type, data) in protobuf_fields(msg) {
+        if let Some(i) = POSITION_FIELDS.iter().position(|&n| n == number)
+            && wire_type == 1
+        {
+            fields[i] = Some(data);
+        }
+    }
+    let read = |msg: &[u8], field: &Option<Range<usize>>| {
+        field.clone().map_or(0.0, |r| {
+            f64::from_le_bytes(msg[r].try_into().expect("fixed64 is 8 bytes"))
+        })
+    };
+    let [lat, lon, heading] = [0, 1, 2].map(|i| read(msg, &fields[i]));
+
+    let values = match *redaction {
+        Redaction::Zero => [0.0; 3],
+        Redaction::Jitter {
+            north_m,
+            east_m,
+            heading_deg,
+        } => {
+            // No fix to move.
+            if lat == 0.0 && lon == 0.0 {
+                return;
+            }
+            let new_lat = (lat + (north_m / EARTH_RADIUS_M).to_degrees()).clamp(-90.0, 90.0);
+            let meters_per_lon_deg = EARTH_RADIUS_M * lat.to_radians().cos();
+            let new_lon = if meters_per_lon_deg > 1.0 {
+                let lon = lon + (east_m / meters_per_lon_deg).to_degrees();
+                (lon + 540.0).rem_euclid(360.0) - 180.0
+            } else {
+                lon
+            };
+            let noise = heading_deg * (2.0 * rng.next_f64() - 1.0);
+            [new_lat, new_lon, (heading + noise).rem_euclid(360.0)]
+        }
+    };
+    for (field, value) in fields.iter().zip(values) {
+        if let Some(r) = field {
+            msg[r.clone()].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+// Small, fast PRNG (SplitMix64); the jitter doesn't need cryptographic strength, only a seed
+// nobody knows.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// A seed from std's per-process random hash keys.
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+/// Options for [`strip_sei`].
+#[derive(Debug, Clone, Default)]
+pub struct StripOptions {
+    /// Remove only the messages carrying Tesla telemetry, keeping other SEI (e.g. `pic_timing`
+    /// or an encoder's own user data). By default every SEI NAL unit is removed.
+    pub telemetry_only: bool,
+}
+
+/// Write a copy of the MP4 at `input` to `output` without SEI; see [`strip_sei_reader`].
+pub fn strip_sei(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    opts: &StripOptions,
+) -> Result<RewriteSummary, Error> {
+    with_files(input.as_ref(), output.as_ref(), |reader, writer| {
+        strip_sei_reader(reader, writer, opts)
+    })
+}
+
+/// Copy an MP4 from `reader` to `writer`, removing SEI from every H.264/H.265 track.
+///
+/// SEI is optional for decoding, so the video plays as before. AV1 metadata OBUs are left in
+/// place.
+pub fn strip_sei_reader<R: Read + Seek, W: Write>(
+    reader: R,
+    writer: W,
+    opts: &StripOptions,
+) -> Result<RewriteSummary, Error> {
+    let mut rewriter = Rewriter::open(reader)?;
+    let mut summary = RewriteSummary::default();
+    rewriter.edit(&mut summary, |sample, summary| {
+        if opts.telemetry_only {
+            return edit_sei_messages(sample, summary, |payload_type, payload| {
+                !is_telemetry(payload_type, payload)
+            });
+        }
+        // Whole NAL units, so SEI too malformed to split into messages goes as well.
+        let mut splices = Vec::new();
+        for (prefix, nal) in nal_units(sample.data, sample.nal_len_size) {
+            if let Some((_, messages)) = sei_nal_messages(sample.codec, nal) {
+                summary.sei_messages_removed += messages.len();
+                splices.push(Splice {
+                    range: prefix..prefix + sample.nal_len_size + nal.len(),
+                    data: Vec::new(),
+                });
+            }
+        }
+        Ok(splices)
+    })?;
+    rewriter.write(writer)?;
+    Ok(summary)
+}
+
+// Open `input`, create `output`, and run a rewrite between them.
+fn with_files<T>(
+    input: &Path,
+    output: &Path,
+    run: impl FnOnce(BufReader<File>, &mut BufWriter<File>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let reader = BufReader::new(File::open(input)?);
+    // Creating the output truncates it, which would destroy the input if they're the same file.
+    if output.exists() && input.canonicalize()? == output.canonicalize()? {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "input and output are the same file",
+        )
+        .into());
+    }
+    let mut writer = BufWriter::new(File::create(output)?);
+    let result = run(reader, &mut writer)?;
+    writer.flush()?;
+    Ok(result)
+}
+
+fn unsupported_codec(codec: &CodecConfig) -> Error {
+    let codec = match codec {
+        CodecConfig::Av1 => "AV1 (no SEI NAL units)",
+        _ => "unknown",
+    };
+    Error::UnsupportedCodec {
+        codec: codec.to_string(),
+    }
+}
+
+// A user_data_unregistered payload the decoder would read telemetry from.
+fn is_telemetry(payload_type: u32, payload: &[u8]) -> bool {
+    payload_type == 5 && telemetry_span(payload_type, payload).is_some()
+}
+
+// Replace `range` of a sample with `data`. A sample's splices are sorted and don't overlap.
+struct Splice {
+    range: Range<usize>,
+    data: Vec<u8>,
+}
+
+// A video sample handed to a rewrite's edit function.
+struct SampleRef<'a> {
+    // Decode-order index in its track.
+    index: usize,
+    codec: &'a CodecConfig,
+    nal_len_size: usize,
+    data: &'a [u8],
+}
+
+// Splices applying `edit` to every SEI message of a sample: it may rewrite the payload in
+// place, and returns whether to keep the message. NAL units left empty are removed.
+fn edit_sei_messages(
+    sample: &SampleRef,
+    summary: &mut RewriteSummary,
+    mut edit: impl FnMut(u32, &mut Vec<u8>) -> bool,
+) -> Result<Vec<Splice>, Error> {
+    let mut splices = Vec::new();
+    for (prefix, nal) in nal_units(sample.data, sample.nal_len_size) {
+        let Some((header_len, messages)) = sei_nal_messages(sample.codec, nal) else {
+            continue;
+        };
+        let mut kept = Vec::with_capacity(messages.len());
+        let mut changed = 0;
+        for (payload_type, original) in &messages {
+            let mut payload = original.clone();
+            if !edit(*payload_type, &mut payload) {
+                continue;
+            }
+            changed += usize::from(payload != *original);
+            kept.push((*payload_type, payload));
+        }
+        let removed = messages.len() - kept.len();
+        if removed == 0 && changed == 0 {
+            continue;
+        }
+        summary.sei_messages_removed += removed;
+        summary.sei_messages_changed += changed;
+
+        let end = prefix + sample.nal_len_size + nal.len();
+        let data = if kept.is_empty() {
+            Vec::new()
+        } else {
+            let kept: Vec<(u32, &[u8])> = kept.iter().map(|(t, p)| (*t, p.as_slice())).collect();
+            length_prefixed(
+                &build_sei_nal(&nal[..header_len], &kept),
+                sample.nal_len_size,
+            )?
+        };
+        splices.push(Splice {
+            range: prefix..end,
+            data,
+        });
+    }
+    Ok(splices)
+}
+
+fn length_prefixed(nal: &[u8], nal_len_size: usize) -> Result<Vec<u8>, Error> {
+    let len = nal.len() as u64;
+    if nal_len_size == 0 || nal_len_size > 4 || len >> (8 * nal_len_size) != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("NAL unit of {len} bytes doesn't fit a {nal_len_size}-byte length prefix"),
+        )
+        .into());
+    }
+    let mut out = len.to_be_bytes()[8 - nal_len_size..].to_vec();
+    out.extend_from_slice(nal);
+    Ok(out)
+}
+
+// A `trak` with sample tables, as laid out in the input.
+struct Trak {
+    track_id: u32,
+    tables: TrackSampleTables,
+    sample_offsets: Vec<u64>,
+    // Offset of the stsz/stz2 box to regenerate.
+    sizes_box: u64,
+    // Per sample; empty until a sample is edited.
+    splices: Vec<Vec<Splice>>,
+    new_sizes: Vec<u32>,
+}
+
+// An `stco`/`co64` box in `moov`, of any track, with the chunk offsets it holds.
+struct ChunkOffsets {
+    node: BoxNode,
+    offsets: Vec<u64>,
+}
+
+// An edited sample, and how much the box holding it grew (or shrank) through the edited samples
+// up to and including this one.
+struct Edit {
+    offset: u64,
+    trak: usize,
+    sample: usize,
+    shift: i64,
+}
+
+// Where a top-level box goes in the output.
+struct Placement {
+    offset: u64,
+    header_len: u64,
+    size: u64,
+}
+
+// Parses an MP4 once, collects per-sample edits, then writes the rewritten file.
+struct Rewriter<R> {
+    reader: R,
+    tree: Vec<BoxNode>,
+    traks: Vec<Trak>,
+    chunk_offsets: Vec<ChunkOffsets>,
+    // Offsets of boxes in `moov` to leave out.
+    dropped: Vec<u64>,
+    // Index into `traks` of the track the extractor selects.
+    selected: Option<usize>,
+}
+
+fn child<'a>(node: &'a BoxNode, box_type: &str) -> Option<&'a BoxNode> {
+    node.children.iter().find(|c| c.box_type == box_type)
+}
+
+// The descendants of `node` of the given types, in file order.
+fn collect_boxes<'a>(node: &'a BoxNode, box_types: &[&str], out: &mut Vec<&'a BoxNode>) {
+    for c in &node.children {
+        if box_types.contains(&c.box_type.as_str()) {
+            out.push(c);
+        }
+        collect_boxes(c, box_types, out);
+    }
+}
+
+fn invalid_box(node: &BoxNode, message: impl Into<String>) -> Error {
+    Error::Mp4InvalidBox {
+        context: "rewrite".to_string(),
+        box_type: node.box_type.clone(),
+        offset: node.offset,
+        message: message.into(),
+    }
+}
+
+// Fail on a malformed box: rewriting it could corrupt the output.
+fn check_box(node: &BoxNode) -> Result<(), Error> {
+    match &node.error {
+        Some(e) => Err(invalid_box(node, e.clone())),
+        None => Ok(()),
+    }
+}
+
+impl<R: Read + Seek> Rewriter<R> {
+    fn open(mut reader: R) -> Result<Self, Error> {
+        let tree = read_box_tree(&mut reader)?;
+        for node in &tree {
+            check_box(node)?;
+            if node.box_type == "moof" {
+                return Err(invalid_box(node, "fragmented MP4 is not supported"));
+            }
+        }
+        let moov = tree
+            .iter()
+            .find(|n| n.box_type == "moov")
+            .ok_or(Error::NoTracksFound)?;
+
+        let mut traks = Vec::new();
+        for trak in moov.children.iter().filter(|n| n.box_type == "trak") {
+            let Some(mdia) = child(trak, "mdia") else {
+                continue;
+            };
+            let Some(minf) = child(mdia, "minf") else {
+                continue;
+            };
+            let (Some(stbl), Some(tkhd)) = (child(minf, "stbl"), child(trak, "tkhd")) else {
+                continue;
+            };
+            for node in [trak, mdia, minf, stbl].into_iter().chain(&stbl.children) {
+                check_box(node)?;
+            }
+            let find = |types: [&str; 2]| {
+                stbl.children
+                    .iter()
+                    .find(|c| types.contains(&c.box_type.as_str()))
+                    .map(|c| c.offset)
+            };
+            let Some(sizes_box) = find(["stsz", "stz2"]) else {
+                continue;
+            };
+
+            let tables = read_stbl(&mut reader, stbl)?;
+            let sample_offsets = build_sample_offsets(&tables)?;
+            let new_sizes = tables.sample_sizes.clone();
+            traks.push(Trak {
+                track_id: read_track_id(&mut reader, tkhd)?,
+                tables,
+                sample_offsets,
+                sizes_box,
+                splices: Vec::new(),
+                new_sizes,
+            });
+        }
+
+        // Every track's, including those left out above, since edits move their chunks too.
+        let mut offset_nodes = Vec::new();
+        collect_boxes(moov, &["stco", "co64"], &mut offset_nodes);
+        let mut chunk_offsets = Vec::new();
+        for node in offset_nodes {
+            check_box(node)?;
+            chunk_offsets.push(ChunkOffsets {
+                node: node.clone(),
+                offsets: read_chunk_offsets(&mut reader, node)?,
+            });
+        }
+
+        let mp4 = parse_mp4(&mut reader)?;
+        let selected_id = select_largest_track(&mp4.tracks)
+            .and_then(|(_, t)| t.track_header.as_ref())
+            .map(|h| h.track_id);
+        let selected = selected_id.and_then(|id| traks.iter().position(|t| t.track_id == id));
+
+        Ok(Rewriter {
+            reader,
+            tree,
+            traks,
+            chunk_offsets,
+            dropped: Vec::new(),
+            selected,
+        })
+    }
+
+    // Leave the location entries of the movie's user data out of the output; returns how many
+    // there were.
+    fn drop_location_user_data(&mut self) -> Result<usize, Error> {
+        let moov = self
+            .tree
+            .iter()
+            .find(|n| n.box_type == "moov")
+            .expect("checked in open");
+        let mut found = Vec::new();
+        location_user_data(&mut self.reader, moov, &[], &mut found)?;
+        self.dropped.extend(found.iter().map(|n| n.offset));
+        Ok(found.len())
+    }
+
+    // Sample count and first sample entry codec of the selected track.
+    fn selected_track(&self) -> Option<(usize, &CodecConfig)> {
+        let trak = &self.traks[self.selected?];
+        let codec = trak.tables.codecs.first().unwrap_or(&CodecConfig::Unknown);
+        Some((trak.sample_offsets.len(), codec))
+    }
+
+    // Run `edit` over every H.264/H.265 sample of every track.
+    fn edit(
+        &mut self,
+        summary: &mut RewriteSummary,
+        edit: impl FnMut(&SampleRef, &mut RewriteSummary) -> Result<Vec<Splice>, Error>,
+    ) -> Result<(), Error> {
+        self.edit_where(summary, |_, _| true, edit)
+    }
+
+    // `edit`, over only the samples `wanted` asks for given whether their track is the selected
+    // one and their index. The others aren't read.
+    fn edit_where(
+        &mut self,
+        summary: &mut RewriteSummary,
+        wanted: impl Fn(bool, usize) -> bool,
+        mut edit: impl FnMut(&SampleRef, &mut RewriteSummary) -> Result<Vec<Splice>, Error>,
+    ) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        for (trak_index, trak) in self.traks.iter_mut().enumerate() {
+            let codec_indices = build_sample_codec_indices(&trak.tables);
+            trak.splices = (0..trak.sample_offsets.len()).map(|_| Vec::new()).collect();
+
+            for index in 0..trak.sample_offsets.len() {
+                let entry = codec_indices.get(index).copied().unwrap_or(0);
+                let codec = trak
+                    .tables
+                    .codecs
+                    .get(entry)
+                    .unwrap_or(&CodecConfig::Unknown);
+                let nal_len_size = match codec {
+                    CodecConfig::Avc { nal_len_size } | CodecConfig::Hevc { nal_len_size } => {
+                        *nal_len_size
+                    }
+                    _ => continue,
+                };
+                if !wanted(self.selected == Some(trak_index), index) {
+                    continue;
+                }
+
+                buf.resize(trak.tables.sample_sizes[index] as usize, 0);
+                self.reader
+                    .seek(SeekFrom::Start(trak.sample_offsets[index]))?;
+                self.reader.read_exact(&mut buf)?;
+                let sample = SampleRef {
+                    index,
+                    codec,
+                    nal_len_size,
+                    data: &buf,
+                };
+                let mut splices = edit(&sample, summary)?;
+                if splices.is_empty() {
+                    continue;
+                }
+                splices.sort_by_key(|s| (s.range.start, s.range.end));
+
+                let removed: usize = splices.iter().map(|s| s.range.len()).sum();
+                let added: usize = splices.iter().map(|s| s.data.len()).sum();
+                let new_size = (buf.len() - removed + added) as u64;
+                trak.new_sizes[index] = u32::try_from(new_size).map_err(|_| {
+                    Error::Io(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "rewritten sample exceeds 4 GiB",
+                    ))
+                })?;
+                trak.splices[index] = splices;
+                summary.samples_changed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn write<W: Write>(mut self, mut out: W) -> Result<(), Error> {
+        let edits = self.edits_by_box()?;
+
+        // 32-bit chunk offsets unless the new layout needs more.
+        let mut use_co64 = false;
+        let (moov, placements) = loop {
+            let moov_len = self.build_moov(&[], use_co64)?.len() as u64;
+            let placements = self.place(moov_len, &edits);
+            let offsets = self.map_chunk_offsets(&placements, &edits)?;
+            let max = offsets.iter().flatten().copied().max().unwrap_or(0);
+            if !use_co64 && max > u32::MAX as u64 {
+                use_co64 = true;
+                continue;
+            }
+            break (self.build_moov(&offsets, use_co64)?, placements);
+        };
+
+        let tree = std::mem::take(&mut self.tree);
+        for ((node, edits), placed) in tree.iter().zip(&edits).zip(&placements) {
+            if node.box_type == "moov" {
+                out.write_all(&moov)?;
+                continue;
+            }
+            if edits.is_empty() {
+                self.copy_range(&mut out, node.offset, node.size)?;
+                continue;
+            }
+            let box_type = node.box_type.as_bytes().try_into().expect("fourcc");
+            write_box_header(
+                &mut out,
+                box_type,
+                placed.size - placed.header_len,
+                placed.header_len,
+            )?;
+            let mut pos = node.offset + node.header_len;
+            for e in edits {
+                self.copy_range(&mut out, pos, e.offset - pos)?;
+                self.write_sample(&mut out, e.trak, e.sample)?;
+                pos = e.offset + self.traks[e.trak].tables.sample_sizes[e.sample] as u64;
+            }
+            self.copy_range(&mut out, pos, node.offset + node.size - pos)?;
+        }
+        Ok(())
+    }
+
+    // The edited samples of each top-level box, in file order.
+    fn edits_by_box(&self) -> Result<Vec<Vec<Edit>>, Error> {
+        let mut samples: Vec<(u64, usize, usize)> = Vec::new();
+        for (t, trak) in self.traks.iter().enumerate() {
+            for (i, splices) in trak.splices.iter().enumerate() {
+                if !splices.is_empty() {
+                    samples.push((trak.sample_offsets[i], t, i));
+                }
+            }
+        }
+        samples.sort_unstable();
+
+        let mut edits: Vec<Vec<Edit>> = self.tree.iter().map(|_| Vec::new()).collect();
+        let mut end = 0;
+        for (offset, t, i) in samples {
+            let (old_size, new_size) = (
+                self.traks[t].tables.sample_sizes[i],
+                self.traks[t].new_sizes[i],
+            );
+            let holder = self.tree.iter().position(|n| {
+                n.box_type != "moov"
+                    && offset >= n.offset + n.header_len
+                    && offset + old_size as u64 <= n.offset + n.size
+            });
+            let Some(b) = holder.filter(|_| offset >= end) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "sample at offset {offset} overlaps another or lies outside the media data"
+                    ),
+                )
+                .into());
+            };
+            end = offset + old_size as u64;
+            let before = edits[b].last().map_or(0, |e| e.shift);
+            edits[b].push(Edit {
+                offset,
+                trak: t,
+                sample: i,
+                shift: before + i64::from(new_size) - i64::from(old_size),
+            });
+        }
+        Ok(edits)
+    }
+
+    // Where each top-level box goes once `moov` is `moov_len` bytes.
+    fn place(&self, moov_len: u64, edits: &[Vec<Edit>]) -> Vec<Placement> {
+        let mut pos = 0;
+        self.tree
+            .iter()
+            .zip(edits)
+            .map(|(node, edits)| {
+                let (header_len, size) = match edits.last() {
+                    _ if node.box_type == "moov" => (node.header_len, moov_len),
+                    None => (node.header_len, node.size),
+                    Some(last) => {
+                        let payload =
+                            (node.size - node.header_len).saturating_add_signed(last.shift);
+                        let header_len = if payload + 8 > u32::MAX as u64 {
+                            16
+                        } else {
+                            node.header_len
+                        };
+                        (header_len, header_len + payload)
+                    }
+                };
+                let placed = Placement {
+                    offset: pos,
+                    header_len,
+                    size,
+                };
+                pos += size;
+                placed
+            })
+            .collect()
+    }
+
+    // Every `stco`/`co64` table with its offsets moved to where `placements` puts the chunks.
+    fn map_chunk_offsets(
+        &self,
+        placements: &[Placement],
+        edits: &[Vec<Edit>],
+    ) -> Result<Vec<Vec<u64>>, Error> {
+        let map = |table: &ChunkOffsets, offset: u64| {
+            // An empty chunk may sit at the very end of the payload.
+            let holder = self.tree.iter().position(|n| {
+                n.box_type != "moov"
+                    && offset >= n.offset + n.header_len
+                    && offset <= n.offset + n.size
+            });
+            let Some(b) = holder else {
+                return Err(invalid_box(
+                    &table.node,
+                    format!("chunk offset {offset} lies outside the media data"),
+                ));
+            };
+            let node = &self.tree[b];
+            let before = edits[b].partition_point(|e| e.offset < offset);
+            let shift = before.checked_sub(1).map_or(0, |k| edits[b][k].shift);
+            let in_payload = (offset - node.offset - node.header_len).saturating_add_signed(shift);
+            Ok(placements[b].offset + placements[b].header_len + in_payload)
+        };
+        self.chunk_offsets
+            .iter()
+            .map(|table| table.offsets.iter().map(|&o| map(table, o)).collect())
+            .collect()
+    }
+
+    fn write_sample<W: Write>(&mut self, out: &mut W, t: usize, i: usize) -> Result<(), Error> {
+        let trak = &self.traks[t];
+        let (offset, size) = (trak.sample_offsets[i], trak.tables.sample_sizes[i] as u64);
+        let splices = trak.splices.get(i).filter(|s| !s.is_empty());
+        let Some(splices) = splices else {
+            return self.copy_range(out, offset, size);
+        };
+
+        let mut data = vec![0u8; size as usize];
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.reader.read_exact(&mut data)?;
+        let mut pos = 0usize;
+        for s in splices {
+            out.write_all(&data[pos..s.range.start])?;
+            out.write_all(&s.data)?;
+            pos = s.range.end;
+        }
+        out.write_all(&data[pos..])?;
+        Ok(())
+    }
+
+    fn copy_range<W: Write>(&mut self, out: &mut W, offset: u64, len: u64) -> Result<(), Error> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let copied = io::copy(&mut (&mut self.reader).take(len), out)?;
+        if copied != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(())
+    }
+
+    // The moov box with regenerated sample size and chunk offset tables, the latter in the order
+    // of `self.chunk_offsets`. `chunk_offsets` may be empty when only the size matters.
+    fn build_moov(&mut self, chunk_offsets: &[Vec<u64>], use_co64: bool) -> Result<Vec<u8>, Error> {
+        let mut replacements: HashMap<u64, Vec<u8>> = HashMap::new();
+        for trak in &self.traks {
+            if trak.new_sizes != trak.tables.sample_sizes {
+                replacements.insert(trak.sizes_box, stsz_box(&trak.new_sizes));
+            }
+        }
+        for &offset in &self.dropped {
+            replacements.insert(offset, Vec::new());
+        }
+        for (t, table) in self.chunk_offsets.iter().enumerate() {
+            let offsets = match chunk_offsets.get(t) {
+                Some(offsets) => offsets.clone(),
+                None => vec![0; table.offsets.len()],
+            };
+            replacements.insert(table.node.offset, chunk_offset_box(&offsets, use_co64));
+        }
+        let moov = self
+            .tree
+            .iter()
+            .find(|n| n.box_type == "moov")
+            .cloned()
+            .expect("checked in open");
+        self.rebuild(&moov, &replacements)
+    }
+
+    // A box with some descendants replaced; subtrees without replacements are copied as is.
+    fn rebuild(
+        &mut self,
+        node: &BoxNode,
+        replacements: &HashMap<u64, Vec<u8>>,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(bytes) = replacements.get(&node.offset) {
+            return Ok(bytes.clone());
+        }
+        let end = node.offset + node.size;
+        let touched = replacements.keys().any(|&o| o > node.offset && o < end);
+        if !touched || node.children.is_empty() {
+            let mut raw = Vec::with_capacity(node.size as usize);
+            self.copy_range(&mut raw, node.offset, node.size)?;
+            return Ok(raw);
+        }
+
+        let payload_start = node.offset + node.header_len;
+        let first = node.children[0].offset;
+        let last_end = node.children.last().map_or(first, |c| c.offset + c.size);
+        let mut payload = Vec::new();
+        self.copy_range(&mut payload, payload_start, first - payload_start)?;
+        for c in &node.children {
+            payload.extend(self.rebuild(c, replacements)?);
+        }
+        self.copy_range(&mut payload, last_end, end - last_end)?;
+
+        let mut out = Vec::with_capacity(payload.len() + 16);
+        let box_type = node.box_type.as_bytes().try_into().expect("fourcc");
+        write_box_header(&mut out, box_type, payload.len() as u64, node.header_len)?;
+        out.extend(payload);
+        Ok(out)
+    }
+}
+
+// A box header for `payload_len` bytes; `header_len` 16 selects the 64-bit size form.
+fn write_box_header<W: Write>(
+    out: &mut W,
+    box_type: &[u8; 4],
+    payload_len: u64,
+    header_len: u64,
+) -> io::Result<()> {
+    if header_len == 16 || payload_len + 8 > u32::MAX as u64 {
+        out.write_all(&1u32.to_be_bytes())?;
+        out.write_all(box_type)?;
+        out.write_all(&(payload_len + 16).to_be_bytes())
+    } else {
+        out.write_all(&((payload_len + 8) as u32).to_be_bytes())?;
+        out.write_all(box_type)
+    }
+}
+
+fn full_box(box_type: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 12);
+    out.extend_from_slice(&((body.len() + 12) as u32).to_be_bytes());
+    out.extend_from_slice(box_type);
+    // version 0, flags 0
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(body);
+    out
+}
+
+fn stsz_box(sizes: &[u32]) -> Vec<u8> {
+    // sample_size 0: every sample has its own entry.
+    let mut body = vec![0; 4];
+    body.extend_from_slice(&(sizes.len() as u32).to_be_bytes());
+    for s in sizes {
+        body.extend_from_slice(&s.to_be_bytes());
+    }
+    full_box(b"stsz", &body)
+}
+
+fn chunk_offset_box(offsets: &[u64], use_co64: bool) -> Vec<u8> {
+    let mut body = (offsets.len() as u32).to_be_bytes().to_vec();
+    for &o in offsets {
+        if use_co64 {
+            body.extend_from_slice(&o.to_be_bytes());
+        } else {
+            body.extend_from_slice(&(o as u32).to_be_bytes());
+        }
+    }
+    full_box(if use_co64 { b"co64" } else { b"stco" }, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::extract::extractor_from_reader;
+
+    fn mp4_box(typ: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut b = ((8 + payload.len()) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(typ);
+        b.extend_from_slice(payload);
+        b
+    }
+
+    fn fields(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn trak(
+        tkhd: bool,
+        handler: &[u8; 4],
+        entry: &[u8; 4],
+        sizes: &[u32],
+        chunks: &[u32],
+    ) -> Vec<u8> {
+        let mut hdlr = vec![0u8; 8];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0u8; 13]);
+        let mut stsd = fields(&[0, 1]);
+        stsd.extend(mp4_box(entry, &[0u8; 78]));
+        let stbl = [
+            mp4_box(b"stsd", &stsd),
+            mp4_box(
+                b"stsz",
+                &fields(&[&[0, 0, sizes.len() as u32], sizes].concat()),
+            ),
+            mp4_box(
+                b"stco",
+                &fields(&[&[0, chunks.len() as u32], chunks].concat()),
+            ),
+            // Two samples per chunk.
+            mp4_box(b"stsc", &fields(&[0, 1, 1, 2, 1])),
+        ]
+        .concat();
+        let minf = mp4_box(b"minf", &mp4_box(b"stbl", &stbl));
+        let mdia = mp4_box(b"mdia", &[mp4_box(b"hdlr", &hdlr), minf].concat());
+        let mut children = Vec::new();
+        if tkhd {
+            // Version 0, track ID 1, 1280x960.
+            let mut header = fields(&[0, 0, 0, 1, 0, 0]);
+            header.extend_from_slice(&[0u8; 52]);
+            header.extend(fields(&[1280 << 16, 960 << 16]));
+            children.extend(mp4_box(b"tkhd", &header));
+        }
+        children.extend(mdia);
+        mp4_box(b"trak", &children)
+    }
+
+    fn metadata(seq: u64) -> pb::SeiMetadata {
+        pb::SeiMetadata {
+            frame_seq_no: seq,
+            vehicle_speed_mps: 12.5,
+            latitude_deg: 37.7749,
+            longitude_deg: -122.4194,
+            heading_deg: 90.0,
+            ..Default::default()
+        }
+    }
+
+    fn slice(k: u8) -> Vec<u8> {
+        length_prefixed(&[0x65, 0x88, k, 0xAA, 0xBB], 4).unwrap()
+    }
+
+    fn text_item(key: u32, text: &str) -> Vec<u8> {
+        let data = mp4_box(b"data", &[&fields(&[1, 0]), text.as_bytes()].concat());
+        mp4_box(&key.to_be_bytes(), &data)
+    }
+
+    // User data with where the clip was recorded, as QuickTime `©xyz` text and as an ISO 6709
+    // `meta` item, next to entries that aren't locations.
+    fn user_data() -> Vec<u8> {
+        let location = "+37.7749-122.4194/";
+        let udta_text = |key: &[u8; 4], text: &str| {
+            let header = [(text.len() as u16).to_be_bytes(), [0x55, 0xC4]].concat();
+            mp4_box(key, &[header.as_slice(), text.as_bytes()].concat())
+        };
+        let mut keys = fields(&[0, 2]);
+        for key in [
+            "com.apple.quicktime.make",
+            "com.apple.quicktime.location.ISO6709",
+        ] {
+            keys.extend(fields(&[
+                8 + key.len() as u32,
+                u32::from_be_bytes(*b"mdta"),
+            ]));
+            keys.extend_from_slice(key.as_bytes());
+        }
+        let ilst = [text_item(1, "Tesla"), text_item(2, location)].concat();
+        let meta = [
+            fields(&[0]),
+            mp4_box(b"keys", &keys),
+            mp4_box(b"ilst", &ilst),
+        ]
+        .concat();
+        let udta = [
+            udta_text(b"\xA9xyz", location),
+            udta_text(b"\xA9too", "Lavf"),
+            mp4_box(b"meta", &meta),
+        ]
+        .concat();
+        mp4_box(b"udta", &udta)
+    }
+
+    // Four H.264 samples with telemetry, and a track without `tkhd` (which the rewriter doesn't
+    // model) whose chunks sit between the video chunks, after bytes no track references.
+    fn clip() -> (Vec<u8>, Vec<Vec<u8>>) {
+        let video: Vec<Vec<u8>> = (0..4u8)
+            .map(|k| {
+                let payload = [
+                    TESLA_SEI_UUID.as_slice(),
+                    &metadata(1000 + u64::from(k)).encode_to_vec(),
+                ]
+                .concat();
+                let sei = build_sei_nal(&[6], &[(5, &payload)]);
+                [length_prefixed(&sei, 4).unwrap(), slice(k)].concat()
+            })
+            .collect();
+        let audio: Vec<Vec<u8>> = (0..4u8).map(|k| vec![0xA0 + k; 5]).collect();
+        let unreferenced = [0xEE; 3];
+
+        let moov_with = |video_chunks: &[u32], audio_chunks: &[u32]| {
+            let sizes =
+                |samples: &[Vec<u8>]| samples.iter().map(|s| s.len() as u32).collect::<Vec<_>>();
+            let traks = [
+                trak(true, b"vide", b"avc1", &sizes(&video), video_chunks),
+                trak(false, b"soun", b"mp4a", &sizes(&audio), audio_chunks),
+            ];
+            mp4_box(b"moov", &[traks.concat(), user_data()].concat())
+        };
+        let ftyp = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        // The chunk offsets don't change the moov size, so measure it first.
+        let mdat_start = (ftyp.len() + moov_with(&[0, 0], &[0, 0]).len() + 8) as u32;
+        let mut payload = unreferenced.to_vec();
+        let mut video_chunks = Vec::new();
+        let mut audio_chunks = Vec::new();
+        for pair in 0..2 {
+            video_chunks.push(mdat_start + payload.len() as u32);
+            payload.extend(video[2 * pair..2 * pair + 2].concat());
+            audio_chunks.push(mdat_start + payload.len() as u32);
+            payload.extend(audio[2 * pair..2 * pair + 2].concat());
+        }
+        let mp4 = [
+            ftyp,
+            moov_with(&video_chunks, &audio_chunks),
+            mp4_box(b"mdat", &payload),
+        ]
+        .concat();
+        (mp4, audio)
+    }
+
+    // Each track's samples, read through its own tables.
+    fn samples(mp4: &[u8]) -> Vec<Vec<Vec<u8>>> {
+        let mut reader = Cursor::new(mp4);
+        let tree = read_box_tree(&mut reader).unwrap();
+        let moov = tree.iter().find(|n| n.box_type == "moov").unwrap();
+        let mut tracks = Vec::new();
+        for trak in moov.children.iter().filter(|n| n.box_type == "trak") {
+            let minf = child(child(trak, "mdia").unwrap(), "minf").unwrap();
+            let tables = read_stbl(&mut reader, child(minf, "stbl").unwrap()).unwrap();
+            let offsets = build_sample_offsets(&tables).unwrap();
+            let track = offsets
+                .iter()
+                .zip(&tables.sample_sizes)
+                .map(|(&o, &size)| mp4[o as usize..o as usize + size as usize].to_vec())
+                .collect();
+            tracks.push(track);
+        }
+        tracks
+    }
+
+    fn mdat_payload(mp4: &[u8]) -> &[u8] {
+        let tree = read_box_tree(&mut Cursor::new(mp4)).unwrap();
+        let mdat = tree.iter().find(|n| n.box_type == "mdat").unwrap();
+        &mp4[(mdat.offset + mdat.header_len) as usize..(mdat.offset + mdat.size) as usize]
+    }
+
+    #[test]
+    fn strip_keeps_other_tracks_and_unreferenced_bytes() {
+        let (input, audio) = clip();
+        let mut output = Vec::new();
+        let summary =
+            strip_sei_reader(Cursor::new(&input), &mut output, &StripOptions::default()).unwrap();
+        assert_eq!(summary.samples_changed, 4);
+        assert_eq!(summary.sei_messages_removed, 4);
+
+        let tracks = samples(&output);
+        assert_eq!(tracks[0], (0..4).map(slice).collect::<Vec<_>>());
+        assert_eq!(tracks[1], audio);
+        assert_eq!(mdat_payload(&output)[..3], [0xEE; 3]);
+    }
+
+    #[test]
+    fn redact_rewrites_only_positions() {
+        let (input, audio) = clip();
+        let mut output = Vec::new();
+        let summary =
+            redact_gps_reader(Cursor::new(&input), &mut output, &RedactOptions::default()).unwrap();
+        assert_eq!(summary.sei_messages_changed, 4);
+        assert_eq!(summary.user_data_removed, 2);
+
+        let tracks = samples(&output);
+        assert_eq!(tracks[1], audio);
+        for (k, sample) in tracks[0].iter().enumerate() {
+            assert!(sample.ends_with(&slice(k as u8)));
+        }
+
+        let mut extractor = extractor_from_reader(Cursor::new(output)).unwrap();
+        for seq in 1000..1004 {
+            let event = extractor.next_event().unwrap().unwrap();
+            let expected = pb::SeiMetadata {
+                latitude_deg: 0.0,
+                longitude_deg: 0.0,
+                heading_deg: 0.0,
+                ..metadata(seq)
+            };
+            assert_eq!(event.metadata, expected);
+        }
+        assert!(extractor.next_event().unwrap().is_none());
+        let keys: Vec<&str> = extractor
+            .user_data()
+            .iter()
+            .map(|e| e.key.as_str())
+            .collect();
+        assert_eq!(keys, ["\u{a9}too", "com.apple.quicktime.make"]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use crate::geo::GeoPoint;
+use crate::trip::Trip;
+
+/// Options for clustering trips into routes.
+#[derive(Debug, Clone)]
+pub struct RouteOptions {
+    /// Size of the grid cells that start/end points and waypoints are snapped to, in meters.
+    ///
+    /// Larger cells tolerate more variation (different parking spots, lane changes) at the cost
+    /// of merging genuinely different routes.
+    pub cell_size_m: f64,
+    /// Number of intermediate waypoints, evenly spaced by distance, that make up the path hash.
+    pub waypoints: usize,
+}
+
+impl Default for RouteOptions {
+    fn default() -> Self {
+        RouteOptions {
+            cell_size_m: 500.0,
+            waypoints: 3,
+        }
+    }
+}
+
+/// A grid cell of [`RouteOptions::cell_size_m`] meters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GridCell {
+    pub row: i64,
+    pub col: i64,
+}
+
+/// The identity of a route: where it starts, where it ends, and (hashed) how it gets there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RouteKey {
+    pub start: GridCell,
+    pub end: GridCell,
+    /// Stable FNV-1a hash of the snapped intermediate waypoints.
+    pub path_hash: u64,
+}
+
+/// A group of trips that follow the same route, with aggregate statistics.
+#[derive(Debug, Clone)]
+pub struct RouteCluster {
+    pub key: RouteKey,
+    /// Indices into the trip slice passed to [`cluster_routes`].
+    pub trips: Vec<usize>,
+    /// Start point of the first trip in the cluster.
+    pub start: GeoPoint,
+    /// End point of the first trip in the cluster.
+    pub end: GeoPoint,
+    pub total_distance_m: f64,
+    pub total_duration: Duration,
+    pub min_duration: Duration,
+    pub max_duration: Duration,
+    pub first_start: Option<NaiveDateTime>,
+    pub last_start: Option<NaiveDateTime>,
+}
+
+impl RouteCluster {
+    pub fn trip_count(&self) -> usize {
+        self.trips.len()
+    }
+
+    pub fn mean_distance_m(&self) -> f64 {
+        self.total_distance_m / self.trips.len() as f64
+    }
+
+    pub fn mean_duration(&self) -> Duration {
+        self.total_duration / self.trips.len() as u32
+    }
+}
+
+// Meters per degree of latitude (and of longitude at the equator).
+const METERS_PER_DEG: f64 = 111_320.0;
+
+fn snap(p: &GeoPoint, cell_size_m: f64) -> GridCell {
+    let row = (p.latitude_deg * METERS_PER_DEG / cell_size_m).floor();
+    // Use the row's latitude so cells stay roughly square away from the equator.
+    let lat = (row + 0.5) * cell_size_m / METERS_PER_DEG;
+    let m_per_deg_lon = (METERS_PER_DEG * lat.to_radians().cos()).max(1.0);
+    let col = (p.longitude_deg * m_per_deg_lon / cell_size_m).floor();
+    GridCell {
+        row: row as i64,
+        col: col as i64,
+    }
+}
+
+// Points at evenly spaced fractions of the path's length (excluding both ends).
+fn waypoints(path: &[GeoPoint], n: usize) -> Vec<GeoPoint> {
+    let cumulative: Vec<f64> = std::iter::once(0.0)
+        .chain(path.windows(2).scan(0.0, |acc, w| {
+            *acc += w[0].distance_m(&w[1]);
+            Some(*acc)
+        }))
+        .collect();
+    let total = cumulative.last().copied().unwrap_or(0.0);
+
+    (1..=n)
+        .map(|i| {
+            let target = total * i as f64 / (n + 1) as f64;
+            let idx = cumulative.partition_point(|&d| d < target);
+            path[idx.min(path.len() - 1)]
+        })
+        .collect()
+}
+
+fn fnv1a(cells: &[GridCell]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for c in cells {
+        for b in c.row.to_le_bytes().into_iter().chain(c.col.to_le_bytes()) {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    h
+}
+
+// `Option::min` treats `None` as smallest; here a missing time should just be ignored.
+fn min_some(a: Option<NaiveDateTime>, b: Option<NaiveDateTime>) -> Option<NaiveDateTime> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Compute the route key for a trip, or `None` if it has no GPS path.
+pub fn route_key(trip: &Trip, opts: &RouteOptions) -> Option<RouteKey> {
+    let first = trip.path.first()?;
+    let last = trip.path.last()?;
+
+    let mut cells: Vec<GridCell> = waypoints(&trip.path, opts.waypoints)
+        .iter()
+        .map(|p| snap(p, opts.cell_size_m))
+        .collect();
+    cells.dedup();
+
+    Some(RouteKey {
+        start: snap(first, opts.cell_size_m),
+        end: snap(last, opts.cell_size_m),
+        path_hash: fnv1a(&cells),
+    })
+}
+
+/// Cluster trips by route similarity.
+///
+/// Trips are grouped when their start and end fall in the same grid cells and their
+/// intermediate waypoints snap to the same cells. Clusters are returned most-travelled first.
+/// Trips without a GPS path are left out.
+pub fn cluster_routes(trips: &[Trip], opts: &RouteOptions) -> Vec<RouteCluster> {
+    let mut by_key: HashMap<RouteKey, RouteCluster> = HashMap::new();
+
+    for (i, trip) in trips.iter().enumerate() {
+        let Some(key) = route_key(trip, opts) else {
+            continue;
+        };
+
+        let cluster = by_key.entry(key).or_insert_with(|| RouteCluster {
+            key,
+            trips: Vec::new(),
+            start: trip.path[0],
+            end: trip.path[trip.path.len() - 1],
+            total_distance_m: 0.0,
+            total_duration: Duration::ZERO,
+            min_duration: trip.duration,
+            max_duration: trip.duration,
+            first_start: trip.start,
+            last_start: trip.start,
+        });
+
+        cluster.trips.push(i);
+        cluster.total_distance_m += trip.distance_m;
+        cluster.total_duration += trip.duration;
+        cluster.min_duration = cluster.min_duration.min(trip.duration);
+        cluster.max_duration = cluster.max_duration.max(trip.duration);
+        cluster.first_start = min_some(cluster.first_start, trip.start);
+        cluster.last_start = cluster.last_start.max(trip.start);
+    }
+
+    let mut clusters: Vec<RouteCluster> = by_key.into_values().collect();
+    clusters.sort_by(|a, b| {
+        b.trips
+            .len()
+            .cmp(&a.trips.len())
+            .then_with(|| a.first_start.cmp(&b.first_start))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    clusters
+}
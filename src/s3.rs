@@ -0,0 +1,249 @@
+//! Read clips straight from S3 (or an S3-compatible store) with ranged `GetObject` requests.
+//!
+//! [`S3RangeReader`] implements Tokio's `AsyncRead + AsyncSeek`, so it plugs into
+//! [`async_extractor_from_reader`] like a local file: opening fetches the MP4 index, then
+//! samples are fetched as they are decoded, in [`S3Options::read_ahead`]-sized ranges.
+//! [`async_extractor_from_s3`] does both steps.
+//!
+//! The caller supplies the `aws_sdk_s3::Client`, so credentials, region, endpoint and retry
+//! policy come from the application's own SDK configuration.
+
+use std::future::Future;
+use std::io::{self, SeekFrom};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use aws_sdk_s3::Client;
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+use crate::async_extract::{async_extractor_from_reader, AsyncSeiExtractor};
+use crate::Error;
+
+type Fetch = Pin<Box<dyn Future<Output = io::Result<Vec<u8>>> + Send>>;
+
+/// Options for [`S3RangeReader::open_with_options`].
+#[derive(Debug, Clone)]
+pub struct S3Options {
+    /// Bytes fetched per `GetObject` request. S3 bills and delays per request, so this is larger
+    /// than a local read buffer: a miss fetches enough for the next several frames.
+    pub read_ahead: usize,
+}
+
+impl Default for S3Options {
+    fn default() -> Self {
+        Self {
+            read_ahead: 1024 * 1024,
+        }
+    }
+}
+
+/// A seekable async reader over one S3 object, fetched in byte ranges on demand.
+pub struct S3RangeReader {
+    client: Client,
+    bucket: String,
+    key: String,
+    len: u64,
+    pos: u64,
+    read_ahead: usize,
+    buf: Vec<u8>,
+    buf_start: u64,
+    requests: u64,
+    // In-flight ranged GET and the offset it starts at.
+    fetch: Option<(u64, Fetch)>,
+}
+
+impl S3RangeReader {
+    /// Open `s3://bucket/key` with default options.
+    pub async fn open(
+        client: Client,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+    ) -> Result<Self, Error> {
+        Self::open_with_options(client, bucket, key, &S3Options::default()).await
+    }
+
+    /// Open `s3://bucket/key`, learning the object size from the first range response.
+    ///
+    /// The first `read_ahead` bytes (`ftyp`, and `moov` for clips written with it up front) are
+    /// kept as the initial buffer, so no separate `HeadObject` request is made. An empty object
+    /// answers that range with 416 and opens with length 0.
+    pub async fn open_with_options(
+        client: Client,
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        opts: &S3Options,
+    ) -> Result<Self, Error> {
+        let (bucket, key) = (bucket.into(), key.into());
+        let read_ahead = opts.read_ahead.max(1);
+        let resp = client
+            .get_object()
+            .bucket(&bucket)
+            .key(&key)
+            .range(format!("bytes=0-{}", read_ahead - 1))
+            .send()
+            .await;
+        let (len, buf) = match resp {
+            // An empty object can't satisfy any range.
+            Err(e) if e.raw_response().is_some_and(|r| r.status().as_u16() == 416) => {
+                (0, Vec::new())
+            }
+            Err(e) => return Err(Error::S3(Box::new(e.into()))),
+            Ok(resp) => {
+                let len = resp
+                    .content_range()
+                    .and_then(|r| content_range(r).map(|(_, total)| total))
+                    .or(resp.content_length().map(|n| n as u64))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("no object size in the response for s3://{bucket}/{key}"),
+                        )
+                    })?;
+                let buf = resp.body.collect().await.map_err(io::Error::other)?;
+                (len, buf.to_vec())
+            }
+        };
+
+        Ok(S3RangeReader {
+            client,
+            bucket,
+            key,
+            len,
+            pos: 0,
+            read_ahead,
+            buf,
+            buf_start: 0,
+            requests: 1,
+            fetch: None,
+        })
+    }
+
+    /// The object's bucket.
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// The object's key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Object size in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the object is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of `GetObject` requests issued so far, the size probe included.
+    pub fn request_count(&self) -> u64 {
+        self.requests
+    }
+
+    fn start_fetch(&mut self, min_len: usize) {
+        let want = min_len.max(self.read_ahead) as u64;
+        let (start, end) = (self.pos, (self.pos + want).min(self.len) - 1);
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .range(format!("bytes={start}-{end}"));
+        self.requests += 1;
+        self.fetch = Some((
+            start,
+            Box::pin(async move {
+                let resp = request
+                    .send()
+                    .await
+                    .map_err(|e| io::Error::other(aws_sdk_s3::Error::from(e)))?;
+                if resp.content_range().and_then(content_range).map(|(s, _)| s) != Some(start) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected Content-Range for bytes {start}-{end}"),
+                    ));
+                }
+                let data = resp.body.collect().await.map_err(io::Error::other)?;
+                Ok(data.to_vec())
+            }),
+        ));
+    }
+}
+
+impl AsyncRead for S3RangeReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if out.remaining() == 0 || this.pos >= this.len {
+            return Poll::Ready(Ok(()));
+        }
+        let buf_end = this.buf_start + this.buf.len() as u64;
+        if this.pos < this.buf_start || this.pos >= buf_end {
+            // A fetch left over from before a seek is for the wrong range.
+            if this
+                .fetch
+                .as_ref()
+                .is_none_or(|(start, _)| *start != this.pos)
+            {
+                this.start_fetch(out.remaining());
+            }
+            let (_, fetch) = this.fetch.as_mut().expect("fetch in flight");
+            let data = ready!(fetch.as_mut().poll(cx));
+            this.fetch = None;
+            this.buf = data?;
+            this.buf_start = this.pos;
+            if this.buf.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("empty range response for s3://{}/{}", this.bucket, this.key),
+                )));
+            }
+        }
+        let at = (this.pos - this.buf_start) as usize;
+        let n = out.remaining().min(this.buf.len() - at);
+        out.put_slice(&this.buf[at..at + n]);
+        this.pos += n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for S3RangeReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let target = match position {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => this.len.checked_add_signed(d),
+            SeekFrom::Current(d) => this.pos.checked_add_signed(d),
+        };
+        this.pos = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position")
+        })?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+/// Open `s3://bucket/key` with default options and create an async extractor over it.
+pub async fn async_extractor_from_s3(
+    client: Client,
+    bucket: impl Into<String>,
+    key: impl Into<String>,
+) -> Result<AsyncSeiExtractor<S3RangeReader>, Error> {
+    async_extractor_from_reader(S3RangeReader::open(client, bucket, key).await?).await
+}
+
+// (first byte, complete length) from `bytes a-b/len`.
+fn content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.parse().ok()?, total.parse().ok()?))
+}
@@ -0,0 +1,150 @@
+//! Versioned JSON export format.
+//!
+//! Every JSON document the CLI writes carries an [`OutputSchemaVersion`], so archives written by
+//! one release can still be read (and upgraded) by later ones. When the format changes:
+//! 1. add a variant to [`OutputSchemaVersion`] describing what changed, and point
+//!    [`OutputSchemaVersion::CURRENT`] at it;
+//! 2. teach [`read_json_export`] to upgrade documents of the previous version.
+//!
+//! CSV output is deliberately unversioned: its header row already names every column, and
+//! adding a version line would break CSV readers.
+
+use std::io::{Read, Write};
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Error;
+
+/// Version of the JSON export format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OutputSchemaVersion {
+    /// A bare JSON array of records, as written before exports were versioned.
+    V1,
+    /// An object wrapping the records: `{"schema_version": 2, "records": [...]}`.
+    ///
+    /// Migration from V1: the array becomes `records`; the records themselves are unchanged.
+    V2,
+}
+
+impl OutputSchemaVersion {
+    /// The version written by this release.
+    pub const CURRENT: OutputSchemaVersion = OutputSchemaVersion::V2;
+
+    pub fn as_u32(self) -> u32 {
+        match self {
+            OutputSchemaVersion::V1 => 1,
+            OutputSchemaVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_u32(v: u32) -> Option<Self> {
+        match v {
+            1 => Some(OutputSchemaVersion::V1),
+            2 => Some(OutputSchemaVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+// The top-level JSON document of a current-version export.
+#[derive(Serialize)]
+struct JsonExport<'a, T> {
+    schema_version: u32,
+    records: &'a [T],
+}
+
+/// Write `records` as a pretty-printed JSON document in the current schema version.
+pub fn write_json_export<T: Serialize>(out: &mut dyn Write, records: &[T]) -> Result<(), Error> {
+    let doc = JsonExport {
+        schema_version: OutputSchemaVersion::CURRENT.as_u32(),
+        records,
+    };
+    serde_json::to_writer_pretty(&mut *out, &doc)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Writes a compact JSON document in the current schema version one record at a time, so an
+/// export of any size needs no more memory than a record.
+///
+/// The output is what [`write_json_export`] writes, without the pretty-printing.
+pub struct JsonExportWriter<'a> {
+    out: &'a mut dyn Write,
+    records: usize,
+}
+
+impl<'a> JsonExportWriter<'a> {
+    /// Start the document.
+    pub fn new(out: &'a mut dyn Write) -> Result<Self, Error> {
+        write!(
+            out,
+            r#"{{"schema_version":{},"records":["#,
+            OutputSchemaVersion::CURRENT.as_u32()
+        )?;
+        Ok(JsonExportWriter { out, records: 0 })
+    }
+
+    /// Append a record.
+    pub fn write_record<T: Serialize>(&mut self, record: &T) -> Result<(), Error> {
+        if self.records > 0 {
+            self.out.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut *self.out, record)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Close the document. Without this the output is not valid JSON.
+    pub fn finish(self) -> Result<(), Error> {
+        writeln!(self.out, "]}}")?;
+        Ok(())
+    }
+}
+
+/// Read a JSON export written by this or any earlier release.
+///
+/// Returns the version the document was written in, along with its records upgraded to the
+/// current layout. Documents from a newer release fail with
+/// [`Error::UnsupportedSchemaVersion`].
+pub fn read_json_export<T: DeserializeOwned>(
+    reader: impl Read,
+) -> Result<(OutputSchemaVersion, Vec<T>), Error> {
+    let doc: Value = serde_json::from_reader(reader)?;
+
+    let (version, records) = match doc {
+        // V1: bare array.
+        Value::Array(records) => (OutputSchemaVersion::V1, records),
+        Value::Object(mut obj) => {
+            let found = obj
+                .get("schema_version")
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+            let version = u32::try_from(found)
+                .ok()
+                .and_then(OutputSchemaVersion::from_u32)
+                .ok_or(Error::UnsupportedSchemaVersion {
+                    found,
+                    supported: OutputSchemaVersion::CURRENT.as_u32(),
+                })?;
+            // A document without `records` is some other JSON file, not an empty export.
+            let records = obj
+                .remove("records")
+                .ok_or_else(|| serde_json::Error::missing_field("records"))?;
+            (version, serde_json::from_value(records)?)
+        }
+        _ => {
+            return Err(Error::UnsupportedSchemaVersion {
+                found: 0,
+                supported: OutputSchemaVersion::CURRENT.as_u32(),
+            });
+        }
+    };
+
+    let records = records
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<Vec<T>, _>>()?;
+    Ok((version, records))
+}
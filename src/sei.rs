@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use prost::Message;
 
 use crate::mp4::CodecConfig;
@@ -7,6 +9,14 @@ use crate::pb;
 // NAL + SEI parsing
 // -----------------------------
 fn split_nals_length_prefixed(sample: &[u8], nal_len_size: usize) -> Vec<&[u8]> {
+    nal_units(sample, nal_len_size)
+        .into_iter()
+        .map(|(_, nal)| nal)
+        .collect()
+}
+
+// NAL units of a length-prefixed sample, each with the offset of its length prefix.
+pub(crate) fn nal_units(sample: &[u8], nal_len_size: usize) -> Vec<(usize, &[u8])> {
     let mut out = Vec::new();
     let mut i = 0usize;
     while i + nal_len_size <= sample.len() {
@@ -20,17 +30,18 @@ fn split_nals_length_prefixed(sample: &[u8], nal_len_size: usize) -> Vec<&[u8]>
                 as usize,
             _ => break,
         };
+        let prefix = i;
         i += nal_len_size;
         if i + len > sample.len() || len == 0 {
             break;
         }
-        out.push(&sample[i..i + len]);
+        out.push((prefix, &sample[i..i + len]));
         i += len;
     }
     out
 }
 
-fn remove_emulation_prevention(rbsp: &[u8]) -> Vec<u8> {
+pub(crate) fn remove_emulation_prevention(rbsp: &[u8]) -> Vec<u8> {
     // Remove 0x03 after 0x00 0x00 sequences (H264/H265)
     let mut out = Vec::with_capacity(rbsp.len());
     let mut i = 0usize;
@@ -103,13 +114,183 @@ fn parse_sei_messages(rbsp: &[u8]) -> Vec<(u32, Vec<u8>)> {
     out
 }
 
-fn try_decode_sei_metadata_from_payload(payload_type: u32, payload: &[u8]) -> Option<pb::SeiMetadata> {
+/// Counts of which decode heuristics fired, for tuning them against a clip corpus.
+///
+/// Every SEI message (or AV1 metadata OBU) examined is either `decoded` or `undecoded`; each
+/// decoded one is attributed to exactly one of the `by_*` candidates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// Samples (or NAL units, for elementary streams) scanned.
+    pub samples: usize,
+    /// SEI messages / metadata OBUs examined.
+    pub messages: usize,
+    /// Messages that produced telemetry.
+    pub decoded: usize,
+    /// Messages where no candidate decoded.
+    pub undecoded: usize,
+    /// Decoded after the `0x42.. 0x69` magic prefix (the path Tesla's own viewer uses).
+    pub by_magic_prefix: usize,
+    /// Decoded after skipping a 16-byte UUID.
+    pub by_uuid_skip: usize,
+    /// Decoded from the payload as-is.
+    pub by_raw_payload: usize,
+    /// Decoded from a scan for a leading protobuf field-1 tag (`0x08`).
+    pub by_tag_scan: usize,
+    /// Decodes that only succeeded after dropping a trailing `0x80` stop bit.
+    pub trailing_byte_stripped: usize,
+    /// Candidates that decoded to an all-defaults message and were rejected as false positives.
+    pub rejected_empty: usize,
+}
+
+impl DecodeStats {
+    /// Add another set of counts to this one.
+    pub fn merge(&mut self, other: &DecodeStats) {
+        self.samples += other.samples;
+        self.messages += other.messages;
+        self.decoded += other.decoded;
+        self.undecoded += other.undecoded;
+        self.by_magic_prefix += other.by_magic_prefix;
+        self.by_uuid_skip += other.by_uuid_skip;
+        self.by_raw_payload += other.by_raw_payload;
+        self.by_tag_scan += other.by_tag_scan;
+        self.trailing_byte_stripped += other.trailing_byte_stripped;
+        self.rejected_empty += other.rejected_empty;
+    }
+}
+
+/// A protobuf field the crate's `SeiMetadata` schema doesn't know, kept as it was on the wire.
+///
+/// Newer firmware may add fields; seeing these means the schema has moved ahead of this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+    /// The protobuf field number.
+    pub number: u32,
+    /// The protobuf wire type: 0 (varint), 1 (64-bit), 2 (length-delimited) or 5 (32-bit).
+    pub wire_type: u8,
+    /// The encoded value: the varint bytes, the fixed 4 or 8 bytes, or the contents of a
+    /// length-delimited field (without its length prefix).
+    pub data: Vec<u8>,
+}
+
+// Field numbers of `SeiMetadata` in proto/dashcam.proto, listed by build.rs.
+const KNOWN_FIELD_NUMBERS: &[u32] = include!(concat!(env!("OUT_DIR"), "/sei_metadata_fields.rs"));
+
+// Bytes of the varint at the start of `data`, and its value.
+fn read_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &b) in data.iter().take(10).enumerate() {
+        value |= ((b & 0x7F) as u64) << (i * 7);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+// Top-level fields of an encoded protobuf message as (number, wire type, value bytes), where
+// the value of a length-delimited field excludes its length prefix. Stops quietly at anything
+// malformed.
+pub(crate) fn protobuf_fields(msg: &[u8]) -> Vec<(u32, u8, Range<usize>)> {
+    let mut out = Vec::new();
+    let mut i = 0usize;
+    while i < msg.len() {
+        let Some((key, n)) = read_varint(&msg[i..]) else {
+            break;
+        };
+        i += n;
+        let number = (key >> 3) as u32;
+        let wire_type = (key & 7) as u8;
+        let (start, len) = match wire_type {
+            0 => match read_varint(&msg[i..]) {
+                Some((_, n)) => (i, n),
+                None => break,
+            },
+            1 => (i, 8),
+            2 => match read_varint(&msg[i..]) {
+                Some((len, n)) => (i + n, len as usize),
+                None => break,
+            },
+            5 => (i, 4),
+            // Groups are deprecated and never sent by Tesla.
+            _ => break,
+        };
+        let end = start.saturating_add(len);
+        if end > msg.len() {
+            break;
+        }
+        out.push((number, wire_type, start..end));
+        i = end;
+    }
+    out
+}
+
+// The fields of an encoded SeiMetadata outside the schema; prost already accepted the message.
+fn unknown_fields(msg: &[u8]) -> Vec<UnknownField> {
+    protobuf_fields(msg)
+        .into_iter()
+        .filter(|(number, _, _)| !KNOWN_FIELD_NUMBERS.contains(number))
+        .map(|(number, wire_type, data)| UnknownField {
+            number,
+            wire_type,
+            data: msg[data].to_vec(),
+        })
+        .collect()
+}
+
+/// One of the ways the decoder looks for telemetry inside an SEI payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeHeuristic {
+    /// After a run of `0x42` bytes and a `0x69` (the path Tesla's own viewer uses).
+    MagicPrefix,
+    /// After the 16-byte UUID of a user_data_unregistered payload.
+    UuidSkip,
+    /// The payload as-is.
+    RawPayload,
+    /// At a `0x08` byte (protobuf field 1, varint) within the first 64 bytes.
+    TagScan,
+}
+
+/// A payload offset the decoder tries, as listed by
+/// [`RawSeiMessage::decode_candidates`](crate::RawSeiMessage::decode_candidates).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeCandidate {
+    pub heuristic: DecodeHeuristic,
+    /// Byte offset into the payload (UUID included) where decoding starts.
+    pub offset: usize,
+    /// Whether the bytes from here decode as telemetry. The decoder uses the first candidate
+    /// that does.
+    pub decodes: bool,
+}
+
+fn try_decode_sei_metadata_from_payload(
+    payload_type: u32,
+    payload: &[u8],
+    uuid: Option<[u8; 16]>,
+    stats: &mut DecodeStats,
+) -> Option<DecodedSei> {
+    stats.messages += 1;
+    let Some((metadata, encoded)) = decode_payload_candidates(payload_type, payload, stats) else {
+        stats.undecoded += 1;
+        return None;
+    };
+    stats.decoded += 1;
+    Some(DecodedSei {
+        metadata,
+        uuid,
+        unknown_fields: unknown_fields(encoded),
+        #[cfg(feature = "reflect")]
+        raw_metadata: encoded.to_vec(),
+    })
+}
+
+// The places decoding is tried, most trustworthy first, without repeats.
+fn payload_candidates(payload_type: u32, payload: &[u8]) -> Vec<(DecodeHeuristic, &[u8])> {
     // Tesla often uses user_data_unregistered (type 5) which typically starts with a 16-byte UUID.
     // Some files may include additional header bytes; we try a small set of plausible offsets.
     //
     // IMPORTANT: protobuf decode of an empty slice is valid and yields an all-defaults message.
     // If we accidentally pass an empty slice (e.g., UUID-only payload), we emit bogus rows.
-    let mut candidates: Vec<&[u8]> = Vec::new();
+    let mut candidates: Vec<(DecodeHeuristic, &[u8])> = Vec::new();
 
     // Tesla's JS looks for a magic prefix of 0x42 bytes followed by 0x69, then decodes the bytes
     // after that marker. Implement that first to avoid false positives.
@@ -121,7 +302,7 @@ fn try_decode_sei_metadata_from_payload(payload_type: u32, payload: &[u8]) -> Op
         if i > 0 && i < payload.len() && payload[i] == 0x69 {
             let start = i + 1;
             if start < payload.len() {
-                candidates.push(&payload[start..]);
+                candidates.push((DecodeHeuristic::MagicPrefix, &payload[start..]));
             }
         }
     }
@@ -129,60 +310,139 @@ fn try_decode_sei_metadata_from_payload(payload_type: u32, payload: &[u8]) -> Op
     // Try skipping UUID for type 5.
     // NOTE: payload.len()==16 means UUID only; decoding an empty slice yields a default protobuf.
     if payload_type == 5 && payload.len() > 16 {
-        candidates.push(&payload[16..]);
+        candidates.push((DecodeHeuristic::UuidSkip, &payload[16..]));
     }
 
     // Always try the payload as-is (fallback).
     if !payload.is_empty() {
-        candidates.push(payload);
+        candidates.push((DecodeHeuristic::RawPayload, payload));
     }
 
     // Heuristic: protobuf messages often start with tag 0x08 (field 1, varint).
     let scan_len = payload.len().min(64);
     for i in 0..scan_len {
         if payload[i] == 0x08 && i + 2 <= payload.len() {
-            candidates.push(&payload[i..]);
+            candidates.push((DecodeHeuristic::TagScan, &payload[i..]));
         }
     }
 
     // Deduplicate by pointer+len to avoid repeated decode attempts.
-    candidates.dedup_by(|a, b| a.as_ptr() == b.as_ptr() && a.len() == b.len());
+    candidates.dedup_by(|a, b| a.1.as_ptr() == b.1.as_ptr() && a.1.len() == b.1.len());
+    candidates
+}
 
-    for cand in candidates {
-        if cand.is_empty() {
-            continue;
-        }
+// Decode one candidate: the message, the bytes it came from, and whether a trailing stop bit
+// had to be dropped.
+fn decode_candidate<'a>(
+    cand: &'a [u8],
+    stats: &mut DecodeStats,
+) -> Option<(pb::SeiMetadata, &'a [u8], bool)> {
+    if cand.is_empty() {
+        return None;
+    }
 
-        // Tesla's JS drops the last byte because it doesn't parse payloadSize.
-        // Our parser should already exclude rbsp_trailing_bits, but some payloads still appear to
-        // carry a trailing stop bit; try both when present.
-        let mut decode_attempts: [&[u8]; 2] = [cand, &[]];
-        let mut attempt_count = 1usize;
-        if cand.len() > 1 && cand[cand.len() - 1] == 0x80 {
-            decode_attempts[1] = &cand[..cand.len() - 1];
-            attempt_count = 2;
+    // Tesla's JS drops the last byte because it doesn't parse payloadSize.
+    // Our parser should already exclude rbsp_trailing_bits, but some payloads still appear to
+    // carry a trailing stop bit; try both when present.
+    let mut decode_attempts: [&[u8]; 2] = [cand, &[]];
+    let mut attempt_count = 1usize;
+    if cand.len() > 1 && cand[cand.len() - 1] == 0x80 {
+        decode_attempts[1] = &cand[..cand.len() - 1];
+        attempt_count = 2;
+    }
+
+    for (attempt_i, attempt) in decode_attempts.into_iter().take(attempt_count).enumerate() {
+        if attempt.is_empty() {
+            continue;
         }
 
-        for attempt in decode_attempts.into_iter().take(attempt_count) {
-            if attempt.is_empty() {
+        if let Ok(msg) = pb::SeiMetadata::decode(attempt) {
+            // Guard against false-positives: empty payloads decode as an all-defaults message.
+            if msg.version == 0 && msg.frame_seq_no == 0 {
+                stats.rejected_empty += 1;
                 continue;
             }
-
-            if let Ok(msg) = pb::SeiMetadata::decode(attempt) {
-                // Guard against false-positives: empty payloads decode as an all-defaults message.
-                if msg.version == 0 && msg.frame_seq_no == 0 {
-                    continue;
-                }
-                return Some(msg);
-            }
+            return Some((msg, attempt, attempt_i == 1));
         }
     }
+    None
+}
 
+// The decoded message and the bytes it was decoded from.
+fn decode_payload_candidates<'a>(
+    payload_type: u32,
+    payload: &'a [u8],
+    stats: &mut DecodeStats,
+) -> Option<(pb::SeiMetadata, &'a [u8])> {
+    for (kind, cand) in payload_candidates(payload_type, payload) {
+        let Some((msg, encoded, stripped)) = decode_candidate(cand, stats) else {
+            continue;
+        };
+        match kind {
+            DecodeHeuristic::MagicPrefix => stats.by_magic_prefix += 1,
+            DecodeHeuristic::UuidSkip => stats.by_uuid_skip += 1,
+            DecodeHeuristic::RawPayload => stats.by_raw_payload += 1,
+            DecodeHeuristic::TagScan => stats.by_tag_scan += 1,
+        }
+        if stripped {
+            stats.trailing_byte_stripped += 1;
+        }
+        return Some((msg, encoded));
+    }
     None
 }
 
+// Every candidate the decoder would try on a payload, and whether each decodes.
+pub(crate) fn decode_candidates(payload_type: u32, payload: &[u8]) -> Vec<DecodeCandidate> {
+    payload_candidates(payload_type, payload)
+        .into_iter()
+        .map(|(heuristic, cand)| DecodeCandidate {
+            heuristic,
+            offset: cand.as_ptr() as usize - payload.as_ptr() as usize,
+            decodes: decode_candidate(cand, &mut DecodeStats::default()).is_some(),
+        })
+        .collect()
+}
+
+// A decoded telemetry message, the UUID of the user_data_unregistered SEI it came from, any
+// fields newer than the schema, and the bytes it was decoded from.
+pub(crate) struct DecodedSei {
+    pub(crate) metadata: pb::SeiMetadata,
+    pub(crate) uuid: Option<[u8; 16]>,
+    pub(crate) unknown_fields: Vec<UnknownField>,
+    // The protobuf bytes `metadata` was decoded from, only kept for the `dynamic` module.
+    #[cfg(feature = "reflect")]
+    pub(crate) raw_metadata: Vec<u8>,
+}
+
+// The uuid_iso_iec_11578 field that starts every user_data_unregistered (type 5) payload.
+pub(crate) fn user_data_uuid(payload_type: u32, payload: &[u8]) -> Option<[u8; 16]> {
+    if payload_type != 5 {
+        return None;
+    }
+    payload.get(..16)?.try_into().ok()
+}
+
+// Tesla only uses user_data_unregistered.
+pub(crate) const DEFAULT_SEI_PAYLOAD_TYPES: &[u32] = &[5];
+
+// Whether messages of `payload_type` should be decoded; `None` admits every type.
+fn payload_type_wanted(payload_types: Option<&[u32]>, payload_type: u32) -> bool {
+    payload_types.is_none_or(|types| types.contains(&payload_type))
+}
+
 // Identify SEI NALs and decode protobufs.
-pub(crate) fn decode_sei_from_sample(codec: &CodecConfig, sample: &[u8]) -> Vec<pb::SeiMetadata> {
+pub(crate) fn decode_sei_from_sample(
+    codec: &CodecConfig,
+    sample: &[u8],
+    payload_types: Option<&[u32]>,
+    stats: &mut DecodeStats,
+) -> Vec<DecodedSei> {
+    stats.samples += 1;
+    if let CodecConfig::Av1 = codec {
+        return decode_metadata_obus(sample, payload_types, stats);
+    }
+
     let nal_len_size = match codec {
         CodecConfig::Avc { nal_len_size } => *nal_len_size,
         CodecConfig::Hevc { nal_len_size } => *nal_len_size,
@@ -193,44 +453,251 @@ pub(crate) fn decode_sei_from_sample(codec: &CodecConfig, sample: &[u8]) -> Vec<
     let mut out = Vec::new();
 
     for nal in nals {
-        if nal.is_empty() {
+        decode_sei_from_nal(codec, nal, payload_types, stats, &mut out);
+    }
+
+    out
+}
+
+// Decode a single NAL unit (header included, no length prefix or start code).
+pub(crate) fn decode_sei_from_nal(
+    codec: &CodecConfig,
+    nal: &[u8],
+    payload_types: Option<&[u32]>,
+    stats: &mut DecodeStats,
+    out: &mut Vec<DecodedSei>,
+) {
+    let Some((_, rbsp)) = sei_rbsp(codec, nal) else {
+        return;
+    };
+
+    for (pt, pl) in parse_sei_messages(rbsp) {
+        if !payload_type_wanted(payload_types, pt) {
             continue;
         }
+        let uuid = user_data_uuid(pt, &pl);
+        let decoded = try_decode_sei_metadata_from_payload(pt, &pl, uuid, stats);
+        out.extend(decoded);
+    }
+}
 
-        match codec {
-            CodecConfig::Avc { .. } => {
-                let nal_type = nal[0] & 0x1F;
-                if nal_type != 6 {
-                    continue;
-                }
-                // NAL header is 1 byte for H.264
-                let rbsp = &nal[1..];
-                for (pt, pl) in parse_sei_messages(rbsp) {
-                    if let Some(msg) = try_decode_sei_metadata_from_payload(pt, &pl) {
-                        out.push(msg);
-                    }
-                }
-            }
-            CodecConfig::Hevc { .. } => {
-                if nal.len() < 2 {
-                    continue;
-                }
-                // HEVC nal_unit_type: bits 1..6 of first byte
-                let nal_type = (nal[0] >> 1) & 0x3F;
-                if nal_type != 39 && nal_type != 40 {
-                    continue; // prefix/suffix SEI
-                }
-                // HEVC NAL header is 2 bytes
-                let rbsp = &nal[2..];
-                for (pt, pl) in parse_sei_messages(rbsp) {
-                    if let Some(msg) = try_decode_sei_metadata_from_payload(pt, &pl) {
-                        out.push(msg);
-                    }
-                }
+// NAL unit type and SEI RBSP (NAL header stripped, emulation prevention still in place) of an
+// SEI NAL unit; `None` for any other NAL unit.
+fn sei_rbsp<'a>(codec: &CodecConfig, nal: &'a [u8]) -> Option<(u8, &'a [u8])> {
+    match codec {
+        CodecConfig::Avc { .. } => {
+            // NAL header is 1 byte for H.264
+            let nal_type = nal.first()? & 0x1F;
+            (nal_type == 6).then(|| (nal_type, &nal[1..]))
+        }
+        CodecConfig::Hevc { .. } => {
+            if nal.len() < 2 {
+                return None;
             }
-            _ => {}
+            // HEVC nal_unit_type: bits 1..6 of first byte; 39/40 are prefix/suffix SEI.
+            // The NAL header is 2 bytes.
+            let nal_type = (nal[0] >> 1) & 0x3F;
+            (nal_type == 39 || nal_type == 40).then(|| (nal_type, &nal[2..]))
+        }
+        _ => None,
+    }
+}
+
+// Every SEI message in a sample as (nal_type, payload_type, payload), without decoding any.
+// AV1 samples carry no SEI and yield nothing.
+pub(crate) fn raw_sei_from_sample(codec: &CodecConfig, sample: &[u8]) -> Vec<(u8, u32, Vec<u8>)> {
+    let nal_len_size = match codec {
+        CodecConfig::Avc { nal_len_size } | CodecConfig::Hevc { nal_len_size } => *nal_len_size,
+        _ => return Vec::new(),
+    };
+
+    let mut out = Vec::new();
+    for nal in split_nals_length_prefixed(sample, nal_len_size) {
+        if let Some((nal_type, rbsp)) = sei_rbsp(codec, nal) {
+            out.extend(
+                parse_sei_messages(rbsp)
+                    .into_iter()
+                    .map(|(pt, payload)| (nal_type, pt, payload)),
+            );
+        }
+    }
+    out
+}
+
+// An SEI message as (payload_type, payload).
+pub(crate) type SeiMessage = (u32, Vec<u8>);
+
+// SEI messages of an SEI NAL unit and the length of its NAL header; `None` for any other NAL
+// unit.
+pub(crate) fn sei_nal_messages(
+    codec: &CodecConfig,
+    nal: &[u8],
+) -> Option<(usize, Vec<SeiMessage>)> {
+    let (_, rbsp) = sei_rbsp(codec, nal)?;
+    Some((nal.len() - rbsp.len(), parse_sei_messages(rbsp)))
+}
+
+// Where the telemetry protobuf sits inside an SEI payload, using the same candidates as the
+// decoder; `None` if the payload carries no telemetry.
+pub(crate) fn telemetry_span(payload_type: u32, payload: &[u8]) -> Option<Range<usize>> {
+    let (_, encoded) =
+        decode_payload_candidates(payload_type, payload, &mut DecodeStats::default())?;
+    let start = encoded.as_ptr() as usize - payload.as_ptr() as usize;
+    Some(start..start + encoded.len())
+}
+
+// Whether a NAL unit carries coded picture data (H.264 types 1-5, H.265 types 0-31).
+pub(crate) fn is_vcl_nal(codec: &CodecConfig, nal: &[u8]) -> bool {
+    let Some(&header) = nal.first() else {
+        return false;
+    };
+    match codec {
+        CodecConfig::Avc { .. } => matches!(header & 0x1F, 1..=5),
+        CodecConfig::Hevc { .. } => (header >> 1) & 0x3F < 32,
+        _ => false,
+    }
+}
+
+// NAL unit header of a new prefix SEI NAL unit; `None` for codecs without SEI.
+pub(crate) fn sei_nal_header(codec: &CodecConfig) -> Option<&'static [u8]> {
+    match codec {
+        CodecConfig::Avc { .. } => Some(&[6]),
+        // nuh_layer_id 0, nuh_temporal_id_plus1 1
+        CodecConfig::Hevc { .. } => Some(&[39 << 1, 1]),
+        _ => None,
+    }
+}
+
+// Serialise SEI messages into a NAL unit behind `header`, with rbsp trailing bits and
+// emulation prevention.
+pub(crate) fn build_sei_nal(header: &[u8], messages: &[(u32, &[u8])]) -> Vec<u8> {
+    // payloadType / payloadSize: a run of 0xFF bytes, then the remainder.
+    fn write_ff_coded(out: &mut Vec<u8>, mut v: usize) {
+        while v >= 255 {
+            out.push(0xFF);
+            v -= 255;
+        }
+        out.push(v as u8);
+    }
+
+    let mut rbsp = Vec::new();
+    for &(payload_type, payload) in messages {
+        write_ff_coded(&mut rbsp, payload_type as usize);
+        write_ff_coded(&mut rbsp, payload.len());
+        rbsp.extend_from_slice(payload);
+    }
+    rbsp.push(0x80);
+
+    let mut nal = header.to_vec();
+    nal.extend(add_emulation_prevention(&rbsp));
+    nal
+}
+
+// Inverse of `remove_emulation_prevention`: no `00 00 0x` (x <= 3) may appear in a NAL unit.
+fn add_emulation_prevention(rbsp: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(rbsp.len() + rbsp.len() / 64);
+    let mut zeros = 0usize;
+    for &b in rbsp {
+        if zeros >= 2 && b <= 3 {
+            out.push(0x03);
+            zeros = 0;
+        }
+        out.push(b);
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+    }
+    out
+}
+
+// Position of the next Annex B start code prefix (`00 00 01`) at or after `from`.
+//
+// A four-byte start code (`00 00 00 01`) is found at its last three bytes; the extra leading zero
+// is left at the end of the previous NAL and trimmed by the caller.
+pub(crate) fn find_start_code(data: &[u8], from: usize) -> Option<usize> {
+    if data.len() < 3 {
+        return None;
+    }
+    (from..data.len() - 2).find(|&i| data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1)
+}
+
+// -----------------------------
+// AV1 metadata OBUs
+// -----------------------------
+const OBU_METADATA: u8 = 5;
+const METADATA_TYPE_ITUT_T35: u64 = 4;
+// Types 6..=31 are "unregistered user private", AV1's counterpart to user_data_unregistered SEI.
+const METADATA_TYPE_USER_PRIVATE: std::ops::RangeInclusive<u64> = 6..=31;
+
+// Unsigned LEB128, as used for OBU sizes and metadata types. Returns (value, bytes read).
+fn read_leb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (i, &b) in data.iter().take(8).enumerate() {
+        value |= ((b & 0x7F) as u64) << (i * 7);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+// Split an MP4 AV1 sample (low-overhead bitstream format) into (obu_type, payload).
+fn split_obus(sample: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut out = Vec::new();
+    let mut i = 0usize;
+    while i < sample.len() {
+        let header = sample[i];
+        let obu_type = (header >> 3) & 0x0F;
+        let has_extension = header & 0x04 != 0;
+        let has_size = header & 0x02 != 0;
+        i += 1 + has_extension as usize;
+
+        let size = if has_size {
+            let Some((size, n)) = sample.get(i..).and_then(read_leb128) else {
+                break;
+            };
+            i += n;
+            size as usize
+        } else {
+            // Without a size field the OBU runs to the end of the sample.
+            sample.len().saturating_sub(i)
+        };
+        if i + size > sample.len() {
+            break;
         }
+        out.push((obu_type, &sample[i..i + size]));
+        i += size;
     }
+    out
+}
 
+// Decode telemetry from metadata OBUs: ITU-T T.35 and user-private metadata payloads go
+// through the same heuristics as SEI user_data_unregistered, and are filtered as payload type 5.
+// Neither carries a UUID.
+fn decode_metadata_obus(
+    sample: &[u8],
+    payload_types: Option<&[u32]>,
+    stats: &mut DecodeStats,
+) -> Vec<DecodedSei> {
+    let mut out = Vec::new();
+    if !payload_type_wanted(payload_types, 5) {
+        return out;
+    }
+    for (obu_type, obu) in split_obus(sample) {
+        if obu_type != OBU_METADATA {
+            continue;
+        }
+        let Some((metadata_type, n)) = read_leb128(obu) else {
+            continue;
+        };
+        let mut payload = &obu[n..];
+        if metadata_type == METADATA_TYPE_ITUT_T35 {
+            // itu_t_t35_country_code, plus an extension byte when it is 0xFF
+            let skip = if payload.first() == Some(&0xFF) { 2 } else { 1 };
+            payload = payload.get(skip..).unwrap_or_default();
+        } else if !METADATA_TYPE_USER_PRIVATE.contains(&metadata_type) {
+            continue;
+        }
+        let decoded = try_decode_sei_metadata_from_payload(5, payload, None, stats);
+        out.extend(decoded);
+    }
     out
 }
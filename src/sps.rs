@@ -0,0 +1,579 @@
+// Sequence parameter set parsing (H.264 7.3.2.1, H.265 7.3.2.2).
+//
+// Only the fields needed for the displayed frame size, frame rate and pic_timing SEI layout are
+// decoded; the rest of the VUI is ignored. Any truncated or unsupported SPS yields `None`.
+
+use crate::sei::remove_emulation_prevention;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SpsInfo {
+    // Frame size after the cropping / conformance window is applied.
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    // From VUI timing info, when signalled.
+    pub(crate) frame_rate: Option<f64>,
+    // VUI (num_units_in_tick, time_scale), the units of pic_timing / time_code clock timestamps.
+    pub(crate) timing: Option<(u32, u32)>,
+    // How H.264 pic_timing SEI messages are laid out; always `None` for H.265.
+    pub(crate) pic_timing: Option<PicTimingLayout>,
+}
+
+// The VUI fields that determine the syntax of an H.264 pic_timing SEI message.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct PicTimingLayout {
+    // (cpb_removal_delay, dpb_output_delay) lengths in bits; `Some` when CpbDpbDelaysPresentFlag.
+    pub(crate) delay_lengths: Option<(u32, u32)>,
+    pub(crate) pic_struct_present: bool,
+    pub(crate) time_offset_length: u32,
+}
+
+// MSB-first bit reader over an RBSP (emulation prevention bytes already removed).
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    pub(crate) fn bit(&mut self) -> Option<u32> {
+        let byte = *self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Some(bit as u32)
+    }
+
+    pub(crate) fn flag(&mut self) -> Option<bool> {
+        Some(self.bit()? == 1)
+    }
+
+    // u(n), n <= 32.
+    pub(crate) fn bits(&mut self, n: u32) -> Option<u32> {
+        let mut v = 0u32;
+        for _ in 0..n {
+            v = (v << 1) | self.bit()?;
+        }
+        Some(v)
+    }
+
+    pub(crate) fn skip(&mut self, n: usize) -> Option<()> {
+        self.pos += n;
+        (self.pos <= self.data.len() * 8).then_some(())
+    }
+
+    // ue(v): exp-Golomb, values up to 2^32 - 2.
+    fn ue(&mut self) -> Option<u32> {
+        let mut zeros = 0u32;
+        while self.bit()? == 0 {
+            zeros += 1;
+            if zeros > 31 {
+                return None;
+            }
+        }
+        let rest = self.bits(zeros)?;
+        Some(((1u64 << zeros) - 1 + rest as u64) as u32)
+    }
+
+    // se(v)
+    fn se(&mut self) -> Option<i32> {
+        let k = self.ue()? as i64;
+        let v = if k % 2 == 1 { (k + 1) / 2 } else { -(k / 2) };
+        Some(v as i32)
+    }
+
+    // i(n): n-bit two's complement, n <= 32.
+    pub(crate) fn signed(&mut self, n: u32) -> Option<i32> {
+        let v = self.bits(n)? as i64;
+        if n == 0 || v < 1 << (n - 1) {
+            return Some(v as i32);
+        }
+        Some((v - (1 << n)) as i32)
+    }
+}
+
+// Subsampling factors (SubWidthC, SubHeightC) for a chroma_format_idc; 4:0:0 and separately
+// coded planes crop in luma samples.
+fn chroma_subsampling(chroma_format_idc: u32, separate_colour_plane: bool) -> (u32, u32) {
+    match chroma_format_idc {
+        _ if separate_colour_plane => (1, 1),
+        1 => (2, 2),
+        2 => (2, 1),
+        _ => (1, 1),
+    }
+}
+
+// Cropped size, or `None` if the window is empty or larger than the coded frame.
+fn cropped(width: u32, height: u32, crop_x: u64, crop_y: u64) -> Option<(u32, u32)> {
+    let width = (width as u64).checked_sub(crop_x)?;
+    let height = (height as u64).checked_sub(crop_y)?;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some((u32::try_from(width).ok()?, u32::try_from(height).ok()?))
+}
+
+fn frame_rate(num_units_in_tick: u32, time_scale: u32, ticks_per_frame: u32) -> Option<f64> {
+    if num_units_in_tick == 0 || time_scale == 0 {
+        return None;
+    }
+    Some(time_scale as f64 / (num_units_in_tick as f64 * ticks_per_frame as f64))
+}
+
+// The VUI fields shared by H.264 and H.265 that precede the codec-specific parts.
+fn skip_vui_prefix(r: &mut BitReader) -> Option<()> {
+    // aspect_ratio_info_present_flag
+    if r.flag()? {
+        let aspect_ratio_idc = r.bits(8)?;
+        // Extended_SAR: sar_width, sar_height
+        if aspect_ratio_idc == 255 {
+            r.skip(32)?;
+        }
+    }
+    // overscan_info_present_flag -> overscan_appropriate_flag
+    if r.flag()? {
+        r.skip(1)?;
+    }
+    // video_signal_type_present_flag
+    if r.flag()? {
+        // video_format, video_full_range_flag
+        r.skip(4)?;
+        // colour_description_present_flag -> primaries, transfer, matrix
+        if r.flag()? {
+            r.skip(24)?;
+        }
+    }
+    // chroma_loc_info_present_flag
+    if r.flag()? {
+        r.ue()?;
+        r.ue()?;
+    }
+    Some(())
+}
+
+// H.264 scaling_list(): only the syntax is consumed.
+fn skip_avc_scaling_list(r: &mut BitReader, size: usize) -> Option<()> {
+    let mut last = 8i32;
+    let mut next = 8i32;
+    for _ in 0..size {
+        if next != 0 {
+            let delta = r.se()?;
+            next = (last + delta + 256) % 256;
+        }
+        if next != 0 {
+            last = next;
+        }
+    }
+    Some(())
+}
+
+// Parse an H.264 SPS NAL unit (including its 1-byte NAL header).
+pub(crate) fn parse_avc_sps(nal: &[u8]) -> Option<SpsInfo> {
+    if nal.first()? & 0x1F != 7 {
+        return None;
+    }
+    let rbsp = remove_emulation_prevention(&nal[1..]);
+    let mut r = BitReader::new(&rbsp);
+
+    let profile_idc = r.bits(8)?;
+    // constraint_set flags + reserved, level_idc
+    r.skip(16)?;
+    // seq_parameter_set_id
+    r.ue()?;
+
+    let mut chroma_format_idc = 1;
+    let mut separate_colour_plane = false;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = r.ue()?;
+        if chroma_format_idc == 3 {
+            separate_colour_plane = r.flag()?;
+        }
+        // bit_depth_luma_minus8, bit_depth_chroma_minus8
+        r.ue()?;
+        r.ue()?;
+        // qpprime_y_zero_transform_bypass_flag
+        r.skip(1)?;
+        // seq_scaling_matrix_present_flag
+        if r.flag()? {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if r.flag()? {
+                    skip_avc_scaling_list(&mut r, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    // log2_max_frame_num_minus4
+    r.ue()?;
+    match r.ue()? {
+        // log2_max_pic_order_cnt_lsb_minus4
+        0 => {
+            r.ue()?;
+        }
+        1 => {
+            // delta_pic_order_always_zero_flag
+            r.skip(1)?;
+            // offset_for_non_ref_pic, offset_for_top_to_bottom_field
+            r.se()?;
+            r.se()?;
+            let cycle = r.ue()?;
+            for _ in 0..cycle {
+                r.se()?;
+            }
+        }
+        _ => {}
+    }
+    // max_num_ref_frames, gaps_in_frame_num_value_allowed_flag
+    r.ue()?;
+    r.skip(1)?;
+
+    let width_mbs = r.ue()? as u64 + 1;
+    let height_map_units = r.ue()? as u64 + 1;
+    let frame_mbs_only = r.flag()?;
+    if !frame_mbs_only {
+        // mb_adaptive_frame_field_flag
+        r.skip(1)?;
+    }
+    // direct_8x8_inference_flag
+    r.skip(1)?;
+
+    let field_factor = if frame_mbs_only { 1 } else { 2 };
+    let width = u32::try_from(width_mbs * 16).ok()?;
+    let height = u32::try_from(height_map_units * 16 * field_factor).ok()?;
+
+    let (mut crop_x, mut crop_y) = (0u64, 0u64);
+    // frame_cropping_flag
+    if r.flag()? {
+        let (sub_w, sub_h) = chroma_subsampling(chroma_format_idc, separate_colour_plane);
+        let (left, right, top, bottom) = (r.ue()?, r.ue()?, r.ue()?, r.ue()?);
+        crop_x = sub_w as u64 * (left as u64 + right as u64);
+        crop_y = sub_h as u64 * field_factor * (top as u64 + bottom as u64);
+    }
+    let (width, height) = cropped(width, height, crop_x, crop_y)?;
+
+    let mut info = SpsInfo {
+        width,
+        height,
+        frame_rate: None,
+        timing: None,
+        pic_timing: None,
+    };
+    // vui_parameters_present_flag; a truncated VUI still leaves a usable size.
+    if r.flag() == Some(true) {
+        parse_avc_vui(&mut r, &mut info);
+    }
+    Some(info)
+}
+
+// Fills in what it can before the VUI runs out.
+fn parse_avc_vui(r: &mut BitReader, info: &mut SpsInfo) -> Option<()> {
+    skip_vui_prefix(r)?;
+    // timing_info_present_flag
+    if r.flag()? {
+        let num_units_in_tick = r.bits(32)?;
+        let time_scale = r.bits(32)?;
+        // fixed_frame_rate_flag
+        r.skip(1)?;
+        // H.264 ticks count fields, two per frame.
+        info.frame_rate = frame_rate(num_units_in_tick, time_scale, 2);
+        info.timing = Some((num_units_in_tick, time_scale));
+    }
+
+    // nal_hrd_parameters_present_flag, vcl_hrd_parameters_present_flag; both carry the same
+    // lengths when present.
+    let mut hrd = None;
+    for _ in 0..2 {
+        if r.flag()? {
+            let lengths = parse_avc_hrd_parameters(r)?;
+            hrd.get_or_insert(lengths);
+        }
+    }
+    if hrd.is_some() {
+        // low_delay_hrd_flag
+        r.skip(1)?;
+    }
+    info.pic_timing = Some(PicTimingLayout {
+        delay_lengths: hrd.map(|(cpb, dpb, _)| (cpb, dpb)),
+        pic_struct_present: r.flag()?,
+        time_offset_length: hrd.map_or(24, |(_, _, t)| t),
+    });
+    Some(())
+}
+
+// H.264 hrd_parameters(); returns the cpb_removal_delay, dpb_output_delay and time_offset
+// lengths in bits.
+fn parse_avc_hrd_parameters(r: &mut BitReader) -> Option<(u32, u32, u32)> {
+    let cpb_cnt = r.ue()? + 1;
+    if cpb_cnt > 32 {
+        return None;
+    }
+    // bit_rate_scale, cpb_size_scale
+    r.skip(8)?;
+    for _ in 0..cpb_cnt {
+        // bit_rate_value_minus1, cpb_size_value_minus1, cbr_flag
+        r.ue()?;
+        r.ue()?;
+        r.skip(1)?;
+    }
+    // initial_cpb_removal_delay_length_minus1
+    r.skip(5)?;
+    let cpb_removal_delay_length = r.bits(5)? + 1;
+    let dpb_output_delay_length = r.bits(5)? + 1;
+    let time_offset_length = r.bits(5)?;
+    Some((
+        cpb_removal_delay_length,
+        dpb_output_delay_length,
+        time_offset_length,
+    ))
+}
+
+// H.265 profile_tier_level(1, max_sub_layers_minus1): only the syntax is consumed.
+fn skip_hevc_profile_tier_level(r: &mut BitReader, max_sub_layers_minus1: u32) -> Option<()> {
+    // general profile_space .. general_level_idc
+    r.skip(96)?;
+    let mut sub_layers = Vec::new();
+    for _ in 0..max_sub_layers_minus1 {
+        // sub_layer_profile_present_flag, sub_layer_level_present_flag
+        sub_layers.push((r.flag()?, r.flag()?));
+    }
+    if max_sub_layers_minus1 > 0 {
+        // reserved_zero_2bits
+        r.skip(2 * (8 - max_sub_layers_minus1 as usize))?;
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            r.skip(88)?;
+        }
+        if level_present {
+            r.skip(8)?;
+        }
+    }
+    Some(())
+}
+
+// H.265 scaling_list_data(): only the syntax is consumed.
+fn skip_hevc_scaling_list_data(r: &mut BitReader) -> Option<()> {
+    for size_id in 0..4u32 {
+        let step = if size_id == 3 { 3 } else { 1 };
+        for _ in (0..6).step_by(step) {
+            // scaling_list_pred_mode_flag
+            if !r.flag()? {
+                // scaling_list_pred_matrix_id_delta
+                r.ue()?;
+            } else {
+                let coef_num = 64.min(1 << (4 + (size_id << 1)));
+                if size_id > 1 {
+                    // scaling_list_dc_coef_minus8
+                    r.se()?;
+                }
+                for _ in 0..coef_num {
+                    r.se()?;
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+// H.265 st_ref_pic_set(idx) in an SPS; returns NumDeltaPocs[idx].
+fn skip_hevc_st_ref_pic_set(r: &mut BitReader, idx: usize, num_delta_pocs: &[u32]) -> Option<u32> {
+    // inter_ref_pic_set_prediction_flag
+    if idx != 0 && r.flag()? {
+        // delta_rps_sign, abs_delta_rps_minus1; in an SPS the reference is always the previous set.
+        r.skip(1)?;
+        r.ue()?;
+        let mut count = 0;
+        for _ in 0..=num_delta_pocs[idx - 1] {
+            // used_by_curr_pic_flag, else use_delta_flag
+            let used = r.flag()?;
+            if used || r.flag()? {
+                count += 1;
+            }
+        }
+        return Some(count);
+    }
+    let num_negative = r.ue()?;
+    let num_positive = r.ue()?;
+    // Bounded by sps_max_dec_pic_buffering; anything larger is corrupt.
+    if num_negative > 16 || num_positive > 16 {
+        return None;
+    }
+    for _ in 0..num_negative + num_positive {
+        // delta_poc_sN_minus1, used_by_curr_pic_sN_flag
+        r.ue()?;
+        r.skip(1)?;
+    }
+    Some(num_negative + num_positive)
+}
+
+// Parse an H.265 SPS NAL unit (including its 2-byte NAL header).
+pub(crate) fn parse_hevc_sps(nal: &[u8]) -> Option<SpsInfo> {
+    if (nal.first()? >> 1) & 0x3F != 33 || nal.len() < 2 {
+        return None;
+    }
+    let rbsp = remove_emulation_prevention(&nal[2..]);
+    let mut r = BitReader::new(&rbsp);
+
+    // sps_video_parameter_set_id
+    r.skip(4)?;
+    let max_sub_layers_minus1 = r.bits(3)?;
+    if max_sub_layers_minus1 > 6 {
+        return None;
+    }
+    // sps_temporal_id_nesting_flag
+    r.skip(1)?;
+    skip_hevc_profile_tier_level(&mut r, max_sub_layers_minus1)?;
+    // sps_seq_parameter_set_id
+    r.ue()?;
+
+    let chroma_format_idc = r.ue()?;
+    let separate_colour_plane = chroma_format_idc == 3 && r.flag()?;
+    let width = r.ue()?;
+    let height = r.ue()?;
+
+    let (mut crop_x, mut crop_y) = (0u64, 0u64);
+    // conformance_window_flag
+    if r.flag()? {
+        let (sub_w, sub_h) = chroma_subsampling(chroma_format_idc, separate_colour_plane);
+        let (left, right, top, bottom) = (r.ue()?, r.ue()?, r.ue()?, r.ue()?);
+        crop_x = sub_w as u64 * (left as u64 + right as u64);
+        crop_y = sub_h as u64 * (top as u64 + bottom as u64);
+    }
+    let (width, height) = cropped(width, height, crop_x, crop_y)?;
+
+    let timing = hevc_vui_timing(&mut r, max_sub_layers_minus1);
+    Some(SpsInfo {
+        width,
+        height,
+        frame_rate: timing.and_then(|(units, scale)| frame_rate(units, scale, 1)),
+        timing,
+        pic_timing: None,
+    })
+}
+
+// Walks the rest of the SPS up to the VUI timing info; returns (num_units_in_tick, time_scale).
+fn hevc_vui_timing(r: &mut BitReader, max_sub_layers_minus1: u32) -> Option<(u32, u32)> {
+    // bit_depth_luma_minus8, bit_depth_chroma_minus8
+    r.ue()?;
+    r.ue()?;
+    let log2_max_poc_lsb = r.ue()? + 4;
+    if log2_max_poc_lsb > 16 {
+        return None;
+    }
+    // sps_sub_layer_ordering_info_present_flag
+    let first = if r.flag()? { 0 } else { max_sub_layers_minus1 };
+    for _ in first..=max_sub_layers_minus1 {
+        // max_dec_pic_buffering_minus1, max_num_reorder_pics, max_latency_increase_plus1
+        r.ue()?;
+        r.ue()?;
+        r.ue()?;
+    }
+    // log2_min_luma_coding_block_size_minus3 .. max_transform_hierarchy_depth_intra
+    for _ in 0..6 {
+        r.ue()?;
+    }
+    // scaling_list_enabled_flag -> sps_scaling_list_data_present_flag
+    if r.flag()? && r.flag()? {
+        skip_hevc_scaling_list_data(r)?;
+    }
+    // amp_enabled_flag, sample_adaptive_offset_enabled_flag
+    r.skip(2)?;
+    // pcm_enabled_flag
+    if r.flag()? {
+        // pcm bit depths (4 + 4)
+        r.skip(8)?;
+        // log2_min_pcm_luma_coding_block_size_minus3, log2_diff_max_min_pcm_luma_coding_block_size
+        r.ue()?;
+        r.ue()?;
+        // pcm_loop_filter_disabled_flag
+        r.skip(1)?;
+    }
+
+    let num_short_term_ref_pic_sets = r.ue()? as usize;
+    if num_short_term_ref_pic_sets > 64 {
+        return None;
+    }
+    let mut num_delta_pocs = Vec::with_capacity(num_short_term_ref_pic_sets);
+    for i in 0..num_short_term_ref_pic_sets {
+        let n = skip_hevc_st_ref_pic_set(r, i, &num_delta_pocs)?;
+        num_delta_pocs.push(n);
+    }
+    // long_term_ref_pics_present_flag
+    if r.flag()? {
+        let num_long_term = r.ue()?;
+        if num_long_term > 32 {
+            return None;
+        }
+        for _ in 0..num_long_term {
+            // lt_ref_pic_poc_lsb_sps, used_by_curr_pic_lt_sps_flag
+            r.skip(log2_max_poc_lsb as usize + 1)?;
+        }
+    }
+    // sps_temporal_mvp_enabled_flag, strong_intra_smoothing_enabled_flag
+    r.skip(2)?;
+    // vui_parameters_present_flag
+    if !r.flag()? {
+        return None;
+    }
+
+    skip_vui_prefix(r)?;
+    // neutral_chroma_indication_flag, field_seq_flag, frame_field_info_present_flag
+    r.skip(3)?;
+    // default_display_window_flag
+    if r.flag()? {
+        for _ in 0..4 {
+            r.ue()?;
+        }
+    }
+    // vui_timing_info_present_flag
+    if !r.flag()? {
+        return None;
+    }
+    let num_units_in_tick = r.bits(32)?;
+    let time_scale = r.bits(32)?;
+    Some((num_units_in_tick, time_scale))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // High profile, 1280x960 (80x60 macroblocks, no cropping), VUI timing of 1/60 s ticks, so
+    // 30 fps, and no HRD. The two zero bytes of num_units_in_tick carry an emulation
+    // prevention byte.
+    pub(crate) const SPS_1280X960: [u8; 20] = [
+        0x67, 0x64, 0x00, 0x28, 0xac, 0xda, 0x01, 0x40, 0x1e, 0x68, 0x40, 0x00, 0x00, 0x03, 0x00,
+        0x40, 0x00, 0x00, 0x0f, 0x21,
+    ];
+
+    #[test]
+    fn avc_sps_size_and_timing() {
+        let info = parse_avc_sps(&SPS_1280X960).unwrap();
+        assert_eq!((info.width, info.height), (1280, 960));
+        assert_eq!(info.frame_rate, Some(30.0));
+        assert_eq!(info.timing, Some((1, 60)));
+        assert_eq!(
+            info.pic_timing,
+            Some(PicTimingLayout {
+                delay_lengths: None,
+                pic_struct_present: false,
+                time_offset_length: 24,
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_avc_sps() {
+        // Cut inside the frame size: nothing usable.
+        assert_eq!(parse_avc_sps(&SPS_1280X960[..6]), None);
+        // Cut inside the VUI timing: the size survives, the frame rate doesn't.
+        let info = parse_avc_sps(&SPS_1280X960[..12]).unwrap();
+        assert_eq!((info.width, info.height), (1280, 960));
+        assert_eq!(info.frame_rate, None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::extract::extractor_from_reader;
+use crate::geo::{GeoPoint, STANDARD_GRAVITY_MPS2};
+use crate::pb::sei_metadata::{AutopilotState, Gear};
+use crate::Error;
+
+/// Time spent in each gear.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GearTimes {
+    pub park: Duration,
+    pub drive: Duration,
+    pub reverse: Duration,
+    pub neutral: Duration,
+    /// Gear values this crate's schema doesn't know.
+    pub other: Duration,
+}
+
+/// The smallest latitude/longitude box containing every GPS fix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBounds {
+    pub min_latitude_deg: f64,
+    pub min_longitude_deg: f64,
+    pub max_latitude_deg: f64,
+    pub max_longitude_deg: f64,
+}
+
+impl GeoBounds {
+    /// The box spanned by two opposite corners, given in either order.
+    pub fn from_corners(a: GeoPoint, b: GeoPoint) -> Self {
+        GeoBounds {
+            min_latitude_deg: a.latitude_deg.min(b.latitude_deg),
+            min_longitude_deg: a.longitude_deg.min(b.longitude_deg),
+            max_latitude_deg: a.latitude_deg.max(b.latitude_deg),
+            max_longitude_deg: a.longitude_deg.max(b.longitude_deg),
+        }
+    }
+
+    /// Whether `point` lies inside the box or on its edge.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        (self.min_latitude_deg..=self.max_latitude_deg).contains(&point.latitude_deg)
+            && (self.min_longitude_deg..=self.max_longitude_deg).contains(&point.longitude_deg)
+    }
+}
+
+/// Whole-clip telemetry totals from [`clip_summary`].
+///
+/// Durations weight each frame by the time until the next one (the last frame runs to the end
+/// of the track), so dropped or irregular frames don't skew them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClipSummary {
+    /// Decoded telemetry messages.
+    pub frames: usize,
+    /// Length of the video track, if the container records one.
+    pub duration: Option<Duration>,
+    /// Distance along consecutive GPS fixes, in meters.
+    pub distance_m: f64,
+    /// Speed range and time-weighted mean, in m/s; `None` without telemetry.
+    pub min_speed_mps: Option<f64>,
+    pub max_speed_mps: Option<f64>,
+    pub mean_speed_mps: Option<f64>,
+    pub gear_time: GearTimes,
+    /// Share of the clip (0.0 to 1.0) with autopilot in any mode; `None` without telemetry.
+    pub autopilot_fraction: Option<f64>,
+    /// Strongest horizontal acceleration (braking, cornering, impact) in g.
+    pub max_horizontal_g: Option<f64>,
+    /// `None` if the clip has no GPS fix.
+    pub bounds: Option<GeoBounds>,
+}
+
+/// Summarize the telemetry of an on-disk MP4.
+pub fn clip_summary(path: impl AsRef<Path>) -> Result<ClipSummary, Error> {
+    clip_summary_reader(File::open(path)?)
+}
+
+/// Summarize the telemetry of any seekable reader.
+pub fn clip_summary_reader<R: Read + Seek>(reader: R) -> Result<ClipSummary, Error> {
+    let mut extractor = extractor_from_reader(reader)?;
+    let duration = extractor.duration();
+
+    let mut frames = Vec::new();
+    while let Some(event) = extractor.next_event()? {
+        frames.push((event.presentation_time, event.metadata));
+    }
+    // Decode order differs from display order on B-frame encodes.
+    frames.sort_by_key(|(t, m)| (*t, m.frame_seq_no));
+
+    // Time each frame is on screen. Without timing, frames share the track duration equally.
+    let even_share = match (duration, frames.len()) {
+        (Some(d), n) if n > 0 => d / n as u32,
+        _ => Duration::ZERO,
+    };
+    let mut shown = Vec::with_capacity(frames.len());
+    for (i, (t, _)) in frames.iter().enumerate() {
+        let next = match frames.get(i + 1) {
+            Some((next, _)) => *next,
+            None => duration,
+        };
+        shown.push(match (t, next) {
+            (Some(t), Some(next)) if next > *t => next - *t,
+            (Some(_), _) => shown.last().copied().unwrap_or(even_share),
+            (None, _) => even_share,
+        });
+    }
+
+    let mut summary = ClipSummary {
+        frames: frames.len(),
+        duration,
+        distance_m: 0.0,
+        min_speed_mps: None,
+        max_speed_mps: None,
+        mean_speed_mps: None,
+        gear_time: GearTimes::default(),
+        autopilot_fraction: None,
+        max_horizontal_g: None,
+        bounds: None,
+    };
+    let mut total = Duration::ZERO;
+    let mut autopilot = Duration::ZERO;
+    let mut speed_time = 0.0;
+    let mut last_fix: Option<GeoPoint> = None;
+
+    for ((_, m), dt) in frames.iter().zip(shown) {
+        let speed = f64::from(m.vehicle_speed_mps);
+        summary.min_speed_mps = Some(summary.min_speed_mps.map_or(speed, |s| s.min(speed)));
+        summary.max_speed_mps = Some(summary.max_speed_mps.map_or(speed, |s| s.max(speed)));
+        speed_time += speed * dt.as_secs_f64();
+        total += dt;
+
+        let gear = &mut summary.gear_time;
+        *match Gear::try_from(m.gear_state) {
+            Ok(Gear::Park) => &mut gear.park,
+            Ok(Gear::Drive) => &mut gear.drive,
+            Ok(Gear::Reverse) => &mut gear.reverse,
+            Ok(Gear::Neutral) => &mut gear.neutral,
+            Err(_) => &mut gear.other,
+        } += dt;
+        if m.autopilot_state != AutopilotState::None as i32 {
+            autopilot += dt;
+        }
+
+        let g = m
+            .linear_acceleration_mps2_x
+            .hypot(m.linear_acceleration_mps2_y)
+            / STANDARD_GRAVITY_MPS2;
+        if g.is_finite() {
+            summary.max_horizontal_g = Some(summary.max_horizontal_g.map_or(g, |x| x.max(g)));
+        }
+
+        if let Some(fix) = GeoPoint::from_metadata(m) {
+            if let Some(prev) = last_fix {
+                summary.distance_m += prev.distance_m(&fix);
+            }
+            last_fix = Some(fix);
+            let b = summary.bounds.get_or_insert(GeoBounds {
+                min_latitude_deg: fix.latitude_deg,
+                min_longitude_deg: fix.longitude_deg,
+                max_latitude_deg: fix.latitude_deg,
+                max_longitude_deg: fix.longitude_deg,
+            });
+            b.min_latitude_deg = b.min_latitude_deg.min(fix.latitude_deg);
+            b.min_longitude_deg = b.min_longitude_deg.min(fix.longitude_deg);
+            b.max_latitude_deg = b.max_latitude_deg.max(fix.latitude_deg);
+            b.max_longitude_deg = b.max_longitude_deg.max(fix.longitude_deg);
+        }
+    }
+
+    if !frames.is_empty() {
+        let total_s = total.as_secs_f64();
+        // With no usable timing at all, fall back to per-frame averages.
+        let (mean, fraction) = if total_s > 0.0 {
+            (speed_time / total_s, autopilot.as_secs_f64() / total_s)
+        } else {
+            let n = frames.len() as f64;
+            let speeds: f64 = frames
+                .iter()
+                .map(|(_, m)| f64::from(m.vehicle_speed_mps))
+                .sum();
+            let engaged = frames
+                .iter()
+                .filter(|(_, m)| m.autopilot_state != AutopilotState::None as i32)
+                .count();
+            (speeds / n, engaged as f64 / n)
+        };
+        summary.mean_speed_mps = Some(mean);
+        summary.autopilot_fraction = Some(fraction);
+    }
+    Ok(summary)
+}
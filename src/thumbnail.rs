@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::extract::{extractor_from_reader, SeiEvent};
+use crate::Error;
+
+/// Why a moment was suggested as a thumbnail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HintKind {
+    /// The strongest horizontal acceleration (hard braking, swerving, impact) above
+    /// [`ThumbnailOptions::harsh_accel_mps2`].
+    HarshEvent,
+    /// The highest vehicle speed in the clip.
+    MaxSpeed,
+    /// The middle of the part of the clip where the car is moving.
+    MidDrive,
+    /// The middle of the clip, used when the telemetry offers nothing better (parked, no SEI).
+    ClipMiddle,
+}
+
+/// A suggested preview frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailHint {
+    pub kind: HintKind,
+    /// 0-based sample index in the selected track (decode order).
+    pub sample_index: usize,
+    /// When the frame is displayed, relative to the start of the clip. Seek here in a player or
+    /// thumbnailer such as `ffmpeg -ss`.
+    pub presentation_time: Option<Duration>,
+}
+
+/// Thresholds for [`thumbnail_hints`].
+#[derive(Debug, Clone)]
+pub struct ThumbnailOptions {
+    /// Minimum horizontal acceleration, in m/s², for a [`HintKind::HarshEvent`] hint.
+    pub harsh_accel_mps2: f64,
+    /// Speed above which the car counts as moving for [`HintKind::MidDrive`], in m/s.
+    pub moving_speed_mps: f64,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            // ~0.4 g: well past normal braking and cornering.
+            harsh_accel_mps2: 4.0,
+            moving_speed_mps: 2.0,
+        }
+    }
+}
+
+/// Suggest representative frames of an on-disk MP4 for preview generators.
+pub fn thumbnail_hints(
+    path: impl AsRef<Path>,
+    opts: &ThumbnailOptions,
+) -> Result<Vec<ThumbnailHint>, Error> {
+    thumbnail_hints_reader(File::open(path)?, opts)
+}
+
+/// Suggest representative frames from any seekable reader.
+///
+/// Hints are returned most interesting first (harsh event, max speed, mid-drive), with at most
+/// one hint per sample. If none of those apply, a single [`HintKind::ClipMiddle`] hint is
+/// returned. Only an empty track yields no hints.
+pub fn thumbnail_hints_reader<R: Read + Seek>(
+    reader: R,
+    opts: &ThumbnailOptions,
+) -> Result<Vec<ThumbnailHint>, Error> {
+    let mut extractor = extractor_from_reader(reader)?;
+
+    let mut harsh: Option<(f64, SeiEvent)> = None;
+    let mut fastest: Option<(f64, SeiEvent)> = None;
+    let mut moving: Vec<SeiEvent> = Vec::new();
+
+    while let Some(event) = extractor.next_event()? {
+        let m = &event.metadata;
+        let accel = m
+            .linear_acceleration_mps2_x
+            .hypot(m.linear_acceleration_mps2_y);
+        let speed = m.vehicle_speed_mps as f64;
+
+        if accel.is_finite()
+            && accel >= opts.harsh_accel_mps2
+            && harsh.as_ref().is_none_or(|(best, _)| accel > *best)
+        {
+            harsh = Some((accel, event.clone()));
+        }
+        if speed.is_finite()
+            && speed > 0.0
+            && fastest.as_ref().is_none_or(|(best, _)| speed > *best)
+        {
+            fastest = Some((speed, event.clone()));
+        }
+        if speed >= opts.moving_speed_mps {
+            moving.push(event);
+        }
+    }
+
+    moving.sort_by_key(|e| e.presentation_index);
+    let mid_drive = moving.get(moving.len() / 2).cloned();
+
+    let mut hints: Vec<ThumbnailHint> = Vec::new();
+    let candidates = [
+        (HintKind::HarshEvent, harsh.map(|(_, e)| e)),
+        (HintKind::MaxSpeed, fastest.map(|(_, e)| e)),
+        (HintKind::MidDrive, mid_drive),
+    ];
+    for (kind, event) in candidates {
+        if let Some(e) = event
+            && !hints.iter().any(|h| h.sample_index == e.sample_index)
+        {
+            hints.push(ThumbnailHint {
+                kind,
+                sample_index: e.sample_index,
+                presentation_time: e.presentation_time,
+            });
+        }
+    }
+
+    let total = extractor.total_samples();
+    if hints.is_empty() && total > 0 {
+        // Middle of the clip in display order.
+        let middle = extractor.presentation_order()[total / 2];
+        hints.push(ThumbnailHint {
+            kind: HintKind::ClipMiddle,
+            sample_index: middle,
+            presentation_time: extractor.presentation_time(middle),
+        });
+    }
+
+    Ok(hints)
+}
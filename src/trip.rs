@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+
+use crate::catalog::{Catalog, CatalogClip};
+use crate::extract::extractor_from_path;
+use crate::geo::GeoPoint;
+use crate::Error;
+
+/// Options for grouping catalog clips into trips.
+#[derive(Debug, Clone)]
+pub struct TripOptions {
+    /// Largest gap between the end of one clip and the start of the next within a trip.
+    pub max_gap: Duration,
+    /// Trips shorter than this (e.g. parked Sentry recordings) are dropped.
+    pub min_distance_m: f64,
+    /// GPS fixes closer than this to the previously kept point are dropped from the path.
+    ///
+    /// This keeps paths small and stops GPS jitter while stationary from adding distance.
+    pub min_point_spacing_m: f64,
+}
+
+impl Default for TripOptions {
+    fn default() -> Self {
+        TripOptions {
+            max_gap: Duration::from_secs(120),
+            min_distance_m: 100.0,
+            min_point_spacing_m: 5.0,
+        }
+    }
+}
+
+/// A continuous drive assembled from consecutive clips.
+#[derive(Debug, Clone)]
+pub struct Trip {
+    /// Clips making up the trip, in order (one camera per recorded minute).
+    pub clips: Vec<PathBuf>,
+    /// Local start time of the first clip, if known.
+    pub start: Option<NaiveDateTime>,
+    /// Local end time of the last clip, if known.
+    pub end: Option<NaiveDateTime>,
+    /// Total recorded duration of the trip's clips.
+    pub duration: Duration,
+    /// Distance along the GPS path, in meters.
+    pub distance_m: f64,
+    /// Simplified GPS path (see [`TripOptions::min_point_spacing_m`]).
+    pub path: Vec<GeoPoint>,
+}
+
+// Clips without a duration in their headers are assumed to be one TeslaCam minute.
+const DEFAULT_CLIP_DURATION: Duration = Duration::from_secs(60);
+
+impl Catalog {
+    /// Group the catalog's clips into trips.
+    ///
+    /// A new trip starts whenever the gap between clips exceeds [`TripOptions::max_gap`], or
+    /// a clip has no TeslaCam timestamp. Clips that fail to parse are skipped; IO errors are
+    /// returned.
+    pub fn trips(&self, opts: &TripOptions) -> Result<Vec<Trip>, Error> {
+        let mut trips: Vec<Trip> = Vec::new();
+        let mut current: Option<Trip> = None;
+
+        for clip in self.telemetry_clips() {
+            let Some((duration, points)) = read_clip_path(clip, opts)? else {
+                continue;
+            };
+
+            // Overlapping clips (negative gap) always continue the trip.
+            let continues = match (current.as_ref().and_then(|t| t.end), clip.start()) {
+                (Some(end), Some(start)) => (start - end)
+                    .to_std()
+                    .map_or(true, |gap| gap <= opts.max_gap),
+                _ => false,
+            };
+
+            if !continues {
+                trips.extend(current.take());
+            }
+            let trip = current.get_or_insert_with(|| Trip {
+                clips: Vec::new(),
+                start: clip.start(),
+                end: None,
+                duration: Duration::ZERO,
+                distance_m: 0.0,
+                path: Vec::new(),
+            });
+
+            trip.clips.push(clip.path.clone());
+            trip.end = clip.start().map(|start| start + duration);
+            trip.duration += duration;
+            for p in points {
+                push_point(trip, p, opts.min_point_spacing_m);
+            }
+        }
+        trips.extend(current);
+
+        trips.retain(|t| t.distance_m >= opts.min_distance_m);
+        Ok(trips)
+    }
+}
+
+fn push_point(trip: &mut Trip, p: GeoPoint, min_spacing_m: f64) {
+    match trip.path.last() {
+        Some(last) => {
+            let d = last.distance_m(&p);
+            if d >= min_spacing_m {
+                trip.distance_m += d;
+                trip.path.push(p);
+            }
+        }
+        None => trip.path.push(p),
+    }
+}
+
+fn read_clip_path(
+    clip: &CatalogClip,
+    opts: &TripOptions,
+) -> Result<Option<(Duration, Vec<GeoPoint>)>, Error> {
+    let mut extractor = match extractor_from_path(&clip.path) {
+        Ok(e) => e,
+        Err(Error::Io(e)) => return Err(Error::Io(e)),
+        Err(_) => return Ok(None),
+    };
+    let duration = extractor.duration().unwrap_or(DEFAULT_CLIP_DURATION);
+
+    let mut points: Vec<GeoPoint> = Vec::new();
+    while let Some(event) = extractor.next_event()? {
+        let Some(p) = GeoPoint::from_metadata(&event.metadata) else {
+            continue;
+        };
+        if points
+            .last()
+            .is_none_or(|last| last.distance_m(&p) >= opts.min_point_spacing_m)
+        {
+            points.push(p);
+        }
+    }
+
+    Ok(Some((duration, points)))
+}
@@ -0,0 +1,239 @@
+//! Strict syntax checks for the SEI NAL units in a track.
+//!
+//! The extractor is deliberately lenient: it reads whatever messages it can and skips the rest.
+//! [`SeiExtractor::validate_sei`](crate::SeiExtractor::validate_sei) instead reports every place
+//! a sample departs from the H.264/H.265 SEI syntax, for checking the output of
+//! [`inject_sei`](crate::inject_sei) and friends or of third-party muxers.
+
+use std::fmt;
+
+use crate::mp4::CodecConfig;
+use crate::sei::remove_emulation_prevention;
+
+/// One syntax violation found by [`SeiExtractor::validate_sei`](crate::SeiExtractor::validate_sei).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeiViolation {
+    /// The 0-based sample index in the selected track.
+    pub sample_index: usize,
+    /// Absolute file offset of the NAL unit's length prefix.
+    pub file_offset: u64,
+    /// NAL unit type, `None` when the sample's NAL framing itself is broken.
+    pub nal_type: Option<u8>,
+    pub kind: SeiViolationKind,
+}
+
+/// What is wrong; byte offsets are relative to the start of the NAL unit (its header included)
+/// for emulation prevention problems, and to the start of the unescaped SEI RBSP otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeiViolationKind {
+    /// A NAL unit length prefix runs past the end of the sample.
+    NalLengthOverrun { declared: usize, available: usize },
+    /// Bytes at the end of the sample too short to hold a length prefix.
+    TrailingSampleBytes { len: usize },
+    /// `0x00 0x00` followed by `0x00`, `0x01` or `0x02`: a sequence emulation prevention should
+    /// have escaped.
+    UnescapedSequence { offset: usize },
+    /// An emulation prevention byte (`0x00 0x00 0x03`) followed by a byte other than
+    /// `0x00`-`0x03`.
+    InvalidEmulationPrevention { offset: usize },
+    /// The RBSP holds no `rbsp_stop_one_bit` at all.
+    MissingTrailingBits,
+    /// The last non-zero byte is not `0x80`: either the stop bit isn't byte-aligned or message
+    /// data was cut short.
+    InvalidTrailingBits { byte: u8 },
+    /// Zero bytes after the `rbsp_trailing_bits`.
+    TrailingZeroBytes { count: usize },
+    /// A `payloadType`/`payloadSize` field runs into the trailing bits.
+    TruncatedMessageHeader { offset: usize },
+    /// A message's `payloadSize` runs past the SEI data.
+    PayloadOverrun {
+        offset: usize,
+        payload_type: u32,
+        payload_size: usize,
+        available: usize,
+    },
+    /// An SEI NAL unit without any message.
+    EmptySei,
+}
+
+impl fmt::Display for SeiViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NalLengthOverrun {
+                declared,
+                available,
+            } => write!(
+                f,
+                "NAL length {declared} runs past the sample ({available} bytes left)"
+            ),
+            Self::TrailingSampleBytes { len } => {
+                write!(f, "{len} bytes after the last NAL unit")
+            }
+            Self::UnescapedSequence { offset } => {
+                write!(f, "unescaped start code prefix at byte {offset}")
+            }
+            Self::InvalidEmulationPrevention { offset } => {
+                write!(f, "emulation prevention byte at {offset} escapes nothing")
+            }
+            Self::MissingTrailingBits => write!(f, "no rbsp_trailing_bits"),
+            Self::InvalidTrailingBits { byte } => {
+                write!(f, "last non-zero byte is 0x{byte:02x}, not 0x80")
+            }
+            Self::TrailingZeroBytes { count } => {
+                write!(f, "{count} zero bytes after rbsp_trailing_bits")
+            }
+            Self::TruncatedMessageHeader { offset } => {
+                write!(f, "message header at byte {offset} is cut short")
+            }
+            Self::PayloadOverrun {
+                offset,
+                payload_type,
+                payload_size,
+                available,
+            } => write!(
+                f,
+                "payload type {payload_type} at byte {offset} declares {payload_size} bytes, \
+                 {available} available"
+            ),
+            Self::EmptySei => write!(f, "SEI NAL unit without messages"),
+        }
+    }
+}
+
+// Violations in one sample as (offset of the NAL length prefix in the sample, NAL type, kind).
+pub(crate) fn validate_sample(
+    codec: &CodecConfig,
+    sample: &[u8],
+) -> Vec<(usize, Option<u8>, SeiViolationKind)> {
+    let (nal_len_size, header_len) = match codec {
+        CodecConfig::Avc { nal_len_size } => (*nal_len_size, 1),
+        CodecConfig::Hevc { nal_len_size } => (*nal_len_size, 2),
+        _ => return Vec::new(),
+    };
+    if !(1..=4).contains(&nal_len_size) {
+        return Vec::new();
+    }
+
+    let mut out = Vec::new();
+    let mut i = 0usize;
+    while i < sample.len() {
+        let prefix = i;
+        if i + nal_len_size > sample.len() {
+            let len = sample.len() - i;
+            out.push((prefix, None, SeiViolationKind::TrailingSampleBytes { len }));
+            break;
+        }
+        let len = sample[i..i + nal_len_size]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        i += nal_len_size;
+        if len > sample.len() - i {
+            let kind = SeiViolationKind::NalLengthOverrun {
+                declared: len,
+                available: sample.len() - i,
+            };
+            out.push((prefix, None, kind));
+            break;
+        }
+        let nal = &sample[i..i + len];
+        i += len;
+
+        let nal_type = match (codec, nal.first()) {
+            (CodecConfig::Avc { .. }, Some(&b)) => b & 0x1F,
+            (_, Some(&b)) => (b >> 1) & 0x3F,
+            (_, None) => continue,
+        };
+        let is_sei = match codec {
+            CodecConfig::Avc { .. } => nal_type == 6,
+            _ => nal_type == 39 || nal_type == 40,
+        };
+        if !is_sei || nal.len() < header_len {
+            continue;
+        }
+        for kind in validate_sei_nal(nal, header_len) {
+            out.push((prefix, Some(nal_type), kind));
+        }
+    }
+    out
+}
+
+fn validate_sei_nal(nal: &[u8], header_len: usize) -> Vec<SeiViolationKind> {
+    let mut out = Vec::new();
+
+    // Emulation prevention, on the escaped bytes.
+    let mut zeros = 0usize;
+    for (offset, &b) in nal.iter().enumerate().skip(header_len) {
+        if zeros >= 2 {
+            match b {
+                0x00..=0x02 => out.push(SeiViolationKind::UnescapedSequence { offset: offset - 2 }),
+                0x03 => {
+                    if let Some(&next) = nal.get(offset + 1)
+                        && next > 0x03
+                    {
+                        out.push(SeiViolationKind::InvalidEmulationPrevention { offset });
+                    }
+                    zeros = 0;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+    }
+
+    // rbsp_trailing_bits: the last non-zero byte must be exactly the byte-aligned stop bit.
+    let rbsp = remove_emulation_prevention(&nal[header_len..]);
+    let Some(stop) = rbsp.iter().rposition(|&b| b != 0) else {
+        out.push(SeiViolationKind::MissingTrailingBits);
+        return out;
+    };
+    if rbsp[stop] != 0x80 {
+        out.push(SeiViolationKind::InvalidTrailingBits { byte: rbsp[stop] });
+    }
+    if stop + 1 < rbsp.len() {
+        let count = rbsp.len() - stop - 1;
+        out.push(SeiViolationKind::TrailingZeroBytes { count });
+    }
+
+    // sei_message()s must exactly fill the bytes before the stop bit.
+    let data = &rbsp[..stop];
+    if data.is_empty() {
+        out.push(SeiViolationKind::EmptySei);
+        return out;
+    }
+    let mut i = 0usize;
+    while i < data.len() {
+        let offset = i;
+        let (Some(payload_type), Some(payload_size)) =
+            (read_ff_coded(data, &mut i), read_ff_coded(data, &mut i))
+        else {
+            out.push(SeiViolationKind::TruncatedMessageHeader { offset });
+            break;
+        };
+        let available = data.len() - i;
+        if payload_size > available {
+            out.push(SeiViolationKind::PayloadOverrun {
+                offset,
+                payload_type: payload_type as u32,
+                payload_size,
+                available,
+            });
+            break;
+        }
+        i += payload_size;
+    }
+    out
+}
+
+// A payloadType/payloadSize value: a run of 0xFF bytes, then the last byte.
+fn read_ff_coded(data: &[u8], i: &mut usize) -> Option<usize> {
+    let mut v = 0usize;
+    loop {
+        let b = *data.get(*i)?;
+        *i += 1;
+        v += b as usize;
+        if b != 0xFF {
+            return Some(v);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Non-fatal data-quality problems the extractor worked around.
+//!
+//! Malformed clips are read as far as possible instead of failing: boxes that overrun their
+//! container are cut short, chunks missing from `stsc` borrow the previous chunk's sample count,
+//! and SEI messages that don't decode are skipped. Each of these is recorded as a [`Warning`]
+//! on the extractor ([`SeiExtractor::warnings`](crate::SeiExtractor::warnings)), so pipelines can
+//! log them or flag the clip without treating it as unreadable.
+
+use std::fmt;
+
+/// A problem in the input that was tolerated rather than reported as an [`Error`](crate::Error).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A box's declared size runs past the end of its container; it was read up to the
+    /// container's end.
+    BoxClamped {
+        /// The container being walked, e.g. `"moov"` or `"stbl"`.
+        context: String,
+        box_type: String,
+        /// File offset of the box header.
+        offset: u64,
+        /// Size declared in the box header.
+        size: u64,
+        /// File offset where the container ends.
+        container_end: u64,
+    },
+    /// Chunks of the selected track that no `stsc` run covered, given the sample count of the
+    /// chunk before them.
+    StscGapsFilled { chunks: usize },
+    /// Samples dropped by [`ExtractOptions::repair`](crate::ExtractOptions::repair) to make the
+    /// sample tables consistent.
+    SamplesDropped { count: usize },
+    /// SEI messages of a wanted payload type in a sample that didn't decode as telemetry, e.g.
+    /// corrupted payloads or another tool's user data.
+    UndecodableSei {
+        sample_index: usize,
+        /// Absolute file offset where the sample begins.
+        file_offset: u64,
+        messages: usize,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::BoxClamped {
+                context,
+                box_type,
+                offset,
+                size,
+                container_end,
+            } => write!(
+                f,
+                "{context}: box '{box_type}' at offset {offset} declares size {size} past the \
+                 container end at {container_end}; clamped"
+            ),
+            Warning::StscGapsFilled { chunks } => write!(
+                f,
+                "{chunks} chunks missing from stsc; assumed the previous chunk's sample count"
+            ),
+            Warning::SamplesDropped { count } => {
+                write!(f, "{count} samples dropped repairing the sample tables")
+            }
+            Warning::UndecodableSei {
+                sample_index,
+                file_offset,
+                messages,
+            } => write!(
+                f,
+                "sample {sample_index} (offset {file_offset}): {messages} SEI messages didn't \
+                 decode as telemetry"
+            ),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! wasm-bindgen bindings for decoding telemetry in the browser (`wasm` feature).
+//!
+//! Build with `default-features = false, features = ["wasm"]` for `wasm32-unknown-unknown`;
+//! there's no file system there, so clips are passed in as bytes, e.g. from a `File` the user
+//! dropped on the page:
+//!
+//! ```js
+//! import init, { SeiDecoder, extractTelemetry } from "./pkg/tesla_sei.js";
+//! await init();
+//! const bytes = new Uint8Array(await file.arrayBuffer());
+//! for (const frame of extractTelemetry(bytes)) {
+//!   console.log(frame.frameSeqNo, frame.vehicleSpeedMps);
+//! }
+//! ```
+//!
+//! [`SeiDecoder`] decodes one frame per call instead, to keep long clips from blocking the page.
+
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::extract::{extractor_from_reader, SeiEvent, SeiExtractor};
+use crate::flat::SeiMetadataFlat;
+
+/// One frame's telemetry. Fields carry the camelCase names protobuf.js gives the
+/// `SeiMetadata` fields; enums are their raw values.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub version: u32,
+    #[wasm_bindgen(js_name = gearState)]
+    pub gear_state: i32,
+    /// A `BigInt` in JavaScript.
+    #[wasm_bindgen(js_name = frameSeqNo)]
+    pub frame_seq_no: u64,
+    #[wasm_bindgen(js_name = vehicleSpeedMps)]
+    pub vehicle_speed_mps: f32,
+    #[wasm_bindgen(js_name = acceleratorPedalPosition)]
+    pub accelerator_pedal_position: f32,
+    #[wasm_bindgen(js_name = steeringWheelAngle)]
+    pub steering_wheel_angle: f32,
+    #[wasm_bindgen(js_name = blinkerOnLeft)]
+    pub blinker_on_left: bool,
+    #[wasm_bindgen(js_name = blinkerOnRight)]
+    pub blinker_on_right: bool,
+    #[wasm_bindgen(js_name = brakeApplied)]
+    pub brake_applied: bool,
+    #[wasm_bindgen(js_name = autopilotState)]
+    pub autopilot_state: i32,
+    #[wasm_bindgen(js_name = latitudeDeg)]
+    pub latitude_deg: f64,
+    #[wasm_bindgen(js_name = longitudeDeg)]
+    pub longitude_deg: f64,
+    #[wasm_bindgen(js_name = headingDeg)]
+    pub heading_deg: f64,
+    #[wasm_bindgen(js_name = linearAccelerationMps2X)]
+    pub linear_acceleration_mps2_x: f64,
+    #[wasm_bindgen(js_name = linearAccelerationMps2Y)]
+    pub linear_acceleration_mps2_y: f64,
+    #[wasm_bindgen(js_name = linearAccelerationMps2Z)]
+    pub linear_acceleration_mps2_z: f64,
+    /// 0-based sample index, in decode order.
+    #[wasm_bindgen(js_name = sampleIndex)]
+    pub sample_index: u32,
+    /// 0-based position in display order.
+    #[wasm_bindgen(js_name = presentationIndex)]
+    pub presentation_index: u32,
+    /// Display time from the start of the clip, in seconds, for seeking a `<video>`;
+    /// `undefined` if the track has no timing.
+    #[wasm_bindgen(js_name = presentationTime)]
+    pub presentation_time_s: Option<f64>,
+}
+
+impl From<SeiEvent> for Frame {
+    fn from(event: SeiEvent) -> Self {
+        let m = SeiMetadataFlat::from(&event.metadata);
+        Frame {
+            version: m.version,
+            gear_state: m.gear_state,
+            frame_seq_no: m.frame_seq_no,
+            vehicle_speed_mps: m.vehicle_speed_mps,
+            accelerator_pedal_position: m.accelerator_pedal_position,
+            steering_wheel_angle: m.steering_wheel_angle,
+            blinker_on_left: m.blinker_on_left,
+            blinker_on_right: m.blinker_on_right,
+            brake_applied: m.brake_applied,
+            autopilot_state: m.autopilot_state,
+            latitude_deg: m.latitude_deg,
+            longitude_deg: m.longitude_deg,
+            heading_deg: m.heading_deg,
+            linear_acceleration_mps2_x: m.linear_acceleration_mps2_x,
+            linear_acceleration_mps2_y: m.linear_acceleration_mps2_y,
+            linear_acceleration_mps2_z: m.linear_acceleration_mps2_z,
+            // wasm32 has 32-bit usize.
+            sample_index: event.sample_index as u32,
+            presentation_index: event.presentation_index as u32,
+            presentation_time_s: event.presentation_time.map(|t| t.as_secs_f64()),
+        }
+    }
+}
+
+/// Decodes a clip held in memory one frame at a time.
+#[wasm_bindgen]
+pub struct SeiDecoder {
+    inner: SeiExtractor<Cursor<Vec<u8>>>,
+}
+
+#[wasm_bindgen]
+impl SeiDecoder {
+    /// Parse the clip's MP4 index. The bytes are copied, so the caller's buffer can be reused.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<SeiDecoder, JsError> {
+        let inner = extractor_from_reader(Cursor::new(bytes.to_vec()))?;
+        Ok(SeiDecoder { inner })
+    }
+
+    /// The next frame's telemetry, in decode order; `undefined` at the end of the clip.
+    #[wasm_bindgen(js_name = next)]
+    pub fn next_frame(&mut self) -> Result<Option<Frame>, JsError> {
+        Ok(self.inner.next_event()?.map(Frame::from))
+    }
+
+    /// Number of video samples in the clip, for progress bars.
+    #[wasm_bindgen(getter, js_name = totalSamples)]
+    pub fn total_samples(&self) -> u32 {
+        self.inner.total_samples() as u32
+    }
+}
+
+/// Decode every frame's telemetry from a whole clip, in decode order.
+#[wasm_bindgen(js_name = extractTelemetry)]
+pub fn extract_telemetry(bytes: &[u8]) -> Result<Vec<Frame>, JsError> {
+    let mut decoder = SeiDecoder::new(bytes)?;
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.next_frame()? {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
@@ -0,0 +1,275 @@
+//! Follow a TeslaCam folder and extract telemetry from clips as the car finishes them.
+//!
+//! [`watch_dir`] watches a directory tree (via `notify`) and yields a Tokio `Stream` of
+//! [`WatchEvent`]s: the SEI events of each new `.mp4` once it is complete, followed by a
+//! per-clip summary. A clip counts as complete when the writer closes it or, on filesystems that
+//! don't report closes (SMB/NFS mounts of the car's USB drive), when its size has stopped
+//! changing for [`WatchOptions::settle`].
+//!
+//! Native change notifications don't cross network mounts; set
+//! [`WatchOptions::poll_interval`] to rescan the tree instead.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc as std_mpsc;
+use std::time::{Duration, Instant};
+
+use notify::event::{AccessKind, AccessMode};
+use notify::{Config, Event, EventKind, PollWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::extract::{extractor_from_path, SeiEvent};
+use crate::Error;
+
+/// Options for [`watch_dir`].
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// How long a clip's size must stay unchanged before it is treated as finished, when no
+    /// close notification arrives.
+    pub settle: Duration,
+    /// Rescan the tree at this interval instead of using native notifications, for network
+    /// mounts where those don't arrive.
+    pub poll_interval: Option<Duration>,
+    /// Also extract the clips already present when watching starts.
+    pub include_existing: bool,
+    /// Channel capacity, as for [`stream_from_path`](crate::stream_from_path).
+    pub buffer: usize,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            settle: Duration::from_secs(5),
+            poll_interval: None,
+            include_existing: false,
+            buffer: 64,
+        }
+    }
+}
+
+/// One item of a [`watch_dir`] stream.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// A decoded event from a finished clip.
+    Sei { path: PathBuf, event: SeiEvent },
+    /// All of a clip's events have been sent.
+    ClipDone { path: PathBuf, events: usize },
+    /// A finished clip couldn't be read; events sent before the error stand.
+    ClipFailed { path: PathBuf, error: Error },
+}
+
+/// Watch `root` recursively and stream the telemetry of each clip finished from now on.
+///
+/// The watcher and the clip reader each run on a blocking thread
+/// (`tokio::task::spawn_blocking`) until the stream is dropped; a slow consumer delays reading
+/// clips but not noticing them. Clips are read one at a time in the order they finish, and each
+/// path is read only once. A failing clip yields [`WatchEvent::ClipFailed`] and watching
+/// continues; an `Err` item means the watcher itself failed, and ends the stream.
+///
+/// Errors setting up the watcher (e.g. `root` doesn't exist) are returned directly.
+pub fn watch_dir(
+    root: impl Into<PathBuf>,
+    opts: &WatchOptions,
+) -> Result<ReceiverStream<Result<WatchEvent, Error>>, Error> {
+    let root = root.into();
+    let (notify_tx, notify_rx) = std_mpsc::channel();
+    let mut watcher: Box<dyn Watcher + Send> = match opts.poll_interval {
+        Some(interval) => Box::new(PollWatcher::new(
+            notify_tx,
+            Config::default().with_poll_interval(interval),
+        )?),
+        None => Box::new(notify::recommended_watcher(notify_tx)?),
+    };
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+
+    let mut queue = ClipQueue {
+        settle: opts.settle,
+        pending: HashMap::new(),
+        seen: HashSet::new(),
+    };
+    if opts.include_existing {
+        let mut existing = Vec::new();
+        collect_mp4s(&root, &mut existing)?;
+        existing.sort();
+        for path in existing {
+            queue.touch(path, false);
+        }
+    }
+
+    let (tx, rx) = mpsc::channel(opts.buffer.max(1));
+    // Finished clips go to a second thread that reads them and waits on the stream, so a slow
+    // consumer never holds up draining notifications and timing the settle period.
+    let (clip_tx, clip_rx) = std_mpsc::channel::<Result<PathBuf, Error>>();
+    let closed = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        for clip in clip_rx {
+            let sent = match clip {
+                Ok(path) => send_clip(&tx, path),
+                Err(err) => {
+                    let _ = tx.blocking_send(Err(err));
+                    false
+                }
+            };
+            if !sent {
+                return;
+            }
+        }
+    });
+
+    let tick = (opts.settle / 4).clamp(Duration::from_millis(50), Duration::from_secs(1));
+    tokio::task::spawn_blocking(move || {
+        // Keep the watcher alive for as long as the worker runs.
+        let _watcher = watcher;
+        loop {
+            match notify_rx.recv_timeout(tick) {
+                Ok(Ok(event)) => queue.record(event),
+                Ok(Err(err)) => {
+                    let _ = clip_tx.send(Err(err.into()));
+                    return;
+                }
+                Err(std_mpsc::RecvTimeoutError::Timeout) => {}
+                Err(std_mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+            for path in queue.finished() {
+                // The reader has stopped because the stream was dropped.
+                if clip_tx.send(Ok(path)).is_err() {
+                    return;
+                }
+            }
+            if closed.is_closed() {
+                return;
+            }
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+// Clips seen changing, waiting to be finished.
+struct ClipQueue {
+    settle: Duration,
+    // Last observed size, when it was observed, and whether the writer closed the file.
+    pending: HashMap<PathBuf, (Option<u64>, Instant, bool)>,
+    // Clips already read, so later touches (copies, attribute changes) don't re-read them.
+    seen: HashSet<PathBuf>,
+}
+
+impl ClipQueue {
+    fn record(&mut self, event: Event) {
+        let closed = matches!(
+            event.kind,
+            EventKind::Access(AccessKind::Close(AccessMode::Write))
+        );
+        for path in event.paths {
+            if !is_mp4(&path) {
+                continue;
+            }
+            match event.kind {
+                EventKind::Remove(_) => {
+                    self.pending.remove(&path);
+                    self.seen.remove(&path);
+                }
+                EventKind::Create(_) | EventKind::Modify(_) => self.touch(path, false),
+                _ if closed => self.touch(path, true),
+                _ => {}
+            }
+        }
+    }
+
+    fn touch(&mut self, path: PathBuf, closed: bool) {
+        if self.seen.contains(&path) {
+            return;
+        }
+        let size = fs::metadata(&path).ok().map(|m| m.len());
+        let entry = self
+            .pending
+            .entry(path)
+            .or_insert((size, Instant::now(), false));
+        if entry.0 != size {
+            *entry = (size, Instant::now(), entry.2);
+        }
+        entry.2 |= closed;
+    }
+
+    // Clips that were closed or whose size has settled, oldest change first.
+    fn finished(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let mut done = Vec::new();
+        // Deleted before it settled (a missed remove notification).
+        self.pending.retain(|path, _| path.exists());
+        for (path, (size, changed, closed)) in &mut self.pending {
+            let current = fs::metadata(path).ok().map(|m| m.len());
+            if current != *size {
+                (*size, *changed) = (current, now);
+                continue;
+            }
+            if current.is_some_and(|len| len > 0)
+                && (*closed || now.duration_since(*changed) >= self.settle)
+            {
+                done.push((*changed, path.clone()));
+            }
+        }
+        done.sort();
+        done.into_iter()
+            .map(|(_, path)| {
+                self.pending.remove(&path);
+                self.seen.insert(path.clone());
+                path
+            })
+            .collect()
+    }
+}
+
+// Send one clip's events; false once the stream has been dropped.
+fn send_clip(tx: &mpsc::Sender<Result<WatchEvent, Error>>, path: PathBuf) -> bool {
+    let mut extractor = match extractor_from_path(&path) {
+        Ok(e) => e,
+        Err(error) => {
+            return tx
+                .blocking_send(Ok(WatchEvent::ClipFailed { path, error }))
+                .is_ok()
+        }
+    };
+    let mut events = 0;
+    for item in &mut extractor {
+        let event = match item {
+            Ok(event) => event,
+            // The extractor can repeat a read error forever; give up on the clip at the first.
+            Err(error) => {
+                return tx
+                    .blocking_send(Ok(WatchEvent::ClipFailed { path, error }))
+                    .is_ok();
+            }
+        };
+        let path = path.clone();
+        if tx
+            .blocking_send(Ok(WatchEvent::Sei { path, event }))
+            .is_err()
+        {
+            return false;
+        }
+        events += 1;
+    }
+    tx.blocking_send(Ok(WatchEvent::ClipDone { path, events }))
+        .is_ok()
+}
+
+fn is_mp4(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp4"))
+}
+
+fn collect_mp4s(path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_mp4s(&path, out)?;
+        } else if is_mp4(&path) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Build tasks that don't belong in the `tesla-sei` binary.
+//!
+//! `cargo xtask manpages DIR` writes `tesla-sei.1` and one `tesla-sei-<subcommand>.1` per
+//! subcommand into DIR, from the same argument definitions the binary parses.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::CommandFactory;
+
+// The binary's argument definitions, compiled here on their own.
+#[allow(dead_code)]
+#[path = "../../src/cli/args.rs"]
+mod args;
+
+fn main() -> ExitCode {
+    let mut task = std::env::args().skip(1);
+    match (task.next().as_deref(), task.next(), task.next()) {
+        (Some("manpages"), Some(dir), None) => match manpages(PathBuf::from(dir)) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprintln!("usage: cargo xtask manpages DIR");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn manpages(dir: PathBuf) -> std::io::Result<()> {
+    std::fs::create_dir_all(&dir)?;
+    clap_mangen::generate_to(args::Cli::command(), &dir)
+}